@@ -52,7 +52,7 @@ fn main() -> anyhow::Result<()> {
 	drop(socket);
 
 	// Generate the characters we're going to send to the client: everything in the ASCII range.
-	let chars: Vec<u8> = (b' '..b'~').into_iter().collect();
+	let chars: Vec<u8> = (b' '..b'~').collect();
 
 	// Send characters repeatedly until the client disconnects.
 	loop {
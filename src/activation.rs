@@ -0,0 +1,188 @@
+use crate::{
+	errors::OpenMultipleError,
+	open_multiple,
+	spawn_with_sockets,
+	SocketAddr,
+	SocketAppOptions,
+	SocketUserOptions,
+};
+use socket2::Socket;
+use std::{
+	ffi::c_int,
+	io,
+	os::fd::{AsFd, AsRawFd, BorrowedFd},
+	process::{Child, Command},
+	time::Duration,
+};
+
+/// The parent side of socket activation: a set of sockets that are opened by this process, and handed to a child process when there is activity on one of them.
+///
+/// This does for a child process what systemd does for a service with a socket unit in `Accept=no` mode. The sockets are opened up front, so connections to them are queued up instead of refused, but the child process is only started once the first connection (or datagram) arrives. The child receives all of the sockets with [`spawn_with_sockets`], and accepts the waiting connection itself. It can open the sockets with a <code>systemd-name:<var>name</var></code> address, like any other socket inherited from systemd.
+///
+/// This process keeps its own copies of the sockets, so when the child process exits, it can wait for activity and start another child process again, the same way.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::{ActivationServer, SocketAddr, SocketAppOptions, SocketUserOptions};
+/// # use std::process::Command;
+/// # fn example_fn() -> Result<(), Box<dyn std::error::Error>> {
+/// let server = ActivationServer::open(
+/// 	&[("127.0.0.1:8080".parse()?, "http")],
+/// 	&SocketAppOptions::new(socket2::Type::STREAM),
+/// 	&SocketUserOptions::default(),
+/// )?;
+///
+/// loop {
+/// 	// The child process opens its socket with the address `systemd-name:http`.
+/// 	let mut child = server.wait_and_spawn(&mut Command::new("my-server"))?;
+/// 	child.wait()?;
+/// }
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+#[derive(Debug)]
+pub struct ActivationServer {
+	sockets: Vec<(Socket, String)>,
+}
+
+impl ActivationServer {
+	/// Opens the given sockets, with [`open_multiple`], and pairs each of them with a name. The child process sees these names in the `LISTEN_FDNAMES` environment variable.
+	///
+	/// Names must be 1 to 255 printable ASCII characters, and must not contain `:`. This is checked when the child process is spawned, not by this function.
+	///
+	///
+	/// # Errors
+	///
+	/// Same as `open_multiple`.
+	pub fn open(
+		addresses: &[(SocketAddr, &str)],
+		app_options: &SocketAppOptions,
+		user_options: &SocketUserOptions,
+	) -> Result<Self, OpenMultipleError> {
+		let (addresses, names): (Vec<SocketAddr>, Vec<&str>) = addresses.iter().cloned().unzip();
+		let sockets: Vec<Socket> = open_multiple(&addresses, app_options, user_options)?;
+
+		Ok(Self::new(sockets.into_iter().zip(names.into_iter().map(str::to_owned)).collect()))
+	}
+
+	/// Creates an `ActivationServer` from sockets that have already been opened, each paired with its name.
+	pub fn new(sockets: Vec<(Socket, String)>) -> Self {
+		Self { sockets }
+	}
+
+	/// Returns the sockets, each paired with its name.
+	pub fn sockets(&self) -> &[(Socket, String)] {
+		&self.sockets
+	}
+
+	/// Waits until there is a connection waiting to be accepted, or a datagram waiting to be received, on any of the sockets.
+	///
+	/// Returns `Ok(true)` if there is, or `Ok(false)` if `timeout` elapsed first. If `timeout` is `None`, this waits indefinitely.
+	///
+	/// Nothing is accepted or received, so the same activity is still waiting for the child process.
+	///
+	///
+	/// # Errors
+	///
+	/// Any I/O error raised by the operating system. This also fails with [`io::ErrorKind::InvalidInput`] if there are no sockets.
+	pub fn wait(&self, timeout: Option<Duration>) -> io::Result<bool> {
+		if self.sockets.is_empty() {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "there are no sockets to wait on"));
+		}
+
+		let mut poll_fds: Vec<libc::pollfd> =
+			self.sockets.iter()
+			.map(|(socket, _)| libc::pollfd {
+				fd: socket.as_raw_fd(),
+				events: libc::POLLIN,
+				revents: 0,
+			})
+			.collect();
+
+		let timeout: c_int = match timeout {
+			Some(timeout) => c_int::try_from(timeout.as_millis()).unwrap_or(c_int::MAX),
+			None => -1,
+		};
+
+		loop {
+			// Safety: `poll_fds` is a valid array of `pollfd`s, of the length given.
+			let ready: c_int = unsafe {
+				libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as _, timeout)
+			};
+
+			if ready >= 0 {
+				break Ok(ready > 0);
+			}
+
+			let error = io::Error::last_os_error();
+
+			if error.kind() != io::ErrorKind::Interrupted {
+				break Err(error);
+			}
+		}
+	}
+
+	/// Spawns a child process with [`spawn_with_sockets`], passing it all of the sockets, without waiting for activity first.
+	///
+	/// See `spawn_with_sockets` for caveats and errors.
+	pub fn spawn(&self, command: &mut Command) -> io::Result<Child> {
+		let sockets: Vec<(BorrowedFd<'_>, &str)> =
+			self.sockets.iter()
+			.map(|(socket, name)| (socket.as_fd(), name.as_str()))
+			.collect();
+
+		spawn_with_sockets(command, &sockets)
+	}
+
+	/// Waits indefinitely for activity on any of the sockets, as with [`wait`][Self::wait], then spawns a child process, as with [`spawn`][Self::spawn].
+	pub fn wait_and_spawn(&self, command: &mut Command) -> io::Result<Child> {
+		self.wait(None)?;
+		self.spawn(command)
+	}
+}
+
+#[test]
+fn test_activation_server() {
+	use std::{
+		io::Read,
+		process::Stdio,
+	};
+
+	let server = ActivationServer::open(
+		&[("127.0.0.1:0".parse().unwrap(), "first"), ("127.0.0.1:0".parse().unwrap(), "second")],
+		&SocketAppOptions::new(socket2::Type::STREAM),
+		&SocketUserOptions::default(),
+	).unwrap();
+
+	// There's no activity yet.
+	assert!(!server.wait(Some(Duration::from_millis(10))).unwrap());
+
+	// Connect to the second socket. The connection is queued, but not accepted.
+	let second_addr = server.sockets()[1].0.local_addr().unwrap();
+	let _client = std::net::TcpStream::connect(second_addr.as_socket().unwrap()).unwrap();
+
+	let mut command = Command::new("sh");
+	command
+	.args(["-c", r#"echo "$LISTEN_FDS $LISTEN_FDNAMES""#])
+	.stdout(Stdio::piped());
+
+	let mut child: Child = server.wait_and_spawn(&mut command).unwrap();
+
+	let mut output = String::new();
+	child.stdout.take().unwrap().read_to_string(&mut output).unwrap();
+	assert!(child.wait().unwrap().success());
+
+	assert_eq!(output, "2 first:second\n");
+
+	// The connection is still waiting to be accepted.
+	assert!(server.wait(Some(Duration::ZERO)).unwrap());
+	server.sockets()[1].0.accept().unwrap();
+
+	assert_eq!(ActivationServer::new(Vec::new()).wait(None).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
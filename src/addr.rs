@@ -1,9 +1,14 @@
 use crate::{
 	errors::{
 		CleanupSocketError,
+		FromSockAddrError,
 		InvalidSocketAddrError,
+		OpenSocketError,
 	},
 	is_unix_socket,
+	SocketAddrOptions,
+	SocketAppOptions,
+	SocketUserOptions,
 	sys,
 };
 use std::{
@@ -19,11 +24,9 @@ use std::{
 use crate::{
 	convert::AnyStdSocket,
 	make_socket_inheritable,
-	SocketAppOptions,
-	SocketUserOptions,
 };
 
-#[cfg(all(feature = "serde", test))]
+#[cfg(test)]
 use assert_matches::assert_matches;
 
 /// The address to bind a socket to, or a description of an inherited socket to use. This is one of the three parameters to [`open`][crate::open()].
@@ -33,13 +36,16 @@ use assert_matches::assert_matches;
 /// This type is designed to be parsed or converted from other types, namely:
 ///
 /// * From a string, using [`str::parse`] or [`FromStr::from_str`]. The documentation for each variant has a “Syntax” section explaining the expected syntax.
-/// * [`From`] various standard library socket address types.
+/// * [`From`] various standard library socket address types, and from a tuple of an IP address and port number, like `(Ipv4Addr::LOCALHOST, 8080)`.
 /// * `From` [`PathBuf`], which produces [`SocketAddr::Unix`].
-/// * [`TryFrom`] `std::os::unix::net::SocketAddr` (Unix-like platforms only), which produces [`SocketAddr::Unix`] if the input address has a pathname, or fails if the input address is unnamed or (Linux only) has an abstract name.
+/// * [`TryFrom`] [`socket2::SockAddr`], such as the local or peer address of a socket. This produces [`SocketAddr::Ip`], [`SocketAddr::Unix`], [`SocketAddr::UnixAbstract`], or [`SocketAddr::Vsock`], depending on the address family, and fails with [`FromSockAddrError`] for unnamed Unix-domain socket addresses and other address families.
+/// * [`TryFrom`] `std::os::unix::net::SocketAddr` (Unix-like platforms only), which produces [`SocketAddr::Unix`] if the input address has a pathname, or (Android and Linux only) [`SocketAddr::UnixAbstract`] if the input address has an abstract name that is valid UTF-8. It fails if the input address is unnamed.
 #[cfg_attr(feature = "serde", doc = r#"
 * From a serialization format supported by [`serde`]. The serialized representation is expected to be a string, also using the syntax described in the aforementioned “Syntax” sections.
 "#)]
 ///
+/// There are also convenience constructors for common defaults, like [`SocketAddr::any_v4`], [`SocketAddr::loopback`], and [`SocketAddr::unix`].
+///
 /// The [`Default`] for this type is the IPv4 address 127.0.0.1, with no port specified.
 ///
 ///
@@ -58,9 +64,15 @@ pub enum SocketAddr {
 	/// * `1.2.3.4:5`, an IPv4 address with port number
 	/// * `1::2`, a non-bracketed IPv6 address without port number
 	/// * `[1::2]:3`, a bracketed IPv6 address with port number
+	/// * `fe80::1%eth0`, a non-bracketed IPv6 address with zone ID and without port number
+	/// * `[fe80::1%eth0]:3`, a bracketed IPv6 address with zone ID and port number
 	///
 	/// If no port number is given, then [`SocketAppOptions::default_port`] is used as the port number instead. If that is also `None`, then [`open`][crate::open()] will raise an error.
 	///
+	/// The zone ID, which follows `%`, selects a network interface for link-local IPv6 addresses. It can be either an interface name (like `eth0`) or an interface index (like `2`). Interface names are looked up when the address is [opened][crate::open()], not when it is parsed.
+	///
+	/// Any form with a port number can instead have a port range, like `1.2.3.4:8000-8100` or `[1::2]:8000-8100`. When opened, the socket is bound to the first port in the range that is available. Use [`Socket::local_addr`][socket2::Socket::local_addr] on the opened socket to find out which port that was. If no port in the range is available, `open` raises [`OpenSocketError::PortRangeExhausted`][crate::errors::OpenSocketError::PortRangeExhausted].
+	///
 	/// # Availability
 	///
 	/// All platforms.
@@ -71,6 +83,37 @@ pub enum SocketAddr {
 
 		/// The port, if any.
 		port: Option<u16>,
+
+		/// The IPv6 zone ID (also called scope ID), if any. This is either the name or the numeric index of a network interface. It is ignored for IPv4 addresses.
+		zone: Option<String>,
+
+		/// The last port of a port range, if any. If this is `Some`, then [`open`][crate::open()] tries each port from `port` to this one, inclusive, and uses the first one that it can bind to. This is ignored if `port` is `None`.
+		port_range_end: Option<u16>,
+	},
+
+	/// An Internet socket address on all network interfaces, also known as the wildcard address.
+	///
+	/// When [opened][crate::open()], this is either the IPv4 address `0.0.0.0` or the IPv6 address `[::]`, depending on [`SocketAppOptions::wildcard_ipv6`].
+	///
+	/// # Syntax
+	///
+	/// * `*`, without port number
+	/// * <code>*:<var>port</var></code>, with port number
+	/// * <code>:<var>port</var></code>, with port number
+	/// * <code>*:<var>first</var>-<var>last</var></code> or <code>:<var>first</var>-<var>last</var></code>, with port range, as with the `Ip` variant
+	///
+	/// If no port number is given, then [`SocketAppOptions::default_port`] is used, same as with the `Ip` variant.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	#[non_exhaustive]
+	IpWildcard {
+		/// The port, if any.
+		port: Option<u16>,
+
+		/// The last port of a port range, if any. This works the same way as with the `Ip` variant.
+		port_range_end: Option<u16>,
 	},
 
 	/// A Unix-domain socket at the given path.
@@ -90,7 +133,7 @@ pub enum SocketAddr {
 	///
 	/// Although this library supports Unix-domain sockets on Windows, note that the Rust standard library and Tokio currently do not. Converting a Unix-domain socket to [`AnyStdSocket`] on Windows will result in the [`AnyStdSocket::Other`] variant, not any of the `AnyStdSocket` variants for Unix-domain sockets.
 	///
-	/// Some platforms, namely Linux and Windows, support Unix-domain sockets whose name is in an “abstract namespace” instead of the file system. That is not currently supported by this library.
+	/// Some platforms, namely Linux and Windows, support Unix-domain sockets whose name is in an “abstract namespace” instead of the file system. Those are represented by the [`UnixAbstract`][Self::UnixAbstract] variant instead of this one.
 	///
 	/// Unix-domain socket names and paths are severely limited in length. The maximum length is platform-defined.
 	#[non_exhaustive]
@@ -99,6 +142,47 @@ pub enum SocketAddr {
 		path: PathBuf,
 	},
 
+	/// A Unix-domain socket with the given name in the abstract namespace.
+	///
+	/// Abstract sockets are not files. They don't appear in the file system, so they never need to be deleted, and they don't have an owner, group, or permissions. Using [`SocketUserOptions::unix_socket_permissions`] or similar options with an abstract socket is an error.
+	///
+	/// # Syntax
+	///
+	/// <code>@<var>name</var></code>. The `@` is not part of the name.
+	///
+	/// # Availability
+	///
+	/// All platforms, but only Android, Linux, and Windows support abstract Unix-domain sockets. [Opening][crate::open()] this kind of address on other platforms results in an [`OpenSocketError::UnsupportedSocketAddr`][crate::errors::OpenSocketError::UnsupportedSocketAddr] error.
+	///
+	/// Availability notes for the `Unix` variant also apply to this variant.
+	#[non_exhaustive]
+	UnixAbstract {
+		/// The name of the socket, without the leading `@`.
+		name: String,
+	},
+
+	/// A [vsock](https://man7.org/linux/man-pages/man7/vsock.7.html) socket, for communication between virtual machines and their host.
+	///
+	/// # Syntax
+	///
+	/// <code>vsock:<var>cid</var>:<var>port</var></code>, where <code><var>cid</var></code> and <code><var>port</var></code> are non-negative integers. <code><var>cid</var></code> can also be `any`, which is short for [`VMADDR_CID_ANY`][Self::VMADDR_CID_ANY].
+	///
+	/// When listening, the CID is usually `any`.
+	///
+	/// # Availability
+	///
+	/// All platforms, but only Android and Linux support vsock sockets. [Opening][crate::open()] this kind of address on other platforms results in an [`OpenSocketError::UnsupportedSocketAddr`][crate::errors::OpenSocketError::UnsupportedSocketAddr] error.
+	///
+	/// Vsock sockets are always converted to [`AnyStdSocket::Other`][crate::convert::AnyStdSocket::Other], because the standard library has no socket types for them.
+	#[non_exhaustive]
+	Vsock {
+		/// The context identifier (CID) of the virtual machine or host.
+		cid: u32,
+
+		/// The port number.
+		port: u32,
+	},
+
 	/// An existing socket inherited from the parent process.
 	///
 	/// Only sockets that have been made inheritable can be inherited. When spawning a child process from a Rust program (such as an integration test) that is to inherit a socket from the parent process, use the [`make_socket_inheritable`][crate::make_socket_inheritable()] function to make it inheritable.
@@ -126,9 +210,28 @@ pub enum SocketAddr {
 		// 2. `BorrowedSocket` and `OwnedSocket` guarantee that the socket is valid. That is not known at the time of parsing. It is verified by `open`, which duplicates the alleged socket (which fails if no such socket exists) and then checks various things about the alleged socket (which fails if it's not a socket). That's still only mostly safe, but storing a `BorrowedSocket` or `OwnedSocket` here makes the representation that it's definitely a valid socket, which is definitely not safe.
 	},
 
+	/// An existing socket inherited from the parent process, whose file descriptor number (on Unix-like platforms) or `SOCKET` handle (on Windows) is in an environment variable.
+	///
+	/// This is for launchers that pass the socket's number in an environment variable of their choosing, instead of on the command line. The environment variable is read when the address is [opened][crate::open()], not when it is parsed. Apart from that, this works the same way as the `Inherit` variant.
+	///
+	/// # Syntax
+	///
+	/// <code>env:<var>name</var></code> where <code><var>name</var></code> is the name of the environment variable.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	///
+	/// Availability notes for the `Inherit` variant also apply to this variant.
+	#[non_exhaustive]
+	InheritEnv {
+		/// The name of the environment variable.
+		var: String,
+	},
+
 	/// An existing socket inherited from the parent process, as the standard input.
 	///
-	/// This can be used with inetd sockets in `wait` mode, but is not compatible with `nowait` mode.
+	/// This can be used with inetd sockets in `wait` mode with [`open`][crate::open()], and in `nowait` mode with [`open_connection`][crate::open_connection]. In `nowait` mode, inetd accepts each connection itself, and the standard input is the connected socket.
 	///
 	/// This is like the `Inherit` variant above, except the socket file descriptor number or Windows `SOCKET` handle is determined as follows:
 	///
@@ -151,7 +254,7 @@ pub enum SocketAddr {
 	///
 	/// This is similar to the `Inherit` variant, but different in the systemd environment variables `LISTEN_FDS` and `LISTEN_PID` are checked before using the socket. See [the systemd documentation](https://www.freedesktop.org/software/systemd/man/sd_listen_fds.html) for details about these.
 	///
-	/// Systemd socket units used with this must be in `Accept=no` mode.
+	/// Systemd socket units used with this must be in `Accept=no` mode, unless the socket is opened with [`open_connection`][crate::open_connection].
 	///
 	/// # Syntax
 	///
@@ -170,23 +273,296 @@ pub enum SocketAddr {
 		/// The socket's file descriptor number.
 		socket: sys::RawSocket,
 	},
+
+	/// The first socket inherited from systemd socket activation.
+	///
+	/// This is the same as <code>systemd:3</code> (see the `SystemdNumeric` variant), for the common case where the application is activated with only one socket. To use all of the inherited sockets, see [`SocketAddr::all_systemd`].
+	///
+	/// Systemd socket units used with this must be in `Accept=no` mode, unless the socket is opened with [`open_connection`][crate::open_connection].
+	///
+	/// # Syntax
+	///
+	/// The exact string `systemd`.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	///
+	/// Availability notes for the `SystemdNumeric` variant also apply to this variant.
+	#[cfg(not(windows))]
+	#[non_exhaustive]
+	SystemdFirst,
+
+	/// An existing socket inherited from systemd socket activation, identified by name instead of file descriptor number.
+	///
+	/// This is similar to the `SystemdNumeric` variant, but the socket is looked up in the systemd environment variable `LISTEN_FDNAMES`, which systemd sets from the `FileDescriptorName=` setting of each socket unit (or the name of the socket unit, if that setting is absent). This way, the configuration doesn't depend on the order in which systemd passes the sockets.
+	///
+	/// If more than one inherited socket has the given name, as happens when a socket unit has more than one `Listen…=` setting, then the first one is used.
+	///
+	/// Systemd socket units used with this must be in `Accept=no` mode, unless the socket is opened with [`open_connection`][crate::open_connection].
+	///
+	/// # Syntax
+	///
+	/// <code>systemd-name:<var>name</var></code> where <code><var>name</var></code> is the name of a socket inherited from systemd.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	///
+	/// Availability notes for the `SystemdNumeric` variant also apply to this variant.
+	#[cfg(not(windows))]
+	#[non_exhaustive]
+	SystemdNamed {
+		/// The socket's name, as it appears in `LISTEN_FDNAMES`.
+		name: String,
+	},
+
+	/// An existing socket inherited from launchd socket activation.
+	///
+	/// The socket is retrieved with `launch_activate_socket`, using the name of one of the sockets in the `Sockets` dictionary of the launchd job's property list. See the `launchd.plist(5)` manual page for details.
+	///
+	/// Launchd may create more than one socket for the same name, such as one for IPv4 and one for IPv6. In that case, the first one is used, and the others are closed.
+	///
+	/// # Syntax
+	///
+	/// <code>launchd:<var>name</var></code> where <code><var>name</var></code> is the name of a socket in the launchd job's property list.
+	///
+	/// # Availability
+	///
+	/// All platforms, but only macOS has launchd. [Opening][crate::open()] this kind of address on other platforms results in an [`OpenSocketError::UnsupportedSocketAddr`][crate::errors::OpenSocketError::UnsupportedSocketAddr] error.
+	#[non_exhaustive]
+	Launchd {
+		/// The socket's name, as it appears in the launchd job's property list.
+		name: String,
+	},
+
+	/// A Windows socket handed off by another process with `WSADuplicateSocketW`.
+	///
+	/// This is a more robust alternative to handle inheritance on Windows, where inherited socket handles can be unusable if a layered service provider is installed, and an inheritable handle is inherited by every child process rather than just the intended one. Instead, the parent process calls `WSADuplicateSocketW` with the process ID of the child process, which fills in a `WSAPROTOCOL_INFOW` structure, and passes that structure to the child process. The child process then gets its own socket handle from it with `WSASocketW`. The Windows-only function `duplicate_socket_for_process` does the parent process's part.
+	///
+	/// A `WSAPROTOCOL_INFOW` structure can only be used once. Opening the same address a second time fails.
+	///
+	/// # Syntax
+	///
+	/// <code>wsainfo:<var>data</var></code> where <code><var>data</var></code> is the `WSAPROTOCOL_INFOW` structure, encoded in standard base64 with padding.
+	///
+	/// # Availability
+	///
+	/// All platforms, but only Windows has `WSADuplicateSocketW`. [Opening][crate::open()] this kind of address on other platforms results in an [`OpenSocketError::UnsupportedSocketAddr`][crate::errors::OpenSocketError::UnsupportedSocketAddr] error.
+	#[non_exhaustive]
+	WsaProtocolInfo {
+		/// The raw bytes of the `WSAPROTOCOL_INFOW` structure.
+		info: Vec<u8>,
+	},
+
+	/// One end of a pair of sockets that are connected to each other, within the same process. The other end can be retrieved with [`SocketAddr::take_pair_peer`].
+	///
+	/// This is useful for testing, and for applications that can run a client in the same process as the server, using the same configuration surface as any other socket.
+	///
+	/// What [`open`][crate::open()] does with this depends on the [`SocketAppOptions`]:
+	///
+	/// * If [`SocketAppOptions::listen`] is true and [`SocketAppOptions::type`] is [`socket2::Type::STREAM`], then `open` returns a TCP socket listening on an ephemeral port on the loopback interface, and the other end is a socket that is already connected to it. The connection is waiting to be accepted.
+	/// * Otherwise, `open` returns one of two sockets that are connected to each other, as created by `socketpair` (on Unix-like platforms) or emulated with a loopback TCP connection or pair of loopback UDP sockets (on Windows).
+	///
+	/// Each time this address is opened, a new pair is created, and any unclaimed other end of a previous pair with the same name is closed.
+	///
+	/// # Syntax
+	///
+	/// The exact string `pair`, or <code>pair:<var>name</var></code>. Different names can be used to keep several pairs apart. The name for plain `pair` is the empty string.
+	///
+	/// # Availability
+	///
+	/// All platforms. On Windows, only stream and datagram sockets are supported.
+	#[non_exhaustive]
+	Pair {
+		/// The name of the pair.
+		name: String,
+	},
+
+	/// Another socket address, with options that override some of the [`SocketUserOptions`] for this address only.
+	///
+	/// # Syntax
+	///
+	/// <code><var>address</var>?<var>options</var></code>, where <code><var>address</var></code> is any other socket address, and <code><var>options</var></code> is a query string as described in the documentation for [`SocketAddrOptions`], like <code>./my.sock?mode=660&backlog=1024</code>.
+	///
+	/// Unix-domain socket paths can also contain `?`. The part after the last `?` is only taken to be options if every `&`-separated part of it contains `=`.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	#[non_exhaustive]
+	WithOptions {
+		/// The socket address.
+		addr: Box<SocketAddr>,
+
+		/// The options for this address.
+		options: SocketAddrOptions,
+	},
 }
 
 impl SocketAddr {
+	/// The wildcard vsock CID, for use with [`SocketAddr::Vsock`]. A socket bound to this CID accepts connections addressed to any CID of the local machine.
+	pub const VMADDR_CID_ANY: u32 = u32::MAX;
+
 	/// Returns true if and only if this `SocketAddr` is one of the inherited variants, like `Inherit` or `SystemdNumeric`.
 	pub fn is_inherited(&self) -> bool {
 		match self {
 			| Self::Inherit { .. }
+			| Self::InheritEnv { .. }
 			| Self::InheritStdin
 			=> true,
 
 			#[cfg(not(windows))]
-			Self::SystemdNumeric { .. } => true,
+			| Self::SystemdFirst
+			| Self::SystemdNumeric { .. }
+			| Self::SystemdNamed { .. }
+			=> true,
+
+			Self::Launchd { .. } => true,
+			Self::WsaProtocolInfo { .. } => true,
+
+			Self::WithOptions { addr, .. } => addr.is_inherited(),
+
+			_ => false,
+		}
+	}
+
+	/// Returns which [kind][AddressKinds] of address this is. For a [`SocketAddr::WithOptions`], returns the kind of the address inside it.
+	///
+	/// The returned value always has exactly one flag set.
+	pub fn kind(&self) -> AddressKinds {
+		match self {
+			Self::Ip { .. } | Self::IpWildcard { .. } => AddressKinds::IP,
+			Self::Unix { .. } | Self::UnixAbstract { .. } => AddressKinds::UNIX,
+			Self::Vsock { .. } => AddressKinds::VSOCK,
+			Self::Inherit { .. } | Self::InheritEnv { .. } => AddressKinds::INHERIT,
+			Self::InheritStdin => AddressKinds::STDIN,
+
+			#[cfg(not(windows))]
+			Self::SystemdFirst | Self::SystemdNumeric { .. } | Self::SystemdNamed { .. } => AddressKinds::SYSTEMD,
+
+			Self::Launchd { .. } => AddressKinds::LAUNCHD,
+			Self::WsaProtocolInfo { .. } => AddressKinds::WSA_PROTOCOL_INFO,
+			Self::Pair { .. } => AddressKinds::PAIR,
+			Self::WithOptions { addr, .. } => addr.kind(),
+		}
+	}
+
+	/// Returns true if and only if this `SocketAddr` is an Internet socket address, that is, [`SocketAddr::Ip`] or [`SocketAddr::IpWildcard`].
+	pub fn is_ip(&self) -> bool {
+		matches!(self.without_options(), Self::Ip { .. } | Self::IpWildcard { .. })
+	}
+
+	/// Returns true if and only if this `SocketAddr` is a non-inherited Unix-domain socket address, that is, [`SocketAddr::Unix`] or [`SocketAddr::UnixAbstract`].
+	pub fn is_unix(&self) -> bool {
+		matches!(self.without_options(), Self::Unix { .. } | Self::UnixAbstract { .. })
+	}
+
+	/// Returns the IP address, if this is a [`SocketAddr::Ip`].
+	///
+	/// This returns `None` for a [`SocketAddr::IpWildcard`], because which wildcard address it stands for depends on [`SocketAppOptions::wildcard_ipv6`].
+	pub fn ip(&self) -> Option<IpAddr> {
+		match self.without_options() {
+			Self::Ip { addr, .. } => Some(*addr),
+			_ => None,
+		}
+	}
+
+	/// Returns the port number, if this is a [`SocketAddr::Ip`] or [`SocketAddr::IpWildcard`] and it has a port number. If it has a port range, this is the first port in the range.
+	pub fn port(&self) -> Option<u16> {
+		match self.without_options() {
+			| Self::Ip { port, .. }
+			| Self::IpWildcard { port, .. }
+			=> *port,
+
+			_ => None,
+		}
+	}
+
+	/// Sets the port number, if this is a [`SocketAddr::Ip`] or [`SocketAddr::IpWildcard`]. Any port range is removed. `None` removes the port number, so that [`SocketAppOptions::default_port`] is used instead.
+	///
+	/// Returns true if the port number was set, or false if this is some other kind of address, in which case nothing is changed.
+	pub fn set_port(&mut self, port: Option<u16>) -> bool {
+		match self.without_options_mut() {
+			| Self::Ip { port: old_port, port_range_end, .. }
+			| Self::IpWildcard { port: old_port, port_range_end }
+			=> {
+				*old_port = port;
+				*port_range_end = None;
+				true
+			},
 
 			_ => false,
 		}
 	}
 
+	/// Returns the path of the socket, if this is a [`SocketAddr::Unix`].
+	pub fn unix_path(&self) -> Option<&Path> {
+		match self.without_options() {
+			Self::Unix { path } => Some(path),
+			_ => None,
+		}
+	}
+
+	/// Returns the name of the socket, if this is a [`SocketAddr::UnixAbstract`].
+	pub fn unix_abstract_name(&self) -> Option<&str> {
+		match self.without_options() {
+			Self::UnixAbstract { name } => Some(name),
+			_ => None,
+		}
+	}
+
+	/// Returns the inherited socket's file descriptor number or Windows `SOCKET` handle, if this is a [`SocketAddr::Inherit`] or [`SocketAddr::SystemdNumeric`].
+	///
+	/// This returns `None` for other inherited variants, like `InheritEnv` and `SystemdNamed`, because their socket isn't known until they are [opened][crate::open()].
+	pub fn inherited_fd(&self) -> Option<sys::RawSocket> {
+		match self.without_options() {
+			Self::Inherit { socket } => Some(*socket),
+
+			#[cfg(not(windows))]
+			Self::SystemdNumeric { socket } => Some(*socket),
+
+			_ => None,
+		}
+	}
+
+	/// Returns the per-address options, if this is a [`SocketAddr::WithOptions`].
+	pub fn options(&self) -> Option<&SocketAddrOptions> {
+		match self {
+			Self::WithOptions { options, .. } => Some(options),
+			_ => None,
+		}
+	}
+
+	/// Returns the address without its per-address options, if this is a [`SocketAddr::WithOptions`]. Otherwise, returns `self`.
+	///
+	/// The other accessor methods, like [`port`][Self::port], already look through `WithOptions`, so this is only needed to match on the address's variant directly.
+	pub fn without_options(&self) -> &Self {
+		match self {
+			Self::WithOptions { addr, .. } => addr.without_options(),
+			_ => self,
+		}
+	}
+
+	/// Mutable version of [`without_options`][Self::without_options].
+	pub fn without_options_mut(&mut self) -> &mut Self {
+		match self {
+			Self::WithOptions { addr, .. } => addr.without_options_mut(),
+			_ => self,
+		}
+	}
+
+	/// If this is a [`SocketAddr::Pair`], takes the other end of the socket pair most recently [opened][crate::open()] with this address.
+	///
+	/// Returns `None` if this is not a `SocketAddr::Pair`, if it has not been opened yet, or if the other end has already been taken.
+	pub fn take_pair_peer(&self) -> Option<socket2::Socket> {
+		match self {
+			Self::Pair { name } => crate::pair::take_pair_peer(name),
+			Self::WithOptions { addr, .. } => addr.take_pair_peer(),
+			_ => None,
+		}
+	}
+
 	/// Deletes the indicated path-based Unix-domain socket, if applicable.
 	///
 	/// Specifically, this method does the following:
@@ -217,8 +593,10 @@ impl SocketAddr {
 	/// [BSD syslogd]: https://svnweb.freebsd.org/base/head/usr.sbin/syslogd/syslogd.c?revision=291328&view=markup#l565
 	/// [TOCTTOU]: https://en.wikipedia.org/wiki/Time-of-check_to_time-of-use
 	pub fn cleanup(&self) -> Result<(), CleanupSocketError> {
-		if let Self::Unix { path, .. } = self {
-			cleanup_unix_path_socket(path)?;
+		match self {
+			Self::Unix { path, .. } => { cleanup_unix_path_socket(path)?; },
+			Self::WithOptions { addr, .. } => addr.cleanup()?,
+			_ => {}
 		}
 
 		Ok(())
@@ -236,10 +614,110 @@ impl SocketAddr {
 
 		match self {
 			Self::Unix { path } => do_resolve(path),
+			Self::WithOptions { addr, .. } => addr.resolve_base_dir(base_dir),
 			_ => {}
 		}
 	}
 
+	/// Checks whether this address could be [opened][crate::open()] with the given options, without actually opening anything.
+	///
+	/// This is meant for configuration validators and `--check-config` modes, so that problems can be reported before the application starts up. It checks that:
+	///
+	/// * a port number is present, or the application has a [default port][SocketAppOptions::default_port];
+	/// * a Unix-domain socket path is not too long for the platform;
+	/// * the IPv6 zone, if any, names an existing network interface;
+	/// * the kind of address is supported on this platform; and
+	/// * none of the user options are inapplicable to this kind of address.
+	///
+	/// Passing this check does not guarantee that `open` will succeed. Whether the address can be bound to, or whether an inherited socket actually exists and is of the right type, can only be checked by opening it.
+	///
+	///
+	/// # Errors
+	///
+	/// Returns the same error that `open` would return for the problem that was found.
+	pub fn validate(
+		&self,
+		app_options: &SocketAppOptions,
+		user_options: &SocketUserOptions,
+	) -> Result<(), OpenSocketError> {
+		crate::open::validate(self, app_options, user_options)
+	}
+
+	/// Parses a socket address written in the syntax of the [`tokio_listener`](https://docs.rs/tokio-listener) crate, for applications that are migrating from it and need to keep accepting existing configuration.
+	///
+	/// The following forms are translated:
+	///
+	/// * `inetd` becomes [`SocketAddr::InheritStdin`].
+	/// * `sd-listen` becomes [`SocketAddr::SystemdFirst`].
+	/// * <code>sd-listen:<var>name</var></code> becomes [`SocketAddr::SystemdNamed`].
+	/// * A path starting with `/` or `.` becomes [`SocketAddr::Unix`].
+	///
+	/// Anything else, including IP addresses and `@`-prefixed abstract Unix-domain socket names, is parsed with the usual syntax, as with [`FromStr`].
+	///
+	///
+	/// # Errors
+	///
+	/// In addition to the usual parsing errors, this raises [`InvalidSocketAddrError::UnsupportedCompat`] for forms that have no equivalent here. That includes `sd-listen:*`, which stands for all inherited sockets at once (see [`SocketAddr::all_systemd`] instead), and the `sd-listen` forms on Windows.
+	pub fn from_tokio_listener_str(s: &str) -> Result<Self, InvalidSocketAddrError> {
+		let unsupported = || InvalidSocketAddrError::UnsupportedCompat { addr: s.to_owned() };
+
+		if s == "inetd" {
+			return Ok(Self::InheritStdin);
+		}
+
+		if s == "sd-listen" {
+			cfg_if::cfg_if! {
+				if #[cfg(windows)] {
+					return Err(unsupported());
+				}
+				else {
+					return Ok(Self::SystemdFirst);
+				}
+			}
+		}
+
+		if let Some(name) = s.strip_prefix("sd-listen:") {
+			if name == "*" {
+				return Err(unsupported());
+			}
+
+			cfg_if::cfg_if! {
+				if #[cfg(windows)] {
+					return Err(unsupported());
+				}
+				else {
+					return Ok(Self::SystemdNamed { name: name.to_owned() });
+				}
+			}
+		}
+
+		if s.starts_with(['/', '.']) {
+			return Ok(Self::Unix { path: s.into() });
+		}
+
+		s.parse()
+	}
+
+	/// Creates a new [`SocketAddr::Ip`] with the IPv4 wildcard address `0.0.0.0` and the given port.
+	pub fn any_v4(port: u16) -> Self {
+		(Ipv4Addr::UNSPECIFIED, port).into()
+	}
+
+	/// Creates a new [`SocketAddr::Ip`] with the IPv6 wildcard address `[::]` and the given port.
+	pub fn any_v6(port: u16) -> Self {
+		(Ipv6Addr::UNSPECIFIED, port).into()
+	}
+
+	/// Creates a new [`SocketAddr::Ip`] with the IPv4 loopback address `127.0.0.1` and the given port.
+	pub fn loopback(port: u16) -> Self {
+		(Ipv4Addr::LOCALHOST, port).into()
+	}
+
+	/// Creates a new [`SocketAddr::Unix`] with the given path.
+	pub fn unix(path: impl Into<PathBuf>) -> Self {
+		Self::Unix { path: path.into() }
+	}
+
 	/// Creates a new [`SocketAddr::Inherit`] with the given socket.
 	///
 	/// This method exists because `SocketAddr::Inherit` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `Inherit` variant, then this method will assign reasonable default values to them.
@@ -296,6 +774,178 @@ impl SocketAddr {
 	pub fn new_systemd_numeric(socket: sys::RawSocket) -> Self {
 		Self::SystemdNumeric { socket }
 	}
+
+	/// Returns a [`SocketAddr::SystemdNumeric`] for each socket inherited from systemd socket activation, in order, according to the `LISTEN_PID` and `LISTEN_FDS` environment variables.
+	///
+	/// If the process was not started by systemd socket activation, the returned `Vec` is empty.
+	///
+	/// This is for applications that can listen on any number of sockets, and should use all of the sockets they're given.
+	///
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(not(windows))]
+	pub fn all_systemd() -> Vec<Self> {
+		match *sys::SD_LISTEN_FDS_END {
+			Some(sd_listen_fds_end) =>
+				(sys::SD_LISTEN_FDS_START..sd_listen_fds_end)
+				.map(Self::new_systemd_numeric)
+				.collect(),
+
+			None => Vec::new(),
+		}
+	}
+
+	/// Returns a [`SocketAddr::SystemdNumeric`] for each socket inherited from systemd socket activation that has the given name in the `LISTEN_FDNAMES` environment variable, in order.
+	///
+	/// Unlike [`SocketAddr::SystemdNamed`], which only refers to the first socket with a name, this finds all of them. That's useful when a socket unit has more than one `Listen…=` setting, and for sockets that were put in the systemd file descriptor store with [`systemd::store_fds`][crate::systemd::store_fds], where several sockets are commonly stored under the same name.
+	///
+	/// If there are no such sockets, the returned `Vec` is empty.
+	///
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(not(windows))]
+	pub fn all_systemd_named(name: &str) -> Vec<Self> {
+		sys::sd_listen_fds_by_name(name)
+		.into_iter()
+		.map(Self::new_systemd_numeric)
+		.collect()
+	}
+
+	/// Creates a new [`SocketAddr::SystemdNamed`] with the given socket name.
+	///
+	/// This method exists because `SocketAddr::SystemdNamed` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `SystemdNamed` variant, then this method will assign reasonable default values to them.
+	///
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(not(windows))]
+	pub fn new_systemd_named(name: impl Into<String>) -> Self {
+		Self::SystemdNamed { name: name.into() }
+	}
+
+	/// Creates a new [`SocketAddr::IpWildcard`] with the given port, if any.
+	///
+	/// This method exists because `SocketAddr::IpWildcard` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `IpWildcard` variant, then this method will assign reasonable default values to them.
+	pub fn new_ip_wildcard(port: Option<u16>) -> Self {
+		Self::IpWildcard { port, port_range_end: None }
+	}
+
+	/// Creates a new [`SocketAddr::UnixAbstract`] with the given name.
+	///
+	/// This method exists because `SocketAddr::UnixAbstract` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `UnixAbstract` variant, then this method will assign reasonable default values to them.
+	pub fn new_unix_abstract(name: impl Into<String>) -> Self {
+		Self::UnixAbstract { name: name.into() }
+	}
+
+	/// Creates a new [`SocketAddr::Vsock`] with the given CID and port.
+	///
+	/// This method exists because `SocketAddr::Vsock` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `Vsock` variant, then this method will assign reasonable default values to them.
+	pub fn new_vsock(cid: u32, port: u32) -> Self {
+		Self::Vsock { cid, port }
+	}
+
+	/// Creates a new [`SocketAddr::InheritEnv`] with the given environment variable name.
+	///
+	/// This method exists because `SocketAddr::InheritEnv` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `InheritEnv` variant, then this method will assign reasonable default values to them.
+	pub fn new_inherit_env(var: impl Into<String>) -> Self {
+		Self::InheritEnv { var: var.into() }
+	}
+
+	/// Creates a new [`SocketAddr::Launchd`] with the given socket name.
+	///
+	/// This method exists because `SocketAddr::Launchd` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `Launchd` variant, then this method will assign reasonable default values to them.
+	pub fn new_launchd(name: impl Into<String>) -> Self {
+		Self::Launchd { name: name.into() }
+	}
+
+	/// Creates a new [`SocketAddr::WsaProtocolInfo`] with the given raw `WSAPROTOCOL_INFOW` structure.
+	///
+	/// This method exists because `SocketAddr::WsaProtocolInfo` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `WsaProtocolInfo` variant, then this method will assign reasonable default values to them.
+	pub fn new_wsa_protocol_info(info: impl Into<Vec<u8>>) -> Self {
+		Self::WsaProtocolInfo { info: info.into() }
+	}
+
+	/// Creates a new [`SocketAddr::WithOptions`] with the given address and options.
+	///
+	/// This method exists because `SocketAddr::WithOptions` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `WithOptions` variant, then this method will assign reasonable default values to them.
+	pub fn new_with_options(addr: SocketAddr, options: SocketAddrOptions) -> Self {
+		Self::WithOptions { addr: Box::new(addr), options }
+	}
+
+	/// Creates a new [`SocketAddr::Pair`] with the given name.
+	///
+	/// This method exists because `SocketAddr::Pair` is marked with the `non_exhaustive` attribute, and therefore cannot be instantiated directly. If a future version of this library adds additional fields to the `Pair` variant, then this method will assign reasonable default values to them.
+	pub fn new_pair(name: impl Into<String>) -> Self {
+		Self::Pair { name: name.into() }
+	}
+}
+
+bitflags::bitflags! {
+	/// A set of kinds of [`SocketAddr`], for [`SocketAppOptions::allowed_kinds`].
+	///
+	/// Each kind corresponds to one or more `SocketAddr` variants. Use [`SocketAddr::kind`] to find out which kind an address is.
+	#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+	pub struct AddressKinds: u32 {
+		/// Internet sockets: [`SocketAddr::Ip`] and [`SocketAddr::IpWildcard`].
+		const IP = 1 << 0;
+
+		/// Unix-domain sockets: [`SocketAddr::Unix`] and [`SocketAddr::UnixAbstract`].
+		const UNIX = 1 << 1;
+
+		/// Virtual machine sockets: [`SocketAddr::Vsock`].
+		const VSOCK = 1 << 2;
+
+		/// Sockets inherited by number: [`SocketAddr::Inherit`] and [`SocketAddr::InheritEnv`].
+		const INHERIT = 1 << 3;
+
+		/// A socket inherited as standard input: [`SocketAddr::InheritStdin`].
+		const STDIN = 1 << 4;
+
+		/// Sockets inherited from systemd: `SocketAddr::SystemdFirst`, `SocketAddr::SystemdNumeric`, and `SocketAddr::SystemdNamed`.
+		const SYSTEMD = 1 << 5;
+
+		/// Sockets inherited from launchd: [`SocketAddr::Launchd`].
+		const LAUNCHD = 1 << 6;
+
+		/// Socket pairs: [`SocketAddr::Pair`].
+		const PAIR = 1 << 7;
+
+		/// Windows sockets handed off with `WSADuplicateSocketW`: [`SocketAddr::WsaProtocolInfo`].
+		const WSA_PROTOCOL_INFO = 1 << 8;
+
+		/// All kinds of inherited sockets. Applications that can't safely use a socket they didn't open themselves can leave these out.
+		const INHERITED = Self::INHERIT.bits() | Self::STDIN.bits() | Self::SYSTEMD.bits() | Self::LAUNCHD.bits() | Self::WSA_PROTOCOL_INFO.bits();
+	}
+}
+
+impl AddressKinds {
+	/// Returns a human-readable name for this kind of address, for error messages. Only meaningful if exactly one flag is set, as with the return value of [`SocketAddr::kind`].
+	pub(crate) fn name(self) -> &'static str {
+		match self {
+			Self::IP => "IP",
+			Self::UNIX => "Unix-domain socket",
+			Self::VSOCK => "vsock",
+			Self::INHERIT => "inherited socket",
+			Self::STDIN => "standard input",
+			Self::SYSTEMD => "systemd socket",
+			Self::LAUNCHD => "launchd socket",
+			Self::PAIR => "socket pair",
+			Self::WSA_PROTOCOL_INFO => "duplicated Windows socket",
+			_ => "this kind of",
+		}
+	}
+}
+
+impl Default for AddressKinds {
+	/// Returns all kinds of addresses.
+	fn default() -> Self {
+		Self::all()
+	}
 }
 
 fn str_is_unix_domain_socket_prefix(s: &str) -> bool {
@@ -324,17 +974,67 @@ impl Default for SocketAddr {
 		Self::Ip {
 			addr: Ipv4Addr::LOCALHOST.into(),
 			port: None,
+			zone: None,
+			port_range_end: None,
+		}
+	}
+}
+
+impl FromStr for SocketAddr {
+	type Err = InvalidSocketAddrError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		// See if it has a query string with options, like `./my.sock?mode=660`.
+		if let Some((addr, options)) = s.rsplit_once('?') {
+		if !options.is_empty() && options.split('&').all(|option| option.contains('=')) {
+			return Ok(Self::WithOptions {
+				addr: Box::new(addr.parse()?),
+				options: options.parse()?,
+			});
+		}}
+
+		// See if it's `stdin`.
+		if s == "stdin" {
+			return Ok(Self::InheritStdin {});
+		}
+
+		// See if it's `pair` or `pair:name`.
+		if s == "pair" {
+			return Ok(Self::Pair { name: String::new() });
+		}
+
+		if let Some(name) = s.strip_prefix("pair:") {
+			return Ok(Self::Pair { name: name.to_owned() });
+		}
+
+		// See if it's `systemd`.
+		#[cfg(not(windows))]
+		if s == "systemd" {
+			return Ok(Self::SystemdFirst);
+		}
+
+		// See if it's `systemd-name:name`.
+		#[cfg(not(windows))]
+		if let Some(name) = s.strip_prefix("systemd-name:") {
+			return Ok(Self::SystemdNamed { name: name.to_owned() });
 		}
-	}
-}
 
-impl FromStr for SocketAddr {
-	type Err = InvalidSocketAddrError;
+		// See if it's `env:name`.
+		if let Some(var) = s.strip_prefix("env:") {
+			return Ok(Self::InheritEnv { var: var.to_owned() });
+		}
 
-	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		// See if it's `stdin`.
-		if s == "stdin" {
-			return Ok(Self::InheritStdin {});
+		// See if it's `launchd:name`.
+		if let Some(name) = s.strip_prefix("launchd:") {
+			return Ok(Self::Launchd { name: name.to_owned() });
+		}
+
+		// See if it's `wsainfo:data`.
+		if let Some(info) = s.strip_prefix("wsainfo:") {
+			return match crate::base64::decode(info) {
+				Some(info) => Ok(Self::WsaProtocolInfo { info }),
+				None => Err(InvalidSocketAddrError::InvalidWsaProtocolInfo),
+			};
 		}
 
 		// See if it's `fd:n`, `socket:n`, or `systemd:n`.
@@ -394,6 +1094,25 @@ impl FromStr for SocketAddr {
 			}
 		}
 
+		// See if it's `vsock:cid:port`.
+		if let Some(vsock) = s.strip_prefix("vsock:") {
+			let (cid, port) = vsock.split_once(':').unwrap_or((vsock, ""));
+
+			let cid: u32 = match cid {
+				"any" => Self::VMADDR_CID_ANY,
+				cid => cid.parse().map_err(|error| InvalidSocketAddrError::InvalidVsock { error })?,
+			};
+
+			let port: u32 = port.parse().map_err(|error| InvalidSocketAddrError::InvalidVsock { error })?;
+
+			return Ok(Self::Vsock { cid, port });
+		}
+
+		// See if it's a Unix-domain socket in the abstract namespace.
+		if let Some(name) = s.strip_prefix('@') {
+			return Ok(Self::UnixAbstract { name: name.to_owned() });
+		}
+
 		// See if it's a Unix-domain socket with a path.
 		if str_is_unix_domain_socket_prefix(s) {
 			return Ok(Self::Unix {
@@ -401,31 +1120,134 @@ impl FromStr for SocketAddr {
 			})
 		}
 
-		// Assume anything else must be an IP address with optional port number. Try to parse it as that. If that fails, signal that the address is unrecognized.
+		// Assume anything else must be an IP address with optional port number or port range. Try to parse it as that. If that fails, signal that the address is unrecognized.
 
-		// See if it's an IP address without port number.
-		if let Ok(addr) = IpAddr::from_str(s) {
-			return Ok(addr.into());
+		// See if there's a port range, like `127.0.0.1:8000-8100`. IP addresses never contain `-`, but IPv6 zone IDs (which are often interface names) can, so this is only a port range if the `-` is between two port numbers.
+		if let Some((s, port_range_end)) = split_port_range(s) {
+			let port_range_end: u16 = port_range_end.parse().map_err(|error| InvalidSocketAddrError::InvalidPort { error })?;
+			let mut addr: Self = parse_ip(s)?;
+
+			match &mut addr {
+				| Self::Ip { port: Some(port), port_range_end: end, .. }
+				| Self::IpWildcard { port: Some(port), port_range_end: end }
+				if *port <= port_range_end
+				=> *end = Some(port_range_end),
+
+				_ => return Err(InvalidSocketAddrError::InvalidPortRange),
+			}
+
+			return Ok(addr);
 		}
 
-		// See if it's an IP address with port number.
-		match std::net::SocketAddr::from_str(s) {
-			Ok(addr) => Ok(addr.into()),
+		parse_ip(s)
+	}
+}
+
+/// Parses the IP address forms of [`SocketAddr`], without port range: `Ip` and `IpWildcard`.
+fn parse_ip(s: &str) -> Result<SocketAddr, InvalidSocketAddrError> {
+	// See if it's `*`, `*:port`, or `:port`.
+	if s == "*" {
+		return Ok(SocketAddr::IpWildcard { port: None, port_range_end: None });
+	}
 
-			// If not, then give up.
-			Err(ip_error) => Err(InvalidSocketAddrError::Unrecognized {
-				ip_error,
-			}),
+	// Careful: non-bracketed IPv6 addresses like `::1` also start with `:`.
+	if let Some(port) = s.strip_prefix("*:").or_else(|| s.strip_prefix(':').filter(|port| !port.contains(':'))) {
+		let port: u16 = port.parse().map_err(|error| InvalidSocketAddrError::InvalidPort { error })?;
+		return Ok(SocketAddr::IpWildcard { port: Some(port), port_range_end: None });
+	}
+
+	// See if it's an IPv6 address with a zone ID. The standard library doesn't parse zone IDs, so split it off first.
+	if let Some((addr, zone)) = s.split_once('%') {
+		let (addr, zone, port): (&str, &str, Option<u16>) = match addr.strip_prefix('[') {
+			Some(addr) => {
+				let (zone, port) = zone.split_once(']').ok_or(InvalidSocketAddrError::InvalidZone)?;
+
+				let port: Option<u16> = match port {
+					"" => None,
+					port => Some(
+						port.strip_prefix(':')
+						.ok_or(InvalidSocketAddrError::InvalidZone)?
+						.parse()
+						.map_err(|error| InvalidSocketAddrError::InvalidPort { error })?
+					),
+				};
+
+				(addr, zone, port)
+			},
+
+			None => (addr, zone, None),
+		};
+
+		if zone.is_empty() || zone.contains(['%', '[', ']']) {
+			return Err(InvalidSocketAddrError::InvalidZone);
 		}
+
+		let addr: Ipv6Addr =
+			addr.parse()
+			.map_err(|ip_error| InvalidSocketAddrError::Unrecognized { ip_error })?;
+
+		return Ok(SocketAddr::Ip {
+			addr: addr.into(),
+			port,
+			zone: Some(zone.to_owned()),
+			port_range_end: None,
+		});
+	}
+
+	// See if it's an IP address without port number.
+	if let Ok(addr) = IpAddr::from_str(s) {
+		return Ok(addr.into());
+	}
+
+	// See if it's an IP address with port number.
+	match std::net::SocketAddr::from_str(s) {
+		Ok(addr) => Ok(addr.into()),
+
+		// If not, then give up.
+		Err(ip_error) => Err(InvalidSocketAddrError::Unrecognized {
+			ip_error,
+		}),
+	}
+}
+
+/// If `s` ends with a port range, like `127.0.0.1:8000-8100`, then splits it into the part before the `-` and the part after.
+fn split_port_range(s: &str) -> Option<(&str, &str)> {
+	let is_port = |port: &str| !port.is_empty() && port.bytes().all(|byte| byte.is_ascii_digit());
+
+	let (first, port_range_end) = s.rsplit_once('-')?;
+	let (_, port) = first.rsplit_once(':')?;
+
+	if is_port(port) && is_port(port_range_end) {
+		Some((first, port_range_end))
+	}
+	else {
+		None
 	}
 }
 
 impl Display for SocketAddr {
 	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		match self {
-			Self::Ip { addr, port: None } => write!(f, "{addr}"),
+			Self::Ip { addr, port, zone, port_range_end } => {
+				match (port, zone) {
+					(None, None) => write!(f, "{addr}")?,
 
-			Self::Ip { addr, port: Some(port) } => write!(f, "{}", std::net::SocketAddr::new(*addr, *port)),
+					(Some(port), None) => write!(f, "{}", std::net::SocketAddr::new(*addr, *port))?,
+
+					(None, Some(zone)) => write!(f, "{addr}%{zone}")?,
+
+					(Some(port), Some(zone)) => write!(f, "[{addr}%{zone}]:{port}")?,
+				}
+
+				fmt_port_range_end(f, *port, *port_range_end)
+			},
+
+			Self::IpWildcard { port: None, .. } => write!(f, "*"),
+
+			Self::IpWildcard { port: Some(port), port_range_end } => {
+				write!(f, "*:{port}")?;
+				fmt_port_range_end(f, Some(*port), *port_range_end)
+			},
 
 			Self::Unix { path } => {
 				let path = path.to_string_lossy();
@@ -437,19 +1259,43 @@ impl Display for SocketAddr {
 				write!(f, "{path}")
 			},
 
+			Self::UnixAbstract { name } => write!(f, "@{name}"),
+
+			Self::Vsock { cid: Self::VMADDR_CID_ANY, port } => write!(f, "vsock:any:{port}"),
+			Self::Vsock { cid, port } => write!(f, "vsock:{cid}:{port}"),
+
 			#[cfg(windows)] Self::Inherit { socket } => write!(f, "socket:{socket}"),
 			#[cfg(not(windows))] Self::Inherit { socket } => write!(f, "fd:{socket}"),
 			Self::InheritStdin {} => write!(f, "stdin"),
+			#[cfg(not(windows))] Self::SystemdFirst => write!(f, "systemd"),
 			#[cfg(not(windows))] Self::SystemdNumeric { socket } => write!(f, "systemd:{socket}"),
+			#[cfg(not(windows))] Self::SystemdNamed { name } => write!(f, "systemd-name:{name}"),
+			Self::InheritEnv { var } => write!(f, "env:{var}"),
+			Self::Launchd { name } => write!(f, "launchd:{name}"),
+			Self::WsaProtocolInfo { info } => write!(f, "wsainfo:{}", crate::base64::encode(info)),
+			Self::Pair { name } if name.is_empty() => write!(f, "pair"),
+			Self::Pair { name } => write!(f, "pair:{name}"),
+			Self::WithOptions { addr, options } if options.is_empty() => write!(f, "{addr}"),
+			Self::WithOptions { addr, options } => write!(f, "{addr}?{options}"),
 		}
 	}
 }
 
+/// Writes the `-last` part of a port range, if there is one.
+fn fmt_port_range_end(f: &mut Formatter, port: Option<u16>, port_range_end: Option<u16>) -> fmt::Result {
+	match (port, port_range_end) {
+		(Some(_), Some(port_range_end)) => write!(f, "-{port_range_end}"),
+		_ => Ok(()),
+	}
+}
+
 impl From<IpAddr> for SocketAddr {
 	fn from(addr: IpAddr) -> Self {
 		Self::Ip {
 			addr,
 			port: None,
+			zone: None,
+			port_range_end: None,
 		}
 	}
 }
@@ -459,6 +1305,8 @@ impl From<Ipv4Addr> for SocketAddr {
 		Self::Ip {
 			addr: addr.into(),
 			port: None,
+			zone: None,
+			port_range_end: None,
 		}
 	}
 }
@@ -468,6 +1316,8 @@ impl From<Ipv6Addr> for SocketAddr {
 		Self::Ip {
 			addr: addr.into(),
 			port: None,
+			zone: None,
+			port_range_end: None,
 		}
 	}
 }
@@ -477,6 +1327,8 @@ impl From<SocketAddrV4> for SocketAddr {
 		Self::Ip {
 			addr: (*addr.ip()).into(),
 			port: Some(addr.port()),
+			zone: None,
+			port_range_end: None,
 		}
 	}
 }
@@ -486,16 +1338,96 @@ impl From<SocketAddrV6> for SocketAddr {
 		Self::Ip {
 			addr: (*addr.ip()).into(),
 			port: Some(addr.port()),
+			zone: match addr.scope_id() {
+				0 => None,
+				scope_id => Some(scope_id.to_string()),
+			},
+			port_range_end: None,
 		}
 	}
 }
 
 impl From<std::net::SocketAddr> for SocketAddr {
 	fn from(addr: std::net::SocketAddr) -> Self {
+		match addr {
+			std::net::SocketAddr::V4(addr) => addr.into(),
+			std::net::SocketAddr::V6(addr) => addr.into(),
+		}
+	}
+}
+
+impl<I: Into<IpAddr>> From<(I, u16)> for SocketAddr {
+	fn from((addr, port): (I, u16)) -> Self {
 		Self::Ip {
-			addr: addr.ip(),
-			port: Some(addr.port()),
+			addr: addr.into(),
+			port: Some(port),
+			zone: None,
+			port_range_end: None,
+		}
+	}
+}
+
+impl TryFrom<&socket2::SockAddr> for SocketAddr {
+	type Error = FromSockAddrError;
+
+	fn try_from(addr: &socket2::SockAddr) -> Result<Self, Self::Error> {
+		if let Some(addr) = addr.as_socket() {
+			return Ok(addr.into());
+		}
+
+		#[cfg(unix)]
+		if addr.is_unix() {
+			if let Some(path) = addr.as_pathname() {
+				return Ok(Self::Unix { path: path.to_owned() });
+			}
+
+			if let Some(name) = addr.as_abstract_namespace() {
+				return match std::str::from_utf8(name) {
+					Ok(name) => Ok(Self::UnixAbstract { name: name.to_owned() }),
+					Err(_) => Err(FromSockAddrError::InvalidUnixAbstractName),
+				};
+			}
+
+			return Err(FromSockAddrError::UnnamedUnix);
 		}
+
+		#[cfg(windows)]
+		if addr.is_unix() {
+			// `socket2` doesn't offer a way to get the path on Windows, so read it from the `sockaddr_un` directly. The path follows the 16-bit address family, and is null-terminated unless it fills the whole structure. An empty path means the address is unnamed.
+			let addr_bytes: &[u8] = unsafe {
+				// Safety: `addr.as_ptr()` points to a valid socket address structure that is `addr.len()` bytes long.
+				std::slice::from_raw_parts(addr.as_ptr() as *const u8, addr.len() as usize)
+			};
+
+			let path: &[u8] =
+				addr_bytes.get(2..).unwrap_or_default()
+				.split(|byte| *byte == 0)
+				.next().unwrap_or_default();
+
+			if path.is_empty() {
+				return Err(FromSockAddrError::UnnamedUnix);
+			}
+
+			// Windows paths in `sockaddr_un` are UTF-8.
+			return Ok(Self::Unix { path: String::from_utf8_lossy(path).into_owned().into() });
+		}
+
+		#[cfg(any(target_os = "android", target_os = "linux"))]
+		if let Some((cid, port)) = addr.as_vsock_address() {
+			return Ok(Self::Vsock { cid, port });
+		}
+
+		Err(FromSockAddrError::UnsupportedFamily {
+			domain: addr.domain(),
+		})
+	}
+}
+
+impl TryFrom<socket2::SockAddr> for SocketAddr {
+	type Error = FromSockAddrError;
+
+	fn try_from(addr: socket2::SockAddr) -> Result<Self, Self::Error> {
+		Self::try_from(&addr)
 	}
 }
 
@@ -506,18 +1438,32 @@ impl From<PathBuf> for SocketAddr {
 }
 
 #[cfg(unix)]
-impl<'a> TryFrom<&'a std::os::unix::net::SocketAddr> for SocketAddr {
+impl TryFrom<&std::os::unix::net::SocketAddr> for SocketAddr {
 	type Error = ();
 
 	fn try_from(addr: &std::os::unix::net::SocketAddr) -> Result<Self, Self::Error> {
 		if let Some(path) = addr.as_pathname() {
-			Ok(Self::Unix {
+			return Ok(Self::Unix {
 				path: path.to_owned(),
-			})
+			});
 		}
-		else {
-			Err(())
+
+		#[cfg(any(target_os = "android", target_os = "linux"))] {
+			#[cfg(target_os = "android")]
+			use std::os::android::net::SocketAddrExt;
+
+			#[cfg(target_os = "linux")]
+			use std::os::linux::net::SocketAddrExt;
+
+			if let Some(name) = addr.as_abstract_name() {
+			if let Ok(name) = std::str::from_utf8(name) {
+				return Ok(Self::UnixAbstract {
+					name: name.to_owned(),
+				});
+			}}
 		}
+
+		Err(())
 	}
 }
 
@@ -533,7 +1479,8 @@ impl TryFrom<std::os::unix::net::SocketAddr> for SocketAddr {
 	}
 }
 
-pub(crate) fn cleanup_unix_path_socket(path: &Path) -> Result<(), CleanupSocketError> {
+/// Deletes the Unix-domain socket at `path`, if there is one. Returns whether a socket was actually deleted.
+pub(crate) fn cleanup_unix_path_socket(path: &Path) -> Result<bool, CleanupSocketError> {
 	let is_unix_socket: bool =
 		is_unix_socket(path)
 		.or_else(|error| {
@@ -548,13 +1495,14 @@ pub(crate) fn cleanup_unix_path_socket(path: &Path) -> Result<(), CleanupSocketE
 		.map_err(|error| CleanupSocketError::Stat { error })?;
 
 	if is_unix_socket {
-		if let Err(error) = fs::remove_file(path) {
-		if error.kind() != io::ErrorKind::NotFound {
-			return Err(CleanupSocketError::Unlink { error });
-		}}
+		match fs::remove_file(path) {
+			Ok(()) => return Ok(true),
+			Err(error) if error.kind() == io::ErrorKind::NotFound => {},
+			Err(error) => return Err(CleanupSocketError::Unlink { error }),
+		}
 	}
 
-	Ok(())
+	Ok(false)
 }
 
 #[test]
@@ -569,6 +1517,8 @@ fn test_serde() {
 			SocketAddr::Ip {
 				addr: Ipv4Addr::LOCALHOST.into(),
 				port: Some(27910),
+				zone: None,
+				port_range_end: None,
 			},
 			"127.0.0.1:27910",
 			None,
@@ -578,6 +1528,8 @@ fn test_serde() {
 			SocketAddr::Ip {
 				addr: Ipv4Addr::LOCALHOST.into(),
 				port: None,
+				zone: None,
+				port_range_end: None,
 			},
 			"127.0.0.1",
 			None,
@@ -587,6 +1539,8 @@ fn test_serde() {
 			SocketAddr::Ip {
 				addr: Ipv4Addr::LOCALHOST.into(),
 				port: Some(0),
+				zone: None,
+				port_range_end: None,
 			},
 			"127.0.0.1:0",
 			None,
@@ -596,6 +1550,8 @@ fn test_serde() {
 			SocketAddr::Ip {
 				addr: Ipv6Addr::from(0x2607_f8b0_400a_0804_0000_0000_0000_200e_u128).into(),
 				port: Some(27910),
+				zone: None,
+				port_range_end: None,
 			},
 			"[2607:f8b0:400a:804::200e]:27910",
 			None,
@@ -605,6 +1561,8 @@ fn test_serde() {
 			SocketAddr::Ip {
 				addr: Ipv6Addr::from(0x2607_f8b0_400a_0804_0000_0000_0000_200e_u128).into(),
 				port: Some(0),
+				zone: None,
+				port_range_end: None,
 			},
 			"[2607:f8b0:400a:804::200e]:0",
 			None,
@@ -614,11 +1572,95 @@ fn test_serde() {
 			SocketAddr::Ip {
 				addr: Ipv6Addr::from(0x2607_f8b0_400a_0804_0000_0000_0000_200e_u128).into(),
 				port: None,
+				zone: None,
+				port_range_end: None,
 			},
 			"2607:f8b0:400a:804::200e",
 			None,
 		),
 
+		(
+			SocketAddr::Ip {
+				addr: Ipv6Addr::from(0xfe80_0000_0000_0000_0000_0000_0000_0001_u128).into(),
+				port: Some(27910),
+				zone: Some("eth0".into()),
+				port_range_end: None,
+			},
+			"[fe80::1%eth0]:27910",
+			None,
+		),
+
+		(
+			SocketAddr::Ip {
+				addr: Ipv6Addr::from(0xfe80_0000_0000_0000_0000_0000_0000_0001_u128).into(),
+				port: None,
+				zone: Some("2".into()),
+				port_range_end: None,
+			},
+			"fe80::1%2",
+			None,
+		),
+
+		(
+			SocketAddr::Ip {
+				addr: Ipv4Addr::LOCALHOST.into(),
+				port: Some(8000),
+				zone: None,
+				port_range_end: Some(8100),
+			},
+			"127.0.0.1:8000-8100",
+			None,
+		),
+
+		(
+			SocketAddr::Ip {
+				addr: Ipv6Addr::from(0xfe80_0000_0000_0000_0000_0000_0000_0001_u128).into(),
+				port: Some(8000),
+				zone: Some("br-1234".into()),
+				port_range_end: Some(8100),
+			},
+			"[fe80::1%br-1234]:8000-8100",
+			None,
+		),
+
+		(
+			SocketAddr::Ip {
+				addr: Ipv6Addr::from(0xfe80_0000_0000_0000_0000_0000_0000_0001_u128).into(),
+				port: None,
+				zone: Some("br-1234".into()),
+				port_range_end: None,
+			},
+			"fe80::1%br-1234",
+			None,
+		),
+
+		(
+			SocketAddr::IpWildcard {
+				port: Some(27910),
+				port_range_end: None,
+			},
+			"*:27910",
+			None,
+		),
+
+		(
+			SocketAddr::IpWildcard {
+				port: Some(8000),
+				port_range_end: Some(8100),
+			},
+			"*:8000-8100",
+			None,
+		),
+
+		(
+			SocketAddr::IpWildcard {
+				port: None,
+				port_range_end: None,
+			},
+			"*",
+			None,
+		),
+
 		(
 			// If `SocketAddr::Unix::path` is a plain relative path with no recognized prefix, a prefix will be added, and preserved upon round trip.
 			SocketAddr::Unix {
@@ -640,6 +1682,32 @@ fn test_serde() {
 			None,
 		),
 
+		(
+			SocketAddr::UnixAbstract {
+				name: "foo".into(),
+			},
+			"@foo",
+			None,
+		),
+
+		(
+			SocketAddr::Vsock {
+				cid: 3,
+				port: 1234,
+			},
+			"vsock:3:1234",
+			None,
+		),
+
+		(
+			SocketAddr::Vsock {
+				cid: SocketAddr::VMADDR_CID_ANY,
+				port: 1234,
+			},
+			"vsock:any:1234",
+			None,
+		),
+
 		(
 			SocketAddr::Inherit {
 				socket: 31337,
@@ -654,12 +1722,27 @@ fn test_serde() {
 			None,
 		),
 
+		(
+			SocketAddr::InheritEnv {
+				var: "MY_SOCKET_FD".into(),
+			},
+			"env:MY_SOCKET_FD",
+			None,
+		),
+
 		(
 			SocketAddr::InheritStdin,
 			"stdin",
 			None,
 		),
 
+		#[cfg(not(windows))]
+		(
+			SocketAddr::SystemdFirst,
+			"systemd",
+			None,
+		),
+
 		#[cfg(not(windows))]
 		(
 			SocketAddr::SystemdNumeric {
@@ -668,6 +1751,99 @@ fn test_serde() {
 			"systemd:3",
 			None,
 		),
+
+		#[cfg(not(windows))]
+		(
+			SocketAddr::SystemdNamed {
+				name: "http".into(),
+			},
+			"systemd-name:http",
+			None,
+		),
+
+		(
+			SocketAddr::Launchd {
+				name: "Listeners".into(),
+			},
+			"launchd:Listeners",
+			None,
+		),
+
+		(
+			SocketAddr::WsaProtocolInfo {
+				info: b"foobar".to_vec(),
+			},
+			"wsainfo:Zm9vYmFy",
+			None,
+		),
+
+		(
+			SocketAddr::WithOptions {
+				addr: Box::new(SocketAddr::Ip {
+					addr: Ipv4Addr::LOCALHOST.into(),
+					port: Some(27910),
+					zone: None,
+					port_range_end: None,
+				}),
+				options: SocketAddrOptions {
+					ip_socket_v6_only: Some(false),
+					listen_socket_backlog: Some(1024),
+					..Default::default()
+				},
+			},
+			"127.0.0.1:27910?v6only=false&backlog=1024",
+			None,
+		),
+
+		#[cfg(unix)]
+		(
+			SocketAddr::WithOptions {
+				addr: Box::new(SocketAddr::Unix {
+					path: abs_unix_path.clone(),
+				}),
+				options: SocketAddrOptions {
+					unix_socket_permissions: Some(nix::sys::stat::Mode::from_bits(0o660).unwrap()),
+					..Default::default()
+				},
+			},
+			&format!("{}?mode=660", abs_unix_path.display()),
+			None,
+		),
+
+		(
+			SocketAddr::WithOptions {
+				addr: Box::new(SocketAddr::InheritEnv {
+					var: "MY_SOCKET_FD".into(),
+				}),
+				options: SocketAddrOptions {
+					inherited_socket_expected_addr: Some(Box::new(SocketAddr::Ip {
+						addr: Ipv4Addr::LOCALHOST.into(),
+						port: Some(8080),
+						zone: None,
+						port_range_end: None,
+					})),
+					..Default::default()
+				},
+			},
+			"env:MY_SOCKET_FD?expect=127.0.0.1:8080",
+			None,
+		),
+
+		(
+			SocketAddr::Pair {
+				name: String::new(),
+			},
+			"pair",
+			None,
+		),
+
+		(
+			SocketAddr::Pair {
+				name: "client".into(),
+			},
+			"pair:client",
+			None,
+		),
 	] {
 		let expected_roundtrip: &SocketAddr = expected_roundtrip.as_ref().unwrap_or(&addr);
 
@@ -689,3 +1865,115 @@ fn test_serde() {
 		}
 	}
 }
+
+#[test]
+fn test_parse_ip_wildcard() {
+	assert_eq!(SocketAddr::from_str(":27910").unwrap(), SocketAddr::IpWildcard { port: Some(27910), port_range_end: None });
+	assert_matches!(SocketAddr::from_str("::1").unwrap(), SocketAddr::Ip { port: None, .. });
+	assert_matches!(SocketAddr::from_str("*:"), Err(InvalidSocketAddrError::InvalidPort { .. }));
+	assert_matches!(SocketAddr::from_str(":65536"), Err(InvalidSocketAddrError::InvalidPort { .. }));
+}
+
+#[test]
+fn test_parse_ip_zone() {
+	assert_matches!(SocketAddr::from_str("fe80::1%"), Err(InvalidSocketAddrError::InvalidZone));
+	assert_matches!(SocketAddr::from_str("[fe80::1%eth0"), Err(InvalidSocketAddrError::InvalidZone));
+	assert_matches!(SocketAddr::from_str("[fe80::1%eth0]27910"), Err(InvalidSocketAddrError::InvalidZone));
+	assert_matches!(SocketAddr::from_str("[fe80::1%eth0]:x"), Err(InvalidSocketAddrError::InvalidPort { .. }));
+	assert_matches!(SocketAddr::from_str("127.0.0.1%eth0"), Err(InvalidSocketAddrError::Unrecognized { .. }));
+}
+
+#[test]
+fn test_parse_wsa_protocol_info() {
+	assert_eq!(SocketAddr::from_str("wsainfo:").unwrap(), SocketAddr::WsaProtocolInfo { info: vec![] });
+	assert_matches!(SocketAddr::from_str("wsainfo:Zm9v!A=="), Err(InvalidSocketAddrError::InvalidWsaProtocolInfo));
+	assert_matches!(SocketAddr::from_str("wsainfo:Zm9"), Err(InvalidSocketAddrError::InvalidWsaProtocolInfo));
+}
+
+#[test]
+fn test_parse_port_range() {
+	assert_eq!(SocketAddr::from_str(":8000-8100").unwrap(), SocketAddr::IpWildcard { port: Some(8000), port_range_end: Some(8100) });
+	assert_matches!(SocketAddr::from_str("127.0.0.1:8100-8000"), Err(InvalidSocketAddrError::InvalidPortRange));
+	assert_matches!(SocketAddr::from_str("127.0.0.1:8000-65536"), Err(InvalidSocketAddrError::InvalidPort { .. }));
+}
+
+#[test]
+fn test_parse_options() {
+	assert_eq!(SocketAddr::from_str("./foo?bar").unwrap(), SocketAddr::Unix { path: "./foo?bar".into() });
+	assert_matches!(SocketAddr::from_str("./foo?bar=1"), Err(InvalidSocketAddrError::UnknownOption { key }) if key == "bar");
+	assert_matches!(SocketAddr::from_str("./foo?backlog=x"), Err(InvalidSocketAddrError::InvalidOptionValue { key, .. }) if key == "backlog");
+}
+
+#[test]
+fn test_from_tokio_listener_str() {
+	assert_eq!(SocketAddr::from_tokio_listener_str("inetd").unwrap(), SocketAddr::InheritStdin);
+	assert_eq!(SocketAddr::from_tokio_listener_str(".hidden.sock").unwrap(), SocketAddr::Unix { path: ".hidden.sock".into() });
+	assert_eq!(SocketAddr::from_tokio_listener_str("127.0.0.1:8080").unwrap(), SocketAddr::from_str("127.0.0.1:8080").unwrap());
+	assert_matches!(SocketAddr::from_tokio_listener_str("sd-listen:*"), Err(InvalidSocketAddrError::UnsupportedCompat { .. }));
+
+	#[cfg(not(windows))] {
+		assert_eq!(SocketAddr::from_tokio_listener_str("sd-listen").unwrap(), SocketAddr::SystemdFirst);
+		assert_eq!(SocketAddr::from_tokio_listener_str("sd-listen:http").unwrap(), SocketAddr::SystemdNamed { name: "http".into() });
+	}
+}
+
+#[test]
+fn test_accessors() {
+	let mut addr = SocketAddr::from_str("127.0.0.1:8000-8100?backlog=10").unwrap();
+	assert!(addr.is_ip());
+	assert!(!addr.is_unix());
+	assert_eq!(addr.ip(), Some(Ipv4Addr::LOCALHOST.into()));
+	assert_eq!(addr.port(), Some(8000));
+	assert!(addr.set_port(Some(9000)));
+	assert_eq!(addr.to_string(), "127.0.0.1:9000?backlog=10");
+
+	let mut addr = SocketAddr::from_str("./foo").unwrap();
+	assert!(addr.is_unix());
+	assert_eq!(addr.unix_path(), Some(Path::new("./foo")));
+	assert_eq!(addr.port(), None);
+	assert!(!addr.set_port(Some(9000)));
+
+	assert_eq!(SocketAddr::from_str("fd:3").unwrap().inherited_fd(), Some(3));
+}
+
+#[test]
+fn test_constructors() {
+	assert_eq!(SocketAddr::any_v4(80).to_string(), "0.0.0.0:80");
+	assert_eq!(SocketAddr::any_v6(80).to_string(), "[::]:80");
+	assert_eq!(SocketAddr::loopback(80).to_string(), "127.0.0.1:80");
+	assert_eq!(SocketAddr::from((IpAddr::from(Ipv6Addr::LOCALHOST), 80)).to_string(), "[::1]:80");
+	assert_eq!(SocketAddr::unix("/run/foo.sock").unix_path(), Some(Path::new("/run/foo.sock")));
+}
+
+#[test]
+fn test_try_from_sockaddr() {
+	let addr: socket2::SockAddr = std::net::SocketAddr::from((Ipv4Addr::LOCALHOST, 80)).into();
+	assert_eq!(SocketAddr::try_from(&addr).unwrap(), SocketAddr::loopback(80));
+
+	let addr = socket2::SockAddr::unix("./foo").unwrap();
+	assert_eq!(SocketAddr::try_from(addr).unwrap(), SocketAddr::unix("./foo"));
+
+	#[cfg(unix)] {
+		let (socket, _) = socket2::Socket::pair(socket2::Domain::UNIX, socket2::Type::STREAM, None).unwrap();
+		assert_matches!(SocketAddr::try_from(socket.local_addr().unwrap()), Err(FromSockAddrError::UnnamedUnix));
+	}
+}
+
+#[test]
+fn test_validate() {
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let mut user_options = SocketUserOptions::default();
+
+	assert_matches!(SocketAddr::from_str("127.0.0.1").unwrap().validate(&app_options, &user_options), Err(OpenSocketError::PortRequired));
+	assert_matches!(SocketAddr::from_str("127.0.0.1:80").unwrap().validate(&app_options, &user_options), Ok(()));
+
+	app_options.default_port = Some(80);
+	assert_matches!(SocketAddr::from_str("127.0.0.1").unwrap().validate(&app_options, &user_options), Ok(()));
+
+	let long_path: String = format!("./{}", "x".repeat(200));
+	assert_matches!(SocketAddr::unix(long_path).validate(&app_options, &user_options), Err(OpenSocketError::InvalidUnixPath { .. }));
+
+	user_options.ip_socket_v6_only = true;
+	assert_matches!(SocketAddr::from_str("fd:3").unwrap().validate(&app_options, &user_options), Err(OpenSocketError::InapplicableUserOption { name: "ip_socket_v6_only" }));
+	assert_matches!(SocketAddr::from_str("fd:3?v6only=false").unwrap().validate(&app_options, &user_options), Ok(()));
+}
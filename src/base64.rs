@@ -0,0 +1,88 @@
+//! Standard base64 (RFC 4648, with padding), for [`SocketAddr::WsaProtocolInfo`][crate::SocketAddr::WsaProtocolInfo]. The `base64` crate needs a newer Rust than this library does, and this is all that's needed here.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+	let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+
+	for chunk in bytes.chunks(3) {
+		let group: u32 =
+			u32::from(chunk[0]) << 16 |
+			u32::from(chunk.get(1).copied().unwrap_or(0)) << 8 |
+			u32::from(chunk.get(2).copied().unwrap_or(0));
+
+		for index in 0..4 {
+			if index <= chunk.len() {
+				encoded.push(char::from(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize]));
+			}
+			else {
+				encoded.push('=');
+			}
+		}
+	}
+
+	encoded
+}
+
+/// Decodes `s`. Returns `None` if it isn't valid base64, including if its padding is missing.
+pub(crate) fn decode(s: &str) -> Option<Vec<u8>> {
+	let s: &[u8] = s.as_bytes();
+
+	if s.len() % 4 != 0 {
+		return None;
+	}
+
+	let mut decoded = Vec::with_capacity(s.len() / 4 * 3);
+
+	for (chunk_index, chunk) in s.chunks(4).enumerate() {
+		let is_last: bool = (chunk_index + 1) * 4 == s.len();
+
+		let padding: usize = match chunk {
+			[.., b'=', b'='] if is_last => 2,
+			[.., b'='] if is_last => 1,
+			_ => 0,
+		};
+
+		let mut group: u32 = 0;
+
+		for &c in &chunk[..4 - padding] {
+			let value: u8 = match c {
+				b'A'..=b'Z' => c - b'A',
+				b'a'..=b'z' => c - b'a' + 26,
+				b'0'..=b'9' => c - b'0' + 52,
+				b'+' => 62,
+				b'/' => 63,
+				_ => return None,
+			};
+
+			group = group << 6 | u32::from(value);
+		}
+
+		group <<= 6 * padding;
+
+		decoded.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+	}
+
+	Some(decoded)
+}
+
+#[test]
+fn test_base64() {
+	for (bytes, encoded) in [
+		(&b""[..], ""),
+		(b"f", "Zg=="),
+		(b"fo", "Zm8="),
+		(b"foo", "Zm9v"),
+		(b"foob", "Zm9vYg=="),
+		(b"fooba", "Zm9vYmE="),
+		(b"foobar", "Zm9vYmFy"),
+		(b"\xfb\xff\xfe", "+//+"),
+	] {
+		assert_eq!(encode(bytes), encoded);
+		assert_eq!(decode(encoded).as_deref(), Some(bytes), "{encoded}");
+	}
+
+	for invalid in ["Zg", "Zg=", "Z===", "Zg==Zg==", "Zm9v!A==", "Zm9v\nYmFy"] {
+		assert_eq!(decode(invalid), None, "{invalid}");
+	}
+}
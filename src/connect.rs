@@ -0,0 +1,469 @@
+use crate::{
+	ConnectAppOptions,
+	ConnectHookContext,
+	ConnectUserOptions,
+	errors::OpenSocketError,
+	open,
+	open::{resolve_port, resolve_scope_id},
+	SocketAddr,
+	SocketAppOptions,
+	SocketUserOptions,
+	sys,
+};
+use socket2::Socket;
+use std::{
+	collections::VecDeque,
+	io,
+	net::{IpAddr, SocketAddrV6},
+	time::{Duration, Instant},
+};
+
+#[cfg(feature = "tokio")]
+use {
+	std::{
+		future::Future,
+		pin::Pin,
+		task::Poll,
+	},
+	tokio::net::{TcpSocket, TcpStream},
+};
+
+/// Client-side counterpart of [`open`][open()]. Opens a socket and connects it to the given address (or claims an inherited, already-connected socket).
+///
+/// This is for applications that let the user configure where to connect to, such as an upstream server or database, with the same syntax used for listening sockets. For example, `--upstream=10.0.0.1:5432`, `--upstream=./db.sock`, or `--upstream=fd:3`.
+///
+/// Stream-type sockets are connected to the peer. Datagram-type sockets are also connected, which sets the default destination for sending and filters out packets from any other source.
+///
+///
+/// # Supported addresses
+///
+/// * [`SocketAddr::Ip`], [`SocketAddr::Unix`], [`SocketAddr::UnixAbstract`], and [`SocketAddr::Vsock`] open a new socket and connect it to that address. An IP address without a port number connects to [`ConnectAppOptions::default_port`].
+/// * Inherited sockets, such as `fd:3` or `stdin`, are claimed the same way as with `open`, except that it is an error if an inherited stream-type socket is in a listening state.
+/// * [`SocketAddr::Pair`] creates a pair of connected sockets, as with `open` when [`SocketAppOptions::listen`] is false.
+/// * [`SocketAddr::WithOptions`] connects to the inner address. The options only apply to listening sockets, and are ignored.
+///
+/// [`SocketAddr::IpWildcard`] and IP addresses with a port range can only be listened on. Connecting to one results in [`OpenSocketError::Unconnectable`].
+///
+///
+/// # Example
+///
+/// ```no_run
+/// use std::io::Write;
+///
+/// # fn example_fn() -> std::io::Result<()> {
+/// let upstream_addr: socket_config::SocketAddr;
+/// let user_options: socket_config::ConnectUserOptions;
+/// # upstream_addr = unimplemented!();
+/// # user_options = unimplemented!();
+///
+/// let mut app_options = socket_config::ConnectAppOptions::new(socket2::Type::STREAM);
+/// app_options.default_port = Some(5432);
+///
+/// let mut socket: socket2::Socket = socket_config::open_connect(
+/// 	&upstream_addr,
+/// 	&app_options,
+/// 	&user_options,
+/// )?;
+///
+/// socket.write_all(b"Hello, world!\n")?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+pub fn open_connect(
+	address: &SocketAddr,
+	app_options: &ConnectAppOptions,
+	user_options: &ConnectUserOptions,
+) -> Result<Socket, OpenSocketError> {
+	let orig_address = address;
+
+	let connect_new = |address: socket2::SockAddr| -> Result<Socket, OpenSocketError> {
+		let socket: Socket = new_connect_socket(orig_address, &address, app_options, user_options)?;
+
+		match user_options.connect_timeout_ms {
+			Some(timeout) => socket.connect_timeout(&address, Duration::from_millis(timeout)),
+			None => socket.connect(&address),
+		}
+		.map_err(|error| OpenSocketError::Connect { error })?;
+
+		Ok(socket)
+	};
+
+	let socket: Socket = match address {
+		SocketAddr::Ip { port_range_end: Some(_), .. } |
+		SocketAddr::IpWildcard { .. } => return Err(OpenSocketError::Unconnectable),
+
+		SocketAddr::Ip { addr, port, zone, port_range_end: None } => {
+			let port: u16 = resolve_port(*port, app_options.default_port, app_options.r#type)?;
+			let scope_id: u32 = resolve_scope_id(*addr, zone.as_deref())?;
+
+			let addr: std::net::SocketAddr = match *addr {
+				IpAddr::V6(addr) => SocketAddrV6::new(addr, port, 0, scope_id).into(),
+				addr => std::net::SocketAddr::new(addr, port),
+			};
+
+			connect_new(addr.into())?
+		},
+
+		SocketAddr::Unix { path } => {
+			let address =
+				socket2::SockAddr::unix(path)
+				.map_err(|error| OpenSocketError::InvalidUnixPath { error })?;
+
+			connect_new(address)?
+		},
+
+		SocketAddr::UnixAbstract { name } => {
+			cfg_if::cfg_if! {
+				if #[cfg(any(target_os = "android", target_os = "linux", windows))] {
+					connect_new(crate::open::unix_abstract_sockaddr(name)?)?
+				}
+				else {
+					let _ = name;
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
+		},
+
+		SocketAddr::Vsock { cid, port } => {
+			cfg_if::cfg_if! {
+				if #[cfg(any(target_os = "android", target_os = "linux"))] {
+					connect_new(socket2::SockAddr::vsock(*cid, *port))?
+				}
+				else {
+					let _ = (cid, port);
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
+		},
+
+		SocketAddr::WithOptions { addr, options: _ } => open_connect(addr, app_options, user_options)?,
+
+		// Everything else is either inherited or a socket pair. `open` already knows how to claim those, and checks that an inherited socket is not listening when `listen` is false.
+		_ => {
+			let mut open_app_options = SocketAppOptions::new(app_options.r#type);
+			open_app_options.protocol = app_options.protocol;
+			open_app_options.listen = false;
+
+			open(address, &open_app_options, &SocketUserOptions::default())?
+		},
+	};
+
+	Ok(socket)
+}
+
+/// Connects to whichever of several candidate addresses answers first, using the “Happy Eyeballs” algorithm described in [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305).
+///
+/// This is for when a host name resolves to more than one address, typically both IPv6 and IPv4 addresses. Trying them one at a time means that an unreachable address (such as an IPv6 address on a network with broken IPv6 routing) delays the connection until the attempt times out. Instead, this function starts a connection attempt to the first candidate, and if it hasn't succeeded within [`ConnectAppOptions::connection_attempt_delay`], starts another attempt to the next candidate in parallel, and so on. The first attempt to succeed wins, and the rest are abandoned. If an attempt fails, the next one is started right away, without waiting for the delay.
+///
+/// The candidates are reordered so that IPv6 and IPv4 addresses alternate, starting with the family of the first candidate. Otherwise, the order of the candidates is kept, so they should be given in order of preference, as returned by the resolver.
+///
+/// All of the attempts are made on the calling thread, using non-blocking sockets, so the attempts that lose are closed as soon as there is a winner. [`ConnectAppOptions::before_connect`] is called before each attempt is started. [`ConnectUserOptions::connect_timeout_ms`] applies to each attempt separately. The returned socket is in blocking mode.
+///
+#[cfg_attr(feature = "tokio", doc = r#"
+See [`open_connect_happy_eyeballs_tokio`] for an asynchronous version of this function."#)]
+///
+///
+/// # Example
+///
+/// ```no_run
+/// use std::net::ToSocketAddrs;
+///
+/// # fn example_fn() -> std::io::Result<()> {
+/// let app_options = socket_config::ConnectAppOptions::new(socket2::Type::STREAM);
+/// let user_options = socket_config::ConnectUserOptions::default();
+///
+/// let socket: socket2::Socket = socket_config::open_connect_happy_eyeballs(
+/// 	("example.com", 80).to_socket_addrs()?,
+/// 	&app_options,
+/// 	&user_options,
+/// )?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+///
+///
+/// # Errors
+///
+/// If every attempt fails, or there are no candidates, returns [`OpenSocketError::ConnectAttemptsFailed`]. If a socket can't be created, or `before_connect` fails, that error is returned right away, without trying any more candidates.
+pub fn open_connect_happy_eyeballs(
+	candidates: impl IntoIterator<Item = std::net::SocketAddr>,
+	app_options: &ConnectAppOptions,
+	user_options: &ConnectUserOptions,
+) -> Result<Socket, OpenSocketError> {
+	struct Attempt {
+		candidate: std::net::SocketAddr,
+		socket: Socket,
+		deadline: Option<Instant>,
+	}
+
+	let mut candidates: VecDeque<std::net::SocketAddr> = interleave_address_families(candidates);
+	let timeout: Option<Duration> = user_options.connect_timeout_ms.map(Duration::from_millis);
+
+	let mut attempts: Vec<Attempt> = Vec::new();
+	let mut errors: Vec<(std::net::SocketAddr, io::Error)> = Vec::new();
+	let mut next_attempt_at: Instant = Instant::now();
+
+	// Once a winner is picked, it's put back into blocking mode. The other attempts' sockets are closed when `attempts` is dropped, which abandons them.
+	let finish = |socket: Socket| -> Result<Socket, OpenSocketError> {
+		socket.set_nonblocking(false)
+		.map_err(|error| OpenSocketError::Connect { error })?;
+
+		Ok(socket)
+	};
+
+	loop {
+		let now = Instant::now();
+
+		// Start another attempt if there are none in progress, or if the last one was started long enough ago, or failed.
+		if !candidates.is_empty() && (attempts.is_empty() || now >= next_attempt_at) {
+			let candidate: std::net::SocketAddr = candidates.pop_front().unwrap();
+			let address: socket2::SockAddr = candidate.into();
+			let socket: Socket = new_connect_socket(&candidate.into(), &address, app_options, user_options)?;
+
+			socket.set_nonblocking(true)
+			.map_err(|error| OpenSocketError::CreateSocket { error })?;
+
+			next_attempt_at = now + app_options.connection_attempt_delay;
+
+			match socket.connect(&address) {
+				// Some kinds of sockets, such as datagram sockets, connect right away.
+				Ok(()) => return finish(socket),
+
+				Err(error) if sys::is_connect_in_progress(&error) => attempts.push(Attempt {
+					candidate,
+					socket,
+					deadline: timeout.map(|timeout| now + timeout),
+				}),
+
+				Err(error) => {
+					errors.push((candidate, error));
+					next_attempt_at = now;
+				},
+			}
+
+			continue;
+		}
+
+		if attempts.is_empty() {
+			return Err(OpenSocketError::ConnectAttemptsFailed { errors });
+		}
+
+		// Give up on attempts that have taken too long.
+		attempts.retain(|attempt| match attempt.deadline {
+			Some(deadline) if now >= deadline => {
+				errors.push((attempt.candidate, io::ErrorKind::TimedOut.into()));
+				next_attempt_at = now;
+				false
+			},
+
+			_ => true,
+		});
+
+		// Wait for an attempt to finish, or until it's time to start another attempt or give up on one.
+		let wake_at: Option<Instant> =
+			attempts.iter()
+			.filter_map(|attempt| attempt.deadline)
+			.chain((!candidates.is_empty()).then_some(next_attempt_at))
+			.min();
+
+		let sockets: Vec<&Socket> = attempts.iter().map(|attempt| &attempt.socket).collect();
+
+		let ready: Vec<bool> =
+			sys::poll_writable(&sockets, wake_at.map(|wake_at| wake_at.saturating_duration_since(now)))
+			.map_err(|error| OpenSocketError::Connect { error })?;
+
+		let mut index: usize = 0;
+		let mut ready = ready.into_iter();
+
+		while index < attempts.len() {
+			if ready.next() != Some(true) {
+				index += 1;
+				continue;
+			}
+
+			let attempt: Attempt = attempts.remove(index);
+
+			match attempt.socket.take_error() {
+				Ok(None) => return finish(attempt.socket),
+
+				Ok(Some(error)) | Err(error) => {
+					errors.push((attempt.candidate, error));
+
+					// Start the next attempt right away, instead of waiting for the delay to elapse.
+					next_attempt_at = Instant::now();
+				},
+			}
+		}
+	}
+}
+
+/// Asynchronous version of [`open_connect_happy_eyeballs`], for use with [`tokio`].
+///
+/// Unlike the blocking version, this only makes TCP connections, so [`ConnectAppOptions::type`] must be [`socket2::Type::STREAM`]. Any other type results in [`OpenSocketError::UnsupportedConnectType`]. All of the sockets are created, and [`ConnectAppOptions::before_connect`] is called for each of them, before this function returns. The returned future doesn't borrow `app_options` or `user_options`, and is `Send`, so it can be spawned as a task.
+///
+///
+/// # Availability
+///
+/// Requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+pub fn open_connect_happy_eyeballs_tokio(
+	candidates: impl IntoIterator<Item = std::net::SocketAddr>,
+	app_options: &ConnectAppOptions,
+	user_options: &ConnectUserOptions,
+) -> impl Future<Output = Result<TcpStream, OpenSocketError>> + Send + 'static {
+	let sockets: Result<VecDeque<(std::net::SocketAddr, TcpSocket)>, OpenSocketError> = (|| {
+		if app_options.r#type != socket2::Type::STREAM {
+			return Err(OpenSocketError::UnsupportedConnectType {
+				actual: app_options.r#type,
+			});
+		}
+
+		interleave_address_families(candidates)
+		.into_iter()
+		.map(|candidate| {
+			let socket: Socket = new_connect_socket(&candidate.into(), &candidate.into(), app_options, user_options)?;
+
+			socket.set_nonblocking(true)
+			.map_err(|error| OpenSocketError::CreateSocket { error })?;
+
+			Ok((candidate, TcpSocket::from_std_stream(socket.into())))
+		})
+		.collect()
+	})();
+
+	let delay: Duration = app_options.connection_attempt_delay;
+	let timeout: Option<Duration> = user_options.connect_timeout_ms.map(Duration::from_millis);
+
+	async move {
+		type Attempt = Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send>>;
+
+		let mut sockets = sockets?;
+		let mut attempts: Vec<(std::net::SocketAddr, Attempt)> = Vec::new();
+		let mut errors: Vec<(std::net::SocketAddr, io::Error)> = Vec::new();
+		let mut next_attempt_timer = Box::pin(tokio::time::sleep(delay));
+
+		let start_attempt = |attempts: &mut Vec<(std::net::SocketAddr, Attempt)>, (candidate, socket): (std::net::SocketAddr, TcpSocket)| {
+			let attempt: Attempt = match timeout {
+				Some(timeout) => Box::pin(async move {
+					tokio::time::timeout(timeout, socket.connect(candidate)).await
+					.unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+				}),
+
+				None => Box::pin(socket.connect(candidate)),
+			};
+
+			attempts.push((candidate, attempt));
+		};
+
+		std::future::poll_fn(move |cx| loop {
+			// Start another attempt if there are none in progress, or if the last one was started long enough ago.
+			if !sockets.is_empty() && (attempts.is_empty() || next_attempt_timer.as_mut().poll(cx).is_ready()) {
+				start_attempt(&mut attempts, sockets.pop_front().unwrap());
+				next_attempt_timer.as_mut().reset(tokio::time::Instant::now() + delay);
+				continue;
+			}
+
+			let mut any_failed: bool = false;
+			let mut index: usize = 0;
+
+			while index < attempts.len() {
+				match attempts[index].1.as_mut().poll(cx) {
+					Poll::Ready(Ok(stream)) => return Poll::Ready(Ok(stream)),
+
+					Poll::Ready(Err(error)) => {
+						let (candidate, _) = attempts.remove(index);
+						errors.push((candidate, error));
+						any_failed = true;
+					},
+
+					Poll::Pending => index += 1,
+				}
+			}
+
+			if attempts.is_empty() && sockets.is_empty() {
+				return Poll::Ready(Err(OpenSocketError::ConnectAttemptsFailed {
+					errors: std::mem::take(&mut errors),
+				}));
+			}
+
+			// If an attempt failed, start the next one right away, instead of waiting for the delay to elapse.
+			if any_failed && !sockets.is_empty() {
+				start_attempt(&mut attempts, sockets.pop_front().unwrap());
+				next_attempt_timer.as_mut().reset(tokio::time::Instant::now() + delay);
+				continue;
+			}
+
+			return Poll::Pending;
+		}).await
+	}
+}
+
+/// Creates a socket for connecting to `address`, and calls [`ConnectAppOptions::before_connect`] on it.
+fn new_connect_socket(
+	address: &SocketAddr,
+	sockaddr: &socket2::SockAddr,
+	app_options: &ConnectAppOptions,
+	user_options: &ConnectUserOptions,
+) -> Result<Socket, OpenSocketError> {
+	sys::startup_socket_api();
+
+	let mut socket: Socket =
+		Socket::new(sockaddr.domain(), app_options.r#type, app_options.protocol)
+		.map_err(|error| OpenSocketError::CreateSocket { error })?;
+
+	if let Some(before_connect) = &app_options.before_connect {
+		let hook_context = ConnectHookContext {
+			address,
+			sockaddr,
+			user_options,
+		};
+
+		before_connect(&mut socket, &hook_context)
+		.map_err(OpenSocketError::BeforeConnect)?;
+	}
+
+	Ok(socket)
+}
+
+/// Reorders connection candidates so that IPv6 and IPv4 addresses alternate, starting with the family of the first candidate, as described in [RFC 8305 section 4](https://www.rfc-editor.org/rfc/rfc8305#section-4). The relative order of the addresses in each family is kept.
+fn interleave_address_families(candidates: impl IntoIterator<Item = std::net::SocketAddr>) -> VecDeque<std::net::SocketAddr> {
+	let mut candidates = candidates.into_iter().peekable();
+
+	let first_is_ipv6: bool = match candidates.peek() {
+		Some(candidate) => candidate.is_ipv6(),
+		None => return VecDeque::new(),
+	};
+
+	let (mut first_family, mut second_family): (VecDeque<_>, VecDeque<_>) =
+		candidates.partition(|candidate| candidate.is_ipv6() == first_is_ipv6);
+
+	let mut interleaved = VecDeque::with_capacity(first_family.len() + second_family.len());
+
+	loop {
+		match (first_family.pop_front(), second_family.pop_front()) {
+			(None, None) => break,
+			(first, second) => interleaved.extend(first.into_iter().chain(second)),
+		}
+	}
+
+	interleaved
+}
+
+#[test]
+fn test_interleave_address_families() {
+	let candidates: Vec<std::net::SocketAddr> = vec![
+		"[2001:db8::1]:80".parse().unwrap(),
+		"[2001:db8::2]:80".parse().unwrap(),
+		"[2001:db8::3]:80".parse().unwrap(),
+		"192.0.2.1:80".parse().unwrap(),
+	];
+
+	assert_eq!(
+		Vec::from(interleave_address_families(candidates.clone())),
+		vec![candidates[0], candidates[3], candidates[1], candidates[2]],
+	);
+
+	assert!(interleave_address_families([]).is_empty());
+}
@@ -5,6 +5,76 @@ use crate::sys;
 use socket2::Socket;
 use std::io;
 
+cfg_if! {
+	if #[cfg(feature = "async-std")] {
+		mod async_std;
+		pub use self::async_std::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(feature = "tokio")] {
+		mod idle_timeout;
+		pub use self::idle_timeout::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(all(feature = "io-uring", target_os = "linux"))] {
+		mod uring;
+		pub use self::uring::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(all(feature = "mio", unix))] {
+		mod mio;
+		pub use self::mio::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(feature = "native-tls")] {
+		mod native_tls;
+		pub use self::native_tls::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(feature = "quinn")] {
+		mod quinn;
+		pub use self::quinn::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(feature = "rustls")] {
+		mod rustls;
+		pub use self::rustls::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(feature = "tokio")] {
+		mod serve;
+		pub use self::serve::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(feature = "smol")] {
+		mod smol;
+		pub use self::smol::*;
+	}
+}
+
+cfg_if! {
+	if #[cfg(feature = "tls-listener")] {
+		pub(crate) mod tls;
+		pub use self::tls::*;
+	}
+}
+
 cfg_if! {
 	if #[cfg(feature = "tokio")] {
 		mod tokio;
@@ -26,7 +96,7 @@ cfg_if! {
 /// # use std::io;
 /// # fn example_fn() -> io::Result<()> {
 /// # let address: socket_config::SocketAddr = unimplemented!();
-/// # let app_options: socket_config::SocketAppOptions<'static> = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
 /// # let user_options: socket_config::SocketUserOptions = unimplemented!();
 /// let socket: AnyStdSocket = socket_config::open(
 /// 	&address,
@@ -46,16 +116,28 @@ cfg_if! {
 ///
 /// Listening sockets are mapped to the `TcpListener` or `UnixListener` variant, and connected sockets are mapped to the `TcpStream` or `UnixStream` variant. Sockets that are neither listening nor connected are mapped to the `Other` variant.
 ///
-/// **Warning:** On platforms other than AIX, Android, FreeBSD, Fuchsia, Linux, and Windows, it is not possible to check whether a socket is listening. It is therefore **assumed** on such platforms that a non-connected socket is a listening socket. Sockets that are neither listening nor connected will not be properly detected on such platforms.
+/// **Warning:** On platforms other than AIX, Android, DragonFly BSD, FreeBSD, Fuchsia, iOS, Linux, macOS, NetBSD, OpenBSD, and Windows, it is not possible to check whether a socket is listening. It is therefore **assumed** on such platforms that a non-connected socket is a listening socket. Sockets that are neither listening nor connected will not be properly detected on such platforms.
 ///
 ///
 /// # Transport protocol checking
 ///
 /// When converting a socket to this type, if the socket's domain is [IPv4][socket2::Domain::IPV4] or [IPv6][socket2::Domain::IPV6], and if the platform is Android, FreeBSD, Fuchsia, Linux, or Windows, then the conversion checks the transport protocol of Internet-domain sockets, and maps the socket to the appropriate variant. If the transport protocol is neither TCP nor UDP, then the socket is mapped to the `Other` variant.
 ///
+/// This means that sockets using other transport protocols, such as UDP-Lite, DCCP, and SCTP, are mapped to the `Other` variant, even though UDP-Lite sockets are datagram-type and DCCP and SCTP sockets can be listening or connected. Such sockets can be extracted with [`AnyStdSocket::into_other`] and used directly.
+///
 /// **Warning:** On platforms other than Android, FreeBSD, Fuchsia, Linux, and Windows, it is not possible to check the transport protocol of a socket. It is therefore **assumed** on such platforms that IPv4/IPv6-domain [stream][socket2::Type::STREAM] and [datagram][socket2::Type::DGRAM] sockets are TCP and UDP, respectively. This assumption is usually but not always correct; for example, an IPv4 stream-type socket is probably TCP, but it might be SCTP.
 ///
 ///
+/// # Raw sockets
+///
+/// [Raw sockets][socket2::Type::RAW] are always mapped to the `Other` variant, even if their protocol is TCP or UDP. Use [`AnyStdSocket::into_other`] to extract them.
+///
+///
+/// # Other address families
+///
+/// Sockets in address families that the standard library has no socket types for, such as [vsock][crate::SocketAddr::Vsock], are always mapped to the `Other` variant. Use [`AnyStdSocket::into_other`] to extract them.
+///
+///
 /// # Availability
 ///
 /// All platforms, but the variants starting with `Unix` are only available on Unix-like platforms.
@@ -156,7 +238,7 @@ impl TryFrom<Socket> for AnyStdSocket {
 				false,
 			) | (
 				_,
-				_,
+				socket2::Type::STREAM,
 				Some(socket2::Protocol::TCP),
 				None | Some(true),
 				false,
@@ -170,7 +252,7 @@ impl TryFrom<Socket> for AnyStdSocket {
 				true,
 			) | (
 				_,
-				_,
+				socket2::Type::STREAM,
 				Some(socket2::Protocol::TCP),
 				Some(false),
 				true,
@@ -184,7 +266,7 @@ impl TryFrom<Socket> for AnyStdSocket {
 				_,
 			) | (
 				_,
-				_,
+				socket2::Type::DGRAM,
 				Some(socket2::Protocol::UDP),
 				_,
 				_,
@@ -217,11 +299,34 @@ impl TryFrom<Socket> for AnyStdSocket {
 				_,
 			) => Self::UnixDatagram(socket.into()),
 
+			// Everything else, including UDP-Lite, DCCP, SCTP, and vsock sockets, has no standard library equivalent.
 			_ => Self::Other(socket),
 		})
 	}
 }
 
+impl AnyStdSocket {
+	/// Returns a reference to the socket, if this is the [`Other`][Self::Other] variant.
+	pub fn as_other(&self) -> Option<&Socket> {
+		match self {
+			Self::Other(socket) => Some(socket),
+			_ => None,
+		}
+	}
+
+	/// Extracts the socket, if this is the [`Other`][Self::Other] variant. Otherwise, returns `self` unchanged in the `Err` variant.
+	///
+	/// Sockets that the standard library has no type for, such as [raw sockets][socket2::Type::RAW], are converted to the `Other` variant. This method can be used to get them back out.
+	///
+	/// To get a `Socket` from any variant, not just `Other`, use the `From<AnyStdSocket>` implementation for [`socket2::Socket`] instead.
+	pub fn into_other(self) -> Result<Socket, Self> {
+		match self {
+			Self::Other(socket) => Ok(socket),
+			_ => Err(self),
+		}
+	}
+}
+
 impl From<AnyStdSocket> for Socket {
 	fn from(socket: AnyStdSocket) -> Self {
 		match socket {
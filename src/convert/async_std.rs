@@ -0,0 +1,444 @@
+use async_std::io::{Read, Write};
+use crate::{
+	convert::AnyStdSocket,
+	errors::IntoAsyncStdError,
+};
+
+#[cfg(doc)]
+use crate::SocketUserOptions;
+use pin_project::pin_project;
+use socket2::{SockAddr, Socket};
+use std::{
+	io,
+	pin::Pin,
+	task,
+};
+
+#[cfg(unix)]
+use {
+	crate::{PeerCredentials, UnixPeerAcl},
+	std::path::Path,
+};
+
+#[cfg(windows)]
+use std::os::windows::io::{AsRawSocket, AsSocket, BorrowedSocket, RawSocket};
+
+#[cfg(not(windows))]
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+#[cfg(unix)]
+fn unix_sockaddr_into(addr: async_std::os::unix::net::SocketAddr) -> SockAddr {
+	let pathname =
+		addr.as_pathname()
+		.unwrap_or(Path::new(""));
+
+	SockAddr::unix(pathname)
+	.expect("unexpected error constructing a Unix-domain socket address that's already known to be valid")
+}
+
+/// A [stream-type][socket2::Type::STREAM] listening socket, either TCP or Unix-domain, adapted for use with [`async_std`].
+///
+/// This is the async-std counterpart of [`AnyTokioListener`][crate::convert::AnyTokioListener]. Much like [`async_std::net::TcpListener`], an `AnyAsyncStdListener` is used to accept connections using the [`accept`][Self::accept] method.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::{AnyAsyncStdListener, AnyAsyncStdStream};
+/// # use std::io;
+/// # async fn example_fn() -> io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// let socket: AnyAsyncStdListener = socket_config::open(
+/// 	&address,
+/// 	&app_options,
+/// 	&user_options,
+/// )?.try_into()?;
+///
+/// loop {
+/// 	let (connection, peer_addr): (AnyAsyncStdStream, socket2::SockAddr) =
+/// 		socket.accept().await?;
+///
+/// 	// …do something with the connection…
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The call to `try_into` will fail with an [`IntoAsyncStdError`] if the socket is inappropriate, such as a UDP socket. That includes listening sockets using transport protocols other than TCP, such as SCTP, and sockets in address families other than IP and Unix-domain, such as vsock; async-std has no socket types for those.
+///
+///
+/// # Availability
+///
+/// All platforms, but the `Unix` variant is only available on Unix-like platforms. Converting a Unix-domain socket on Windows will result in an error.
+///
+/// Requires the `async-std` feature.
+#[derive(Debug, derive_more::From)]
+#[non_exhaustive]
+pub enum AnyAsyncStdListener {
+	/// A TCP listening socket.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	Tcp(async_std::net::TcpListener),
+
+	/// A Unix-domain [stream-type][socket2::Type::STREAM] listening socket.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only. async-std does not support Unix-domain sockets on Windows.
+	#[cfg(unix)] Unix(async_std::os::unix::net::UnixListener),
+}
+
+impl AnyAsyncStdListener {
+	/// Accepts a new connection.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_std::net::TcpListener::accept`] or [`async_std::os::unix::net::UnixListener::accept`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_std::net::TcpListener::accept`]."#)]
+	///
+	/// If this is a TCP listening socket with `TCP_NODELAY` set (such as with [`SocketUserOptions::tcp_nodelay`]), then `TCP_NODELAY` is also set on the accepted connection, since not every operating system does that automatically.
+	pub async fn accept(&self) -> io::Result<(AnyAsyncStdStream, SockAddr)> {
+		match self {
+			Self::Tcp(l) => {
+				let (socket, addr) = l.accept().await?;
+
+				// Not every operating system copies `TCP_NODELAY` from the listening socket to accepted connections, so copy it here. If the listening socket can't be checked, assume it doesn't have `TCP_NODELAY`.
+				if socket2::SockRef::from(l).nodelay().unwrap_or(false) {
+					socket.set_nodelay(true)?;
+				}
+
+				Ok((socket.into(), addr.into()))
+			},
+
+			#[cfg(unix)] Self::Unix(l) => {
+				let (socket, addr) = l.accept().await?;
+				Ok((socket.into(), unix_sockaddr_into(addr)))
+			},
+		}
+	}
+
+	/// Accepts a new connection, like [`accept`][Self::accept], and checks the connecting process's credentials against `acl`.
+	///
+	/// This works the same way as [`accept_allowed`][crate::accept_allowed()], including how errors are reported. In particular, a connection from a peer that isn't allowed to connect results in an error, which the application should log and then keep accepting connections.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)]
+	pub async fn accept_allowed(&self, acl: &UnixPeerAcl) -> io::Result<(AnyAsyncStdStream, SockAddr, Option<PeerCredentials>)> {
+		let (stream, peer_addr) = self.accept().await?;
+		let credentials: Option<PeerCredentials> = acl.check_accepted(&socket2::SockRef::from(&stream), &peer_addr)?;
+		Ok((stream, peer_addr, credentials))
+	}
+
+	/// Returns the local address that this listener is bound to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_std::net::TcpListener::local_addr`] or [`async_std::os::unix::net::UnixListener::local_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_std::net::TcpListener::local_addr`]."#)]
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Tcp(l) => l.local_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(l) => l.local_addr().map(unix_sockaddr_into),
+		}
+	}
+}
+
+impl TryFrom<AnyStdSocket> for AnyAsyncStdListener {
+	type Error = IntoAsyncStdError;
+
+	fn try_from(socket: AnyStdSocket) -> Result<Self, Self::Error> {
+		match socket {
+			AnyStdSocket::TcpListener(l) => {
+				l.set_nonblocking(true)
+				.map_err(|error| IntoAsyncStdError::SetNonBlocking { error })?;
+
+				Ok(Self::Tcp(l.into()))
+			}
+
+			#[cfg(unix)]
+			AnyStdSocket::UnixListener(l) => {
+				l.set_nonblocking(true)
+				.map_err(|error| IntoAsyncStdError::SetNonBlocking { error })?;
+
+				Ok(Self::Unix(l.into()))
+			}
+
+			_ => Err(IntoAsyncStdError::Inappropriate {
+				socket,
+			}),
+		}
+	}
+}
+
+impl TryFrom<Socket> for AnyAsyncStdListener {
+	type Error = IntoAsyncStdError;
+
+	fn try_from(socket: Socket) -> Result<Self, Self::Error> {
+		let socket: AnyStdSocket =
+			socket.try_into()
+			.map_err(|error| IntoAsyncStdError::Check { error })?;
+
+		socket.try_into()
+	}
+}
+
+impl TryFrom<AnyAsyncStdListener> for Socket {
+	type Error = io::Error;
+
+	fn try_from(l: AnyAsyncStdListener) -> Result<Self, Self::Error> {
+		match l {
+			AnyAsyncStdListener::Tcp(l) => std::net::TcpListener::try_from(l).map(Socket::from),
+			#[cfg(unix)] AnyAsyncStdListener::Unix(l) => std::os::unix::net::UnixListener::try_from(l).map(Socket::from),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsFd for AnyAsyncStdListener {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		match self {
+			Self::Tcp(l) => l.as_fd(),
+			#[cfg(unix)] Self::Unix(l) => l.as_fd(),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsRawFd for AnyAsyncStdListener {
+	fn as_raw_fd(&self) -> RawFd {
+		match self {
+			Self::Tcp(l) => l.as_raw_fd(),
+			#[cfg(unix)] Self::Unix(l) => l.as_raw_fd(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsRawSocket for AnyAsyncStdListener {
+	fn as_raw_socket(&self) -> RawSocket {
+		match self {
+			Self::Tcp(l) => l.as_raw_socket(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsSocket for AnyAsyncStdListener {
+	fn as_socket(&self) -> BorrowedSocket<'_> {
+		match self {
+			Self::Tcp(l) => l.as_socket(),
+		}
+	}
+}
+
+/// A connected [stream-type][socket2::Type::STREAM] socket, either TCP or Unix-domain, adapted for use with [`async_std`].
+///
+/// `AnyAsyncStdStream`s are usually obtained from a call to [`AnyAsyncStdListener::accept`]. This type implements [`Read`] and [`Write`], and is used to communicate with the connected peer in much the same way as an [`async_std::net::TcpStream`].
+///
+/// Converting an `AnyAsyncStdStream` back into a [`Socket`] fails if there are other handles to the same async-std stream, such as a clone of it.
+///
+///
+/// # Availability
+///
+/// All platforms, but the `Unix` variant is only available on Unix-like platforms. Converting a Unix-domain socket on Windows will result in an error.
+///
+/// Requires the `async-std` feature.
+#[derive(Debug, derive_more::From)]
+#[pin_project(project = AnyAsyncStdStreamProj)]
+pub enum AnyAsyncStdStream {
+	/// A connected TCP socket.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	Tcp(#[pin] async_std::net::TcpStream),
+
+	/// A connected Unix-domain [stream-type][socket2::Type::STREAM] socket.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only. async-std does not support Unix-domain sockets on Windows.
+	#[cfg(unix)] Unix(#[pin] async_std::os::unix::net::UnixStream),
+}
+
+impl AnyAsyncStdStream {
+	/// Returns the local address that this socket is bound to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_std::net::TcpStream::local_addr`] or [`async_std::os::unix::net::UnixStream::local_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_std::net::TcpStream::local_addr`]."#)]
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Tcp(s) => s.local_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(s) => s.local_addr().map(unix_sockaddr_into),
+		}
+	}
+
+	/// Returns the remote address that this socket is connected to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_std::net::TcpStream::peer_addr`] or [`async_std::os::unix::net::UnixStream::peer_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_std::net::TcpStream::peer_addr`]."#)]
+	pub fn peer_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Tcp(s) => s.peer_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(s) => s.peer_addr().map(unix_sockaddr_into),
+		}
+	}
+}
+
+impl Read for AnyAsyncStdStream {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		buf: &mut [u8],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnyAsyncStdStreamProj::Tcp(s) => s.poll_read(cx, buf),
+			#[cfg(unix)] AnyAsyncStdStreamProj::Unix(s) => s.poll_read(cx, buf),
+		}
+	}
+
+	fn poll_read_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		bufs: &mut [io::IoSliceMut],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnyAsyncStdStreamProj::Tcp(s) => s.poll_read_vectored(cx, bufs),
+			#[cfg(unix)] AnyAsyncStdStreamProj::Unix(s) => s.poll_read_vectored(cx, bufs),
+		}
+	}
+}
+
+impl Write for AnyAsyncStdStream {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		buf: &[u8],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnyAsyncStdStreamProj::Tcp(s) => s.poll_write(cx, buf),
+			#[cfg(unix)] AnyAsyncStdStreamProj::Unix(s) => s.poll_write(cx, buf),
+		}
+	}
+
+	fn poll_write_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		bufs: &[io::IoSlice],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnyAsyncStdStreamProj::Tcp(s) => s.poll_write_vectored(cx, bufs),
+			#[cfg(unix)] AnyAsyncStdStreamProj::Unix(s) => s.poll_write_vectored(cx, bufs),
+		}
+	}
+
+	fn poll_flush(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+	) -> task::Poll<io::Result<()>> {
+		match self.project() {
+			AnyAsyncStdStreamProj::Tcp(s) => s.poll_flush(cx),
+			#[cfg(unix)] AnyAsyncStdStreamProj::Unix(s) => s.poll_flush(cx),
+		}
+	}
+
+	fn poll_close(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+	) -> task::Poll<io::Result<()>> {
+		match self.project() {
+			AnyAsyncStdStreamProj::Tcp(s) => s.poll_close(cx),
+			#[cfg(unix)] AnyAsyncStdStreamProj::Unix(s) => s.poll_close(cx),
+		}
+	}
+}
+
+impl TryFrom<AnyStdSocket> for AnyAsyncStdStream {
+	type Error = IntoAsyncStdError;
+
+	fn try_from(socket: AnyStdSocket) -> Result<Self, Self::Error> {
+		match socket {
+			AnyStdSocket::TcpStream(s) => {
+				s.set_nonblocking(true)
+				.map_err(|error| IntoAsyncStdError::SetNonBlocking { error })?;
+
+				Ok(Self::Tcp(s.into()))
+			}
+
+			#[cfg(unix)]
+			AnyStdSocket::UnixStream(s) => {
+				s.set_nonblocking(true)
+				.map_err(|error| IntoAsyncStdError::SetNonBlocking { error })?;
+
+				Ok(Self::Unix(s.into()))
+			}
+
+			_ => Err(IntoAsyncStdError::Inappropriate {
+				socket,
+			}),
+		}
+	}
+}
+
+impl TryFrom<Socket> for AnyAsyncStdStream {
+	type Error = IntoAsyncStdError;
+
+	fn try_from(socket: Socket) -> Result<Self, Self::Error> {
+		let socket: AnyStdSocket =
+			socket.try_into()
+			.map_err(|error| IntoAsyncStdError::Check { error })?;
+
+		socket.try_into()
+	}
+}
+
+impl TryFrom<AnyAsyncStdStream> for Socket {
+	type Error = io::Error;
+
+	fn try_from(socket: AnyAsyncStdStream) -> Result<Self, Self::Error> {
+		match socket {
+			AnyAsyncStdStream::Tcp(s) => std::net::TcpStream::try_from(s).map(Socket::from),
+			#[cfg(unix)] AnyAsyncStdStream::Unix(s) => std::os::unix::net::UnixStream::try_from(s).map(Socket::from),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsFd for AnyAsyncStdStream {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		match self {
+			Self::Tcp(s) => s.as_fd(),
+			#[cfg(unix)] Self::Unix(s) => s.as_fd(),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsRawFd for AnyAsyncStdStream {
+	fn as_raw_fd(&self) -> RawFd {
+		match self {
+			Self::Tcp(s) => s.as_raw_fd(),
+			#[cfg(unix)] Self::Unix(s) => s.as_raw_fd(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsRawSocket for AnyAsyncStdStream {
+	fn as_raw_socket(&self) -> RawSocket {
+		match self {
+			Self::Tcp(s) => s.as_raw_socket(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsSocket for AnyAsyncStdStream {
+	fn as_socket(&self) -> BorrowedSocket<'_> {
+		match self {
+			Self::Tcp(s) => s.as_socket(),
+		}
+	}
+}
@@ -0,0 +1,251 @@
+use pin_project::pin_project;
+use std::{
+	future::Future,
+	io,
+	pin::Pin,
+	task,
+	time::Duration,
+};
+use tokio::{
+	io::{AsyncRead, AsyncWrite, ReadBuf},
+	time::{Instant, Sleep},
+};
+
+/// Wraps a connection, such as an [`AnyTokioStream`][crate::convert::AnyTokioStream], and fails it if nothing is sent or received on it for too long.
+///
+/// This protects a server from clients that connect and then stop responding, or that deliberately send and receive as slowly as possible, holding the connection open forever. Without protection like this, enough such clients can exhaust the server's memory or file descriptors.
+///
+/// The timer starts when a read or write has to wait, and stops each time the wrapped connection finishes a read or a write. If the timer runs out, the read or write that is waiting fails with [`io::ErrorKind::TimedOut`], and so do all later reads and writes. The application should then drop the connection, which closes it.
+///
+/// The timer doesn't run while no read or write is waiting, so a connection isn't failed while the application itself is busy, such as while it's waiting for a slow backend to answer.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::{AnyTokioListener, IdleTimeout};
+/// # use std::time::Duration;
+/// # use tokio::io::AsyncReadExt;
+/// # async fn example_fn() -> std::io::Result<()> {
+/// # let listener: AnyTokioListener = unimplemented!();
+/// let (connection, _) = listener.accept().await?;
+/// let mut connection = IdleTimeout::new(connection, Duration::from_secs(60));
+///
+/// let mut request = Vec::new();
+/// connection.read_to_end(&mut request).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Requires the `tokio` feature.
+#[derive(Debug)]
+#[pin_project(project = IdleTimeoutProj)]
+pub struct IdleTimeout<S> {
+	#[pin]
+	inner: S,
+	timeout: Option<Duration>,
+	waiting_since: Option<Instant>,
+	sleep: Option<Pin<Box<Sleep>>>,
+	timed_out: bool,
+}
+
+impl<S> IdleTimeout<S> {
+	/// Wraps `inner`, failing it after waiting `timeout` for a read or write to finish.
+	pub fn new(inner: S, timeout: Duration) -> Self {
+		Self::with_timeout(inner, Some(timeout))
+	}
+
+	/// Wraps `inner`, with an optional timeout. If `timeout` is `None`, there is no timeout, and this wrapper does nothing. This is convenient when the timeout is an optional setting of the application.
+	pub fn with_timeout(inner: S, timeout: Option<Duration>) -> Self {
+		Self {
+			inner,
+			timeout,
+			waiting_since: None,
+			sleep: None,
+			timed_out: false,
+		}
+	}
+
+	/// Returns the timeout, or `None` if there is none.
+	pub fn timeout(&self) -> Option<Duration> {
+		self.timeout
+	}
+
+	/// Returns whether the timeout has run out, which makes all reads and writes fail.
+	pub fn is_timed_out(&self) -> bool {
+		self.timed_out
+	}
+
+	/// Returns a reference to the wrapped connection.
+	pub fn get_ref(&self) -> &S {
+		&self.inner
+	}
+
+	/// Returns a mutable reference to the wrapped connection.
+	///
+	/// Reads and writes done directly on the wrapped connection don't restart the timer.
+	pub fn get_mut(&mut self) -> &mut S {
+		&mut self.inner
+	}
+
+	/// Unwraps the connection.
+	pub fn into_inner(self) -> S {
+		self.inner
+	}
+}
+
+impl<S> IdleTimeoutProj<'_, S> {
+	/// Handles the result of polling the wrapped connection: stops the timer if the operation finished, or starts or checks the timer if it's waiting.
+	fn poll_activity<T>(&mut self, cx: &mut task::Context<'_>, poll: task::Poll<io::Result<T>>) -> task::Poll<io::Result<T>> {
+		let task::Poll::Pending = poll
+		else {
+			*self.waiting_since = None;
+			return poll;
+		};
+
+		let Some(timeout) = *self.timeout
+		else { return task::Poll::Pending };
+
+		let deadline: Instant = *self.waiting_since.get_or_insert_with(Instant::now) + timeout;
+
+		let sleep: &mut Pin<Box<Sleep>> = self.sleep.get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+
+		if sleep.deadline() != deadline {
+			sleep.as_mut().reset(deadline);
+		}
+
+		task::ready!(sleep.as_mut().poll(cx));
+
+		*self.timed_out = true;
+		task::Poll::Ready(Err(timed_out_error()))
+	}
+}
+
+fn timed_out_error() -> io::Error {
+	io::Error::new(io::ErrorKind::TimedOut, "the connection was idle for too long")
+}
+
+impl<S: AsyncRead> AsyncRead for IdleTimeout<S> {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		buf: &mut ReadBuf,
+	) -> task::Poll<io::Result<()>> {
+		let mut this = self.project();
+
+		if *this.timed_out {
+			return task::Poll::Ready(Err(timed_out_error()));
+		}
+
+		let poll = this.inner.as_mut().poll_read(cx, buf);
+		this.poll_activity(cx, poll)
+	}
+}
+
+impl<S: AsyncWrite> AsyncWrite for IdleTimeout<S> {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context<'_>,
+		buf: &[u8],
+	) -> task::Poll<io::Result<usize>> {
+		let mut this = self.project();
+
+		if *this.timed_out {
+			return task::Poll::Ready(Err(timed_out_error()));
+		}
+
+		let poll = this.inner.as_mut().poll_write(cx, buf);
+		this.poll_activity(cx, poll)
+	}
+
+	fn poll_write_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context<'_>,
+		bufs: &[io::IoSlice<'_>],
+	) -> task::Poll<io::Result<usize>> {
+		let mut this = self.project();
+
+		if *this.timed_out {
+			return task::Poll::Ready(Err(timed_out_error()));
+		}
+
+		let poll = this.inner.as_mut().poll_write_vectored(cx, bufs);
+		this.poll_activity(cx, poll)
+	}
+
+	fn is_write_vectored(&self) -> bool {
+		self.inner.is_write_vectored()
+	}
+
+	fn poll_flush(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context<'_>,
+	) -> task::Poll<io::Result<()>> {
+		let mut this = self.project();
+
+		if *this.timed_out {
+			return task::Poll::Ready(Err(timed_out_error()));
+		}
+
+		let poll = this.inner.as_mut().poll_flush(cx);
+		this.poll_activity(cx, poll)
+	}
+
+	fn poll_shutdown(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context<'_>,
+	) -> task::Poll<io::Result<()>> {
+		// Shutting down is allowed even after the timeout, since it's how the application would close the connection politely.
+		self.project().inner.poll_shutdown(cx)
+	}
+}
+
+#[test]
+fn test_idle_timeout() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async {
+		let (client, mut server) = tokio::io::duplex(64);
+		let mut client = IdleTimeout::new(client, Duration::from_millis(100));
+		let mut buf = [0u8; 1];
+
+		// Activity restarts the timer, so this takes longer than the timeout, but doesn't time out.
+		for _ in 0..3 {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			server.write_all(b"x").await.unwrap();
+			client.read_exact(&mut buf).await.unwrap();
+		}
+
+		// The timer doesn't run while the application is busy between reads, so this doesn't time out either, even though the read has to wait.
+		tokio::time::sleep(Duration::from_millis(200)).await;
+
+		let reader = async {
+			client.read_exact(&mut buf).await.unwrap();
+		};
+		let writer = async {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			server.write_all(b"x").await.unwrap();
+		};
+		tokio::join!(reader, writer);
+
+		// Without activity, the connection times out.
+		let error = client.read(&mut buf).await.unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+		assert!(client.is_timed_out());
+
+		// It stays that way, even if there's data to read.
+		server.write_all(b"x").await.unwrap();
+		assert_eq!(client.read(&mut buf).await.unwrap_err().kind(), io::ErrorKind::TimedOut);
+		assert_eq!(client.write(b"x").await.unwrap_err().kind(), io::ErrorKind::TimedOut);
+	});
+
+	assert_eq!(IdleTimeout::with_timeout((), None).timeout(), None);
+	assert_eq!(IdleTimeout::with_timeout((), Some(Duration::from_millis(1500))).timeout(), Some(Duration::from_millis(1500)));
+}
@@ -0,0 +1,181 @@
+use crate::convert::AnyStdSocket;
+use mio::{
+	event::Source,
+	unix::SourceFd,
+	Interest,
+	Registry,
+	Token,
+};
+use socket2::Socket;
+use std::{
+	io,
+	ops::Deref,
+	os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+};
+
+/// A [`socket2::Socket`] that can be registered with a [`mio::Poll`], for applications with their own event loops.
+///
+/// This is a thin wrapper, which dereferences to the `Socket`, so all of its methods can be used directly. It implements [`mio::event::Source`] by registering the socket's file descriptor, the same way as [`mio::unix::SourceFd`].
+///
+/// The socket is put in non-blocking mode when it's wrapped, as `mio` requires. Wrapping it fails only if that fails.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::MioSocket;
+/// # use std::io;
+/// # fn example_fn() -> io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// let mut socket: MioSocket = socket_config::open(
+/// 	&address,
+/// 	&app_options,
+/// 	&user_options,
+/// )?.try_into()?;
+///
+/// let mut poll = mio::Poll::new()?;
+/// poll.registry().register(&mut socket, mio::Token(0), mio::Interest::READABLE)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only. On Windows, `mio` can only register its own socket types.
+///
+/// Requires the `mio` feature.
+#[derive(Debug)]
+pub struct MioSocket(Socket);
+
+impl MioSocket {
+	/// Unwraps the socket. It's left in non-blocking mode.
+	///
+	/// If the socket is still registered with a [`mio::Poll`], it should be deregistered first.
+	pub fn into_inner(self) -> Socket {
+		self.0
+	}
+}
+
+impl TryFrom<Socket> for MioSocket {
+	type Error = io::Error;
+
+	fn try_from(socket: Socket) -> Result<Self, Self::Error> {
+		socket.set_nonblocking(true)?;
+		Ok(Self(socket))
+	}
+}
+
+impl TryFrom<AnyStdSocket> for MioSocket {
+	type Error = io::Error;
+
+	fn try_from(socket: AnyStdSocket) -> Result<Self, Self::Error> {
+		Socket::from(socket).try_into()
+	}
+}
+
+impl From<MioSocket> for Socket {
+	fn from(socket: MioSocket) -> Self {
+		socket.into_inner()
+	}
+}
+
+impl Deref for MioSocket {
+	type Target = Socket;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl AsFd for MioSocket {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		self.0.as_fd()
+	}
+}
+
+impl AsRawFd for MioSocket {
+	fn as_raw_fd(&self) -> RawFd {
+		self.0.as_raw_fd()
+	}
+}
+
+impl Source for MioSocket {
+	fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+		SourceFd(&self.0.as_raw_fd()).register(registry, token, interests)
+	}
+
+	fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+		SourceFd(&self.0.as_raw_fd()).reregister(registry, token, interests)
+	}
+
+	fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+		SourceFd(&self.0.as_raw_fd()).deregister(registry)
+	}
+}
+
+/// Registers the underlying socket, whichever variant this is, the same way as [`MioSocket`].
+///
+/// Unlike `MioSocket`, converting to an `AnyStdSocket` doesn't put the socket in non-blocking mode. Be sure to do that before registering it, such as with [`TcpListener::set_nonblocking`][std::net::TcpListener::set_nonblocking].
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+///
+/// Requires the `mio` feature.
+impl Source for AnyStdSocket {
+	fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+		SourceFd(&any_std_socket_fd(self)).register(registry, token, interests)
+	}
+
+	fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+		SourceFd(&any_std_socket_fd(self)).reregister(registry, token, interests)
+	}
+
+	fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+		SourceFd(&any_std_socket_fd(self)).deregister(registry)
+	}
+}
+
+fn any_std_socket_fd(socket: &AnyStdSocket) -> RawFd {
+	match socket {
+		AnyStdSocket::TcpListener(s) => s.as_raw_fd(),
+		AnyStdSocket::TcpStream(s) => s.as_raw_fd(),
+		AnyStdSocket::UdpSocket(s) => s.as_raw_fd(),
+		AnyStdSocket::UnixDatagram(s) => s.as_raw_fd(),
+		AnyStdSocket::UnixListener(s) => s.as_raw_fd(),
+		AnyStdSocket::UnixStream(s) => s.as_raw_fd(),
+		AnyStdSocket::Other(s) => s.as_raw_fd(),
+	}
+}
+
+#[test]
+fn test_mio_socket() {
+	use mio::{Events, Poll};
+	use std::time::Duration;
+
+	let listener: Socket = std::net::TcpListener::bind("127.0.0.1:0").unwrap().into();
+	let mut listener = MioSocket::try_from(listener).unwrap();
+	assert!(listener.nonblocking().unwrap());
+
+	let mut poll = Poll::new().unwrap();
+	let mut events = Events::with_capacity(4);
+	poll.registry().register(&mut listener, Token(1), Interest::READABLE).unwrap();
+
+	let _client = std::net::TcpStream::connect(listener.local_addr().unwrap().as_socket().unwrap()).unwrap();
+
+	poll.poll(&mut events, Some(Duration::from_secs(5))).unwrap();
+	assert!(events.iter().any(|event| event.token() == Token(1) && event.is_readable()));
+	listener.accept().unwrap();
+
+	poll.registry().deregister(&mut listener).unwrap();
+
+	// An `AnyStdSocket` can be registered too.
+	let mut socket = AnyStdSocket::try_from(listener.into_inner()).unwrap();
+	assert!(matches!(socket, AnyStdSocket::TcpListener(_)));
+	poll.registry().register(&mut socket, Token(2), Interest::READABLE).unwrap();
+	poll.registry().reregister(&mut socket, Token(3), Interest::READABLE).unwrap();
+	poll.registry().deregister(&mut socket).unwrap();
+}
@@ -0,0 +1,80 @@
+use crate::{
+	convert::{tls::{open_maybe_tls, tls_cert_and_key}, MaybeTlsListener},
+	errors::{OpenTlsError, TlsConfigError},
+	SocketAddr,
+	SocketAppOptions,
+	SocketUserOptions,
+	NativeTlsUserOptions,
+};
+use native_tls::{Identity, TlsAcceptor};
+use std::path::Path;
+
+/// Builds a native-tls [`TlsAcceptor`] from `tls_options`: [`tls_cert`][NativeTlsUserOptions::tls_cert] and [`tls_key`][NativeTlsUserOptions::tls_key]. Returns `None` if the user didn't configure TLS, that is, if neither of those options is set.
+///
+/// This uses the operating system's TLS library: SChannel on Windows, Secure Transport on macOS, and OpenSSL elsewhere. That's for applications that must use the operating system's TLS implementation, such as to comply with a security policy. Otherwise, `rustls_server_config` (with the `rustls` feature) is usually the better choice.
+///
+/// The certificate and key files are read by this function. The key must be in PKCS#8 format.
+///
+///
+/// # Errors
+///
+/// See [`TlsConfigError`].
+///
+///
+/// # Availability
+///
+/// All platforms except iOS and similar (but not macOS), where native-tls can't load a private key from a PEM file.
+///
+/// Requires the `native-tls` feature.
+pub fn native_tls_acceptor(tls_options: &NativeTlsUserOptions) -> Result<Option<TlsAcceptor>, TlsConfigError> {
+	let Some((cert_path, key_path)) = tls_cert_and_key(tls_options.tls_cert.as_deref(), tls_options.tls_key.as_deref())?
+	else { return Ok(None) };
+
+	let cert_chain: Vec<u8> = read_file("tls_cert", cert_path)?;
+	let key: Vec<u8> = read_file("tls_key", key_path)?;
+
+	#[allow(deprecated)] // Deprecated only on platforms where it's not available. See “Availability” above.
+	let identity =
+		Identity::from_pkcs8(&cert_chain, &key)
+		.map_err(|error| TlsConfigError::NativeTls { error })?;
+
+	let acceptor =
+		TlsAcceptor::new(identity)
+		.map_err(|error| TlsConfigError::NativeTls { error })?;
+
+	Ok(Some(acceptor))
+}
+
+fn read_file(option: &'static str, path: &Path) -> Result<Vec<u8>, TlsConfigError> {
+	std::fs::read(path)
+	.map_err(|error| TlsConfigError::ReadFile { option, path: path.to_owned(), error })
+}
+
+/// Like `open_tls` (with the `rustls` feature), but uses native-tls instead of rustls. See [`native_tls_acceptor`] for details.
+///
+/// This opens a listening socket with [`open`][crate::open()], and sets up TLS on it if the user configured it. If not, the returned listener accepts plaintext connections instead.
+///
+/// This must be called from within a Tokio runtime, the same as converting a socket to an [`AnyTokioListener`][crate::convert::AnyTokioListener].
+///
+///
+/// # Errors
+///
+/// See [`OpenTlsError`].
+///
+///
+/// # Availability
+///
+/// Same as `native_tls_acceptor`.
+///
+/// Requires the `native-tls` feature.
+pub fn open_native_tls(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+	tls_options: &NativeTlsUserOptions,
+) -> Result<MaybeTlsListener<tokio_native_tls::TlsAcceptor>, OpenTlsError> {
+	// Check the TLS configuration first, so that a mistake in it doesn't leave a socket behind, such as a Unix-domain socket file.
+	let acceptor: Option<TlsAcceptor> = native_tls_acceptor(tls_options)?;
+
+	open_maybe_tls(address, app_options, user_options, acceptor.map(tokio_native_tls::TlsAcceptor::from))
+}
@@ -0,0 +1,109 @@
+use crate::convert::AnyStdSocket;
+use socket2::Socket;
+use std::io;
+
+#[cfg(doc)]
+use crate::{SocketPreset, SocketUserOptions};
+
+/// Prepares a UDP socket, such as one opened by [`open`][crate::open()], for use with [quinn](::quinn), and converts it to the [`std::net::UdpSocket`] that [`quinn::Endpoint::new`] expects.
+///
+/// This way, a QUIC server can let the user choose its address and socket options the same way as for any other socket. Consider using [`SocketPreset::QuicServer`] for the [`SocketAppOptions`][crate::SocketAppOptions].
+///
+/// The socket is prepared as follows:
+///
+/// * It is put into non-blocking mode. quinn requires that, but doesn't do it itself.
+/// * Socket-wide UDP generic segmentation offload (`UDP_SEGMENT`) is turned off, if the user turned it on with [`SocketUserOptions::udp_segment_size`]. quinn already uses segmentation offload where the operating system supports it, but requests it for each batch of datagrams that it sends. A socket-wide segment size would also apply to the datagrams that quinn sends one at a time, splitting any that are larger than it.
+/// * UDP generic receive offload (`UDP_GRO`) is turned on, whether or not the user turned it on with [`SocketUserOptions::udp_gro`]. quinn understands the coalesced datagrams that this produces. If the kernel is too old to support it, this is silently skipped.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::quinn_socket;
+/// # use std::sync::Arc;
+/// # fn example_fn() -> std::io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// # let endpoint_config: quinn::EndpointConfig = unimplemented!();
+/// # let server_config: quinn::ServerConfig = unimplemented!();
+/// let socket = socket_config::open(&address, &app_options, &user_options)?;
+///
+/// let endpoint = quinn::Endpoint::new(
+/// 	endpoint_config,
+/// 	Some(server_config),
+/// 	quinn_socket(socket)?,
+/// 	Arc::new(quinn::TokioRuntime),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+///
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] if `socket` is not a UDP socket, or with any I/O error raised by the operating system in preparing it.
+///
+///
+/// # Availability
+///
+/// All platforms. The offload settings are only changed on Linux, since other platforms don't have them.
+///
+/// Requires the `quinn` feature.
+pub fn quinn_socket(socket: Socket) -> io::Result<std::net::UdpSocket> {
+	let socket: std::net::UdpSocket = match AnyStdSocket::try_from(socket)? {
+		AnyStdSocket::UdpSocket(socket) => socket,
+		_ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "quinn requires a UDP socket")),
+	};
+
+	socket.set_nonblocking(true)?;
+
+	#[cfg(target_os = "linux")] {
+		let socket = socket2::SockRef::from(&socket);
+
+		if crate::sys::udp_segment(&socket).unwrap_or(0) != 0 {
+			crate::sys::set_udp_segment(&socket, 0)?;
+		}
+
+		match crate::sys::set_udp_gro(&socket, true) {
+			Err(error) if error.raw_os_error() == Some(libc::ENOPROTOOPT) => {}
+			result => result?,
+		}
+	}
+
+	Ok(socket)
+}
+
+#[test]
+fn test_quinn_socket() {
+	use quinn::Runtime;
+
+	let socket: Socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap().into();
+	let local_addr = socket.local_addr().unwrap().as_socket().unwrap();
+
+	#[cfg(target_os = "linux")]
+	if crate::sys::set_udp_segment(&socket, 1200).is_ok() {
+		let socket = quinn_socket(socket.try_clone().unwrap()).unwrap();
+		assert_eq!(crate::sys::udp_segment(&socket2::SockRef::from(&socket)).unwrap(), 0);
+	}
+
+	let socket = quinn_socket(socket).unwrap();
+	assert_eq!(socket.local_addr().unwrap(), local_addr);
+
+	#[cfg(unix)]
+	assert!(socket2::SockRef::from(&socket).nonblocking().unwrap());
+
+	// This is what `quinn::Endpoint::new` does with the socket.
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let socket = quinn::TokioRuntime.wrap_udp_socket(socket).unwrap();
+		assert_eq!(socket.local_addr().unwrap(), local_addr);
+	});
+
+	// A TCP socket isn't a UDP socket.
+	let tcp: Socket = std::net::TcpListener::bind("127.0.0.1:0").unwrap().into();
+	assert_eq!(quinn_socket(tcp).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
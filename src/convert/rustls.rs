@@ -0,0 +1,167 @@
+use crate::{
+	convert::{tls::{open_maybe_tls, tls_cert_and_key}, MaybeTlsListener},
+	errors::{OpenTlsError, TlsConfigError},
+	SocketAddr,
+	SocketAppOptions,
+	SocketUserOptions,
+	TlsUserOptions,
+};
+use rustls::{
+	crypto::CryptoProvider,
+	pki_types::{
+		pem::PemObject,
+		CertificateDer,
+		PrivateKeyDer,
+	},
+	server::WebPkiClientVerifier,
+	RootCertStore,
+	ServerConfig,
+};
+use std::{
+	io,
+	path::Path,
+	sync::Arc,
+};
+use tokio_rustls::TlsAcceptor;
+
+/// Builds a rustls [`ServerConfig`] from `tls_options`: [`tls_cert`][TlsUserOptions::tls_cert], [`tls_key`][TlsUserOptions::tls_key], and [`tls_client_ca`][TlsUserOptions::tls_client_ca]. Returns `None` if the user didn't configure TLS, that is, if none of those options is set.
+///
+/// The certificate and key files are read by this function. The returned configuration has no ALPN protocols; set [`ServerConfig::alpn_protocols`] on it if the application needs them, such as `h2` for HTTP/2.
+///
+/// This uses the process-wide default rustls [`CryptoProvider`] if one has been installed, or else the `ring` provider.
+///
+///
+/// # Errors
+///
+/// See [`TlsConfigError`].
+///
+///
+/// # Availability
+///
+/// Requires the `rustls` feature.
+pub fn rustls_server_config(tls_options: &TlsUserOptions) -> Result<Option<ServerConfig>, TlsConfigError> {
+	if tls_options.tls_cert.is_none() && tls_options.tls_client_ca.is_some() {
+		return Err(TlsConfigError::MissingCert { name: "tls_client_ca" });
+	}
+
+	let Some((cert_path, key_path)) = tls_cert_and_key(tls_options.tls_cert.as_deref(), tls_options.tls_key.as_deref())?
+	else { return Ok(None) };
+
+	let cert_chain: Vec<CertificateDer<'static>> = read_certs("tls_cert", cert_path)?;
+
+	let key =
+		PrivateKeyDer::from_pem_file(key_path)
+		.map_err(|error| TlsConfigError::ReadFile { option: "tls_key", path: key_path.to_owned(), error: pem_error_into(error) })?;
+
+	let provider: Arc<CryptoProvider> =
+		CryptoProvider::get_default()
+		.cloned()
+		.unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()));
+
+	let builder =
+		ServerConfig::builder_with_provider(provider.clone())
+		.with_safe_default_protocol_versions()
+		.map_err(|error| TlsConfigError::Rustls { error })?;
+
+	let builder = match &tls_options.tls_client_ca {
+		Some(ca_path) => {
+			let mut roots = RootCertStore::empty();
+
+			for cert in read_certs("tls_client_ca", ca_path)? {
+				roots.add(cert)
+				.map_err(|error| TlsConfigError::Rustls { error })?;
+			}
+
+			let verifier =
+				WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+				.build()
+				.map_err(|error| TlsConfigError::ClientCa { path: ca_path.clone(), error })?;
+
+			builder.with_client_cert_verifier(verifier)
+		}
+
+		None => builder.with_no_client_auth(),
+	};
+
+	let config =
+		builder.with_single_cert(cert_chain, key)
+		.map_err(|error| TlsConfigError::Rustls { error })?;
+
+	Ok(Some(config))
+}
+
+/// Reads all of the certificates in a PEM file. Fails if there are none.
+fn read_certs(option: &'static str, path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsConfigError> {
+	let read_file_error = |error| TlsConfigError::ReadFile { option, path: path.to_owned(), error: pem_error_into(error) };
+
+	let certs: Vec<CertificateDer<'static>> =
+		CertificateDer::pem_file_iter(path)
+		.map_err(read_file_error)?
+		.collect::<Result<_, _>>()
+		.map_err(read_file_error)?;
+
+	if certs.is_empty() {
+		return Err(read_file_error(rustls::pki_types::pem::Error::NoItemsFound));
+	}
+
+	Ok(certs)
+}
+
+fn pem_error_into(error: rustls::pki_types::pem::Error) -> io::Error {
+	match error {
+		rustls::pki_types::pem::Error::Io(error) => error,
+		error => io::Error::new(io::ErrorKind::InvalidData, error),
+	}
+}
+
+/// Opens a listening socket with [`open`][crate::open()], and sets up TLS on it if the user configured it.
+///
+/// TLS is configured with `tls_options`, as described in [`rustls_server_config`]. If they're not set, the returned listener accepts plaintext connections instead. This way, turning on TLS is a matter of configuration, like everything else about the socket.
+///
+/// The listener uses the default settings of [`AnyTlsListener`][crate::convert::AnyTlsListener]. For different settings, such as a different handshake timeout or ALPN protocols, use `open` and `rustls_server_config` directly, and build the listener with [`AnyTlsListener::builder`][crate::convert::AnyTlsListener::builder].
+///
+/// This must be called from within a Tokio runtime, the same as converting a socket to an [`AnyTokioListener`][crate::convert::AnyTokioListener].
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::open_tls;
+/// # use tokio::io::AsyncWriteExt;
+/// # async fn example_fn() -> std::io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// # let tls_options: socket_config::TlsUserOptions = unimplemented!();
+/// let mut listener = open_tls(&address, &app_options, &user_options, &tls_options)?;
+///
+/// loop {
+/// 	let Ok((mut connection, _)) = listener.accept().await
+/// 	else { continue };
+///
+/// 	// The connection is used the same way, whether it's TLS or not.
+/// 	connection.write_all(b"Hello, world!\n").await?;
+/// }
+/// # }
+/// ```
+///
+///
+/// # Errors
+///
+/// See [`OpenTlsError`].
+///
+///
+/// # Availability
+///
+/// Requires the `rustls` feature.
+pub fn open_tls(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+	tls_options: &TlsUserOptions,
+) -> Result<MaybeTlsListener<TlsAcceptor>, OpenTlsError> {
+	// Check the TLS configuration first, so that a mistake in it doesn't leave a socket behind, such as a Unix-domain socket file.
+	let config: Option<ServerConfig> = rustls_server_config(tls_options)?;
+
+	open_maybe_tls(address, app_options, user_options, config.map(|config| TlsAcceptor::from(Arc::new(config))))
+}
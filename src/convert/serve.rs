@@ -0,0 +1,402 @@
+use crate::{
+	convert::{AnyTokioListener, AnyTokioStream},
+	sys,
+};
+use socket2::{SockAddr, Socket};
+use std::{
+	fmt::{self, Debug, Formatter},
+	future::Future,
+	io,
+	num::{NonZeroU32, NonZeroUsize},
+	pin::{pin, Pin},
+	sync::Arc,
+	task,
+	time::Duration,
+};
+use tokio::{
+	task::JoinSet,
+	time::{Instant, Sleep},
+};
+
+type AcceptErrorHook = Arc<dyn Fn(&io::Error) + Send + Sync>;
+
+/// What [`serve`] does when a new connection arrives while the [maximum number of connections][ServeBuilder::max_connections] are already being served.
+///
+/// # Availability
+///
+/// Requires the `tokio` feature.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ConnectionLimitPolicy {
+	/// Stop accepting connections until one of the connections being served finishes. This is the default.
+	///
+	/// New connections wait in the listening socket's backlog in the meantime. Once the backlog is full, the operating system refuses or ignores further connection attempts, depending on the platform.
+	#[default]
+	Wait,
+
+	/// Accept the new connection and close it right away, without calling the handler. The client sees the connection closed before receiving anything.
+	Close,
+}
+
+/// Accepts connections from `listener` and serves each one with `handler` in its own task, until `shutdown` completes. Then stops accepting, and waits for connections that are still being served to finish.
+///
+/// This is the accept loop that nearly every server needs, with graceful shutdown. It uses the default settings of [`ServeBuilder`]; for different settings, such as a different drain timeout, use that instead.
+///
+/// `handler` is called with each accepted connection and its peer's address, and the future it returns is spawned as a Tokio task. When `shutdown` completes, such as when the process receives a termination signal, `listener` is closed right away with [`close_and_drain`][AnyTokioListener::close_and_drain], so new connections are refused (or, with [`SocketUserOptions::ip_socket_reuse_port`][crate::SocketUserOptions::ip_socket_reuse_port], go to another process that's listening on the same port). Connections that were already waiting to be accepted are still accepted and served. Connections that are being served are given up to the [drain timeout][ServeBuilder::drain_timeout] to finish, after which their tasks are aborted.
+///
+/// Errors in accepting connections don't stop the loop, since they usually only affect one connection. They can be observed with [`ServeBuilder::inspect_accept_errors`]. Errors that mean the process has run out of file descriptors or some other resource pause accepting for a moment instead, as described in [`ServeBuilder::accept_error_backoff`].
+///
+/// Returns the number of connections whose tasks had to be aborted because they didn't finish within the drain timeout.
+///
+/// This must be called from within a Tokio runtime.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::{serve, AnyTokioListener};
+/// # use tokio::io::AsyncWriteExt;
+/// # async fn example_fn() -> std::io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// # let shutdown_signal = std::future::pending::<()>();
+/// let listener: AnyTokioListener = socket_config::open(
+/// 	&address,
+/// 	&app_options,
+/// 	&user_options,
+/// )?.try_into()?;
+///
+/// serve(
+/// 	listener,
+/// 	|mut connection, _peer_addr| async move {
+/// 		let _ = connection.write_all(b"Hello, world!\n").await;
+/// 	},
+/// 	shutdown_signal,
+/// ).await;
+/// # Ok(())
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Requires the `tokio` feature.
+pub async fn serve<H, F>(
+	listener: AnyTokioListener,
+	handler: H,
+	shutdown: impl Future<Output = ()>,
+) -> usize
+where
+	H: FnMut(AnyTokioStream, SockAddr) -> F,
+	F: Future<Output = ()> + Send + 'static,
+{
+	ServeBuilder::new().serve(listener, handler, shutdown).await
+}
+
+/// Builder for [`serve`], with settings other than the defaults.
+///
+/// # Availability
+///
+/// Requires the `tokio` feature.
+#[derive(Clone)]
+#[must_use]
+pub struct ServeBuilder {
+	drain_timeout: Duration,
+	inspect_accept_errors: Option<AcceptErrorHook>,
+	max_connections: Option<NonZeroUsize>,
+	connection_limit_policy: ConnectionLimitPolicy,
+	max_accept_rate: Option<(NonZeroU32, NonZeroU32)>,
+	accept_error_backoff: Duration,
+	reserve_file_descriptor: bool,
+}
+
+impl ServeBuilder {
+	/// The default [drain timeout][Self::drain_timeout].
+	pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+	/// The default [accept error backoff][Self::accept_error_backoff].
+	pub const DEFAULT_ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+	/// Creates a new `ServeBuilder` with the default settings.
+	pub fn new() -> Self {
+		Self {
+			drain_timeout: Self::DEFAULT_DRAIN_TIMEOUT,
+			inspect_accept_errors: None,
+			max_connections: None,
+			connection_limit_policy: ConnectionLimitPolicy::Wait,
+			max_accept_rate: None,
+			accept_error_backoff: Self::DEFAULT_ACCEPT_ERROR_BACKOFF,
+			reserve_file_descriptor: false,
+		}
+	}
+
+	/// Sets how long to wait, after shutdown begins, for connections that are still being served to finish. Any that haven't finished by then are aborted. The default is [`DEFAULT_DRAIN_TIMEOUT`][Self::DEFAULT_DRAIN_TIMEOUT].
+	pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+		self.drain_timeout = timeout;
+		self
+	}
+
+	/// Sets a hook to be called with every error in accepting a connection, such as to log it.
+	pub fn inspect_accept_errors(mut self, hook: impl Fn(&io::Error) + Send + Sync + 'static) -> Self {
+		self.inspect_accept_errors = Some(Arc::new(hook));
+		self
+	}
+
+	/// Sets the maximum number of connections to serve at the same time. The default is no limit.
+	///
+	/// Without a limit, a flood of connections can exhaust the process's memory or file descriptors. What happens to connections beyond the limit is decided by the [`ConnectionLimitPolicy`].
+	///
+	/// Connections that are collected during shutdown, from the listening socket's backlog, are served even if that exceeds the limit, unless the policy is [`ConnectionLimitPolicy::Close`].
+	pub fn max_connections(mut self, max: NonZeroUsize) -> Self {
+		self.max_connections = Some(max);
+		self
+	}
+
+	/// Sets what to do with new connections while the [maximum number of connections][Self::max_connections] are being served. The default is [`ConnectionLimitPolicy::Wait`].
+	pub fn connection_limit_policy(mut self, policy: ConnectionLimitPolicy) -> Self {
+		self.connection_limit_policy = policy;
+		self
+	}
+
+	/// Limits how fast connections are accepted: on average, no more than `per_second` connections per second, with bursts of up to `burst` connections at a time. The default is no limit.
+	///
+	/// This protects slow backends from sudden floods of connections. While the limit is reached, new connections wait in the listening socket's backlog. This is a token bucket: it holds up to `burst` tokens, accepting a connection takes one, and tokens are added at `per_second` tokens per second.
+	///
+	/// Connections that are collected during shutdown, from the listening socket's backlog, are not limited.
+	pub fn max_accept_rate(mut self, per_second: NonZeroU32, burst: NonZeroU32) -> Self {
+		self.max_accept_rate = Some((per_second, burst));
+		self
+	}
+
+	/// Sets how long to pause accepting connections after an error that means the process or the system has run out of some resource, such as file descriptors (`EMFILE` or `ENFILE`) or memory for socket buffers (`ENOBUFS`). The default is [`DEFAULT_ACCEPT_ERROR_BACKOFF`][Self::DEFAULT_ACCEPT_ERROR_BACKOFF].
+	///
+	/// Such an error doesn't remove the waiting connection from the listening socket's backlog, so trying again right away fails the same way, and the accept loop would spin at full CPU usage until the resource is freed. Pausing gives the connections being served a chance to finish and free it. A zero duration disables the pause.
+	///
+	/// Other errors in accepting, such as a connection that was aborted by the client before it could be accepted (`ECONNABORTED`), only affect that one connection, and the loop carries on right away.
+	pub fn accept_error_backoff(mut self, backoff: Duration) -> Self {
+		self.accept_error_backoff = backoff;
+		self
+	}
+
+	/// Sets whether to keep a spare file descriptor (an unbound UDP socket), for shedding load when the process runs out of file descriptors. The default is false.
+	///
+	/// When accepting fails because the process has run out of file descriptors, the spare one is closed, so that the waiting connection can be accepted and closed right away, and then the spare is reopened. That way, the client finds out at once that it can't be served, instead of waiting in the backlog until it gives up.
+	pub fn reserve_file_descriptor(mut self, reserve: bool) -> Self {
+		self.reserve_file_descriptor = reserve;
+		self
+	}
+
+	/// Runs the accept loop with these settings. See [`serve`] for details.
+	pub async fn serve<H, F>(
+		self,
+		listener: AnyTokioListener,
+		mut handler: H,
+		shutdown: impl Future<Output = ()>,
+	) -> usize
+	where
+		H: FnMut(AnyTokioStream, SockAddr) -> F,
+		F: Future<Output = ()> + Send + 'static,
+	{
+		let mut shutdown = pin!(shutdown);
+		let mut tasks: JoinSet<()> = JoinSet::new();
+
+		let mut rate_limiter: Option<AcceptRateLimiter> =
+			self.max_accept_rate
+			.map(|(per_second, burst)| AcceptRateLimiter::new(per_second, burst));
+
+		let mut reserve: Option<Socket> = None;
+
+		let mut backoff: Option<Pin<Box<Sleep>>> = None;
+
+		loop {
+			if self.reserve_file_descriptor && reserve.is_none() {
+				reserve = open_reserve();
+			}
+
+			let accepted = std::future::poll_fn(|cx| {
+				// Collect finished tasks as they go, so that they don't pile up in the `JoinSet`.
+				while let task::Poll::Ready(Some(_)) = tasks.poll_join_next(cx) {}
+
+				if shutdown.as_mut().poll(cx).is_ready() {
+					return task::Poll::Ready(None);
+				}
+
+				if let Some(sleep) = &mut backoff {
+					task::ready!(sleep.as_mut().poll(cx));
+					backoff = None;
+				}
+
+				// At the limit, `tasks` isn't empty, so `poll_join_next` has arranged to wake this task when one of them finishes.
+				if self.at_connection_limit(&tasks) && self.connection_limit_policy == ConnectionLimitPolicy::Wait {
+					return task::Poll::Pending;
+				}
+
+				if let Some(rate_limiter) = &mut rate_limiter {
+					task::ready!(rate_limiter.poll_ready(cx));
+				}
+
+				listener.poll_accept(cx).map(Some)
+			}).await;
+
+			if let (Some(rate_limiter), Some(Ok(_))) = (&mut rate_limiter, &accepted) {
+				rate_limiter.take();
+			}
+
+			match accepted {
+				Some(Err(error)) if sys::is_accept_resource_error(&error) => {
+					self.accept_error(&error);
+
+					if reserve.take().is_some() {
+						// Now that there's a file descriptor free, accept the waiting connection and close it right away.
+						let shed = std::future::poll_fn(|cx| task::Poll::Ready(listener.poll_accept(cx))).await;
+
+						if let task::Poll::Ready(Err(error)) = shed {
+							self.accept_error(&error);
+						}
+					}
+
+					if !self.accept_error_backoff.is_zero() {
+						backoff = Some(Box::pin(tokio::time::sleep(self.accept_error_backoff)));
+					}
+				}
+
+				Some(accepted) => self.spawn(&mut tasks, &mut handler, accepted),
+				None => break,
+			}
+		}
+
+		// Serve the connections that were already waiting to be accepted, instead of resetting them.
+		match listener.close_and_drain(false) {
+			Ok(drain) => for accepted in drain {
+				self.spawn(&mut tasks, &mut handler, accepted);
+			},
+
+			Err(error) => self.accept_error(&error),
+		}
+
+		let drained = tokio::time::timeout(self.drain_timeout, async {
+			while tasks.join_next().await.is_some() {}
+		}).await;
+
+		if drained.is_ok() {
+			return 0;
+		}
+
+		let aborted: usize = tasks.len();
+		tasks.shutdown().await;
+		aborted
+	}
+
+	fn spawn<H, F>(
+		&self,
+		tasks: &mut JoinSet<()>,
+		handler: &mut H,
+		accepted: io::Result<(AnyTokioStream, SockAddr)>,
+	)
+	where
+		H: FnMut(AnyTokioStream, SockAddr) -> F,
+		F: Future<Output = ()> + Send + 'static,
+	{
+		match accepted {
+			Ok(_) if self.at_connection_limit(tasks) && self.connection_limit_policy == ConnectionLimitPolicy::Close => {}
+
+			Ok((connection, peer_addr)) => {
+				tasks.spawn(handler(connection, peer_addr));
+			}
+
+			Err(error) => self.accept_error(&error),
+		}
+	}
+
+	fn at_connection_limit(&self, tasks: &JoinSet<()>) -> bool {
+		self.max_connections.is_some_and(|max| tasks.len() >= max.get())
+	}
+
+	fn accept_error(&self, error: &io::Error) {
+		if let Some(hook) = &self.inspect_accept_errors {
+			hook(error);
+		}
+	}
+}
+
+/// Opens the spare file descriptor for [`ServeBuilder::reserve_file_descriptor`]. If that fails, such as because there are no file descriptors left, it's tried again on the next accept.
+fn open_reserve() -> Option<Socket> {
+	Socket::new(socket2::Domain::IPV4, socket2::Type::DGRAM, None).ok()
+}
+
+/// The token bucket for [`ServeBuilder::max_accept_rate`].
+///
+/// Rather than counting tokens, this keeps track of when the bucket will be full again, which is the same thing, but needs only one `Instant` and no periodic refilling.
+struct AcceptRateLimiter {
+	/// How long it takes to add one token.
+	interval: Duration,
+
+	/// How long it takes to add `burst - 1` tokens.
+	tolerance: Duration,
+
+	/// When the bucket will be full, if no more tokens are taken. A token is available whenever this is no more than `tolerance` in the future.
+	full_at: Instant,
+
+	sleep: Pin<Box<Sleep>>,
+}
+
+impl AcceptRateLimiter {
+	fn new(per_second: NonZeroU32, burst: NonZeroU32) -> Self {
+		let interval: Duration = Duration::from_secs(1) / per_second.get();
+		let now = Instant::now();
+
+		Self {
+			interval,
+			tolerance: interval.saturating_mul(burst.get() - 1),
+			full_at: now,
+			sleep: Box::pin(tokio::time::sleep_until(now)),
+		}
+	}
+
+	/// Checks whether a token is available, without taking it. If not, arranges to wake the task when there is.
+	fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> task::Poll<()> {
+		let Some(available_at) = self.full_at.checked_sub(self.tolerance)
+		else { return task::Poll::Ready(()) };
+
+		if Instant::now() >= available_at {
+			return task::Poll::Ready(());
+		}
+
+		self.sleep.as_mut().reset(available_at);
+		self.sleep.as_mut().poll(cx)
+	}
+
+	/// Takes a token.
+	fn take(&mut self) {
+		self.full_at = self.full_at.max(Instant::now()) + self.interval;
+	}
+}
+
+impl Default for ServeBuilder {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Debug for ServeBuilder {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ServeBuilder")
+		.field("drain_timeout", &self.drain_timeout)
+		.field("max_connections", &self.max_connections)
+		.field("connection_limit_policy", &self.connection_limit_policy)
+		.field("max_accept_rate", &self.max_accept_rate)
+		.field("accept_error_backoff", &self.accept_error_backoff)
+		.field("reserve_file_descriptor", &self.reserve_file_descriptor)
+		.finish_non_exhaustive()
+	}
+}
+
+#[test]
+#[cfg(unix)]
+fn test_accept_resource_error() {
+	assert!(sys::is_accept_resource_error(&io::Error::from_raw_os_error(libc::EMFILE)));
+	assert!(sys::is_accept_resource_error(&io::Error::from_raw_os_error(libc::ENFILE)));
+	assert!(!sys::is_accept_resource_error(&io::Error::from_raw_os_error(libc::ECONNABORTED)));
+	assert!(!sys::is_accept_resource_error(&io::Error::new(io::ErrorKind::Other, "not an OS error")));
+}
@@ -0,0 +1,463 @@
+use async_io::Async;
+use crate::{
+	convert::AnyStdSocket,
+	errors::IntoSmolError,
+};
+
+#[cfg(doc)]
+use crate::SocketUserOptions;
+use pin_project::pin_project;
+use socket2::{SockAddr, Socket};
+use futures_io::{AsyncRead, AsyncWrite};
+use std::{
+	io,
+	pin::Pin,
+	sync::Arc,
+	task,
+};
+
+#[cfg(unix)]
+use {
+	crate::{PeerCredentials, UnixPeerAcl},
+	std::path::Path,
+};
+
+#[cfg(windows)]
+use std::os::windows::io::{AsRawSocket, AsSocket, BorrowedSocket, RawSocket};
+
+#[cfg(not(windows))]
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+#[cfg(unix)]
+fn unix_sockaddr_into(addr: async_net::unix::SocketAddr) -> SockAddr {
+	let pathname =
+		addr.as_pathname()
+		.unwrap_or(Path::new(""));
+
+	SockAddr::unix(pathname)
+	.expect("unexpected error constructing a Unix-domain socket address that's already known to be valid")
+}
+
+/// Takes a standard library socket back out of an `async_net` socket, which is a shared [`Async`] wrapper around it.
+fn into_std<T>(socket: Arc<Async<T>>) -> io::Result<T> {
+	let socket: Async<T> =
+		Arc::try_unwrap(socket)
+		.map_err(|_| io::Error::new(io::ErrorKind::Other, "cannot convert a socket that is still in use by its clones"))?;
+
+	socket.into_inner()
+}
+
+/// A [stream-type][socket2::Type::STREAM] listening socket, either TCP or Unix-domain, adapted for use with [`smol`](https://docs.rs/smol) and other libraries based on [`async_net`].
+///
+/// This is the smol counterpart of [`AnyTokioListener`][crate::convert::AnyTokioListener]. Much like [`async_net::TcpListener`], an `AnySmolListener` is used to accept connections using the [`accept`][Self::accept] method.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::{AnySmolListener, AnySmolStream};
+/// # use std::io;
+/// # async fn example_fn() -> io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// let socket: AnySmolListener = socket_config::open(
+/// 	&address,
+/// 	&app_options,
+/// 	&user_options,
+/// )?.try_into()?;
+///
+/// loop {
+/// 	let (connection, peer_addr): (AnySmolStream, socket2::SockAddr) =
+/// 		socket.accept().await?;
+///
+/// 	// …do something with the connection…
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The call to `try_into` will fail with an [`IntoSmolError`] if the socket is inappropriate, such as a UDP socket. That includes listening sockets using transport protocols other than TCP, such as SCTP, and sockets in address families other than IP and Unix-domain, such as vsock; `async_net` has no socket types for those.
+///
+///
+/// # Availability
+///
+/// All platforms, but the `Unix` variant is only available on Unix-like platforms. Converting a Unix-domain socket on Windows will result in an error.
+///
+/// Requires the `smol` feature.
+#[derive(Debug, derive_more::From)]
+#[non_exhaustive]
+pub enum AnySmolListener {
+	/// A TCP listening socket.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	Tcp(async_net::TcpListener),
+
+	/// A Unix-domain [stream-type][socket2::Type::STREAM] listening socket.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only. `async_net` does not support Unix-domain sockets on Windows.
+	#[cfg(unix)] Unix(async_net::unix::UnixListener),
+}
+
+impl AnySmolListener {
+	/// Accepts a new connection.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_net::TcpListener::accept`] or [`async_net::unix::UnixListener::accept`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_net::TcpListener::accept`]."#)]
+	///
+	/// If this is a TCP listening socket with `TCP_NODELAY` set (such as with [`SocketUserOptions::tcp_nodelay`]), then `TCP_NODELAY` is also set on the accepted connection, since not every operating system does that automatically.
+	pub async fn accept(&self) -> io::Result<(AnySmolStream, SockAddr)> {
+		match self {
+			Self::Tcp(l) => {
+				let (socket, addr) = l.accept().await?;
+
+				// Not every operating system copies `TCP_NODELAY` from the listening socket to accepted connections, so copy it here. If the listening socket can't be checked, assume it doesn't have `TCP_NODELAY`.
+				if socket2::SockRef::from(l).nodelay().unwrap_or(false) {
+					socket.set_nodelay(true)?;
+				}
+
+				Ok((socket.into(), addr.into()))
+			},
+
+			#[cfg(unix)] Self::Unix(l) => {
+				let (socket, addr) = l.accept().await?;
+				Ok((socket.into(), unix_sockaddr_into(addr)))
+			},
+		}
+	}
+
+	/// Accepts a new connection, like [`accept`][Self::accept], and checks the connecting process's credentials against `acl`.
+	///
+	/// This works the same way as [`accept_allowed`][crate::accept_allowed()], including how errors are reported. In particular, a connection from a peer that isn't allowed to connect results in an error, which the application should log and then keep accepting connections.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)]
+	pub async fn accept_allowed(&self, acl: &UnixPeerAcl) -> io::Result<(AnySmolStream, SockAddr, Option<PeerCredentials>)> {
+		let (stream, peer_addr) = self.accept().await?;
+		let credentials: Option<PeerCredentials> = acl.check_accepted(&socket2::SockRef::from(&stream), &peer_addr)?;
+		Ok((stream, peer_addr, credentials))
+	}
+
+	/// Returns the local address that this listener is bound to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_net::TcpListener::local_addr`] or [`async_net::unix::UnixListener::local_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_net::TcpListener::local_addr`]."#)]
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Tcp(l) => l.local_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(l) => l.local_addr().map(unix_sockaddr_into),
+		}
+	}
+}
+
+impl TryFrom<AnyStdSocket> for AnySmolListener {
+	type Error = IntoSmolError;
+
+	fn try_from(socket: AnyStdSocket) -> Result<Self, Self::Error> {
+		match socket {
+			AnyStdSocket::TcpListener(l) => {
+				l.set_nonblocking(true)
+				.map_err(|error| IntoSmolError::SetNonBlocking { error })?;
+
+				let l = l.try_into().map_err(|error| IntoSmolError::Wrap { error })?;
+
+				Ok(Self::Tcp(l))
+			}
+
+			#[cfg(unix)]
+			AnyStdSocket::UnixListener(l) => {
+				l.set_nonblocking(true)
+				.map_err(|error| IntoSmolError::SetNonBlocking { error })?;
+
+				let l = l.try_into().map_err(|error| IntoSmolError::Wrap { error })?;
+
+				Ok(Self::Unix(l))
+			}
+
+			_ => Err(IntoSmolError::Inappropriate {
+				socket,
+			}),
+		}
+	}
+}
+
+impl TryFrom<Socket> for AnySmolListener {
+	type Error = IntoSmolError;
+
+	fn try_from(socket: Socket) -> Result<Self, Self::Error> {
+		let socket: AnyStdSocket =
+			socket.try_into()
+			.map_err(|error| IntoSmolError::Check { error })?;
+
+		socket.try_into()
+	}
+}
+
+impl TryFrom<AnySmolListener> for Socket {
+	type Error = io::Error;
+
+	fn try_from(l: AnySmolListener) -> Result<Self, Self::Error> {
+		match l {
+			AnySmolListener::Tcp(l) => into_std(l.into()).map(Socket::from),
+			#[cfg(unix)] AnySmolListener::Unix(l) => into_std(l.into()).map(Socket::from),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsFd for AnySmolListener {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		match self {
+			Self::Tcp(l) => l.as_fd(),
+			#[cfg(unix)] Self::Unix(l) => l.as_fd(),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsRawFd for AnySmolListener {
+	fn as_raw_fd(&self) -> RawFd {
+		match self {
+			Self::Tcp(l) => l.as_raw_fd(),
+			#[cfg(unix)] Self::Unix(l) => l.as_raw_fd(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsRawSocket for AnySmolListener {
+	fn as_raw_socket(&self) -> RawSocket {
+		match self {
+			Self::Tcp(l) => l.as_raw_socket(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsSocket for AnySmolListener {
+	fn as_socket(&self) -> BorrowedSocket<'_> {
+		match self {
+			Self::Tcp(l) => l.as_socket(),
+		}
+	}
+}
+
+/// A connected [stream-type][socket2::Type::STREAM] socket, either TCP or Unix-domain, adapted for use with [`smol`](https://docs.rs/smol) and other libraries based on [`async_net`].
+///
+/// `AnySmolStream`s are usually obtained from a call to [`AnySmolListener::accept`]. This type implements [`AsyncRead`] and [`AsyncWrite`], and is used to communicate with the connected peer in much the same way as an [`async_net::TcpStream`].
+///
+/// Converting an `AnySmolStream` back into a [`Socket`] fails if there are other handles to the same `async_net` stream, such as a clone of it.
+///
+///
+/// # Availability
+///
+/// All platforms, but the `Unix` variant is only available on Unix-like platforms. Converting a Unix-domain socket on Windows will result in an error.
+///
+/// Requires the `smol` feature.
+#[derive(Debug, derive_more::From)]
+#[pin_project(project = AnySmolStreamProj)]
+pub enum AnySmolStream {
+	/// A connected TCP socket.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	Tcp(#[pin] async_net::TcpStream),
+
+	/// A connected Unix-domain [stream-type][socket2::Type::STREAM] socket.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only. `async_net` does not support Unix-domain sockets on Windows.
+	#[cfg(unix)] Unix(#[pin] async_net::unix::UnixStream),
+}
+
+impl AnySmolStream {
+	/// Returns the local address that this socket is bound to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_net::TcpStream::local_addr`] or [`async_net::unix::UnixStream::local_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_net::TcpStream::local_addr`]."#)]
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Tcp(s) => s.local_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(s) => s.local_addr().map(unix_sockaddr_into),
+		}
+	}
+
+	/// Returns the remote address that this socket is connected to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`async_net::TcpStream::peer_addr`] or [`async_net::unix::UnixStream::peer_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`async_net::TcpStream::peer_addr`]."#)]
+	pub fn peer_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Tcp(s) => s.peer_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(s) => s.peer_addr().map(unix_sockaddr_into),
+		}
+	}
+}
+
+impl AsyncRead for AnySmolStream {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		buf: &mut [u8],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnySmolStreamProj::Tcp(s) => s.poll_read(cx, buf),
+			#[cfg(unix)] AnySmolStreamProj::Unix(s) => s.poll_read(cx, buf),
+		}
+	}
+
+	fn poll_read_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		bufs: &mut [io::IoSliceMut],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnySmolStreamProj::Tcp(s) => s.poll_read_vectored(cx, bufs),
+			#[cfg(unix)] AnySmolStreamProj::Unix(s) => s.poll_read_vectored(cx, bufs),
+		}
+	}
+}
+
+impl AsyncWrite for AnySmolStream {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		buf: &[u8],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnySmolStreamProj::Tcp(s) => s.poll_write(cx, buf),
+			#[cfg(unix)] AnySmolStreamProj::Unix(s) => s.poll_write(cx, buf),
+		}
+	}
+
+	fn poll_write_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		bufs: &[io::IoSlice],
+	) -> task::Poll<io::Result<usize>> {
+		match self.project() {
+			AnySmolStreamProj::Tcp(s) => s.poll_write_vectored(cx, bufs),
+			#[cfg(unix)] AnySmolStreamProj::Unix(s) => s.poll_write_vectored(cx, bufs),
+		}
+	}
+
+	fn poll_flush(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+	) -> task::Poll<io::Result<()>> {
+		match self.project() {
+			AnySmolStreamProj::Tcp(s) => s.poll_flush(cx),
+			#[cfg(unix)] AnySmolStreamProj::Unix(s) => s.poll_flush(cx),
+		}
+	}
+
+	fn poll_close(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+	) -> task::Poll<io::Result<()>> {
+		match self.project() {
+			AnySmolStreamProj::Tcp(s) => s.poll_close(cx),
+			#[cfg(unix)] AnySmolStreamProj::Unix(s) => s.poll_close(cx),
+		}
+	}
+}
+
+impl TryFrom<AnyStdSocket> for AnySmolStream {
+	type Error = IntoSmolError;
+
+	fn try_from(socket: AnyStdSocket) -> Result<Self, Self::Error> {
+		match socket {
+			AnyStdSocket::TcpStream(s) => {
+				s.set_nonblocking(true)
+				.map_err(|error| IntoSmolError::SetNonBlocking { error })?;
+
+				let s = s.try_into().map_err(|error| IntoSmolError::Wrap { error })?;
+
+				Ok(Self::Tcp(s))
+			}
+
+			#[cfg(unix)]
+			AnyStdSocket::UnixStream(s) => {
+				s.set_nonblocking(true)
+				.map_err(|error| IntoSmolError::SetNonBlocking { error })?;
+
+				let s = s.try_into().map_err(|error| IntoSmolError::Wrap { error })?;
+
+				Ok(Self::Unix(s))
+			}
+
+			_ => Err(IntoSmolError::Inappropriate {
+				socket,
+			}),
+		}
+	}
+}
+
+impl TryFrom<Socket> for AnySmolStream {
+	type Error = IntoSmolError;
+
+	fn try_from(socket: Socket) -> Result<Self, Self::Error> {
+		let socket: AnyStdSocket =
+			socket.try_into()
+			.map_err(|error| IntoSmolError::Check { error })?;
+
+		socket.try_into()
+	}
+}
+
+impl TryFrom<AnySmolStream> for Socket {
+	type Error = io::Error;
+
+	fn try_from(socket: AnySmolStream) -> Result<Self, Self::Error> {
+		match socket {
+			AnySmolStream::Tcp(s) => into_std(s.into()).map(Socket::from),
+			#[cfg(unix)] AnySmolStream::Unix(s) => into_std(s.into()).map(Socket::from),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsFd for AnySmolStream {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		match self {
+			Self::Tcp(s) => s.as_fd(),
+			#[cfg(unix)] Self::Unix(s) => s.as_fd(),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsRawFd for AnySmolStream {
+	fn as_raw_fd(&self) -> RawFd {
+		match self {
+			Self::Tcp(s) => s.as_raw_fd(),
+			#[cfg(unix)] Self::Unix(s) => s.as_raw_fd(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsRawSocket for AnySmolStream {
+	fn as_raw_socket(&self) -> RawSocket {
+		match self {
+			Self::Tcp(s) => s.as_raw_socket(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsSocket for AnySmolStream {
+	fn as_socket(&self) -> BorrowedSocket<'_> {
+		match self {
+			Self::Tcp(s) => s.as_socket(),
+		}
+	}
+}
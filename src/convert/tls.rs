@@ -0,0 +1,654 @@
+use crate::{
+	convert::{AnyTokioListener, AnyTokioStream},
+	errors::DetectTlsError,
+};
+use pin_project::pin_project;
+use socket2::SockAddr;
+use std::{
+	fmt::{self, Debug, Formatter},
+	future::Future,
+	io,
+	num::NonZeroUsize,
+	pin::Pin,
+	sync::Arc,
+	task,
+	time::Duration,
+};
+use tls_listener::{AsyncTls, TlsListener};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+use {
+	crate::{
+		errors::{OpenTlsError, TlsConfigError},
+		SocketAddr,
+		SocketAppOptions,
+		SocketUserOptions,
+	},
+	std::path::Path,
+};
+
+/// The error type of [`AnyTlsListener::accept`], for a TLS acceptor of type `T`.
+///
+/// This is [`tls_listener::Error`], which is either an error in accepting a connection (the `ListenerError` variant), or an error in completing the TLS handshake on a connection that was accepted (the `TlsAcceptError` and `HandshakeTimeout` variants).
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+pub type AnyTlsAcceptError<T> = tls_listener::Error<io::Error, <T as AsyncTls<AnyTokioStream>>::Error, SockAddr>;
+
+type HandshakeErrorHook<T> = Arc<dyn Fn(&AnyTlsAcceptError<T>) + Send + Sync>;
+
+/// What [`AnyTlsListener::accept`] does when the TLS handshake on an accepted connection fails or times out.
+///
+/// Handshake errors only affect one connection, and are usually caused by the client (such as a client that doesn't trust the server's certificate, or a port scanner that isn't speaking TLS at all), so the default is to skip them.
+///
+/// Whichever policy is chosen, handshake errors are first passed to the hook set with [`AnyTlsListenerBuilder::inspect_handshake_errors`], if any. Errors in accepting connections, as opposed to errors in the TLS handshake, are always returned.
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TlsHandshakeErrorPolicy {
+	/// Close the connection and keep waiting for another one. This is the default.
+	#[default]
+	Skip,
+
+	/// Return the error from [`AnyTlsListener::accept`].
+	Return,
+}
+
+/// Wraps an [`AnyTokioListener`], performing a TLS handshake on each accepted connection.
+///
+/// This is a thin layer around [`tls_listener::TlsListener`]. It accepts connections and performs TLS handshakes on several at once, returning each connection once its handshake has completed. `T` is the TLS acceptor, such as [`tokio_rustls::TlsAcceptor`](https://docs.rs/tokio-rustls/latest/tokio_rustls/struct.TlsAcceptor.html), which must implement [`tls_listener::AsyncTls`] for [`AnyTokioStream`].
+///
+/// Create one with [`AnyTlsListener::builder`], or [`AnyTlsListener::new`] for the default settings.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::{AnyTlsListener, AnyTokioListener};
+/// # use std::{io, time::Duration};
+/// # async fn example_fn<T>(tls_acceptor: T) -> io::Result<()>
+/// # where T: tls_listener::AsyncTls<socket_config::convert::AnyTokioStream> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// let listener: AnyTokioListener = socket_config::open(
+/// 	&address,
+/// 	&app_options,
+/// 	&user_options,
+/// )?.try_into()?;
+///
+/// let mut listener =
+/// 	AnyTlsListener::builder(tls_acceptor)
+/// 	.handshake_timeout(Duration::from_secs(5))
+/// 	.listen(listener);
+///
+/// loop {
+/// 	let (connection, peer_addr) = match listener.accept().await {
+/// 		Ok(accepted) => accepted,
+/// 		Err(error) => {
+/// 			eprintln!("error accepting a connection: {error}");
+/// 			continue;
+/// 		}
+/// 	};
+///
+/// 	// …serve the connection…
+/// }
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+#[pin_project]
+pub struct AnyTlsListener<T: AsyncTls<AnyTokioStream>> {
+	#[pin] inner: TlsListener<AnyTokioListener, T>,
+	handshake_errors: TlsHandshakeErrorPolicy,
+	inspect_handshake_errors: Option<HandshakeErrorHook<T>>,
+}
+
+impl<T: AsyncTls<AnyTokioStream>> AnyTlsListener<T> {
+	/// Wraps `listener` with the TLS acceptor `tls`, using the default settings.
+	pub fn new(tls: T, listener: AnyTokioListener) -> Self {
+		Self::builder(tls).listen(listener)
+	}
+
+	/// Starts building an `AnyTlsListener` with the TLS acceptor `tls`.
+	pub fn builder(tls: T) -> AnyTlsListenerBuilder<T> {
+		AnyTlsListenerBuilder {
+			inner: tls_listener::builder(tls),
+			handshake_errors: TlsHandshakeErrorPolicy::default(),
+			inspect_handshake_errors: None,
+		}
+	}
+
+	/// Accepts a new connection, and waits for its TLS handshake to complete.
+	///
+	/// # Errors
+	///
+	/// Any error in accepting a connection. Also any error in the TLS handshake, if the [`TlsHandshakeErrorPolicy`] is `Return`.
+	pub async fn accept(&mut self) -> Result<(T::Stream, SockAddr), AnyTlsAcceptError<T>> {
+		std::future::poll_fn(|cx| Pin::new(&mut *self).poll_accept(cx)).await
+	}
+
+	/// Polls to accept a new connection. See [`accept`][Self::accept] for details.
+	#[allow(clippy::type_complexity)]
+	pub fn poll_accept(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Result<(T::Stream, SockAddr), AnyTlsAcceptError<T>>> {
+		let mut this = self.project();
+
+		loop {
+			let error = match task::ready!(this.inner.as_mut().poll_accept(cx)) {
+				Ok(accepted) => return task::Poll::Ready(Ok(accepted)),
+				Err(error @ tls_listener::Error::ListenerError(_)) => return task::Poll::Ready(Err(error)),
+				Err(error) => error,
+			};
+
+			if let Some(hook) = this.inspect_handshake_errors {
+				hook(&error);
+			}
+
+			match this.handshake_errors {
+				TlsHandshakeErrorPolicy::Skip => continue,
+				TlsHandshakeErrorPolicy::Return => return task::Poll::Ready(Err(error)),
+			}
+		}
+	}
+
+	/// Returns the local address that the underlying listener is bound to.
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		self.listener().local_addr()
+	}
+
+	/// Returns the underlying listener.
+	pub fn listener(&self) -> &AnyTokioListener {
+		self.inner.listener()
+	}
+
+	/// Replaces the TLS acceptor, such as to start using a renewed certificate. Connections whose handshakes are already in progress continue with the old one.
+	pub fn replace_acceptor(&mut self, tls: T) {
+		self.inner.replace_acceptor(tls)
+	}
+}
+
+/// Yields connections once their TLS handshakes have completed, the same as [`AnyTlsListener::accept`].
+///
+/// # Availability
+///
+/// Requires the `futures` feature, in addition to `tls-listener`.
+#[cfg(feature = "futures")]
+impl<T: AsyncTls<AnyTokioStream>> futures::Stream for AnyTlsListener<T> {
+	type Item = Result<(T::Stream, SockAddr), AnyTlsAcceptError<T>>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Option<Self::Item>> {
+		self.poll_accept(cx).map(Some)
+	}
+}
+
+impl<T: AsyncTls<AnyTokioStream>> Debug for AnyTlsListener<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AnyTlsListener")
+		.field("listener", self.listener())
+		.field("handshake_errors", &self.handshake_errors)
+		.finish_non_exhaustive()
+	}
+}
+
+/// Builder for [`AnyTlsListener`]. Obtain one with [`AnyTlsListener::builder`].
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+#[must_use]
+pub struct AnyTlsListenerBuilder<T: AsyncTls<AnyTokioStream>> {
+	inner: tls_listener::Builder<T>,
+	handshake_errors: TlsHandshakeErrorPolicy,
+	inspect_handshake_errors: Option<HandshakeErrorHook<T>>,
+}
+
+impl<T: AsyncTls<AnyTokioStream>> AnyTlsListenerBuilder<T> {
+	/// Finishes building the `AnyTlsListener`, wrapping `listener`.
+	pub fn listen(self, listener: AnyTokioListener) -> AnyTlsListener<T> {
+		AnyTlsListener {
+			inner: self.inner.listen(listener),
+			handshake_errors: self.handshake_errors,
+			inspect_handshake_errors: self.inspect_handshake_errors,
+		}
+	}
+
+	/// Sets how long to wait for a TLS handshake to complete before giving up on the connection. The default is [`tls_listener::DEFAULT_HANDSHAKE_TIMEOUT`].
+	pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+		self.inner.handshake_timeout(timeout);
+		self
+	}
+
+	/// Sets how many connections to accept at a time before checking whether any TLS handshakes have completed. The default is [`tls_listener::DEFAULT_ACCEPT_BATCH_SIZE`].
+	pub fn accept_batch_size(mut self, size: NonZeroUsize) -> Self {
+		self.inner.accept_batch_size(size);
+		self
+	}
+
+	/// Sets what to do when a TLS handshake fails or times out. The default is [`TlsHandshakeErrorPolicy::Skip`].
+	pub fn handshake_errors(mut self, policy: TlsHandshakeErrorPolicy) -> Self {
+		self.handshake_errors = policy;
+		self
+	}
+
+	/// Sets a hook to be called with every TLS handshake error, such as to log it, before the [`TlsHandshakeErrorPolicy`] is applied.
+	pub fn inspect_handshake_errors(mut self, hook: impl Fn(&AnyTlsAcceptError<T>) + Send + Sync + 'static) -> Self {
+		self.inspect_handshake_errors = Some(Arc::new(hook));
+		self
+	}
+}
+
+impl<T: AsyncTls<AnyTokioStream>> Clone for AnyTlsListenerBuilder<T> {
+	fn clone(&self) -> Self {
+		Self {
+			inner: self.inner.clone(),
+			handshake_errors: self.handshake_errors,
+			inspect_handshake_errors: self.inspect_handshake_errors.clone(),
+		}
+	}
+}
+
+impl<T: AsyncTls<AnyTokioStream>> Debug for AnyTlsListenerBuilder<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AnyTlsListenerBuilder")
+		.field("handshake_errors", &self.handshake_errors)
+		.finish_non_exhaustive()
+	}
+}
+
+/// A listening socket that either does or doesn't use TLS, depending on configuration.
+///
+/// This lets an application support both plaintext and TLS without handling each one separately. Accepted connections are [`MaybeTlsStream`]s, which can be read and written the same way either way.
+///
+/// With the `rustls` or `native-tls` feature, `open_tls` or `open_native_tls` returns one of these, using TLS only if the user configured a certificate.
+///
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+#[pin_project(project = MaybeTlsListenerProj)]
+pub enum MaybeTlsListener<T: AsyncTls<AnyTokioStream>> {
+	/// A listener that accepts plaintext connections.
+	Plain(AnyTokioListener),
+
+	/// A listener that performs a TLS handshake on each accepted connection.
+	Tls(#[pin] AnyTlsListener<T>),
+}
+
+impl<T: AsyncTls<AnyTokioStream>> MaybeTlsListener<T> {
+	/// Accepts a new connection, and, if this is a TLS listener, waits for its TLS handshake to complete.
+	///
+	/// # Errors
+	///
+	/// Same as [`AnyTlsListener::accept`]. Errors in accepting plaintext connections are returned as [`tls_listener::Error::ListenerError`].
+	pub async fn accept(&mut self) -> Result<(MaybeTlsStream<T::Stream>, SockAddr), AnyTlsAcceptError<T>> {
+		std::future::poll_fn(|cx| Pin::new(&mut *self).poll_accept(cx)).await
+	}
+
+	/// Polls to accept a new connection. See [`accept`][Self::accept] for details.
+	#[allow(clippy::type_complexity)]
+	pub fn poll_accept(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Result<(MaybeTlsStream<T::Stream>, SockAddr), AnyTlsAcceptError<T>>> {
+		match self.project() {
+			MaybeTlsListenerProj::Plain(l) => {
+				l.poll_accept(cx)
+				.map_ok(|(s, addr)| (MaybeTlsStream::Plain(s), addr))
+				.map_err(tls_listener::Error::ListenerError)
+			}
+
+			MaybeTlsListenerProj::Tls(l) => {
+				l.poll_accept(cx)
+				.map_ok(|(s, addr)| (MaybeTlsStream::Tls(s), addr))
+			}
+		}
+	}
+
+	/// Returns the local address that the underlying listener is bound to.
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		self.listener().local_addr()
+	}
+
+	/// Returns the underlying listener.
+	pub fn listener(&self) -> &AnyTokioListener {
+		match self {
+			Self::Plain(l) => l,
+			Self::Tls(l) => l.listener(),
+		}
+	}
+
+	/// Returns true if this listener uses TLS.
+	pub fn is_tls(&self) -> bool {
+		matches!(self, Self::Tls(_))
+	}
+}
+
+impl<T: AsyncTls<AnyTokioStream>> Debug for MaybeTlsListener<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Plain(l) => f.debug_tuple("Plain").field(l).finish(),
+			Self::Tls(l) => f.debug_tuple("Tls").field(l).finish(),
+		}
+	}
+}
+
+/// A connection accepted by a [`MaybeTlsListener`], which either does or doesn't use TLS.
+///
+/// This type implements [`AsyncRead`] and [`AsyncWrite`]. When TLS is used, data read and written is decrypted and encrypted, so both variants are used in the same way.
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+#[derive(Debug)]
+#[pin_project(project = MaybeTlsStreamProj)]
+pub enum MaybeTlsStream<S> {
+	/// A plaintext connection.
+	Plain(#[pin] AnyTokioStream),
+
+	/// A TLS connection. `S` is the TLS stream type, such as [`tokio_rustls::server::TlsStream`](https://docs.rs/tokio-rustls/latest/tokio_rustls/server/struct.TlsStream.html).
+	Tls(#[pin] S),
+}
+
+impl<S> MaybeTlsStream<S> {
+	/// Returns true if this connection uses TLS.
+	pub fn is_tls(&self) -> bool {
+		matches!(self, Self::Tls(_))
+	}
+}
+
+impl<S: AsyncRead> AsyncRead for MaybeTlsStream<S> {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		buf: &mut ReadBuf,
+	) -> task::Poll<Result<(), io::Error>> {
+		match self.project() {
+			MaybeTlsStreamProj::Plain(s) => s.poll_read(cx, buf),
+			MaybeTlsStreamProj::Tls(s) => s.poll_read(cx, buf),
+		}
+	}
+}
+
+impl<S: AsyncWrite> AsyncWrite for MaybeTlsStream<S> {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		buf: &[u8],
+	) -> task::Poll<Result<usize, io::Error>> {
+		match self.project() {
+			MaybeTlsStreamProj::Plain(s) => s.poll_write(cx, buf),
+			MaybeTlsStreamProj::Tls(s) => s.poll_write(cx, buf),
+		}
+	}
+
+	fn poll_flush(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+	) -> task::Poll<Result<(), io::Error>> {
+		match self.project() {
+			MaybeTlsStreamProj::Plain(s) => s.poll_flush(cx),
+			MaybeTlsStreamProj::Tls(s) => s.poll_flush(cx),
+		}
+	}
+
+	fn poll_shutdown(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+	) -> task::Poll<Result<(), io::Error>> {
+		match self.project() {
+			MaybeTlsStreamProj::Plain(s) => s.poll_shutdown(cx),
+			MaybeTlsStreamProj::Tls(s) => s.poll_shutdown(cx),
+		}
+	}
+
+	fn poll_write_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut task::Context,
+		bufs: &[io::IoSlice],
+	) -> task::Poll<Result<usize, io::Error>> {
+		match self.project() {
+			MaybeTlsStreamProj::Plain(s) => s.poll_write_vectored(cx, bufs),
+			MaybeTlsStreamProj::Tls(s) => s.poll_write_vectored(cx, bufs),
+		}
+	}
+
+	fn is_write_vectored(&self) -> bool {
+		match self {
+			Self::Plain(s) => s.is_write_vectored(),
+			Self::Tls(s) => s.is_write_vectored(),
+		}
+	}
+}
+
+/// Wraps a TLS acceptor, so that connections that don't start with a TLS handshake are passed through as plaintext instead.
+///
+/// This is for serving both TLS and plaintext on the same port, such as while migrating clients from one to the other. Use it in place of the TLS acceptor when building an [`AnyTlsListener`]. The connections it yields are [`MaybeTlsStream`]s.
+///
+/// To tell the two apart, it [peeks][AnyTokioStream::peek] at the first byte that the client sends. If the byte is `0x16`, which is how every TLS connection starts (it's the record type of a TLS handshake), then the connection is handed to the wrapped acceptor. Otherwise, it's passed through as is, without consuming that byte. This works for protocols where the client speaks first, and the first byte isn't `0x16`, like HTTP. It doesn't work for protocols where the server speaks first, like SMTP, because the client won't send anything before the server does.
+///
+/// Waiting for the first byte counts toward the handshake timeout, so a plaintext client that doesn't send anything is dropped the same as a TLS client that doesn't complete its handshake.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::{AnyTlsListener, AnyTokioListener, DetectTls};
+/// # async fn example_fn<T>(tls_acceptor: T, listener: AnyTokioListener)
+/// # where
+/// # 	T: tls_listener::AsyncTls<socket_config::convert::AnyTokioStream> + Send + Sync + 'static,
+/// # 	T::AcceptFuture: Send,
+/// # {
+/// let mut listener = AnyTlsListener::new(DetectTls::new(tls_acceptor), listener);
+///
+/// while let Ok((connection, _)) = listener.accept().await {
+/// 	if connection.is_tls() {
+/// 		// …
+/// 	}
+/// }
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+#[derive(Clone, Debug)]
+pub struct DetectTls<T> {
+	tls: T,
+}
+
+impl<T> DetectTls<T> {
+	/// The first byte of every TLS connection, which is the record type of a TLS handshake.
+	pub const TLS_HANDSHAKE_RECORD: u8 = 0x16;
+
+	/// Wraps the TLS acceptor `tls`.
+	pub fn new(tls: T) -> Self {
+		Self { tls }
+	}
+
+	/// Returns the wrapped TLS acceptor.
+	pub fn get_ref(&self) -> &T {
+		&self.tls
+	}
+
+	/// Unwraps the TLS acceptor.
+	pub fn into_inner(self) -> T {
+		self.tls
+	}
+}
+
+impl<T> AsyncTls<AnyTokioStream> for DetectTls<T>
+where
+	T: AsyncTls<AnyTokioStream> + Send + Sync + 'static,
+	T::AcceptFuture: Send,
+{
+	type Stream = MaybeTlsStream<T::Stream>;
+	type Error = DetectTlsError<T::Error>;
+	type AcceptFuture = Pin<Box<dyn Future<Output = Result<Self::Stream, Self::Error>> + Send>>;
+
+	fn accept(&self, stream: AnyTokioStream) -> Self::AcceptFuture {
+		let tls: T = self.tls.clone();
+
+		Box::pin(async move {
+			let mut first_byte = [0u8; 1];
+
+			let is_tls: bool =
+				stream.peek(&mut first_byte).await
+				.map_err(DetectTlsError::Peek)?
+				== 1 && first_byte[0] == Self::TLS_HANDSHAKE_RECORD;
+
+			if is_tls {
+				tls.accept(stream).await
+				.map(MaybeTlsStream::Tls)
+				.map_err(DetectTlsError::Tls)
+			}
+			else {
+				Ok(MaybeTlsStream::Plain(stream))
+			}
+		})
+	}
+}
+
+/// Gets the paths of the certificate chain and private key from the `tls_cert` and `tls_key` options, or `None` if TLS isn't configured. Checks that `tls_key` isn't used without `tls_cert`, and vice versa.
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+pub(crate) fn tls_cert_and_key<'a>(tls_cert: Option<&'a Path>, tls_key: Option<&'a Path>) -> Result<Option<(&'a Path, &'a Path)>, TlsConfigError> {
+	match (tls_cert, tls_key) {
+		(Some(cert_path), Some(key_path)) => Ok(Some((cert_path, key_path))),
+		(Some(_), None) => Err(TlsConfigError::MissingKey),
+		(None, Some(_)) => Err(TlsConfigError::MissingCert { name: "tls_key" }),
+		(None, None) => Ok(None),
+	}
+}
+
+/// Opens a listening socket, and wraps it with the TLS acceptor `tls`, if there is one.
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+pub(crate) fn open_maybe_tls<T: AsyncTls<AnyTokioStream>>(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+	tls: Option<T>,
+) -> Result<MaybeTlsListener<T>, OpenTlsError> {
+	let listener: AnyTokioListener = crate::open(address, app_options, user_options)?.try_into()?;
+
+	Ok(match tls {
+		Some(tls) => MaybeTlsListener::Tls(AnyTlsListener::new(tls, listener)),
+		None => MaybeTlsListener::Plain(listener),
+	})
+}
+
+/// A pretend TLS acceptor for tests, whose “handshake” is the client sending [`FAKE_TLS_HANDSHAKE`].
+#[cfg(all(test, not(any(target_os = "fuchsia", target_os = "solaris"))))]
+#[derive(Clone)]
+struct FakeTls;
+
+#[cfg(all(test, not(any(target_os = "fuchsia", target_os = "solaris"))))]
+const FAKE_TLS_HANDSHAKE: u8 = DetectTls::<FakeTls>::TLS_HANDSHAKE_RECORD;
+
+#[cfg(all(test, not(any(target_os = "fuchsia", target_os = "solaris"))))]
+impl AsyncTls<AnyTokioStream> for FakeTls {
+	type Stream = AnyTokioStream;
+	type Error = io::Error;
+	type AcceptFuture = Pin<Box<dyn Future<Output = io::Result<AnyTokioStream>> + Send>>;
+
+	fn accept(&self, mut stream: AnyTokioStream) -> Self::AcceptFuture {
+		use tokio::io::AsyncReadExt;
+
+		Box::pin(async move {
+			match stream.read_u8().await? {
+				FAKE_TLS_HANDSHAKE => Ok(stream),
+				_ => Err(io::Error::new(io::ErrorKind::InvalidData, "bad handshake")),
+			}
+		})
+	}
+}
+
+#[cfg(all(test, not(any(target_os = "fuchsia", target_os = "solaris"))))]
+#[test]
+fn test_tls_listener() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use tokio::io::AsyncWriteExt;
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async {
+		let new_listener = || AnyTokioListener::try_from(socket2::Socket::from(std::net::TcpListener::bind("127.0.0.1:0").unwrap())).unwrap();
+
+		async fn connect(listener: &AnyTlsListener<FakeTls>) -> tokio::net::TcpStream {
+			tokio::net::TcpStream::connect(listener.local_addr().unwrap().as_socket().unwrap()).await.unwrap()
+		}
+
+		// By default, failed handshakes are skipped, but can be inspected.
+		let skipped = Arc::new(AtomicUsize::new(0));
+		let mut listener =
+			AnyTlsListener::builder(FakeTls)
+			.inspect_handshake_errors({
+				let skipped = skipped.clone();
+				move |error| {
+					assert!(matches!(error, tls_listener::Error::TlsAcceptError { .. }));
+					skipped.fetch_add(1, Ordering::SeqCst);
+				}
+			})
+			.listen(new_listener());
+
+		let mut bad_client = connect(&listener).await;
+		bad_client.write_u8(b'x').await.unwrap();
+		let mut good_client = connect(&listener).await;
+
+		// The bad client's handshake fails as soon as both connections are accepted. The good client's handshake doesn't finish until it's written afterward.
+		let (accepted, _) = tokio::join!(listener.accept(), good_client.write_u8(FAKE_TLS_HANDSHAKE));
+		let (_, peer_addr) = accepted.unwrap();
+		assert_eq!(peer_addr.as_socket(), Some(good_client.local_addr().unwrap()));
+		assert_eq!(skipped.load(Ordering::SeqCst), 1);
+
+		// With `Return`, they're returned instead.
+		let mut listener =
+			AnyTlsListener::builder(FakeTls)
+			.handshake_errors(TlsHandshakeErrorPolicy::Return)
+			.listen(new_listener());
+
+		let mut bad_client = connect(&listener).await;
+		bad_client.write_u8(b'x').await.unwrap();
+		assert!(matches!(listener.accept().await, Err(tls_listener::Error::TlsAcceptError { .. })));
+	});
+}
+
+#[cfg(all(test, not(any(target_os = "fuchsia", target_os = "solaris"))))]
+#[test]
+fn test_detect_tls() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async {
+		let listener = AnyTokioListener::try_from(socket2::Socket::from(std::net::TcpListener::bind("127.0.0.1:0").unwrap())).unwrap();
+		let mut listener = AnyTlsListener::new(DetectTls::new(FakeTls), listener);
+		let server_addr = listener.local_addr().unwrap().as_socket().unwrap();
+
+		// A plaintext client is passed through, with its first byte still there to be read.
+		let mut client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+		client.write_all(b"GET").await.unwrap();
+
+		let (mut connection, _) = listener.accept().await.unwrap();
+		assert!(!connection.is_tls());
+
+		let mut request = [0u8; 3];
+		connection.read_exact(&mut request).await.unwrap();
+		assert_eq!(&request, b"GET");
+
+		// A TLS client is handed to the TLS acceptor.
+		let mut client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+		client.write_u8(FAKE_TLS_HANDSHAKE).await.unwrap();
+
+		let (connection, _) = listener.accept().await.unwrap();
+		assert!(connection.is_tls());
+	});
+}
@@ -2,6 +2,9 @@ use crate::{
 	convert::AnyStdSocket,
 	errors::IntoTokioError,
 };
+
+#[cfg(doc)]
+use crate::SocketUserOptions;
 use pin_project::pin_project;
 use socket2::{SockAddr, Socket};
 use std::{
@@ -12,7 +15,10 @@ use std::{
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
 #[cfg(unix)]
-use std::path::Path;
+use {
+	crate::{PeerCredentials, UnixPeerAcl},
+	std::path::Path,
+};
 
 #[cfg(windows)]
 use std::os::windows::io::{AsRawSocket, AsSocket, BorrowedSocket, RawSocket};
@@ -44,7 +50,7 @@ fn unix_sockaddr_into(addr: tokio::net::unix::SocketAddr) -> SockAddr {
 /// # use std::io;
 /// # async fn example_fn() -> io::Result<()> {
 /// # let address: socket_config::SocketAddr = unimplemented!();
-/// # let app_options: socket_config::SocketAppOptions<'static> = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
 /// # let user_options: socket_config::SocketUserOptions = unimplemented!();
 /// let socket: AnyTokioListener = socket_config::open(
 /// 	&address,
@@ -64,7 +70,7 @@ fn unix_sockaddr_into(addr: tokio::net::unix::SocketAddr) -> SockAddr {
 ///
 /// This opens a socket using [`open`][crate::open()] and then converts it into an `AnyTokioListener`, then accepts connections as [`AnyTokioStream`]s.
 ///
-/// The call to `try_into` will fail with an [`IntoTokioError`] if the socket is inappropriate, such as a UDP socket.
+/// The call to `try_into` will fail with an [`IntoTokioError`] if the socket is inappropriate, such as a UDP socket. That includes listening sockets using transport protocols other than TCP, such as SCTP, and sockets in address families other than IP and Unix-domain, such as vsock; Tokio has no socket types for those. The [`IntoTokioError::Inappropriate`] error contains the socket as an [`AnyStdSocket`], which can be used with `tokio::io::unix::AsyncFd` (on Unix-like platforms) instead.
 ///
 ///
 /// # Availability
@@ -95,28 +101,52 @@ impl AnyTokioListener {
 	///
 	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::TcpListener::accept`] or [`tokio::net::UnixListener::accept`], as appropriate."#)]
 	#[cfg_attr(not(unix), doc = r#"This method delegates to [`tokio::net::TcpListener::accept`]."#)]
+	///
+	/// If this is a TCP listening socket with `TCP_NODELAY` set (such as with [`SocketUserOptions::tcp_nodelay`]), then `TCP_NODELAY` is also set on the accepted connection, since not every operating system does that automatically.
 	pub async fn accept(&self) -> io::Result<(AnyTokioStream, SockAddr)> {
 		match self {
-			Self::Tcp(l) => l.accept().await.map(Self::accept_tcp),
+			Self::Tcp(l) => l.accept().await.and_then(|accepted| Self::accept_tcp(l, accepted)),
 			#[cfg(unix)] Self::Unix(l) => l.accept().await.map(Self::accept_unix),
 		}
 	}
 
+	/// Accepts a new connection, like [`accept`][Self::accept], and checks the connecting process's credentials against `acl`.
+	///
+	/// This works the same way as [`accept_allowed`][crate::accept_allowed()], including how errors are reported. In particular, a connection from a peer that isn't allowed to connect results in an error, which the application should log and then keep accepting connections.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)]
+	pub async fn accept_allowed(&self, acl: &UnixPeerAcl) -> io::Result<(AnyTokioStream, SockAddr, Option<PeerCredentials>)> {
+		let (stream, peer_addr) = self.accept().await?;
+		let credentials: Option<PeerCredentials> = acl.check_accepted(&socket2::SockRef::from(&stream), &peer_addr)?;
+		Ok((stream, peer_addr, credentials))
+	}
+
 	/// Polls to accept a new connection.
 	///
 	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::TcpListener::poll_accept`] or [`tokio::net::UnixListener::poll_accept`], as appropriate."#)]
 	#[cfg_attr(not(unix), doc = r#"This method delegates to [`tokio::net::TcpListener::poll_accept`]."#)]
+	///
+	/// `TCP_NODELAY` is copied from the listening socket to the accepted connection, as with [`accept`][Self::accept].
 	pub fn poll_accept(&self, cx: &mut task::Context<'_>) -> task::Poll<io::Result<(AnyTokioStream, SockAddr)>> {
 		match self {
-			Self::Tcp(l) => l.poll_accept(cx).map_ok(Self::accept_tcp),
+			Self::Tcp(l) => l.poll_accept(cx).map(|result| result.and_then(|accepted| Self::accept_tcp(l, accepted))),
 			#[cfg(unix)] Self::Unix(l) => l.poll_accept(cx).map_ok(Self::accept_unix),
 		}
 	}
 
 	fn accept_tcp(
+		listener: &tokio::net::TcpListener,
 		(socket, addr): (tokio::net::TcpStream, std::net::SocketAddr),
-	) -> (AnyTokioStream, SockAddr) {
-		(socket.into(), addr.into())
+	) -> io::Result<(AnyTokioStream, SockAddr)> {
+		// Not every operating system copies `TCP_NODELAY` from the listening socket to accepted connections, so copy it here. If the listening socket can't be checked, assume it doesn't have `TCP_NODELAY`.
+		if socket2::SockRef::from(listener).nodelay().unwrap_or(false) {
+			socket.set_nodelay(true)?;
+		}
+
+		Ok((socket.into(), addr.into()))
 	}
 
 	#[cfg(unix)]
@@ -126,6 +156,64 @@ impl AnyTokioListener {
 		(socket.into(), unix_sockaddr_into(addr))
 	}
 
+	/// Returns a [`futures::Stream`] of accepted connections, each paired with its peer's address.
+	///
+	/// This is like the `Stream` implementation for `AnyTokioListener` itself, which yields only the connections, but keeps the peer addresses, such as for logging or for deciding whether to serve the connection. Each item is the result of [`poll_accept`][Self::poll_accept]. The stream never ends.
+	///
+	/// # Availability
+	///
+	/// Requires the `futures` feature.
+	#[cfg(feature = "futures")]
+	pub fn incoming_with_addr(&self) -> impl futures::Stream<Item = io::Result<(AnyTokioStream, SockAddr)>> + '_ {
+		futures::stream::poll_fn(move |cx| self.poll_accept(cx).map(Some))
+	}
+
+	/// Sets the IP time-to-live (the socket option `IP_TTL`) of connections accepted from now on.
+	///
+	/// This is set on the listening socket, and accepted connections inherit it. Connections that were already accepted are not affected.
+	///
+	/// # Errors
+	///
+	/// Fails with [`io::ErrorKind::Unsupported`] if this is not a TCP listening socket, or with any I/O error raised by the operating system.
+	pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+		self.tcp_listener()?.set_ttl(ttl)
+	}
+
+	/// Sets whether `TCP_NODELAY` is set on connections accepted from now on.
+	///
+	/// This is set on the listening socket, and copied to each accepted connection by [`accept`][Self::accept] and [`poll_accept`][Self::poll_accept], as with [`SocketUserOptions::tcp_nodelay`]. Connections that were already accepted are not affected.
+	///
+	/// # Errors
+	///
+	/// Fails with [`io::ErrorKind::Unsupported`] if this is not a TCP listening socket, or with any I/O error raised by the operating system.
+	pub fn set_accepted_nodelay(&self, nodelay: bool) -> io::Result<()> {
+		socket2::SockRef::from(self.tcp_listener()?).set_nodelay(nodelay)
+	}
+
+	/// Sets the TCP keepalive parameters of connections accepted from now on, as with [`SocketUserOptions::tcp_keepalive`] and related options. `None` disables TCP keepalive.
+	///
+	/// This is set on the listening socket, and accepted connections inherit it. Connections that were already accepted are not affected.
+	///
+	/// # Errors
+	///
+	/// Fails with [`io::ErrorKind::Unsupported`] if this is not a TCP listening socket, or with any I/O error raised by the operating system, such as if one of the parameters is not supported on this platform.
+	pub fn set_accepted_keepalive(&self, keepalive: Option<&socket2::TcpKeepalive>) -> io::Result<()> {
+		let listener = socket2::SockRef::from(self.tcp_listener()?);
+
+		match keepalive {
+			Some(keepalive) => listener.set_tcp_keepalive(keepalive),
+			None => listener.set_keepalive(false),
+		}
+	}
+
+	fn tcp_listener(&self) -> io::Result<&tokio::net::TcpListener> {
+		match self {
+			Self::Tcp(l) => Ok(l),
+			#[allow(unreachable_patterns)]
+			_ => Err(io::Error::new(io::ErrorKind::Unsupported, "this option only applies to TCP sockets")),
+		}
+	}
+
 	/// Returns the local address that this listener is bound to.
 	///
 	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::TcpListener::local_addr`] or [`tokio::net::UnixListener::local_addr`], as appropriate."#)]
@@ -136,6 +224,33 @@ impl AnyTokioListener {
 			#[cfg(unix)] Self::Unix(l) => l.local_addr().map(unix_sockaddr_into),
 		}
 	}
+
+	/// Stops accepting new connections, and closes this listener once the connections that are already waiting to be accepted have been collected.
+	///
+	/// When a listening socket is closed, any connections in its backlog that haven't been accepted yet are reset. To avoid that, this returns an [`AnyTokioListenerDrain`], which accepts those connections and then closes the listening socket. This is meant for graceful shutdown and zero-downtime restarts, where another process (such as a new version of this one) takes over new connections, and this process finishes serving the ones it has.
+	///
+	/// If `unlink_unix_path` is true and this is a path-based Unix-domain socket, the socket file is deleted first, so that new clients fail to connect right away instead of waiting in the backlog. Don't do this if a new socket may have already been bound at the same path, such as by a replacement process, because that would delete the new socket instead.
+	///
+	/// Connections that arrive while the backlog is being drained are accepted too. For TCP sockets, there's no way to stop that without closing the socket, but the window is short.
+	///
+	/// # Errors
+	///
+	/// Any I/O error raised by the operating system in taking the socket out of Tokio, or in deleting the socket file. In either case, the listener is closed.
+	pub fn close_and_drain(self, unlink_unix_path: bool) -> io::Result<AnyTokioListenerDrain> {
+		let listener: Socket = self.try_into()?;
+
+		if unlink_unix_path {
+			if let Ok(crate::SocketAddr::Unix { path }) = crate::SocketAddr::try_from(&listener.local_addr()?) {
+				crate::addr::cleanup_unix_path_socket(&path)?;
+			}
+		}
+
+		Ok(AnyTokioListenerDrain {
+			// If the listening socket can't be checked, assume it doesn't have `TCP_NODELAY`.
+			nodelay: listener.nodelay().unwrap_or(false),
+			listener: Some(listener),
+		})
+	}
 }
 
 impl TryFrom<AnyStdSocket> for AnyTokioListener {
@@ -192,6 +307,7 @@ impl TryFrom<AnyTokioListener> for Socket {
 	}
 }
 
+/// Yields accepted connections, without their peer addresses. Use [`AnyTokioListener::incoming_with_addr`] to get those too.
 #[cfg(feature = "futures")]
 impl futures::Stream for AnyTokioListener {
 	type Item = io::Result<AnyTokioStream>;
@@ -213,13 +329,13 @@ impl tls_listener::AsyncAccept for AnyTokioListener {
 		self: Pin<&mut Self>,
 		cx: &mut task::Context,
 	) -> task::Poll<Result<(Self::Connection, Self::Address), Self::Error>> {
-		(&*self).poll_accept(cx)
+		AnyTokioListener::poll_accept(&self, cx)
 	}
 }
 
 #[cfg(not(windows))]
 impl AsFd for AnyTokioListener {
-	fn as_fd(&self) -> BorrowedFd {
+	fn as_fd(&self) -> BorrowedFd<'_> {
 		match self {
 			Self::Tcp(l) => l.as_fd(),
 			#[cfg(unix)] Self::Unix(l) => l.as_fd(),
@@ -248,13 +364,67 @@ impl AsRawSocket for AnyTokioListener {
 
 #[cfg(windows)]
 impl AsSocket for AnyTokioListener {
-	fn as_socket(&self) -> BorrowedSocket {
+	fn as_socket(&self) -> BorrowedSocket<'_> {
 		match self {
 			Self::Tcp(l) => l.as_socket(),
 		}
 	}
 }
 
+/// Accepts the connections that were waiting in the backlog of an [`AnyTokioListener`] when it was closed. Returned by [`AnyTokioListener::close_and_drain`].
+///
+/// This is an [`Iterator`] of accepted connections. Accepting never waits, since only connections that are already waiting are accepted. When there are none left, the listening socket is closed, and the iterator ends. Dropping this closes the listening socket too, resetting any connections that are still waiting.
+///
+/// Each connection is accepted the same way as with [`AnyTokioListener::accept`], including copying `TCP_NODELAY`. This must be used from within a Tokio runtime, since each connection is registered with it.
+///
+/// # Availability
+///
+/// Requires the `tokio` feature.
+#[derive(Debug)]
+pub struct AnyTokioListenerDrain {
+	listener: Option<Socket>,
+	nodelay: bool,
+}
+
+impl AnyTokioListenerDrain {
+	/// Returns whether the listening socket has been closed, which happens once there are no more connections waiting.
+	pub fn is_closed(&self) -> bool {
+		self.listener.is_none()
+	}
+
+	fn accept(listener: &Socket, nodelay: bool) -> io::Result<(AnyTokioStream, SockAddr)> {
+		let (socket, peer_addr) = listener.accept()?;
+
+		if nodelay && (peer_addr.is_ipv4() || peer_addr.is_ipv6()) {
+			socket.set_nodelay(true)?;
+		}
+
+		Ok((socket.try_into()?, peer_addr))
+	}
+}
+
+impl Iterator for AnyTokioListenerDrain {
+	type Item = io::Result<(AnyTokioStream, SockAddr)>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let listener: &Socket = self.listener.as_ref()?;
+
+		loop {
+			match Self::accept(listener, self.nodelay) {
+				Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+
+				// The listening socket is still in non-blocking mode, so this means the backlog is empty.
+				Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+					self.listener = None;
+					return None;
+				}
+
+				result => return Some(result),
+			}
+		}
+	}
+}
+
 /// A connected [stream-type][socket2::Type::STREAM] socket, either TCP or Unix-domain, adapted for use with [`tokio`].
 ///
 /// `AnyTokioStream`s are usually obtained from a call to [`AnyTokioListener::accept`]. This type implements [`AsyncRead`] and [`AsyncWrite`], and is used to communicate with the connected peer in much the same way as a [`tokio::net::TcpStream`].
@@ -305,6 +475,94 @@ impl AnyTokioStream {
 			#[cfg(unix)] Self::Unix(s) => s.peer_addr().map(unix_sockaddr_into),
 		}
 	}
+
+	/// Receives data from the peer without removing it from the socket's receive queue (the `MSG_PEEK` flag), waiting until there is some. The same data will be received again by the next read or peek.
+	///
+	/// Returns the number of bytes peeked at, which is zero if the peer has closed the connection.
+	///
+	#[cfg_attr(unix, doc = r#"For TCP connections, this method delegates to [`tokio::net::TcpStream::peek`]. Tokio doesn't have an equivalent for [`tokio::net::UnixStream`], so for Unix-domain connections, this peeks with [`socket2`] instead."#)]
+	#[cfg_attr(not(unix), doc = r#"For TCP connections, this method delegates to [`tokio::net::TcpStream::peek`]."#)]
+	pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+		match self {
+			Self::Tcp(s) => s.peek(buf).await,
+			#[cfg(unix)] Self::Unix(s) => s.async_io(tokio::io::Interest::READABLE, || sockref_peek(socket2::SockRef::from(s), buf)).await,
+		}
+	}
+
+	/// Gets the credentials of the process on the other end of a Unix-domain connection. See [`peer_credentials`][crate::peer_credentials()] for details.
+	///
+	/// # Errors
+	///
+	/// Fails with [`io::ErrorKind::Unsupported`] if this is a TCP connection. Otherwise, same as `peer_credentials`.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)]
+	pub fn peer_credentials(&self) -> io::Result<PeerCredentials> {
+		crate::peer_credentials(&socket2::SockRef::from(self))
+	}
+
+	/// Returns the original destination address of a TCP connection that was redirected to this host by the firewall. See [`original_destination`][crate::original_destination()] for details.
+	///
+	/// # Errors
+	///
+	/// Any I/O error raised by the operating system. This always fails for Unix-domain sockets.
+	///
+	/// # Availability
+	///
+	/// Android, Linux, and Windows only.
+	#[cfg(any(target_os = "android", target_os = "linux", windows))]
+	pub fn original_destination(&self) -> io::Result<std::net::SocketAddr> {
+		match self {
+			Self::Tcp(s) => crate::original_destination(&socket2::SockRef::from(s)),
+			#[cfg(unix)] Self::Unix(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "Unix-domain sockets don't have an original destination address")),
+		}
+	}
+
+	/// Wraps this connection in a [`Framed`][tokio_util::codec::Framed], which uses `codec` to split the incoming byte stream into frames and to encode outgoing frames. The result is both a `Stream` of incoming frames and a `Sink` of outgoing ones, in the sense of the `futures` library.
+	///
+	/// The connection can be taken back out with [`Framed::into_inner`][tokio_util::codec::Framed::into_inner], but any data that was already read into the `Framed`'s buffer is lost that way.
+	///
+	/// # Availability
+	///
+	/// Requires the `tokio-util` feature.
+	#[cfg(feature = "tokio-util")]
+	pub fn framed<C>(self, codec: C) -> tokio_util::codec::Framed<Self, C> {
+		tokio_util::codec::Framed::new(self, codec)
+	}
+
+	/// Wraps this connection in a [`Framed`][tokio_util::codec::Framed] with a [`LengthDelimitedCodec`][tokio_util::codec::LengthDelimitedCodec] in its default configuration: each frame is preceded by its length, as a 32-bit big-endian integer, and frames are limited to 8 MiB.
+	///
+	/// For a different configuration, use [`framed`][Self::framed] with a codec made by [`LengthDelimitedCodec::builder`][tokio_util::codec::LengthDelimitedCodec::builder].
+	///
+	/// # Availability
+	///
+	/// Requires the `tokio-util` feature.
+	#[cfg(feature = "tokio-util")]
+	pub fn framed_length_delimited(self) -> tokio_util::codec::Framed<Self, tokio_util::codec::LengthDelimitedCodec> {
+		self.framed(tokio_util::codec::LengthDelimitedCodec::new())
+	}
+
+	/// Wraps this connection in a [`Framed`][tokio_util::codec::Framed] with a [`LinesCodec`][tokio_util::codec::LinesCodec], which splits the incoming data into UTF-8 lines, and sends each outgoing string followed by a line feed.
+	///
+	/// Incoming lines longer than `max_length` bytes are discarded, and reported as an error. A limit is required, since otherwise a peer could make the server buffer an endless line.
+	///
+	/// # Availability
+	///
+	/// Requires the `tokio-util` feature.
+	#[cfg(feature = "tokio-util")]
+	pub fn framed_lines(self, max_length: usize) -> tokio_util::codec::Framed<Self, tokio_util::codec::LinesCodec> {
+		self.framed(tokio_util::codec::LinesCodec::new_with_max_length(max_length))
+	}
+}
+
+/// Peeks at received data with [`socket2`], whose `peek` method takes a buffer of `MaybeUninit<u8>` instead of `u8`.
+#[cfg(unix)]
+fn sockref_peek(socket: socket2::SockRef<'_>, buf: &mut [u8]) -> io::Result<usize> {
+	// Safety: `peek` only ever writes initialized bytes into the buffer, so the buffer is still fully initialized afterward.
+	let buf = unsafe { &mut *(buf as *mut [u8] as *mut [std::mem::MaybeUninit<u8>]) };
+	socket.peek(buf)
 }
 
 impl AsyncRead for AnyTokioStream {
@@ -427,7 +685,7 @@ impl TryFrom<AnyTokioStream> for Socket {
 
 #[cfg(not(windows))]
 impl AsFd for AnyTokioStream {
-	fn as_fd(&self) -> BorrowedFd {
+	fn as_fd(&self) -> BorrowedFd<'_> {
 		match self {
 			Self::Tcp(s) => s.as_fd(),
 			#[cfg(unix)] Self::Unix(s) => s.as_fd(),
@@ -456,9 +714,293 @@ impl AsRawSocket for AnyTokioStream {
 
 #[cfg(windows)]
 impl AsSocket for AnyTokioStream {
-	fn as_socket(&self) -> BorrowedSocket {
+	fn as_socket(&self) -> BorrowedSocket<'_> {
 		match self {
 			Self::Tcp(s) => s.as_socket(),
 		}
 	}
 }
+
+/// Lets a [`tonic`] server accept connections from an [`AnyTokioListener`], such as with [`Server::serve_with_incoming`][tonic::transport::server::Router::serve_with_incoming], and makes information about each connection available to request handlers as an [`AnyTokioConnectInfo`].
+///
+/// # Availability
+///
+/// Requires the `tonic` feature.
+#[cfg(feature = "tonic")]
+impl tonic::transport::server::Connected for AnyTokioStream {
+	type ConnectInfo = AnyTokioConnectInfo;
+
+	fn connect_info(&self) -> Self::ConnectInfo {
+		match self {
+			Self::Tcp(s) => AnyTokioConnectInfo::Tcp(s.connect_info()),
+			#[cfg(unix)] Self::Unix(s) => AnyTokioConnectInfo::Unix(s.connect_info()),
+		}
+	}
+}
+
+/// Information about a connection accepted by a [`tonic`] server from an [`AnyTokioListener`].
+///
+/// A request handler can get this with [`Request::extensions`][tonic::Request::extensions], like so:
+///
+/// ```no_run
+/// # use socket_config::convert::AnyTokioConnectInfo;
+/// # fn example_fn(request: tonic::Request<()>) {
+/// match request.extensions().get::<AnyTokioConnectInfo>() {
+/// 	Some(AnyTokioConnectInfo::Tcp(info)) => println!("TCP connection from {:?}", info.remote_addr()),
+/// 	Some(other) => println!("other connection: {other:?}"),
+/// 	None => println!("no connection information"),
+/// }
+/// # }
+/// ```
+///
+/// Note that [`Request::remote_addr`][tonic::Request::remote_addr] only looks for tonic's own connection information types, so it always returns `None` for connections accepted from an `AnyTokioListener`. Use this type instead.
+///
+///
+/// # Availability
+///
+/// All platforms, but the `Unix` variant is only available on Unix-like platforms.
+///
+/// Requires the `tonic` feature.
+#[cfg(feature = "tonic")]
+#[derive(Clone, Debug)]
+pub enum AnyTokioConnectInfo {
+	/// A TCP connection.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	Tcp(tonic::transport::server::TcpConnectInfo),
+
+	/// A Unix-domain connection.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)] Unix(tonic::transport::server::UdsConnectInfo),
+}
+
+/// A [datagram-type][socket2::Type::DGRAM] socket, either UDP or Unix-domain, adapted for use with [`tokio`].
+///
+/// Much like [`tokio::net::UdpSocket`], an `AnyTokioDatagram` sends and receives datagrams with methods like [`send_to`][Self::send_to] and [`recv_from`][Self::recv_from]. Addresses are given as [`socket2::SockAddr`], so the same code works with both UDP and Unix-domain sockets.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::AnyTokioDatagram;
+/// # use std::io;
+/// # async fn example_fn() -> io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// let socket: AnyTokioDatagram = socket_config::open(
+/// 	&address,
+/// 	&socket_config::SocketAppOptions::new(socket2::Type::DGRAM),
+/// 	&user_options,
+/// )?.try_into()?;
+///
+/// let mut buf = [0u8; 1500];
+///
+/// loop {
+/// 	// Echo each datagram back to where it came from.
+/// 	let (size, peer_addr) = socket.recv_from(&mut buf).await?;
+/// 	socket.send_to(&buf[..size], &peer_addr).await?;
+/// }
+/// # }
+/// ```
+///
+/// The call to `try_into` will fail with an [`IntoTokioError`] if the socket is inappropriate, such as a TCP socket, or a datagram socket using a transport protocol other than UDP.
+///
+///
+/// # Availability
+///
+/// All platforms, but the `Unix` variant is only available on Unix-like platforms. Converting a Unix-domain socket on Windows will result in an error.
+///
+/// Requires the `tokio` feature.
+#[derive(Debug, derive_more::From)]
+#[non_exhaustive]
+pub enum AnyTokioDatagram {
+	/// A UDP socket.
+	///
+	/// # Availability
+	///
+	/// All platforms.
+	Udp(tokio::net::UdpSocket),
+
+	/// A Unix-domain datagram socket.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only. Tokio currently does not support Unix-domain sockets on Windows.
+	#[cfg(unix)] Unix(tokio::net::UnixDatagram),
+}
+
+impl AnyTokioDatagram {
+	/// Sends a datagram to the given address.
+	///
+	/// # Errors
+	///
+	/// Any I/O error raised by the operating system. This also fails with [`io::ErrorKind::InvalidInput`] if this is a UDP socket and `target` is not an IP socket address.
+	pub async fn send_to(&self, buf: &[u8], target: &SockAddr) -> io::Result<usize> {
+		match self {
+			Self::Udp(s) => {
+				let target: std::net::SocketAddr =
+					target.as_socket()
+					.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "can't send to a non-IP address on a UDP socket"))?;
+
+				s.send_to(buf, target).await
+			},
+
+			// Tokio only takes a path, which can't express every Unix-domain socket address (such as an abstract one), so send through `socket2` instead.
+			#[cfg(unix)] Self::Unix(s) => s.async_io(tokio::io::Interest::WRITABLE, || socket2::SockRef::from(s).send_to(buf, target)).await,
+		}
+	}
+
+	/// Receives a datagram, and returns its size and the address it came from.
+	///
+	/// If the datagram is bigger than `buf`, the rest of it is discarded.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::UdpSocket::recv_from`] or [`tokio::net::UnixDatagram::recv_from`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`tokio::net::UdpSocket::recv_from`]."#)]
+	pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SockAddr)> {
+		match self {
+			Self::Udp(s) => s.recv_from(buf).await.map(|(size, addr)| (size, addr.into())),
+			#[cfg(unix)] Self::Unix(s) => s.recv_from(buf).await.map(|(size, addr)| (size, unix_sockaddr_into(addr))),
+		}
+	}
+
+	/// Sends a datagram to the peer that this socket is connected to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::UdpSocket::send`] or [`tokio::net::UnixDatagram::send`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`tokio::net::UdpSocket::send`]."#)]
+	pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+		match self {
+			Self::Udp(s) => s.send(buf).await,
+			#[cfg(unix)] Self::Unix(s) => s.send(buf).await,
+		}
+	}
+
+	/// Receives a datagram from the peer that this socket is connected to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::UdpSocket::recv`] or [`tokio::net::UnixDatagram::recv`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`tokio::net::UdpSocket::recv`]."#)]
+	pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+		match self {
+			Self::Udp(s) => s.recv(buf).await,
+			#[cfg(unix)] Self::Unix(s) => s.recv(buf).await,
+		}
+	}
+
+	/// Returns the local address that this socket is bound to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::UdpSocket::local_addr`] or [`tokio::net::UnixDatagram::local_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`tokio::net::UdpSocket::local_addr`]."#)]
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Udp(s) => s.local_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(s) => s.local_addr().map(unix_sockaddr_into),
+		}
+	}
+
+	/// Returns the remote address that this socket is connected to.
+	///
+	#[cfg_attr(unix, doc = r#"This method delegates to [`tokio::net::UdpSocket::peer_addr`] or [`tokio::net::UnixDatagram::peer_addr`], as appropriate."#)]
+	#[cfg_attr(not(unix), doc = r#"This method delegates to [`tokio::net::UdpSocket::peer_addr`]."#)]
+	pub fn peer_addr(&self) -> io::Result<SockAddr> {
+		match self {
+			Self::Udp(s) => s.peer_addr().map(SockAddr::from),
+			#[cfg(unix)] Self::Unix(s) => s.peer_addr().map(unix_sockaddr_into),
+		}
+	}
+}
+
+impl TryFrom<AnyStdSocket> for AnyTokioDatagram {
+	type Error = IntoTokioError;
+
+	fn try_from(socket: AnyStdSocket) -> Result<Self, Self::Error> {
+		match socket {
+			AnyStdSocket::UdpSocket(s) => {
+				s.set_nonblocking(true)
+				.map_err(|error| IntoTokioError::SetNonBlocking { error })?;
+
+				let s = s.try_into().map_err(|error| IntoTokioError::Wrap { error })?;
+
+				Ok(Self::Udp(s))
+			}
+
+			#[cfg(unix)]
+			AnyStdSocket::UnixDatagram(s) => {
+				s.set_nonblocking(true)
+				.map_err(|error| IntoTokioError::SetNonBlocking { error })?;
+
+				let s = s.try_into().map_err(|error| IntoTokioError::Wrap { error })?;
+
+				Ok(Self::Unix(s))
+			}
+
+			_ => Err(IntoTokioError::Inappropriate {
+				socket,
+			}),
+		}
+	}
+}
+
+impl TryFrom<Socket> for AnyTokioDatagram {
+	type Error = IntoTokioError;
+
+	fn try_from(socket: Socket) -> Result<Self, Self::Error> {
+		let socket: AnyStdSocket =
+			socket.try_into()
+			.map_err(|error| IntoTokioError::Check { error })?;
+
+		socket.try_into()
+	}
+}
+
+impl TryFrom<AnyTokioDatagram> for Socket {
+	type Error = io::Error;
+
+	fn try_from(socket: AnyTokioDatagram) -> Result<Self, Self::Error> {
+		match socket {
+			AnyTokioDatagram::Udp(s) => s.into_std().map(Socket::from),
+			#[cfg(unix)] AnyTokioDatagram::Unix(s) => s.into_std().map(Socket::from),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsFd for AnyTokioDatagram {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		match self {
+			Self::Udp(s) => s.as_fd(),
+			#[cfg(unix)] Self::Unix(s) => s.as_fd(),
+		}
+	}
+}
+
+#[cfg(not(windows))]
+impl AsRawFd for AnyTokioDatagram {
+	fn as_raw_fd(&self) -> RawFd {
+		match self {
+			Self::Udp(s) => s.as_raw_fd(),
+			#[cfg(unix)] Self::Unix(s) => s.as_raw_fd(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsRawSocket for AnyTokioDatagram {
+	fn as_raw_socket(&self) -> RawSocket {
+		match self {
+			Self::Udp(s) => s.as_raw_socket(),
+		}
+	}
+}
+
+#[cfg(windows)]
+impl AsSocket for AnyTokioDatagram {
+	fn as_socket(&self) -> BorrowedSocket<'_> {
+		match self {
+			Self::Udp(s) => s.as_socket(),
+		}
+	}
+}
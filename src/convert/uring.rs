@@ -0,0 +1,226 @@
+use crate::convert::AnyStdSocket;
+use io_uring::{opcode, types, IoUring};
+use socket2::{SockAddr, Socket};
+use std::{
+	collections::VecDeque,
+	io,
+	os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, RawFd},
+};
+
+/// A [stream-type][socket2::Type::STREAM] listening socket, either TCP or Unix-domain, that accepts connections using [io_uring](https://en.wikipedia.org/wiki/Io_uring).
+///
+/// An `AnyUringListener` keeps a number of accept operations queued in its own io_uring instance. Each call to [`accept`][Self::accept] either returns a connection that was already accepted, or submits more accept operations and waits for at least one of them to finish, collecting every connection that has been accepted by then with the same system call. Under a high connection rate, this accepts many connections per system call, instead of one `epoll_wait` and one `accept4` per connection.
+///
+/// This is blocking I/O: `accept` blocks the calling thread until a connection is accepted. It's meant for a dedicated acceptor thread, which hands the accepted connections off to other threads or to an asynchronous runtime. (`tokio-uring` can't be used for this, because it can't adopt a listening socket that was opened elsewhere.)
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::convert::AnyUringListener;
+/// # use std::io;
+/// # fn example_fn() -> io::Result<()> {
+/// # let address: socket_config::SocketAddr = unimplemented!();
+/// # let app_options: socket_config::SocketAppOptions = unimplemented!();
+/// # let user_options: socket_config::SocketUserOptions = unimplemented!();
+/// let socket = socket_config::open(&address, &app_options, &user_options)?;
+/// let mut listener = AnyUringListener::new(socket, 64)?;
+///
+/// loop {
+/// 	let (connection, peer_addr): (socket2::Socket, socket2::SockAddr) =
+/// 		listener.accept()?;
+///
+/// 	// …hand the connection off to a worker…
+/// }
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Linux only. Requires Linux 5.5 or later, and that io_uring is not disabled (such as by the `kernel.io_uring_disabled` sysctl or a seccomp filter).
+///
+/// Requires the `io-uring` feature.
+pub struct AnyUringListener {
+	listener: Socket,
+	ring: IoUring,
+	depth: u32,
+	in_flight: u32,
+	accepted: VecDeque<io::Result<Socket>>,
+}
+
+impl AnyUringListener {
+	/// Sets up an io_uring instance for accepting connections on `listener`, with up to `depth` accept operations queued at a time.
+	///
+	/// `listener` must be a TCP or Unix-domain listening socket, such as one opened by [`open`][crate::open()].
+	///
+	///
+	/// # Errors
+	///
+	/// Fails with [`io::ErrorKind::InvalidInput`] if `listener` is not a TCP or Unix-domain listening socket, or if `depth` is zero, or with any I/O error raised by the operating system in setting up io_uring.
+	pub fn new(listener: Socket, depth: u32) -> io::Result<Self> {
+		if depth == 0 {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "the io_uring accept queue depth must be at least 1"));
+		}
+
+		let listener: Socket = match AnyStdSocket::try_from(listener)? {
+			listener @ (AnyStdSocket::TcpListener(_) | AnyStdSocket::UnixListener(_)) => listener.into(),
+			_ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "io_uring accept requires a TCP or Unix-domain listening socket")),
+		};
+
+		Ok(Self {
+			listener,
+			ring: IoUring::new(depth)?,
+			depth,
+			in_flight: 0,
+			accepted: VecDeque::new(),
+		})
+	}
+
+	/// Accepts a new connection, blocking until there is one.
+	///
+	/// Accepted connections are not inheritable by child processes, and are in blocking mode.
+	///
+	/// If this is a TCP listening socket with `TCP_NODELAY` set (such as with [`SocketUserOptions::tcp_nodelay`][crate::SocketUserOptions::tcp_nodelay]), then `TCP_NODELAY` is also set on the accepted connection, the same as with [`AnyTokioListener::accept`][crate::convert::AnyTokioListener::accept].
+	///
+	///
+	/// # Errors
+	///
+	/// Any I/O error raised by the operating system, either in accepting the connection or in waiting on io_uring. As with an ordinary `accept`, an error in accepting one connection doesn't stop this listener from accepting others.
+	pub fn accept(&mut self) -> io::Result<(Socket, SockAddr)> {
+		let socket: Socket = loop {
+			if let Some(accepted) = self.accepted.pop_front() {
+				break accepted?;
+			}
+
+			self.submit_and_wait()?;
+		};
+
+		let peer_addr: SockAddr = socket.peer_addr()?;
+
+		if peer_addr.is_ipv4() || peer_addr.is_ipv6() {
+			// Not every operating system copies `TCP_NODELAY` from the listening socket to accepted connections, so copy it here. If the listening socket can't be checked, assume it doesn't have `TCP_NODELAY`.
+			if self.listener.nodelay().unwrap_or(false) {
+				socket.set_nodelay(true)?;
+			}
+		}
+
+		Ok((socket, peer_addr))
+	}
+
+	/// Returns the local address that this listener is bound to.
+	pub fn local_addr(&self) -> io::Result<SockAddr> {
+		self.listener.local_addr()
+	}
+
+	/// Tops up the queued accept operations, and waits for at least one of them to finish.
+	fn submit_and_wait(&mut self) -> io::Result<()> {
+		let fd = types::Fd(self.listener.as_raw_fd());
+
+		while self.in_flight < self.depth {
+			// The peer address is looked up after accepting, so the kernel has nothing to write into this process's memory, and the operations can safely outlive any particular call.
+			let entry =
+				opcode::Accept::new(fd, std::ptr::null_mut(), std::ptr::null_mut())
+				.flags(libc::SOCK_CLOEXEC)
+				.build();
+
+			// Safety: The accept operation doesn't refer to any memory in this process.
+			if unsafe { self.ring.submission().push(&entry) }.is_err() {
+				break;
+			}
+
+			self.in_flight += 1;
+		}
+
+		loop {
+			match self.ring.submit_and_wait(1) {
+				Ok(_) => break,
+				Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+				Err(error) => return Err(error),
+			}
+		}
+
+		for completion in self.ring.completion() {
+			self.in_flight -= 1;
+
+			let result: i32 = completion.result();
+
+			self.accepted.push_back(
+				if result >= 0 {
+					// Safety: A successful accept operation's result is the new connection's file descriptor, which now belongs to this process.
+					Ok(unsafe { Socket::from_raw_fd(result) })
+				}
+				else {
+					Err(io::Error::from_raw_os_error(-result))
+				}
+			);
+		}
+
+		Ok(())
+	}
+}
+
+impl Drop for AnyUringListener {
+	fn drop(&mut self) {
+		// Close any connections that were accepted, but not yet collected. Accept operations that haven't finished are cancelled when the ring is closed.
+		for completion in self.ring.completion() {
+			if completion.result() >= 0 {
+				// Safety: See `submit_and_wait`.
+				drop(unsafe { Socket::from_raw_fd(completion.result()) });
+			}
+		}
+	}
+}
+
+impl std::fmt::Debug for AnyUringListener {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("AnyUringListener")
+		.field("listener", &self.listener)
+		.field("depth", &self.depth)
+		.field("in_flight", &self.in_flight)
+		.finish_non_exhaustive()
+	}
+}
+
+impl AsFd for AnyUringListener {
+	fn as_fd(&self) -> BorrowedFd<'_> {
+		self.listener.as_fd()
+	}
+}
+
+impl AsRawFd for AnyUringListener {
+	fn as_raw_fd(&self) -> RawFd {
+		self.listener.as_raw_fd()
+	}
+}
+
+#[test]
+fn test_uring_listener() {
+	let listener: Socket = std::net::TcpListener::bind("127.0.0.1:0").unwrap().into();
+	listener.set_nodelay(true).unwrap();
+	let local_addr = listener.local_addr().unwrap().as_socket().unwrap();
+
+	let mut listener = match AnyUringListener::new(listener, 4) {
+		Ok(listener) => listener,
+
+		// io_uring is disabled in some environments, such as some containers. There's nothing to test there.
+		Err(error) if matches!(error.raw_os_error(), Some(libc::EPERM | libc::ENOSYS)) => return,
+
+		Err(error) => panic!("{error}"),
+	};
+
+	let clients: Vec<std::net::TcpStream> =
+		(0..3)
+		.map(|_| std::net::TcpStream::connect(local_addr).unwrap())
+		.collect();
+
+	for _ in &clients {
+		let (connection, peer_addr) = listener.accept().unwrap();
+		assert!(clients.iter().any(|client| Some(client.local_addr().unwrap()) == peer_addr.as_socket()));
+		assert!(connection.nodelay().unwrap());
+	}
+
+	// A UDP socket isn't a listening socket.
+	let udp: Socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap().into();
+	assert_eq!(AnyUringListener::new(udp, 4).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
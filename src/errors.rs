@@ -3,25 +3,50 @@
 use std::{
 	io,
 	net,
+	net::IpAddr,
 	num::ParseIntError,
+	ops::RangeInclusive,
+	path::PathBuf,
 };
 
+use crate::{
+	AddressKinds,
+	SocketAddr,
+};
+
+#[cfg(unix)]
+use crate::PeerCredentials;
+
 #[cfg(doc)]
 use {
 	crate::{
 		convert,
+		ConnectAppOptions,
+		IpTos,
 		open,
-		SocketAddr,
+		open_connect,
+		open_connect_happy_eyeballs,
+		open_multiple,
+		RelativeUnixPaths,
 		SocketAppOptions,
 		SocketUserOptions,
 	},
 	std::str::FromStr,
 };
 
+#[cfg(all(doc, unix))]
+use crate::UnixPeerAcl;
+
 #[cfg(all(doc, feature = "tokio"))]
-use crate::convert::{AnyTokioListener, AnyTokioStream};
+use crate::convert::{AnyTokioDatagram, AnyTokioListener, AnyTokioStream};
 
-#[cfg(feature = "tokio")]
+#[cfg(all(doc, feature = "async-std"))]
+use crate::convert::{AnyAsyncStdListener, AnyAsyncStdStream};
+
+#[cfg(all(doc, feature = "smol"))]
+use crate::convert::{AnySmolListener, AnySmolStream};
+
+#[cfg(any(feature = "async-std", feature = "smol", feature = "tokio"))]
 use crate::convert::AnyStdSocket;
 
 /// An error parsing a [`SocketAddr`] [from a string][FromStr].
@@ -29,7 +54,7 @@ use crate::convert::AnyStdSocket;
 #[non_exhaustive]
 pub enum InvalidSocketAddrError {
 	/// The socket address did not fit one of the acceptable patterns.
-	#[error("invalid socket address: must be a valid IP address and port, a Unix-domain socket path, `stdin`, `fd:n`, `socket:n`, or `systemd:n`")]
+	#[error("invalid socket address: must be a valid IP address and port, `*:port`, a Unix-domain socket path, `@name`, `vsock:cid:port`, `stdin`, `fd:n`, `socket:n`, `env:name`, `systemd`, `systemd:n`, `systemd-name:name`, `launchd:name`, `wsainfo:data`, or `pair`")]
 	#[non_exhaustive]
 	Unrecognized {
 		/// The error that occurred when attempting to parse the socket address as an IP address and port.
@@ -44,9 +69,69 @@ pub enum InvalidSocketAddrError {
 		#[source]
 		error: ParseIntError,
 	},
+
+	/// The socket address is in a form with a port number, such as <code>*:<var>port</var></code>, <code>:<var>port</var></code>, or <code>[<var>addr</var>%<var>zone</var>]:<var>port</var></code>, but <code><var>port</var></code> could not be parsed as a port number.
+	#[error("invalid socket address: `port` is not a valid port number: {error}")]
+	#[non_exhaustive]
+	InvalidPort {
+		#[source]
+		error: ParseIntError,
+	},
+
+	/// The socket address is an IP address with a port range, in the form <code><var>first</var>-<var>last</var></code>, but <code><var>first</var></code> is greater than <code><var>last</var></code>.
+	#[error("invalid socket address: the first port of a port range must not be greater than the last")]
+	#[non_exhaustive]
+	InvalidPortRange,
+
+	/// The socket address contains `%`, indicating an IPv6 address with a zone ID, but it is not of the form <code><var>addr</var>%<var>zone</var></code> or <code>[<var>addr</var>%<var>zone</var>]:<var>port</var></code>, or the zone ID is empty.
+	#[error("invalid socket address: an IPv6 address with a zone ID must be of the form `addr%zone` or `[addr%zone]:port`")]
+	#[non_exhaustive]
+	InvalidZone,
+
+	/// The socket address has a query string (like `?mode=660`), but one of the options in it is not recognized on this platform.
+	///
+	/// See [`SocketAddrOptions`][crate::SocketAddrOptions] for the recognized options.
+	#[error("invalid socket address: unrecognized option `{key}`")]
+	#[non_exhaustive]
+	UnknownOption {
+		/// The name of the option.
+		key: String,
+	},
+
+	/// The socket address has a query string (like `?mode=660`), but the value of one of the options in it is not valid for that option.
+	#[error("invalid socket address: invalid value `{value}` for option `{key}`")]
+	#[non_exhaustive]
+	InvalidOptionValue {
+		/// The name of the option.
+		key: String,
+
+		/// The value that could not be parsed.
+		value: String,
+	},
+
+	/// The socket address was parsed with [`SocketAddr::from_tokio_listener_str`], and is in a form that has no equivalent in this library, or not on this platform.
+	#[error("invalid socket address: `{addr}` has no equivalent in this library on this platform")]
+	#[non_exhaustive]
+	UnsupportedCompat {
+		/// The socket address that was being parsed.
+		addr: String,
+	},
+
+	/// The socket address is in the form <code>vsock:<var>cid</var>:<var>port</var></code>, but <code><var>cid</var></code> or <code><var>port</var></code> is missing or could not be parsed as an integer.
+	#[error("invalid socket address: it is of the form `vsock:cid:port`, but `cid` or `port` is missing or not a valid integer: {error}")]
+	#[non_exhaustive]
+	InvalidVsock {
+		#[source]
+		error: ParseIntError,
+	},
+
+	/// The socket address is in the form <code>wsainfo:<var>data</var></code>, but <code><var>data</var></code> is not valid base64.
+	#[error("invalid socket address: it is of the form `wsainfo:data`, but `data` is not valid base64")]
+	#[non_exhaustive]
+	InvalidWsaProtocolInfo,
 }
 
-/// An error that occurred in [opening][open()] a socket.
+/// An error that occurred in [opening][open()] or [connecting][open_connect()] a socket.
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum OpenSocketError {
@@ -61,6 +146,19 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
+	/// The [`SocketAddr`] is a [`SocketAddr::Unix`] with a relative path, but [`SocketAppOptions::relative_unix_paths`] is [`RelativeUnixPaths::Reject`].
+	#[error("the Unix-domain socket path `{}` is relative, but it must be absolute", .path.display())]
+	#[non_exhaustive]
+	RelativeUnixPath {
+		/// The relative path.
+		path: PathBuf,
+	},
+
+	/// The [`SocketAddr`] is of a kind that is not supported on this platform, such as a [`SocketAddr::UnixAbstract`] on a platform that doesn't have abstract Unix-domain sockets, a [`SocketAddr::Vsock`] on a platform that doesn't have vsock, a [`SocketAddr::Launchd`] on a platform other than macOS, or a [`SocketAddr::WsaProtocolInfo`] on a platform other than Windows.
+	#[error("this kind of socket address is not supported on this platform")]
+	#[non_exhaustive]
+	UnsupportedSocketAddr,
+
 	/// The [`SocketAddr`] specifies a socket inherited from systemd socket activation, but no such socket was inherited.
 	///
 	/// # Availability
@@ -71,6 +169,75 @@ pub enum OpenSocketError {
 	#[non_exhaustive]
 	InvalidSystemdFd,
 
+	/// The [`SocketAddr`] specifies a socket inherited from systemd socket activation by name, but no socket with that name was inherited.
+	///
+	/// # Availability
+	///
+	/// Non-Windows platforms only.
+	#[cfg(not(windows))]
+	#[error("no inherited socket named `{name}` (according to the `LISTEN_PID`, `LISTEN_FDS`, and `LISTEN_FDNAMES` environment variables)")]
+	#[non_exhaustive]
+	InvalidSystemdName {
+		/// The name that was looked up.
+		name: String,
+	},
+
+	/// The [`SocketAddr`] specifies a socket inherited from launchd socket activation, but it couldn't be retrieved from launchd.
+	///
+	/// The most likely causes are that the process wasn't started by launchd (`ESRCH`), or the launchd job's property list has no socket with the given name (`ENOENT`).
+	///
+	/// # Availability
+	///
+	/// macOS only.
+	#[cfg(target_os = "macos")]
+	#[error("couldn't get socket from launchd: {error}")]
+	#[non_exhaustive]
+	LaunchdActivate {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// The [`SocketAddr`] is a [`SocketAddr::WsaProtocolInfo`], but a socket couldn't be created from it with `WSASocketW`.
+	///
+	/// The most likely causes are that the `WSAPROTOCOL_INFOW` structure was made for a different process, or it was already used. If the structure has the wrong size, the error kind is [`io::ErrorKind::InvalidInput`].
+	///
+	/// # Availability
+	///
+	/// Windows only.
+	#[cfg(windows)]
+	#[error("couldn't create socket from `WSAPROTOCOL_INFOW`: {error}")]
+	#[non_exhaustive]
+	WsaProtocolInfo {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// The [`SocketAddr`] specifies a socket whose number is in an environment variable, but that environment variable is not set or is not valid Unicode.
+	#[error("couldn't read environment variable `{var}`: {error}")]
+	#[non_exhaustive]
+	InheritEnvVar {
+		/// The name of the environment variable.
+		var: String,
+
+		/// The error that this one arose from.
+		#[source]
+		error: std::env::VarError,
+	},
+
+	/// The [`SocketAddr`] specifies a socket whose number is in an environment variable, but the environment variable's value could not be parsed as a socket file descriptor or handle.
+	#[error("environment variable `{var}` does not contain a valid socket number: {error}")]
+	#[non_exhaustive]
+	InheritEnvSocketNum {
+		/// The name of the environment variable.
+		var: String,
+
+		/// The error that this one arose from.
+		#[source]
+		error: ParseIntError,
+	},
+
 	/// There was an error getting the standard input handle.
 	///
 	/// # Availability
@@ -98,11 +265,19 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
+	/// [`SocketAppOptions::exclusive_inherit`] is true, and the inherited socket has already been opened in this process.
+	#[error("inherited socket {socket} has already been opened; the same socket can't be used twice")]
+	#[non_exhaustive]
+	InheritedAlreadyClaimed {
+		/// The inherited socket's file descriptor number or Windows `SOCKET` handle.
+		socket: crate::sys::RawSocket,
+	},
+
 	/// The [`SocketAddr`] specifies a socket inherited from the parent process (including systemd socket activation), but while the socket does exist, it has the wrong type.
 	#[error("inherited socket has wrong type (expected `{expected:?}`; got `{actual:?}`)")]
 	#[non_exhaustive]
 	InheritWrongType {
-		/// The type that the socket was expected to have.
+		/// The type that the socket was expected to have, which is [`SocketAppOptions::type`]. (The socket also didn't have any of the [`SocketAppOptions::other_inherited_types`].)
 		expected: socket2::Type,
 
 		/// The type that the socket actually has.
@@ -117,6 +292,16 @@ pub enum OpenSocketError {
 		name: &'static str,
 	},
 
+	/// A user option was used that is not supported on this platform.
+	///
+	/// Unlike [`OpenSocketError::InapplicableUserOption`], this error does not depend on what kind of socket is being opened. The option is accepted when parsing options, so that the same configuration can be used on several platforms, but it cannot be honored on this one.
+	#[error("the `{name}` option is not supported on this platform")]
+	#[non_exhaustive]
+	UnsupportedUserOption {
+		/// The name of the option that is not supported, as it appears in the API documentation, such as `ip_socket_reuse_port`.
+		name: &'static str,
+	},
+
 	/// [`socket2::Socket::new`] failed.
 	#[error("couldn't create socket: {error}")]
 	#[non_exhaustive]
@@ -126,6 +311,45 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
+	/// [`SocketAppOptions::type`] is [`socket2::Type::RAW`], and [`socket2::Socket::new`] failed because the process does not have permission to create raw sockets.
+	///
+	/// Most operating systems require special privileges to create raw sockets, such as the capability `CAP_NET_RAW` on Linux, or administrator rights on Windows.
+	#[error("couldn't create raw socket, probably because raw sockets require special privileges (such as `CAP_NET_RAW` on Linux, or administrator rights on Windows): {error}")]
+	#[non_exhaustive]
+	RawPermissionDenied {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// [`SocketUserOptions::ip_socket_transparent`] was used, but setting it failed because the process does not have permission to do so.
+	///
+	/// # Availability
+	///
+	/// Linux only.
+	#[cfg(target_os = "linux")]
+	#[error("couldn't make the socket transparent, probably because `ip_socket_transparent` requires the capability `CAP_NET_ADMIN`: {error}")]
+	#[non_exhaustive]
+	TransparentPermissionDenied {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// [`SocketAppOptions::type`] is [`socket2::Type::RAW`], but [`SocketAppOptions::protocol`] is `None`. Raw sockets require a protocol to be specified explicitly.
+	#[error("raw sockets require a protocol to be specified")]
+	#[non_exhaustive]
+	RawProtocolRequired,
+
+	/// The [`SocketAddr`] is a [`SocketAddr::Pair`], but there was an error creating the pair of sockets.
+	#[error("couldn't create socket pair: {error}")]
+	#[non_exhaustive]
+	CreatePair {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
 	/// The socket is a path-based Unix-domain socket, but there was an error creating any needed parent folders.
 	#[error("couldn't create parent folders: {error}")]
 	#[non_exhaustive]
@@ -151,10 +375,43 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
-	/// [`SocketAppOptions::before_bind`] was used, and it returned an error.
+	/// The address is not one of the [`SocketAppOptions::allowed_kinds`], or [`open_connection`][crate::open_connection] was given an address that is neither inherited nor a socket pair.
+	#[error("this application doesn't accept {} addresses", .kind.name())]
+	#[non_exhaustive]
+	AddressKindNotAllowed {
+		/// The kind of address that was given.
+		kind: AddressKinds,
+	},
+
+	/// [`SocketAppOptions::validate_addr`] was used, and it rejected the address.
+	#[error("{reason}")]
+	#[non_exhaustive]
+	AddressRejected {
+		/// The reason given by `validate_addr`.
+		reason: Box<dyn std::error::Error + Send + Sync>,
+	},
+
+	/// [`SocketAppOptions::before_bind`] (or, with the `tokio` feature, `SocketAppOptions::before_bind_async`) was used, and it returned an error. This is also raised if setting the socket options of the [`SocketAppOptions::preset`] failed.
 	#[error("{0}")]
 	BeforeBind(io::Error),
 
+	/// [`SocketAppOptions::before_bind_async`] was set, but the socket was opened with a blocking function such as [`open`], which can't call it. Use [`open_async`][crate::open_async()] instead.
+	///
+	/// # Availability
+	///
+	/// Requires the `tokio` feature.
+	#[cfg(feature = "tokio")]
+	#[error("this application tried to open a socket with an asynchronous hook using a blocking function; this is a bug in the application")]
+	AsyncHookInBlockingOpen,
+
+	/// [`SocketAppOptions::after_bind`] was used, and it returned an error.
+	#[error("{0}")]
+	AfterBind(io::Error),
+
+	/// [`SocketAppOptions::before_listen`] was used, and it returned an error.
+	#[error("{0}")]
+	BeforeListen(io::Error),
+
 	/// [`socket2::Socket::bind`] failed.
 	#[error("couldn't bind socket to address: {error}")]
 	#[non_exhaustive]
@@ -164,6 +421,20 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
+	/// [`SocketUserOptions::sctp_socket_additional_addresses`] was used, but there was an error binding the socket to those addresses.
+	///
+	/// # Availability
+	///
+	/// Linux only.
+	#[cfg(target_os = "linux")]
+	#[error("couldn't bind SCTP socket to additional addresses: {error}")]
+	#[non_exhaustive]
+	SctpBindAdditional {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
 	/// There was an error setting the owner of the socket.
 	///
 	/// # Availability
@@ -192,6 +463,100 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
+	/// There was an error setting the security context of the socket.
+	///
+	/// # Availability
+	///
+	/// Linux and Android only.
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	#[error("`unix_socket_selinux_context` was used, but there was an error setting the socket's security context: {error}")]
+	#[non_exhaustive]
+	SetSecurityContext {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// [`SocketUserOptions::unix_socket_lock_file`] was used, and another process holds the lock, so the existing socket is still in use.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)]
+	#[error("the Unix-domain socket is in use by another process, which holds the lock file `{}`", .lock_path.display())]
+	#[non_exhaustive]
+	UnixSocketInUse {
+		/// The path of the lock file.
+		lock_path: PathBuf,
+	},
+
+	/// [`SocketUserOptions::unix_socket_lock_file`] was used, but there was an error opening or locking the lock file.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)]
+	#[error("`unix_socket_lock_file` was used, but there was an error locking the lock file: {error}")]
+	#[non_exhaustive]
+	LockFile {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// [`SocketUserOptions::unix_socket_atomic_replace`] was used, but there was an error renaming the new socket from its temporary path to the designated path.
+	///
+	/// # Availability
+	///
+	/// Unix-like platforms only.
+	#[cfg(unix)]
+	#[error("`unix_socket_atomic_replace` was used, but there was an error moving the new socket into place: {error}")]
+	#[non_exhaustive]
+	ReplaceSocket {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// [`ConnectAppOptions::before_connect`] was used, and it returned an error.
+	#[error("{0}")]
+	BeforeConnect(io::Error),
+
+	/// [`socket2::Socket::connect`] failed.
+	#[error("couldn't connect socket to address: {error}")]
+	#[non_exhaustive]
+	Connect {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// Every connection attempt made by [`open_connect_happy_eyeballs`][open_connect_happy_eyeballs()] failed, or there were no candidate addresses to connect to.
+	#[error("couldn't connect to any of the candidate addresses")]
+	#[non_exhaustive]
+	ConnectAttemptsFailed {
+		/// The addresses that were tried, and the errors that occurred in connecting to each of them, in the order they failed.
+		errors: Vec<(net::SocketAddr, io::Error)>,
+	},
+
+	/// The [`SocketAddr`] was passed to [`open_connect`][open_connect()], but it is of a kind that can only be listened on, such as a [`SocketAddr::IpWildcard`] or a [`SocketAddr::Ip`] with a port range.
+	#[error("this kind of socket address can't be connected to")]
+	#[non_exhaustive]
+	Unconnectable,
+
+	/// [`open_connect_happy_eyeballs_tokio`][crate::open_connect_happy_eyeballs_tokio()] was called with a [`ConnectAppOptions::type`] other than [`socket2::Type::STREAM`], which it doesn't support.
+	///
+	/// # Availability
+	///
+	/// Requires the `tokio` feature.
+	#[cfg(feature = "tokio")]
+	#[error("asynchronous Happy Eyeballs only supports stream-type sockets (got `{actual:?}`)")]
+	#[non_exhaustive]
+	UnsupportedConnectType {
+		/// The type that was requested, which is [`ConnectAppOptions::type`].
+		actual: socket2::Type,
+	},
+
 	/// [`socket2::Socket::listen`] failed.
 	#[error("couldn't make the socket listen: {error}")]
 	#[non_exhaustive]
@@ -201,6 +566,15 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
+	/// [`socket2::Socket::local_addr`] failed, after the socket was opened.
+	#[error("couldn't get the socket's local address: {error}")]
+	#[non_exhaustive]
+	LocalAddr {
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
 	/// [`socket2::Socket::type`] failed.
 	///
 	/// This will, in particular, happen if the file descriptor or handle exists but is not a socket.
@@ -212,6 +586,28 @@ pub enum OpenSocketError {
 		error: io::Error,
 	},
 
+	/// The inherited socket is not bound to [`SocketUserOptions::inherited_socket_expected_addr`].
+	#[error("the inherited socket was expected to be bound to `{expected}`, but {}", match .actual {
+		Some(actual) => format!("it is bound to `{actual}`"),
+		None => "its address couldn't be determined".to_owned(),
+	})]
+	#[non_exhaustive]
+	InheritedWrongAddr {
+		/// The address that the socket was expected to be bound to.
+		expected: Box<SocketAddr>,
+
+		/// The address that the socket is actually bound to, or `None` if it can't be represented as a `SocketAddr`, such as an unnamed Unix-domain socket.
+		actual: Option<Box<SocketAddr>>,
+	},
+
+	/// [`SocketUserOptions::inherited_socket_expected_addr`] is not an address that a socket can be bound to, such as `fd:3`.
+	#[error("`{expected}` is not an address that a socket can be bound to, so it can't be the expected address of an inherited socket")]
+	#[non_exhaustive]
+	InvalidExpectedAddr {
+		/// The expected address.
+		expected: SocketAddr,
+	},
+
 	/// The inherited [stream-type][socket2::Type::STREAM] socket is not in a listening state, but [`SocketAppOptions::listen`] is true.
 	#[error("the inherited socket was expected to be in a listening state, but it is not")]
 	#[non_exhaustive]
@@ -222,6 +618,74 @@ pub enum OpenSocketError {
 	#[non_exhaustive]
 	InheritedIsListening,
 
+	/// [`open_connection`][crate::open_connection] was used, but the socket is not connected to a peer.
+	#[error("the inherited socket was expected to be connected, but it is not: {error}")]
+	#[non_exhaustive]
+	InheritedIsNotConnected {
+		/// The error raised when getting the socket's peer address.
+		#[source]
+		error: io::Error,
+	},
+
+	/// The [`SocketAddr`] is a [`SocketAddr::Ip`] with an IPv6 zone ID that is an interface name, or [`SocketUserOptions::udp_multicast_interface`] was used on an IPv6 socket, but there is no network interface with that name.
+	#[error("couldn't find network interface `{name}`: {error}")]
+	#[non_exhaustive]
+	UnknownInterface {
+		/// The name of the network interface.
+		name: String,
+
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// [`SocketUserOptions::udp_multicast_group`] contains an address that is not a multicast address, or is not the same IP version as the socket's address.
+	#[error("`{group}` is not a multicast group address of the same IP version as the socket's address")]
+	#[non_exhaustive]
+	InvalidMulticastGroup {
+		/// The invalid multicast group address.
+		group: IpAddr,
+	},
+
+	/// [`SocketUserOptions::udp_multicast_interface`] was used on an IPv4 socket, but it is not an IPv4 address.
+	#[error("the multicast interface `{interface}` must be given as an IPv4 address for an IPv4 socket")]
+	#[non_exhaustive]
+	InvalidMulticastInterface {
+		/// The invalid multicast interface.
+		interface: String,
+	},
+
+	/// There was an error joining one of the multicast groups in [`SocketUserOptions::udp_multicast_group`].
+	#[error("couldn't join multicast group `{group}`: {error}")]
+	#[non_exhaustive]
+	JoinMulticastGroup {
+		/// The multicast group that couldn't be joined.
+		group: IpAddr,
+
+		/// The error that this one arose from.
+		#[source]
+		error: io::Error,
+	},
+
+	/// The [`SocketAddr`] has a port range, but binding the socket failed for every port in the range.
+	#[error("couldn't bind to any port in the range")]
+	#[non_exhaustive]
+	PortRangeExhausted {
+		/// The port numbers that were tried, and the errors that occurred in binding to each of them, in the order they were tried.
+		errors: Vec<(u16, io::Error)>,
+	},
+
+	/// The port number, or a port number in the port range, is outside of [`SocketAppOptions::allowed_ports`].
+	#[error("port {port} is not allowed here; the allowed ports are {}–{}", .allowed.start(), .allowed.end())]
+	#[non_exhaustive]
+	PortNotAllowed {
+		/// The port number that is not allowed. If the address has a port range, this is the first port number in the range that is not allowed.
+		port: u16,
+
+		/// The allowed port numbers.
+		allowed: RangeInclusive<u16>,
+	},
+
 	/// The [`SocketAddr`] is a [`SocketAddr::Ip`] with no port number, but [`SocketAppOptions::default_port`] is `None`.
 	#[error("a port number is required")]
 	#[non_exhaustive]
@@ -230,43 +694,132 @@ pub enum OpenSocketError {
 
 impl From<OpenSocketError> for io::Error {
 	fn from(error: OpenSocketError) -> Self {
+		io::Error::new(error.io_error_kind(), error)
+	}
+}
+
+impl OpenSocketError {
+	/// The [`io::ErrorKind`] to use when converting this error to an [`io::Error`].
+	fn io_error_kind(&self) -> io::ErrorKind {
 		use io::ErrorKind as EK;
 
-		let kind = match &error {
+		match self {
 			OpenSocketError::InheritWrongType { .. }       => EK::InvalidData ,
 			OpenSocketError::InapplicableUserOption { .. } => EK::InvalidInput,
+			OpenSocketError::UnsupportedUserOption { .. }  => EK::Unsupported ,
+			OpenSocketError::UnsupportedSocketAddr         => EK::Unsupported ,
 			OpenSocketError::InheritedIsListening          => EK::InvalidData ,
 			OpenSocketError::InheritedIsNotListening       => EK::InvalidData ,
+			OpenSocketError::InheritedWrongAddr { .. }     => EK::InvalidData ,
+			OpenSocketError::InvalidExpectedAddr { .. }    => EK::InvalidInput,
 			OpenSocketError::PortRequired                  => EK::InvalidData ,
+			OpenSocketError::PortNotAllowed { .. }         => EK::InvalidInput,
+			OpenSocketError::RelativeUnixPath { .. }       => EK::InvalidInput,
+			OpenSocketError::PortRangeExhausted { .. }     => EK::AddrInUse   ,
+			OpenSocketError::InheritEnvVar { .. }          => EK::NotFound    ,
+			OpenSocketError::InheritEnvSocketNum { .. }    => EK::InvalidData ,
+			OpenSocketError::RawProtocolRequired           => EK::InvalidInput,
+			OpenSocketError::Unconnectable                 => EK::InvalidInput,
+			OpenSocketError::AddressKindNotAllowed { .. }  => EK::InvalidInput,
+			OpenSocketError::AddressRejected { .. }        => EK::InvalidInput,
+			OpenSocketError::InheritedAlreadyClaimed { .. } => EK::InvalidInput,
+
+			| OpenSocketError::InvalidMulticastGroup { .. }
+			| OpenSocketError::InvalidMulticastInterface { .. }
+			=> EK::InvalidInput,
+
+			OpenSocketError::InheritedIsNotConnected { .. } => EK::NotConnected,
+
+			OpenSocketError::ConnectAttemptsFailed { errors } => match errors.last() {
+				Some((_, error)) => error.kind(),
+				None => EK::InvalidInput,
+			},
 
 			| OpenSocketError::InvalidUnixPath { error }
 			| OpenSocketError::DupInherited { error }
 			| OpenSocketError::CreateSocket { error }
+			| OpenSocketError::CreatePair { error }
+			| OpenSocketError::RawPermissionDenied { error }
 			| OpenSocketError::MkdirParents { error }
 			| OpenSocketError::BeforeBind(error)
+			| OpenSocketError::AfterBind(error)
+			| OpenSocketError::BeforeListen(error)
 			| OpenSocketError::Bind { error }
+			| OpenSocketError::BeforeConnect(error)
+			| OpenSocketError::Connect { error }
 			| OpenSocketError::Listen { error }
+			| OpenSocketError::LocalAddr { error }
 			| OpenSocketError::CheckInheritedSocket { error }
 			| OpenSocketError::Cleanup(
 				| CleanupSocketError::Stat { error }
 				| CleanupSocketError::Unlink { error }
 			)
 			| OpenSocketError::SetSockOpt { error, .. }
+			| OpenSocketError::UnknownInterface { error, .. }
+			| OpenSocketError::JoinMulticastGroup { error, .. }
 			=> error.kind(),
 
 			#[cfg(not(windows))]
 			OpenSocketError::InvalidSystemdFd => EK::NotFound,
 
+			#[cfg(not(windows))]
+			OpenSocketError::InvalidSystemdName { .. } => EK::NotFound,
+
+			#[cfg(target_os = "macos")]
+			OpenSocketError::LaunchdActivate { error } => error.kind(),
+
 			#[cfg(windows)]
-			OpenSocketError::WindowsGetStdin { error } => error.kind(),
+			| OpenSocketError::WindowsGetStdin { error }
+			| OpenSocketError::WsaProtocolInfo { error }
+			=> error.kind(),
+
+			#[cfg(feature = "tokio")]
+			OpenSocketError::AsyncHookInBlockingOpen => EK::Unsupported,
+
+			#[cfg(feature = "tokio")]
+			OpenSocketError::UnsupportedConnectType { .. } => EK::Unsupported,
+
+			#[cfg(target_os = "linux")]
+			OpenSocketError::SctpBindAdditional { error } => error.kind(),
+
+			#[cfg(target_os = "linux")]
+			OpenSocketError::TransparentPermissionDenied { error } => error.kind(),
 
 			#[cfg(unix)]
 			| OpenSocketError::SetOwner { error }
 			| OpenSocketError::SetPermissions { error }
+			| OpenSocketError::ReplaceSocket { error }
+			| OpenSocketError::LockFile { error }
 			=> error.kind(),
-		};
 
-		io::Error::new(kind, error)
+			#[cfg(unix)]
+			OpenSocketError::UnixSocketInUse { .. } => EK::AddrInUse,
+
+			#[cfg(any(target_os = "android", target_os = "linux"))]
+			OpenSocketError::SetSecurityContext { error } => error.kind(),
+		}
+	}
+}
+
+/// Error raised by [`open_multiple`], when one of the addresses couldn't be opened.
+#[derive(Debug, thiserror::Error)]
+#[error("couldn't open socket `{address}`: {error}")]
+#[non_exhaustive]
+pub struct OpenMultipleError {
+	/// The position of the address in the list that was passed to `open_multiple`.
+	pub index: usize,
+
+	/// The address that couldn't be opened.
+	pub address: SocketAddr,
+
+	/// The error that occurred in opening it.
+	#[source]
+	pub error: OpenSocketError,
+}
+
+impl From<OpenMultipleError> for io::Error {
+	fn from(error: OpenMultipleError) -> Self {
+		io::Error::new(error.error.io_error_kind(), error)
 	}
 }
 
@@ -303,9 +856,61 @@ impl From<CleanupSocketError> for io::Error {
 	}
 }
 
+/// Error raised when parsing an [`IpTos`] fails.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid IP type of service: must be a DSCP name like `AF41`, or a number from 0 to 255")]
+#[non_exhaustive]
+pub struct InvalidIpTosError;
+
+/// Error raised when accepting a connection from a peer that a [`UnixPeerAcl`] doesn't allow. It's wrapped in an [`io::Error`] with the kind [`io::ErrorKind::PermissionDenied`].
+///
+/// The connection has already been closed by the time this error is raised. The peer's credentials are included, so that the rejected connection can be logged.
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+#[cfg(unix)]
+#[derive(Debug, thiserror::Error)]
+#[error("rejected connection from user ID {} and group ID {}, which is not allowed to connect", .credentials.uid, .credentials.gid)]
+#[non_exhaustive]
+pub struct PeerNotAllowedError {
+	/// The credentials of the peer that tried to connect.
+	pub credentials: PeerCredentials,
+}
+
+#[cfg(unix)]
+impl From<PeerNotAllowedError> for io::Error {
+	fn from(error: PeerNotAllowedError) -> Self {
+		io::Error::new(io::ErrorKind::PermissionDenied, error)
+	}
+}
+
+/// Error raised when converting a [`socket2::SockAddr`] into a [`SocketAddr`], if the address can't be represented as a `SocketAddr`.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum FromSockAddrError {
+	/// The address is in an address family that `SocketAddr` has no variant for.
+	#[error("unsupported socket address family: {domain:?}")]
+	#[non_exhaustive]
+	UnsupportedFamily {
+		/// The address family.
+		domain: socket2::Domain,
+	},
+
+	/// The address is an unnamed Unix-domain socket address, such as the address of one end of a socket pair or the peer address of a client that didn't bind its socket.
+	#[error("unnamed Unix-domain socket addresses can't be represented")]
+	#[non_exhaustive]
+	UnnamedUnix,
+
+	/// The address is a Unix-domain socket address in the abstract namespace, but its name is not valid UTF-8.
+	#[error("abstract Unix-domain socket name is not valid UTF-8")]
+	#[non_exhaustive]
+	InvalidUnixAbstractName,
+}
+
 /// The errors that can occur in setting up a socket for use with Tokio.
 ///
-/// This error type can be raised when converting a socket to [`AnyTokioListener`] or [`AnyTokioStream`].
+/// This error type can be raised when converting a socket to [`AnyTokioListener`], [`AnyTokioStream`], or [`AnyTokioDatagram`].
 ///
 /// # Availability
 ///
@@ -375,3 +980,267 @@ impl From<IntoTokioError> for io::Error {
 		io::Error::new(kind, error)
 	}
 }
+
+/// The errors that can occur in setting up a socket for use with async-std.
+///
+/// This error type can be raised when converting a socket to [`AnyAsyncStdListener`] or [`AnyAsyncStdStream`].
+///
+/// # Availability
+///
+/// Requires the `async-std` feature.
+#[cfg(feature = "async-std")]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum IntoAsyncStdError {
+	/// The socket is the wrong type or protocol. This can happen when trying to convert a UDP socket into an [`AnyAsyncStdListener`], for example.
+	///
+	/// Note that this error can be caused by attempting to use a Unix-domain socket on Windows, which is not currently supported.
+	#[error("inappropriate or unrecognized socket domain, type, or transport protocol")]
+	#[non_exhaustive]
+	Inappropriate {
+		/// The socket that was inappropriate.
+		socket: AnyStdSocket,
+	},
+
+	/// There was an error checking details about the socket, such as its [type][socket2::Type] and [protocol][socket2::Protocol].
+	#[error("couldn't get socket details: {error}")]
+	#[non_exhaustive]
+	Check {
+		#[source]
+		error: io::Error,
+	},
+
+	/// There was an error setting non-blocking mode on the socket.
+	#[error("couldn't set non-blocking mode on socket: {error}")]
+	#[non_exhaustive]
+	SetNonBlocking {
+		#[source]
+		error: io::Error,
+	},
+}
+
+#[cfg(feature = "async-std")]
+impl From<IntoAsyncStdError> for io::Error {
+	fn from(error: IntoAsyncStdError) -> Self {
+		let kind = match &error {
+			IntoAsyncStdError::Inappropriate { .. } => io::ErrorKind::InvalidInput,
+
+			| IntoAsyncStdError::Check { error }
+			| IntoAsyncStdError::SetNonBlocking { error }
+			=> error.kind(),
+		};
+
+		io::Error::new(kind, error)
+	}
+}
+
+/// The errors that can occur in setting up a socket for use with smol.
+///
+/// This error type can be raised when converting a socket to [`AnySmolListener`] or [`AnySmolStream`].
+///
+/// # Availability
+///
+/// Requires the `smol` feature.
+#[cfg(feature = "smol")]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum IntoSmolError {
+	/// The socket is the wrong type or protocol. This can happen when trying to convert a UDP socket into an [`AnySmolListener`], for example.
+	///
+	/// Note that this error can be caused by attempting to use a Unix-domain socket on Windows, which is not currently supported.
+	#[error("inappropriate or unrecognized socket domain, type, or transport protocol")]
+	#[non_exhaustive]
+	Inappropriate {
+		/// The socket that was inappropriate.
+		socket: AnyStdSocket,
+	},
+
+	/// There was an error checking details about the socket, such as its [type][socket2::Type] and [protocol][socket2::Protocol].
+	#[error("couldn't get socket details: {error}")]
+	#[non_exhaustive]
+	Check {
+		#[source]
+		error: io::Error,
+	},
+
+	/// There was an error setting non-blocking mode on the socket.
+	#[error("couldn't set non-blocking mode on socket: {error}")]
+	#[non_exhaustive]
+	SetNonBlocking {
+		#[source]
+		error: io::Error,
+	},
+
+	/// There was an error registering the socket with the `async-io` reactor, which `smol` and `async_net` use to wait for sockets to become ready.
+	#[error("error passing the socket to smol: {error}")]
+	#[non_exhaustive]
+	Wrap {
+		#[source]
+		error: io::Error,
+	},
+}
+
+#[cfg(feature = "smol")]
+impl From<IntoSmolError> for io::Error {
+	fn from(error: IntoSmolError) -> Self {
+		let kind = match &error {
+			IntoSmolError::Inappropriate { .. } => io::ErrorKind::InvalidInput,
+
+			| IntoSmolError::Check { error }
+			| IntoSmolError::SetNonBlocking { error }
+			| IntoSmolError::Wrap { error }
+			=> error.kind(),
+		};
+
+		io::Error::new(kind, error)
+	}
+}
+
+/// The errors that can occur in setting up TLS according to the TLS user options, `TlsUserOptions` or `NativeTlsUserOptions`.
+///
+/// This error type can be raised by the functions that set up TLS, such as `convert::open_tls` and `convert::open_native_tls`.
+///
+/// # Availability
+///
+/// Requires the `rustls` or `native-tls` feature.
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TlsConfigError {
+	/// An option that requires `tls_cert` was used without it.
+	#[error("the `{name}` option requires the `tls_cert` option")]
+	#[non_exhaustive]
+	MissingCert {
+		/// The name of the option that was used without `tls_cert`, like `tls_key`.
+		name: &'static str,
+	},
+
+	/// `tls_cert` was used without `tls_key`.
+	#[error("the `tls_cert` option requires the `tls_key` option")]
+	MissingKey,
+
+	/// One of the TLS files couldn't be read, or doesn't contain what it should.
+	#[error("couldn't read `{}` (given by the `{option}` option): {error}", .path.display())]
+	#[non_exhaustive]
+	ReadFile {
+		/// The name of the option that gave the path, like `tls_cert`.
+		option: &'static str,
+
+		/// The path that couldn't be read.
+		path: PathBuf,
+
+		/// The error that this one arose from. Its kind is [`io::ErrorKind::InvalidData`] if the file was read, but isn't valid PEM or doesn't have any certificates or private key in it.
+		#[source]
+		error: io::Error,
+	},
+
+	/// The certificate authorities in `tls_client_ca` couldn't be used to verify clients.
+	///
+	/// # Availability
+	///
+	/// Requires the `rustls` feature.
+	#[cfg(feature = "rustls")]
+	#[error("couldn't use the certificate authorities in `{}` (given by the `tls_client_ca` option): {error}", .path.display())]
+	#[non_exhaustive]
+	ClientCa {
+		/// The path given by `tls_client_ca`.
+		path: PathBuf,
+
+		/// The error that this one arose from.
+		#[source]
+		error: rustls::server::VerifierBuilderError,
+	},
+
+	/// rustls rejected the configuration, such as because the private key doesn't match the certificate.
+	///
+	/// # Availability
+	///
+	/// Requires the `rustls` feature.
+	#[cfg(feature = "rustls")]
+	#[error("invalid TLS configuration: {error}")]
+	#[non_exhaustive]
+	Rustls {
+		/// The error that this one arose from.
+		#[source]
+		error: rustls::Error,
+	},
+
+	/// native-tls rejected the configuration, such as because the certificate or private key is invalid.
+	///
+	/// # Availability
+	///
+	/// Requires the `native-tls` feature.
+	#[cfg(feature = "native-tls")]
+	#[error("invalid TLS configuration: {error}")]
+	#[non_exhaustive]
+	NativeTls {
+		/// The error that this one arose from.
+		#[source]
+		error: native_tls::Error,
+	},
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+impl From<TlsConfigError> for io::Error {
+	fn from(error: TlsConfigError) -> Self {
+		let kind = match &error {
+			TlsConfigError::ReadFile { error, .. } => error.kind(),
+			_ => io::ErrorKind::InvalidInput,
+		};
+
+		io::Error::new(kind, error)
+	}
+}
+
+/// Error raised by the functions that open a socket and set up TLS on it, such as `convert::open_tls` and `convert::open_native_tls`.
+///
+/// # Availability
+///
+/// Requires the `rustls` or `native-tls` feature.
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum OpenTlsError {
+	/// The socket couldn't be opened.
+	#[error("{0}")]
+	Open(#[from] OpenSocketError),
+
+	/// The socket couldn't be set up for use with Tokio.
+	#[error("{0}")]
+	IntoTokio(#[from] IntoTokioError),
+
+	/// TLS couldn't be set up.
+	#[error("{0}")]
+	TlsConfig(#[from] TlsConfigError),
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+impl From<OpenTlsError> for io::Error {
+	fn from(error: OpenTlsError) -> Self {
+		match error {
+			OpenTlsError::Open(error) => error.into(),
+			OpenTlsError::IntoTokio(error) => error.into(),
+			OpenTlsError::TlsConfig(error) => error.into(),
+		}
+	}
+}
+
+/// Error raised by [`convert::DetectTls`] when accepting a connection.
+///
+/// `E` is the error type of the TLS acceptor that `DetectTls` wraps.
+///
+/// # Availability
+///
+/// Requires the `tls-listener` feature.
+#[cfg(feature = "tls-listener")]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum DetectTlsError<E> {
+	/// There was an error peeking at the first byte of the connection.
+	#[error("couldn't read from the connection: {0}")]
+	Peek(#[source] io::Error),
+
+	/// The connection starts with a TLS handshake, and the TLS acceptor failed to complete it.
+	#[error(transparent)]
+	Tls(E),
+}
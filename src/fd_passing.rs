@@ -0,0 +1,114 @@
+use crate::{
+	sys,
+	SocketAddr,
+};
+use socket2::{SockRef, Socket};
+use std::{
+	io,
+	os::fd::{AsFd, IntoRawFd, OwnedFd},
+};
+
+/// Sends a socket to another process, over a Unix-domain socket that connects the two, such as a [`UnixStream`][std::os::unix::net::UnixStream]. The other process receives it with [`recv_socket`].
+///
+/// This is for multi-process servers that move listening sockets or accepted connections between processes. The socket is duplicated, not moved; this process can keep using it, or close it.
+///
+/// `channel` can be either a stream-type or datagram-type Unix-domain socket. If it's a datagram-type socket, it must be connected.
+///
+///
+/// # Errors
+///
+/// Any I/O error raised by the operating system in sending the socket.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn send_socket(channel: impl AsFd, socket: impl AsFd) -> io::Result<()> {
+	let channel = SockRef::from(&channel);
+
+	loop {
+		match sys::send_with_fds(&channel, &[0], None, &[socket.as_fd()]) {
+			Ok(_) => break Ok(()),
+			Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+			Err(error) => break Err(error),
+		}
+	}
+}
+
+/// Receives a socket sent by [`send_socket`].
+///
+/// The received socket belongs to this process. It is not inheritable by child processes.
+///
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::UnexpectedEof`] if `channel` is a stream-type socket and the other end was closed, with [`io::ErrorKind::InvalidData`] if something other than a socket sent by `send_socket` was received, or with any I/O error raised by the operating system in receiving the socket.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn recv_socket(channel: impl AsFd) -> io::Result<Socket> {
+	let channel = SockRef::from(&channel);
+	let mut buf = [0u8; 1];
+
+	let (received, fds): (usize, Vec<OwnedFd>) = loop {
+		match sys::recv_with_fds(&channel, &mut buf, 1) {
+			Ok(ok) => break ok,
+			Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+			Err(error) => return Err(error),
+		}
+	};
+
+	match (received, fds.into_iter().next()) {
+		(_, Some(fd)) => Ok(Socket::from(fd)),
+		(0, None) => Err(io::ErrorKind::UnexpectedEof.into()),
+		(_, None) => Err(io::Error::new(io::ErrorKind::InvalidData, "received a message without a socket")),
+	}
+}
+
+/// Receives a socket sent by [`send_socket`], like [`recv_socket`], and returns a [`SocketAddr::Inherit`] for it.
+///
+/// This way, a socket received from another process can be used with [`open`][crate::open()] and the rest of this library's socket address machinery, including the checks that `open` makes on inherited sockets. It can also be passed along to a child process, as with [`inherit_socket_in_child`][crate::inherit_socket_in_child].
+///
+/// The received socket is kept open for as long as this process runs, the same as a socket inherited from the parent process. `open` duplicates it, so it can be opened more than once.
+///
+///
+/// # Errors
+///
+/// Same as `recv_socket`.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn recv_socket_addr(channel: impl AsFd) -> io::Result<SocketAddr> {
+	let socket: Socket = recv_socket(channel)?;
+	Ok(SocketAddr::new_inherit(socket.into_raw_fd()))
+}
+
+#[test]
+fn test_send_recv_socket() {
+	use crate::{open, SocketAppOptions, SocketUserOptions};
+	use std::os::unix::net::UnixStream;
+
+	let (sender, receiver) = UnixStream::pair().unwrap();
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+	send_socket(&sender, &listener).unwrap();
+	let received: Socket = recv_socket(&receiver).unwrap();
+	assert_eq!(received.local_addr().unwrap().as_socket(), Some(listener.local_addr().unwrap()));
+
+	// A received socket can be opened as an inherited one.
+	send_socket(&sender, &listener).unwrap();
+	let address: SocketAddr = recv_socket_addr(&receiver).unwrap();
+	let opened: Socket = open(&address, &SocketAppOptions::new(socket2::Type::STREAM), &SocketUserOptions::default()).unwrap();
+	assert_eq!(opened.local_addr().unwrap().as_socket(), Some(listener.local_addr().unwrap()));
+
+	// A message without a socket is rejected.
+	io::Write::write_all(&mut &sender, b"x").unwrap();
+	assert_eq!(recv_socket(&receiver).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+	drop(sender);
+	assert_eq!(recv_socket(&receiver).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+}
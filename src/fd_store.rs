@@ -0,0 +1,223 @@
+use crate::{
+	inherit_socket_in_child,
+	spawn::validate_name,
+	sys,
+};
+use socket2::{Domain, Socket, Type};
+use std::{
+	io,
+	os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+	process::Command,
+};
+
+#[cfg(doc)]
+use crate::SocketAddr;
+
+/// The environment variable that [`FdStore::inherit_in_child`] uses to tell the child process where the store is.
+const FD_STORE_ENV_VAR: &str = "SOCKET_CONFIG_FD_STORE";
+
+/// A place to keep sockets, by name, while the process that uses them restarts or is replaced. This is like the systemd file descriptor store (see [`systemd::store_fds`][crate::systemd::store_fds]), but it works with any supervisor process.
+///
+/// The store is a Unix-domain datagram socket pair. Each stored socket is a datagram in it, containing the socket's name, with the socket itself attached. Stored sockets stay in the store, without anyone having to read them, for as long as any process has the store open.
+///
+/// The supervisor creates the store with [`FdStore::new`], and passes it to each child process it spawns with [`FdStore::inherit_in_child`]. A child process gets the store with [`FdStore::from_env`], takes out any sockets that a previous child process left in it with [`FdStore::take`] or [`FdStore::take_all`], and puts its own sockets in it with [`FdStore::store`]. Because the supervisor keeps the store open, the sockets survive the child process exiting or crashing, and are still listening the whole time, so no connections are refused.
+///
+/// A socket taken from the store is removed from it. A process that wants its sockets to be kept for the next one should store them again.
+///
+/// To use a taken socket with [`open`][crate::open()], and the rest of this library's socket address machinery, make it into a [`SocketAddr::Inherit`] with [`SocketAddr::new_inherit`] and its raw file descriptor.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+#[derive(Debug)]
+pub struct FdStore {
+	sender: Socket,
+	receiver: Socket,
+}
+
+impl FdStore {
+	/// Creates a new, empty store.
+	///
+	///
+	/// # Errors
+	///
+	/// Any I/O error raised by the operating system in creating the socket pair.
+	pub fn new() -> io::Result<Self> {
+		let (sender, receiver) = Socket::pair(Domain::UNIX, Type::DGRAM, None)?;
+		receiver.set_nonblocking(true)?;
+		Ok(Self { sender, receiver })
+	}
+
+	/// Gets the store that this process inherited from its parent with [`FdStore::inherit_in_child`].
+	///
+	/// Returns `Ok(None)` if this process didn't inherit a store. The inherited file descriptors are duplicated, as with inherited sockets opened by [`open`][crate::open()], so this can be called more than once.
+	///
+	///
+	/// # Errors
+	///
+	/// Fails with [`io::ErrorKind::InvalidData`] if the environment variable that identifies the store is malformed, or if the inherited file descriptors aren't datagram sockets, or with any I/O error raised by the operating system in duplicating them.
+	pub fn from_env() -> io::Result<Option<Self>> {
+		let Some(value) = std::env::var_os(FD_STORE_ENV_VAR) else {
+			return Ok(None);
+		};
+
+		let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("the `{FD_STORE_ENV_VAR}` environment variable is malformed"));
+
+		let (sender, receiver): (RawFd, RawFd) =
+			value.to_str()
+			.and_then(|value| value.split_once(','))
+			.and_then(|(sender, receiver)| Some((sender.parse().ok()?, receiver.parse().ok()?)))
+			.ok_or_else(invalid)?;
+
+		let claim = |fd: RawFd| -> io::Result<Socket> {
+			// Safety: The file descriptor was passed by the parent process, which is trusted to have left it open, the same as with any other inherited socket.
+			let fd: BorrowedFd<'_> = unsafe { BorrowedFd::borrow_raw(fd) };
+
+			let socket = Socket::from(fd.try_clone_to_owned()?);
+
+			if socket.r#type()? != Type::DGRAM {
+				return Err(io::Error::new(io::ErrorKind::InvalidData, "the inherited file descriptor store is not a datagram socket"));
+			}
+
+			Ok(socket)
+		};
+
+		Ok(Some(Self {
+			sender: claim(sender)?,
+			receiver: claim(receiver)?,
+		}))
+	}
+
+	/// Arranges for the child process that will be spawned by `command` to inherit this store, with [`inherit_socket_in_child`], and sets an environment variable for [`FdStore::from_env`] to find it with.
+	pub fn inherit_in_child(&self, command: &mut Command) {
+		inherit_socket_in_child(&mut *command, &self.sender);
+		inherit_socket_in_child(&mut *command, &self.receiver);
+		command.env(FD_STORE_ENV_VAR, format!("{},{}", self.sender.as_raw_fd(), self.receiver.as_raw_fd()));
+	}
+
+	/// Puts a socket in the store, under the given name. The socket is duplicated, not moved; this process can keep using it.
+	///
+	/// Names must be 1 to 255 printable ASCII characters, and must not contain `:`. More than one socket can be stored under the same name.
+	///
+	///
+	/// # Errors
+	///
+	/// Fails with [`io::ErrorKind::InvalidInput`] if the name is invalid, or with any I/O error raised by the operating system in sending the socket to the store. The latter include running out of buffer space, if a great many sockets are stored.
+	pub fn store(&self, name: &str, socket: impl AsFd) -> io::Result<()> {
+		validate_name(name)?;
+
+		sys::send_with_fds(&self.sender, name.as_bytes(), None, &[socket.as_fd()])?;
+		Ok(())
+	}
+
+	/// Takes all of the sockets out of the store, each paired with its name, in the order they were stored.
+	///
+	///
+	/// # Errors
+	///
+	/// Any I/O error raised by the operating system in receiving the sockets from the store. Sockets that were taken out before the error are closed.
+	pub fn take_all(&self) -> io::Result<Vec<(String, Socket)>> {
+		let mut sockets: Vec<(String, Socket)> = Vec::new();
+		let mut buf = [0u8; 256];
+
+		loop {
+			let (received, fds): (usize, Vec<OwnedFd>) = match sys::recv_with_fds(&self.receiver, &mut buf, 1) {
+				Ok(ok) => ok,
+				Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+				Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+				Err(error) => return Err(error),
+			};
+
+			// Datagrams without a socket, or with an invalid name, weren't sent by `store`, and are ignored.
+			let Some(fd) = fds.into_iter().next() else {
+				continue;
+			};
+
+			let Ok(name) = std::str::from_utf8(&buf[..received]) else {
+				continue;
+			};
+
+			sockets.push((name.to_owned(), Socket::from(fd)));
+		}
+
+		Ok(sockets)
+	}
+
+	/// Takes the sockets with the given name out of the store, in the order they were stored. Sockets with other names are left in the store.
+	///
+	///
+	/// # Errors
+	///
+	/// Same as [`FdStore::take_all`] and [`FdStore::store`].
+	pub fn take(&self, name: &str) -> io::Result<Vec<Socket>> {
+		let mut taken: Vec<Socket> = Vec::new();
+
+		for (other_name, socket) in self.take_all()? {
+			if other_name == name {
+				taken.push(socket);
+			}
+			else {
+				self.store(&other_name, &socket)?;
+			}
+		}
+
+		Ok(taken)
+	}
+}
+
+#[test]
+fn test_fd_store() {
+	let store = FdStore::new().unwrap();
+	assert!(store.take_all().unwrap().is_empty());
+
+	let first = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let second = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+
+	store.store("http", &first).unwrap();
+	store.store("dns", &second).unwrap();
+	store.store("http", &first).unwrap();
+
+	assert_eq!(store.store("bad:name", &first).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+	// Take out only the `http` sockets. They're duplicates of `first`.
+	let taken: Vec<Socket> = store.take("http").unwrap();
+	assert_eq!(taken.len(), 2);
+
+	for socket in &taken {
+		assert_eq!(socket.local_addr().unwrap().as_socket(), Some(first.local_addr().unwrap()));
+	}
+
+	// The `dns` socket is still there.
+	let rest: Vec<(String, Socket)> = store.take_all().unwrap();
+	assert_eq!(rest.len(), 1);
+	assert_eq!(rest[0].0, "dns");
+	assert_eq!(rest[0].1.local_addr().unwrap().as_socket(), Some(second.local_addr().unwrap()));
+
+	assert!(store.take_all().unwrap().is_empty());
+}
+
+#[test]
+fn test_fd_store_inherit() {
+	use std::{
+		io::Read,
+		process::{Child, Stdio},
+	};
+
+	let store = FdStore::new().unwrap();
+
+	let mut command = Command::new("sh");
+	command
+	.args(["-c", r#"echo "$SOCKET_CONFIG_FD_STORE""#])
+	.stdout(Stdio::piped());
+
+	store.inherit_in_child(&mut command);
+
+	let mut child: Child = command.spawn().unwrap();
+
+	let mut output = String::new();
+	child.stdout.take().unwrap().read_to_string(&mut output).unwrap();
+	assert!(child.wait().unwrap().success());
+
+	assert_eq!(output, format!("{},{}\n", store.sender.as_raw_fd(), store.receiver.as_raw_fd()));
+}
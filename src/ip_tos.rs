@@ -0,0 +1,188 @@
+use crate::errors::InvalidIpTosError;
+use std::{
+	fmt::{self, Display, Formatter},
+	str::FromStr,
+};
+
+/// The type-of-service byte of an IP packet, which carries its [DSCP](https://en.wikipedia.org/wiki/Differentiated_services) (Differentiated Services Code Point) in the upper six bits. This is used for [`SocketUserOptions::ip_socket_tos`][crate::SocketUserOptions::ip_socket_tos], and for the equivalent traffic class byte of an IPv6 packet.
+///
+/// The lower two bits are the ECN (Explicit Congestion Notification) field. They are normally zero here; the operating system or transport protocol manages ECN itself.
+///
+///
+/// # Syntax
+///
+/// Either a DSCP name, or the numeric value of the whole type-of-service byte, in decimal or (with a `0x` prefix) hexadecimal. For example, `AF41`, `136`, and `0x88` are all the same.
+///
+/// The recognized DSCP names, which are not case sensitive, are:
+///
+/// * `CS0` through `CS7`, the class selectors
+/// * `AF11`, `AF12`, `AF13`, `AF21`, `AF22`, `AF23`, `AF31`, `AF32`, `AF33`, `AF41`, `AF42`, and `AF43`, the assured forwarding classes
+/// * `EF`, expedited forwarding
+/// * `VA`, voice admit
+/// * `LE`, lower effort
+///
+/// In a configuration file, the numeric value can also be given as a number instead of a string.
+///
+///
+/// # Availability
+///
+/// All platforms.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IpTos(pub u8);
+
+/// DSCP names and their code points.
+const DSCP_NAMES: &[(&str, u8)] = &[
+	("CS0", 0),
+	("LE", 1),
+	("CS1", 8),
+	("AF11", 10),
+	("AF12", 12),
+	("AF13", 14),
+	("CS2", 16),
+	("AF21", 18),
+	("AF22", 20),
+	("AF23", 22),
+	("CS3", 24),
+	("AF31", 26),
+	("AF32", 28),
+	("AF33", 30),
+	("CS4", 32),
+	("AF41", 34),
+	("AF42", 36),
+	("AF43", 38),
+	("CS5", 40),
+	("VA", 44),
+	("EF", 46),
+	("CS6", 48),
+	("CS7", 56),
+];
+
+impl IpTos {
+	/// Creates an `IpTos` with the given DSCP, and the ECN field set to zero.
+	///
+	/// Only the lower six bits of `dscp` are used.
+	pub const fn from_dscp(dscp: u8) -> Self {
+		Self((dscp & 0b11_1111) << 2)
+	}
+
+	/// Returns the DSCP, which is the upper six bits of the type-of-service byte.
+	pub const fn dscp(self) -> u8 {
+		self.0 >> 2
+	}
+
+	/// Returns the name of this type-of-service byte's DSCP, if it has one and the ECN field is zero.
+	pub fn dscp_name(self) -> Option<&'static str> {
+		if self.0 & 0b11 != 0 {
+			return None;
+		}
+
+		DSCP_NAMES.iter()
+		.find(|(_, dscp)| *dscp == self.dscp())
+		.map(|(name, _)| *name)
+	}
+}
+
+impl FromStr for IpTos {
+	type Err = InvalidIpTosError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Some((_, dscp)) = DSCP_NAMES.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+			return Ok(Self::from_dscp(*dscp));
+		}
+
+		let value: Result<u8, _> = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+			Some(hex) => u8::from_str_radix(hex, 16),
+			None => s.parse(),
+		};
+
+		value
+		.map(Self)
+		.map_err(|_| InvalidIpTosError)
+	}
+}
+
+impl Display for IpTos {
+	/// Writes the DSCP name, if there is one, or else the numeric value. The output can be parsed back into the same `IpTos` with [`FromStr`].
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self.dscp_name() {
+			Some(name) => f.write_str(name),
+			None => write!(f, "{}", self.0),
+		}
+	}
+}
+
+impl From<u8> for IpTos {
+	fn from(value: u8) -> Self {
+		Self(value)
+	}
+}
+
+impl From<IpTos> for u8 {
+	fn from(tos: IpTos) -> Self {
+		tos.0
+	}
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+enum SerdeIpTos {
+	Number(u8),
+	String(String),
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for IpTos {
+	fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+		match SerdeIpTos::deserialize(de)? {
+			SerdeIpTos::Number(value) => Ok(Self(value)),
+			SerdeIpTos::String(s) => s.parse().map_err(serde::de::Error::custom),
+		}
+	}
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for IpTos {
+	fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+		match self.dscp_name() {
+			Some(name) => SerdeIpTos::String(name.to_owned()),
+			None => SerdeIpTos::Number(self.0),
+		}
+		.serialize(ser)
+	}
+}
+
+#[test]
+fn test_ip_tos() {
+	for (string, value) in [
+		("AF41", 0x88),
+		("af41", 0x88),
+		("EF", 0xb8),
+		("CS0", 0),
+		("le", 0x04),
+		("136", 0x88),
+		("0x88", 0x88),
+		("3", 3),
+	] {
+		assert_eq!(string.parse::<IpTos>().unwrap(), IpTos(value), "{string}");
+	}
+
+	for string in ["", "AF44", "256", "0x", "-1"] {
+		assert!(string.parse::<IpTos>().is_err(), "{string}");
+	}
+
+	assert_eq!(IpTos(0x88).to_string(), "AF41");
+	assert_eq!(IpTos(0x89).to_string(), "137");
+	assert_eq!(IpTos::from_dscp(46).dscp_name(), Some("EF"));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_ip_tos_serde() {
+	assert_eq!(serde_json::from_str::<IpTos>(r#""AF41""#).unwrap(), IpTos(0x88));
+	assert_eq!(serde_json::from_str::<IpTos>("136").unwrap(), IpTos(0x88));
+	assert!(serde_json::from_str::<IpTos>(r#""AF44""#).is_err());
+
+	assert_eq!(serde_json::to_string(&IpTos(0x88)).unwrap(), r#""AF41""#);
+	assert_eq!(serde_json::to_string(&IpTos(0x89)).unwrap(), "137");
+}
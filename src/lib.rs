@@ -7,8 +7,12 @@
 //!
 //! The entry point of this library is the [`open`][open()] function, which accepts a socket address and a set of options, and opens a socket accordingly.
 //!
+//! For outbound sockets, such as a connection to an upstream server whose address the user configures, there is also [`open_connect`][open_connect()], which accepts the same socket addresses.
+//!
 //! `open` returns a [`socket2::Socket`], which can be used for ordinary blocking I/O. This library also has the [`AnyStdSocket`][crate::convert::AnyStdSocket] type in the [`convert`] module, which can be used to convert a `socket2::Socket` into one of the [standard library][std]'s socket types.
-#![cfg_attr(feature = "tokio", doc = r#" For non-blocking I/O with [`tokio`], the `convert` module includes [`AnyTokioListener`][crate::convert::AnyTokioListener] and [`AnyTokioStream`][crate::convert::AnyTokioStream]."#)]
+#![cfg_attr(feature = "tokio", doc = r#" For non-blocking I/O with [`tokio`], the `convert` module includes [`AnyTokioListener`][crate::convert::AnyTokioListener], [`AnyTokioStream`][crate::convert::AnyTokioStream], and [`AnyTokioDatagram`][crate::convert::AnyTokioDatagram]."#)]
+#![cfg_attr(feature = "async-std", doc = r#" For [`async_std`], it includes [`AnyAsyncStdListener`][crate::convert::AnyAsyncStdListener] and [`AnyAsyncStdStream`][crate::convert::AnyAsyncStdStream]."#)]
+#![cfg_attr(feature = "smol", doc = r#" For [smol](https://docs.rs/smol), it includes [`AnySmolListener`][crate::convert::AnySmolListener] and [`AnySmolStream`][crate::convert::AnySmolStream]."#)]
 //!
 //!
 //! # Feature flags and platform support
@@ -17,20 +21,40 @@
 //!
 //! Some items in this crate are limited in which platforms they're available on, or behave differently on different platforms, or are only available if a particular feature flag is enabled. Such differences are noted with an “Availability” section in those items' documentation.
 #![cfg_attr(all(
+	feature = "async-std",
 	feature = "clap",
 	feature = "futures",
+	feature = "io-uring",
+	feature = "mio",
+	feature = "native-tls",
+	feature = "quinn",
+	feature = "rustls",
 	feature = "serde",
+	feature = "smol",
+	feature = "tls-listener",
 	feature = "tokio",
+	feature = "tokio-util",
+	feature = "tonic",
 ), doc = r#"
 
 ## Available feature flags
 
 This library has the following feature flags:
 
+* `async-std`: Adds the utility types [`AnyAsyncStdListener`][crate::convert::AnyAsyncStdListener] and [`AnyAsyncStdStream`][crate::convert::AnyAsyncStdStream], for use with [`async_std`].
 * `clap`: Support parsing socket options from the command line using [`clap`]. Specifically, this adds an implementation of [`clap::Args`] for [`SocketUserOptions`].
 * `futures`: Adds an implementation of [`futures::Stream`] for [`AnyTokioListener`][crate::convert::AnyTokioListener]. Only works if the `tokio` feature is also enabled; otherwise, this feature does nothing.
+* `io-uring`: Adds [`AnyUringListener`][crate::convert::AnyUringListener], which accepts connections using io_uring. Only works on Linux; otherwise, this feature does nothing.
+* `mio`: Adds [`MioSocket`][crate::convert::MioSocket], a socket that can be registered with a [`mio::Poll`], and an implementation of [`mio::event::Source`] for [`AnyStdSocket`][crate::convert::AnyStdSocket]. Only works on Unix-like platforms; otherwise, this feature does nothing.
+* `native-tls`: Like `rustls`, but uses the operating system's TLS library through [`native_tls`], with [`NativeTlsUserOptions`] and the function [`open_native_tls`][crate::convert::open_native_tls()]. An application can enable either or both, and choose which one to use. Implies `tls-listener`.
+* `quinn`: Adds the function [`quinn_socket`][crate::convert::quinn_socket()], which prepares a UDP socket for use with a [`quinn`] QUIC endpoint.
+* `rustls`: Adds [`TlsUserOptions`], the TLS options supplied by the user, and the function [`open_tls`][crate::convert::open_tls()], which opens a listening socket that uses TLS if the user configured it, using [`rustls`]. Implies `tls-listener`.
 * `serde`: Support parsing socket options from configuration files or environment variables using [`serde`]. Specifically, this adds an implementation of [`serde::Deserialize`] to [`SocketAddr`] and [`SocketUserOptions`].
-* `tokio`: Adds the utility types [`AnyTokioListener`][crate::convert::AnyTokioListener] and [`AnyTokioStream`][crate::convert::AnyTokioStream].
+* `smol`: Adds the utility types [`AnySmolListener`][crate::convert::AnySmolListener] and [`AnySmolStream`][crate::convert::AnySmolStream], for use with [`smol`](https://docs.rs/smol) and other libraries based on [`async_net`].
+* `tls-listener`: Adds [`AnyTlsListener`][crate::convert::AnyTlsListener], which wraps an [`AnyTokioListener`][crate::convert::AnyTokioListener] and performs a TLS handshake on each accepted connection, using [`tls_listener`], and [`DetectTls`][crate::convert::DetectTls], which serves both TLS and plaintext connections on the same port. Implies `tokio`.
+* `tokio`: Adds the utility types [`AnyTokioListener`][crate::convert::AnyTokioListener], [`AnyTokioStream`][crate::convert::AnyTokioStream], [`AnyTokioDatagram`][crate::convert::AnyTokioDatagram], and [`IdleTimeout`][crate::convert::IdleTimeout], and the function [`serve`][crate::convert::serve()], an accept loop with graceful shutdown.
+* `tokio-util`: Adds the methods [`framed`][crate::convert::AnyTokioStream::framed], [`framed_length_delimited`][crate::convert::AnyTokioStream::framed_length_delimited], and [`framed_lines`][crate::convert::AnyTokioStream::framed_lines] to [`AnyTokioStream`][crate::convert::AnyTokioStream], which wrap a connection in a [`tokio_util::codec::Framed`]. Implies `tokio`.
+* `tonic`: Adds an implementation of [`tonic::transport::server::Connected`] for [`AnyTokioStream`][crate::convert::AnyTokioStream], so that a [`tonic`] server can accept connections from an [`AnyTokioListener`][crate::convert::AnyTokioListener], and the type [`AnyTokioConnectInfo`][crate::convert::AnyTokioConnectInfo]. Implies `tokio`.
 "#)]
 #![cfg_attr(feature = "clap", doc = concat!(r#"
 
@@ -73,21 +97,63 @@ This is an [Echo](https://en.wikipedia.org/wiki/Echo_Protocol) server that modif
 
 #![allow(clippy::tabs_in_doc_comments)] // This project uses tabs for indentation throughout, including in documentation examples.
 
+#[cfg(unix)] mod activation;
 mod addr;
+mod base64;
+mod connect;
 pub mod convert;
 pub mod errors;
+#[cfg(unix)] mod fd_passing;
+#[cfg(unix)] mod fd_store;
+mod ip_tos;
 mod open;
 mod options;
+mod pair;
+#[cfg(unix)] mod peer_cred;
+#[cfg(any(target_os = "android", target_os = "linux"))] mod pktinfo;
+#[cfg(unix)] mod prefork;
+mod preset;
+#[cfg(unix)] mod spawn;
+#[cfg(unix)] pub mod systemd;
+#[cfg(unix)] mod unix_lock;
 #[cfg(unix)] mod unix_security;
+#[cfg(unix)] mod upgrade;
 mod util;
 
 pub use self::{
 	addr::*,
+	connect::*,
+	ip_tos::*,
 	open::*,
 	options::*,
+	preset::*,
 	util::*,
 };
 
+#[cfg(unix)]
+pub use self::activation::*;
+
+#[cfg(unix)]
+pub use self::fd_passing::*;
+
+#[cfg(unix)]
+pub use self::fd_store::*;
+
+#[cfg(unix)]
+pub use self::peer_cred::*;
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub use self::pktinfo::*;
+
+#[cfg(unix)]
+pub use self::prefork::*;
+
+#[cfg(unix)]
+pub use self::spawn::*;
+
+#[cfg(unix)]
+pub use self::upgrade::*;
+
 cfg_if::cfg_if! {
 	if #[cfg(windows)] {
 		#[path = "sys/windows.rs"] mod sys;
@@ -1,6 +1,9 @@
 use crate::{
+	AddressKinds,
 	cleanup_unix_path_socket,
-	errors::OpenSocketError,
+	RelativeUnixPaths,
+	errors::{CleanupSocketError, OpenMultipleError, OpenSocketError},
+	HookContext,
 	SocketAppOptions,
 	SocketAddr,
 	SocketUserOptions,
@@ -9,10 +12,23 @@ use crate::{
 };
 use socket2::Socket;
 use std::{
+	borrow::Cow,
+	collections::BTreeSet,
+	ffi::c_int,
 	fs,
-	path::Path,
+	future::Future,
+	io,
+	net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddrV6},
+	ops::RangeInclusive,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	task,
+	time::Duration,
 };
 
+#[cfg(feature = "tokio")]
+use crate::AsyncHookContext;
+
 #[cfg(doc)]
 use crate::convert::AnyStdSocket;
 
@@ -93,182 +109,567 @@ pub fn open(
 	app_options: &SocketAppOptions,
 	user_options: &SocketUserOptions,
 ) -> Result<Socket, OpenSocketError> {
-	let orig_address = address;
+	open_inner(address, app_options, user_options, false, true)
+	.map(|opened| opened.socket)
+}
 
-	let open_new = |address: socket2::SockAddr| -> Result<Socket, OpenSocketError> {
-		// Is this a path-based Unix-domain socket? (We can't use `socket2::SockAddr::as_pathname` here, because it isn't available on Windows.)
-		let unix_socket_path: Option<&Path> = match orig_address {
-			SocketAddr::Unix { path } => Some(path),
-			_ => None,
-		};
+/// Like [`open`][open()], but if `address` is `None`, opens [`SocketAppOptions::default_address`] instead.
+///
+/// This is useful when the socket address is an optional setting, such as a command-line option that the user might not have given.
+///
+///
+/// # Example
+///
+/// ```no_run
+/// # use socket_config::{SocketAddr, SocketAppOptions, SocketUserOptions};
+/// let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+/// app_options.default_port = Some(8080);
+///
+/// // The user didn't configure an address, so this opens `127.0.0.1:8080`.
+/// let address: Option<SocketAddr> = None;
+/// let socket = socket_config::open_or_default(address.as_ref(), &app_options, &SocketUserOptions::default())?;
+/// # Ok::<(), socket_config::errors::OpenSocketError>(())
+/// ```
+pub fn open_or_default(
+	address: Option<&SocketAddr>,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<Socket, OpenSocketError> {
+	open(address.unwrap_or(&app_options.default_address), app_options, user_options)
+}
+
+/// Like [`open`][open()], but also returns some information about the socket that was opened, such as the local address it ended up bound to. See [`OpenedSocket`] for details.
+pub fn open_with_info(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<OpenedSocket, OpenSocketError> {
+	let OpenInner { socket, unix_path, unlinked_stale_socket, .. } = open_inner(address, app_options, user_options, false, true)?;
+
+	let local_addr: socket2::SockAddr =
+		socket.local_addr()
+		.map_err(|error| OpenSocketError::LocalAddr { error })?;
+
+	let recv_buffer_size: Option<usize> = socket.recv_buffer_size().ok();
+	let send_buffer_size: Option<usize> = socket.send_buffer_size().ok();
+
+	#[cfg(target_os = "linux")]
+	let udp_segment_size: Option<u16> = sys::udp_segment(&socket).ok().filter(|size| *size != 0);
+
+	#[cfg(target_os = "linux")]
+	let udp_gro: bool = sys::udp_gro(&socket).unwrap_or(false);
+
+	Ok(OpenedSocket {
+		socket,
+		local_addr,
+		is_inherited: address.is_inherited(),
+		unix_path,
+		unlinked_stale_socket,
+		recv_buffer_size,
+		send_buffer_size,
+
+		#[cfg(target_os = "linux")]
+		udp_segment_size,
+
+		#[cfg(target_os = "linux")]
+		udp_gro,
+	})
+}
+
+/// Claims an inherited socket that is already connected to a peer, such as one passed by systemd in `Accept=yes` mode, instead of a listening socket.
+///
+/// In `Accept=yes` mode, systemd accepts each incoming connection itself, and starts a new instance of the service for it, passing it the connected socket as file descriptor 3, named `connection`. The address to use for that is therefore `systemd`, or equivalently `systemd-name:connection`. Likewise, inetd in `nowait` mode passes the connected socket as the standard input, for which the address is `stdin`. Other inetd-style service managers can be supported with [`SocketAddr::Inherit`].
+///
+/// This is like [`open`][open()] with [`SocketAppOptions::listen`] set to false, except that `address` must be an [inherited][SocketAddr::is_inherited] address or a [`SocketAddr::Pair`], and the socket is checked to be connected rather than merely not listening. Datagram sockets qualify if they have been connected to a peer address.
+///
+/// # Errors
+///
+/// In addition to the errors that `open` can return, this fails with [`OpenSocketError::AddressKindNotAllowed`] if `address` is neither inherited nor a socket pair, and with [`OpenSocketError::InheritedIsNotConnected`] if the socket isn't connected.
+pub fn open_connection(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<Socket, OpenSocketError> {
+	let kind: AddressKinds = address.kind();
+
+	if !address.is_inherited() && kind != AddressKinds::PAIR {
+		return Err(OpenSocketError::AddressKindNotAllowed { kind });
+	}
+
+	let app_options = SocketAppOptions {
+		listen: false,
+		..app_options.clone()
+	};
+
+	let socket: Socket = open(address, &app_options, user_options)?;
+
+	socket.peer_addr()
+	.map_err(|error| OpenSocketError::InheritedIsNotConnected { error })?;
+
+	Ok(socket)
+}
+
+/// Opens a socket like [`open`][open()], but doesn't put it into the listening state yet. Call [`BoundSocket::finish_listen`] to do that.
+///
+/// This is for servers that need to bind their sockets early, such as while they still have the privileges needed to bind to a port below 1024, but that shouldn't accept connections until they have finished initializing. In the meantime, connection attempts are refused, rather than being queued up.
+///
+/// [`SocketAppOptions::before_listen`] is called by this function, not by `finish_listen`.
+///
+/// Inherited sockets are claimed as usual, and are assumed to already be in the appropriate state, so `finish_listen` does nothing for them. The same goes for [`SocketAddr::Pair`], whose listening socket must already be listening in order to be connected to.
+pub fn open_bound(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<BoundSocket, OpenSocketError> {
+	let OpenInner {
+		socket,
+		deferred_listen,
+		#[cfg(unix)] deferred_replace,
+		..
+	} = open_inner(address, app_options, user_options, true, true)?;
+
+	Ok(BoundSocket {
+		socket,
+		listen: deferred_listen,
 
-		// Prepare any Unix security attributes, if relevant.
 		#[cfg(unix)]
-		crate::unix_security::prepare(user_options, unix_socket_path)?;
-
-		// Check if we need to `listen` on this socket, and if so, what the backlog should be.
-		let listen_backlog: Option<_> = {
-			if app_options.listen && app_options.r#type == socket2::Type::STREAM {
-				Some(
-					user_options.listen_socket_backlog
-					.unwrap_or(SocketUserOptions::DEFAULT_LISTEN_SOCKET_BACKLOG)
-				)
-			}
-			else {
-				check_inapplicable(user_options.listen_socket_backlog, "listen_socket_backlog")?;
-				None
-			}
-		};
+		replace: deferred_replace,
+	})
+}
 
-		// Create the new socket.
-		let mut socket: socket2::Socket =
-			Socket::new(address.domain(), app_options.r#type, app_options.protocol)
-			.map_err(|error| OpenSocketError::CreateSocket { error })?;
+/// A socket that has been bound to its address by [`open_bound`], but is not listening yet.
+#[derive(Debug)]
+pub struct BoundSocket {
+	socket: Socket,
+	listen: Option<ListenParams>,
 
-		if let Some(socket_path) = unix_socket_path {
-			// Clean up the previous socket, if desired and applicable.
-			if !user_options.unix_socket_no_unlink {
-				cleanup_unix_path_socket(socket_path)?;
-			}
+	#[cfg(unix)]
+	replace: Option<UnixReplace>,
+}
 
-			// Create any needed parent folders.
-			if let Some(socket_parent_path) = socket_path.parent() {
-				fs::create_dir_all(socket_parent_path)
-				.map_err(|error| OpenSocketError::MkdirParents { error })?;
-			}
-		}
+impl BoundSocket {
+	/// Returns a reference to the socket, such as to check its local address or set additional options before it starts listening.
+	pub fn socket(&self) -> &Socket {
+		&self.socket
+	}
 
-		// Set socket options.
+	/// Puts the socket into the listening state, if [`SocketAppOptions::listen`] calls for it, and returns the socket.
+	///
+	/// The backlog is the one that would have been used by [`open`][open()], according to [`SocketUserOptions::listen_socket_backlog`]. Any other options that take effect once the socket is listening, such as an accept filter on FreeBSD, are applied too.
+	///
+	/// If [`SocketUserOptions::unix_socket_atomic_replace`] was used, this is also when the socket replaces the existing one. If the `BoundSocket` is dropped instead, the socket is deleted, and the existing one is left in place.
+	pub fn finish_listen(self) -> Result<Socket, OpenSocketError> {
+		if let Some(listen) = &self.listen {
+			listen.listen(&self.socket)?;
+		}
 
-		// `SO_REUSEADDR` is only set for TCP listening sockets on non-Windows platforms, same as the Rust standard library. See explanation: https://github.com/rust-lang/rust/blob/1b225414f325593f974c6b41e671a0a0dc5d7d5e/library/std/src/sys_common/net.rs#L395
-		#[cfg(not(windows))]
-		if listen_backlog.is_some() && is_socket_probably_tcp(&socket, &address, app_options) {
-			socket.set_reuse_address(true)
-			.map_err(|error| OpenSocketError::SetSockOpt {
-				option: "SO_REUSEADDR",
-				error,
-			})?;
+		#[cfg(unix)]
+		if let Some(replace) = self.replace {
+			replace.finish()?;
 		}
 
-		#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
-		if user_options.ip_socket_reuse_port {
-			socket.set_reuse_port(true)
-			.map_err(|error| OpenSocketError::SetSockOpt {
-				option: "SO_REUSEPORT",
-				error,
-			})?;
+		Ok(self.socket)
+	}
+}
+
+/// How to put a socket into the listening state.
+#[derive(Debug)]
+struct ListenParams {
+	backlog: c_int,
+
+	#[cfg(target_os = "freebsd")]
+	accept_filter: Option<String>,
+}
+
+impl ListenParams {
+	fn new(backlog: c_int, user_options: &SocketUserOptions) -> Self {
+		#[cfg(not(target_os = "freebsd"))]
+		let _ = user_options;
+
+		Self {
+			backlog,
+
+			#[cfg(target_os = "freebsd")]
+			accept_filter: user_options.freebsd_accept_filter.clone(),
 		}
+	}
 
-		if user_options.ip_socket_v6_only {
-			socket.set_only_v6(true)
+	fn listen(&self, socket: &Socket) -> Result<(), OpenSocketError> {
+		socket.listen(self.backlog)
+		.map_err(|error| OpenSocketError::Listen { error })?;
+
+		// Accept filters can only be attached to a socket that is already listening.
+		#[cfg(target_os = "freebsd")]
+		if let Some(accept_filter) = &self.accept_filter {
+			sys::set_accept_filter(socket, accept_filter)
 			.map_err(|error| OpenSocketError::SetSockOpt {
-				option: "IPV6_V6ONLY",
+				option: "SO_ACCEPTFILTER",
 				error,
 			})?;
 		}
 
-		// Bind the socket to its address.
-		if let Some(before_bind) = &app_options.before_bind {
-			before_bind(&mut socket)
-			.map_err(OpenSocketError::BeforeBind)?;
+		Ok(())
+	}
+}
+
+/// A path-based Unix-domain socket that is being bound to a temporary path, and is to be renamed to its designated path once it's ready. See [`SocketUserOptions::unix_socket_atomic_replace`].
+///
+/// If this is dropped without calling `finish`, the socket at the temporary path (if any) is deleted.
+#[cfg(unix)]
+#[derive(Debug)]
+struct UnixReplace {
+	path: PathBuf,
+	temp_path: PathBuf,
+	temp_sockaddr: socket2::SockAddr,
+	finished: bool,
+}
+
+#[cfg(unix)]
+impl UnixReplace {
+	fn new(path: &Path) -> Result<Self, OpenSocketError> {
+		use std::{
+			ffi::OsString,
+			sync::atomic::{AtomicU64, Ordering},
+		};
+
+		// Distinguishes temporary paths chosen by different threads in this process.
+		static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+		let file_name = path.file_name().ok_or_else(|| OpenSocketError::InvalidUnixPath {
+			error: io::Error::new(io::ErrorKind::InvalidInput, "Unix-domain socket path has no file name"),
+		})?;
+
+		let mut temp_name = OsString::from(".");
+		temp_name.push(file_name);
+		temp_name.push(format!(".{}.{}.tmp", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+
+		let temp_path: PathBuf = path.with_file_name(temp_name);
+
+		let temp_sockaddr =
+			socket2::SockAddr::unix(&temp_path)
+			.map_err(|error| OpenSocketError::InvalidUnixPath { error })?;
+
+		// A previous process with the same process ID might have left a socket at the temporary path.
+		let _ = cleanup_unix_path_socket(&temp_path);
+
+		Ok(Self {
+			path: path.to_owned(),
+			temp_path,
+			temp_sockaddr,
+			finished: false,
+		})
+	}
+
+	/// Renames the socket to its designated path, replacing any existing socket there.
+	fn finish(mut self) -> Result<(), OpenSocketError> {
+		fs::rename(&self.temp_path, &self.path)
+		.map_err(|error| OpenSocketError::ReplaceSocket { error })?;
+
+		self.finished = true;
+		Ok(())
+	}
+}
+
+#[cfg(unix)]
+impl Drop for UnixReplace {
+	fn drop(&mut self) {
+		if !self.finished {
+			let _ = fs::remove_file(&self.temp_path);
 		}
+	}
+}
 
-		socket.bind(&address)
-		.map_err(|error| OpenSocketError::Bind { error })?;
+/// Result of `open_inner`.
+struct OpenInner {
+	socket: Socket,
 
-		// Set security attributes on the socket, if applicable and configured.
-		#[cfg(unix)]
-		crate::unix_security::apply(user_options, &socket, unix_socket_path)?;
+	/// The path of the newly created Unix-domain socket, if any.
+	unix_path: Option<PathBuf>,
+
+	/// Whether a stale Unix-domain socket was deleted.
+	unlinked_stale_socket: bool,
+
+	/// If `open_inner` was asked to defer listening, and the socket needs to listen, this is how to do that.
+	deferred_listen: Option<ListenParams>,
+
+	/// If listening was deferred, and the socket is to atomically replace an existing one, this is how to do that once it's listening.
+	#[cfg(unix)]
+	deferred_replace: Option<UnixReplace>,
+}
+
+/// Everything that the steps of opening a socket need to know.
+struct OpenContext<'a> {
+	/// The address being opened. If the address given to `open` was a `SocketAddr::WithOptions`, this is the address inside it.
+	address: &'a SocketAddr,
 
-		// Set the socket to listening, if applicable and configured.
-		if let Some(listen_backlog) = listen_backlog {
-			socket.listen(listen_backlog)
-			.map_err(|error| OpenSocketError::Listen { error })?;
+	app_options: &'a SocketAppOptions,
+
+	/// The user options, with the options from any `SocketAddr::WithOptions` applied.
+	user_options: &'a SocketUserOptions,
+
+	/// Whether to leave the socket bound but not listening, for `open_bound`.
+	defer_listen: bool,
+
+	/// Whether to check the address against the application's address policy ([`SocketAppOptions::allowed_kinds`], [`SocketAppOptions::allowed_ports`], and [`SocketAppOptions::validate_addr`]). This is false when opening an address that was derived from one that has already been checked, such as by `open_dual_stack`.
+	check_policy: bool,
+}
+
+impl OpenContext<'_> {
+	fn hook_context<'a>(&'a self, sockaddr: &'a socket2::SockAddr) -> HookContext<'a> {
+		HookContext {
+			address: self.address,
+			sockaddr,
+			user_options: self.user_options,
 		}
+	}
+}
+
+/// What `resolve` decided to do about a `SocketAddr`.
+enum Resolved {
+	/// An existing socket was claimed, such as an inherited socket.
+	Existing(Socket),
+
+	/// A new socket is to be opened and bound to this address.
+	New(socket2::SockAddr),
 
-		Ok(socket)
+	/// A new socket is to be opened and bound to the first available port in a range.
+	NewPortRange {
+		addr: IpAddr,
+		scope_id: u32,
+		ports: RangeInclusive<u16>,
+	},
+}
+
+/// A newly created socket, ready to be bound to its address.
+struct NewSocket {
+	socket: Socket,
+	sockaddr: socket2::SockAddr,
+	unlinked_stale_socket: bool,
+	listen_backlog: Option<c_int>,
+}
+
+/// Implementation of `open`, `open_with_info`, and `open_bound`.
+fn open_inner(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+	defer_listen: bool,
+	check_policy: bool,
+) -> Result<OpenInner, OpenSocketError> {
+	#[cfg(feature = "tokio")]
+	if app_options.before_bind_async.is_some() {
+		return Err(OpenSocketError::AsyncHookInBlockingOpen);
+	}
+
+	let (address, user_options) = merge_options(address, user_options);
+	let address: Cow<SocketAddr> = resolve_relative_unix_path(address, app_options)?;
+
+	let cx = OpenContext {
+		address: &address,
+		app_options,
+		user_options: &user_options,
+		defer_listen,
+		check_policy,
 	};
 
-	let inherit = |socket: sys::RawSocket| -> Result<Socket, OpenSocketError> {
-		sys::startup_socket_api();
+	let opened: OpenInner = now_or_never(open_resolved(resolve(&cx)?, |sockaddr| {
+		std::future::ready(open_new(&cx, sockaddr))
+	}))?;
 
-		#[cfg(unix)] {
-			check_inapplicable(user_options.unix_socket_permissions.as_ref(), "unix_socket_permissions")?;
-			check_inapplicable(user_options.unix_socket_owner.as_ref(), "unix_socket_owner")?;
-			check_inapplicable(user_options.unix_socket_group.as_ref(), "unix_socket_group")?;
-		}
+	finish_open(&cx, opened)
+}
+
+/// Asynchronous version of [`open`][open()], which supports the asynchronous [`SocketAppOptions::before_bind_async`] hook.
+///
+/// Apart from awaiting `before_bind_async`, this does exactly what `open` does, including blocking system calls. Those don't normally take long, but if that is a concern, consider running `open` on a blocking thread instead, such as with `tokio::task::spawn_blocking`.
+///
+///
+/// # Availability
+///
+/// Requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+pub async fn open_async(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<Socket, OpenSocketError> {
+	let (address, user_options) = merge_options(address, user_options);
+	let address: Cow<SocketAddr> = resolve_relative_unix_path(address, app_options)?;
 
-		#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
-		check_inapplicable_bool(user_options.ip_socket_reuse_port, "ip_socket_reuse_port")?;
+	let cx = OpenContext {
+		address: &address,
+		app_options,
+		user_options: &user_options,
+		defer_listen: false,
+		check_policy: true,
+	};
 
-		check_inapplicable_bool(user_options.ip_socket_v6_only, "ip_socket_v6_only")?;
-		check_inapplicable(user_options.listen_socket_backlog, "listen_socket_backlog")?;
+	let opened: OpenInner = open_resolved(resolve(&cx)?, |sockaddr| open_new_async(&cx, sockaddr)).await?;
 
-		// Safety: Inherited socket file descriptors/handles are supplied by the user or by an operating system API. Either way, we assume they're valid.
-		let socket: sys::BorrowedSocket<'_> = unsafe {
-			sys::BorrowedSocket::borrow_raw(socket)
-		};
+	finish_open(&cx, opened)
+	.map(|opened| opened.socket)
+}
 
-		let socket: sys::OwnedSocket =
-			socket.try_clone_to_owned()
-			.map_err(|error| OpenSocketError::DupInherited { error })?;
+/// Opens the socket that `resolve` decided on. `open_new` creates a new socket and binds it to the given address; this is how the blocking and asynchronous versions of `open` differ.
+///
+/// This is an `async fn` so that `open_async` can use it. The blocking version of `open` passes an `open_new` that never waits, and gets the result with `now_or_never`.
+async fn open_resolved<F>(
+	resolved: Resolved,
+	mut open_new: impl FnMut(socket2::SockAddr) -> F,
+) -> Result<OpenInner, OpenSocketError>
+where
+	F: Future<Output = Result<OpenInner, OpenSocketError>>,
+{
+	match resolved {
+		Resolved::Existing(socket) => Ok(OpenInner {
+			socket,
+			unix_path: None,
+			unlinked_stale_socket: false,
+			deferred_listen: None,
 
-		let socket: Socket = Socket::from(socket);
+			#[cfg(unix)]
+			deferred_replace: None,
+		}),
 
-		let actual_type: socket2::Type =
-			socket.r#type()
-			.map_err(|error| OpenSocketError::CheckInheritedSocket { error })?;
+		Resolved::New(sockaddr) => open_new(sockaddr).await,
 
-		if actual_type != app_options.r#type {
-			return Err(OpenSocketError::InheritWrongType {
-				expected: app_options.r#type,
-				actual: actual_type,
-			});
-		}
+		Resolved::NewPortRange { addr, scope_id, ports } => {
+			// Try each port in the range until one of them can be bound to. Only binding errors are worth retrying with a different port; anything else would just fail again.
+			let mut errors: Vec<(u16, io::Error)> = Vec::new();
 
-		// Check whether the socket is in a listening state, if the platform supports that. Ignore errors from the socket API; the only likely error is that the operating system is an old version that doesn't support this check.
-		#[cfg(any(
-			target_os = "aix",
-			target_os = "android",
-			target_os = "freebsd",
-			target_os = "fuchsia",
-			target_os = "linux",
-		))]
-		if actual_type == socket2::Type::STREAM {
-		if let Ok(actual_listen) = socket.is_listener() {
-		if app_options.listen != actual_listen {
-			return Err(match app_options.listen {
-				true => OpenSocketError::InheritedIsNotListening,
-				false => OpenSocketError::InheritedIsListening,
-			});
-		}}}
+			for port in ports {
+				match open_new(ip_sockaddr(addr, port, scope_id)).await {
+					Err(OpenSocketError::Bind { error }) => errors.push((port, error)),
+					result => return result,
+				}
+			}
+
+			Err(OpenSocketError::PortRangeExhausted { errors })
+		},
+	}
+}
+
+/// Gets the output of a future that never waits, such as the one returned by `open_resolved` in the blocking version of `open`.
+///
+/// # Panics
+///
+/// Panics if the future does wait.
+fn now_or_never<T>(future: impl Future<Output = T>) -> T {
+	struct NoopWaker;
+
+	impl task::Wake for NoopWaker {
+		fn wake(self: Arc<Self>) {}
+	}
 
-		Ok(socket)
+	let waker = task::Waker::from(Arc::new(NoopWaker));
+	let future = std::pin::pin!(future);
+
+	match future.poll(&mut task::Context::from_waker(&waker)) {
+		task::Poll::Ready(output) => output,
+		task::Poll::Pending => panic!("a future that was expected to never wait is waiting"),
+	}
+}
+
+/// If `address` is a `SocketAddr::WithOptions`, returns the address inside it, and `user_options` with the address's options applied.
+fn merge_options<'a>(
+	mut address: &'a SocketAddr,
+	user_options: &'a SocketUserOptions,
+) -> (&'a SocketAddr, Cow<'a, SocketUserOptions>) {
+	let mut user_options = Cow::Borrowed(user_options);
+
+	while let SocketAddr::WithOptions { addr, options } = address {
+		options.apply_to(user_options.to_mut());
+		address = addr;
+	}
+
+	(address, user_options)
+}
+
+/// Applies [`SocketAppOptions::relative_unix_paths`] to the address, if it's a Unix-domain socket with a relative path.
+fn resolve_relative_unix_path<'a>(
+	address: &'a SocketAddr,
+	app_options: &SocketAppOptions,
+) -> Result<Cow<'a, SocketAddr>, OpenSocketError> {
+	let SocketAddr::Unix { path } = address else {
+		return Ok(Cow::Borrowed(address));
 	};
 
-	let socket: Socket = match address {
-		SocketAddr::Ip { addr, port } => {
-			let port: u16 =
-				(*port)
-				.or(app_options.default_port)
-				.ok_or(OpenSocketError::PortRequired)?;
+	if path.is_absolute() {
+		return Ok(Cow::Borrowed(address));
+	}
 
-			let addr = std::net::SocketAddr::new(*addr, port);
+	match &app_options.relative_unix_paths {
+		RelativeUnixPaths::Allow => Ok(Cow::Borrowed(address)),
+		RelativeUnixPaths::Reject => Err(OpenSocketError::RelativeUnixPath { path: path.clone() }),
+		RelativeUnixPaths::ResolveAgainst(base) => Ok(Cow::Owned(SocketAddr::Unix { path: base.join(path) })),
+	}
+}
 
-			open_new(addr.into())?
-		}
+/// Decides how to open the socket, and claims it right away if it's inherited.
+fn resolve(cx: &OpenContext) -> Result<Resolved, OpenSocketError> {
+	if cx.check_policy {
+		check_address_policy(cx.address, cx.app_options, cx.user_options)?;
+	}
+
+	Ok(match cx.address {
+		SocketAddr::Ip { addr, port, zone, port_range_end } => resolve_ip(cx, *addr, *port, zone.as_deref(), *port_range_end)?,
+
+		SocketAddr::IpWildcard { port, port_range_end } => {
+			let addr: IpAddr = match cx.app_options.wildcard_ipv6 {
+				true => Ipv6Addr::UNSPECIFIED.into(),
+				false => Ipv4Addr::UNSPECIFIED.into(),
+			};
+
+			resolve_ip(cx, addr, *port, None, *port_range_end)?
+		},
 
 		SocketAddr::Unix { path } => {
 			let address =
 				socket2::SockAddr::unix(path)
 				.map_err(|error| OpenSocketError::InvalidUnixPath { error })?;
 
-			open_new(address)?
+			Resolved::New(address)
+		},
+
+		SocketAddr::UnixAbstract { name } => {
+			cfg_if::cfg_if! {
+				if #[cfg(any(target_os = "android", target_os = "linux", windows))] {
+					Resolved::New(unix_abstract_sockaddr(name)?)
+				}
+				else {
+					let _ = name;
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
+		},
+
+		SocketAddr::Vsock { cid, port } => {
+			cfg_if::cfg_if! {
+				if #[cfg(any(target_os = "android", target_os = "linux"))] {
+					Resolved::New(socket2::SockAddr::vsock(*cid, *port))
+				}
+				else {
+					let _ = (cid, port);
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
 		},
 
-		SocketAddr::Inherit { socket } => inherit(*socket)?,
+		SocketAddr::Inherit { socket } => Resolved::Existing(inherit(cx, *socket)?),
 
-		SocketAddr::InheritStdin {} => {
+		SocketAddr::InheritEnv { var } => {
+			let socket: String =
+				std::env::var(var)
+				.map_err(|error| OpenSocketError::InheritEnvVar { var: var.clone(), error })?;
+
+			let socket: sys::RawSocket =
+				socket.trim().parse()
+				.map_err(|error| OpenSocketError::InheritEnvSocketNum { var: var.clone(), error })?;
+
+			Resolved::Existing(inherit(cx, socket)?)
+		},
+
+		SocketAddr::InheritStdin => {
 			let socket: sys::RawSocket = sys::get_stdin_as_socket().map_err(|error| -> OpenSocketError {
 				match error {
 					// This can only fail on Windows.
@@ -277,7 +678,17 @@ pub fn open(
 				}
 			})?;
 
-			inherit(socket)?
+			Resolved::Existing(inherit(cx, socket)?)
+		},
+
+		#[cfg(not(windows))]
+		SocketAddr::SystemdFirst => {
+			if sys::SD_LISTEN_FDS_END.is_some() {
+				Resolved::Existing(inherit(cx, sys::SD_LISTEN_FDS_START)?)
+			}
+			else {
+				return Err(OpenSocketError::InvalidSystemdFd)
+			}
 		},
 
 		#[cfg(not(windows))]
@@ -286,13 +697,2601 @@ pub fn open(
 				*socket >= sys::SD_LISTEN_FDS_START ||
 				sys::SD_LISTEN_FDS_END.is_some_and(|sd_listen_fds_end| *socket <= sd_listen_fds_end)
 			{
-				inherit(*socket)?
+				Resolved::Existing(inherit(cx, *socket)?)
 			}
 			else {
 				return Err(OpenSocketError::InvalidSystemdFd)
 			}
 		},
+
+		#[cfg(not(windows))]
+		SocketAddr::SystemdNamed { name } => {
+			let socket =
+				sys::sd_listen_fd_by_name(name)
+				.ok_or_else(|| OpenSocketError::InvalidSystemdName { name: name.clone() })?;
+
+			Resolved::Existing(inherit(cx, socket)?)
+		},
+
+		SocketAddr::Launchd { name } => {
+			cfg_if::cfg_if! {
+				if #[cfg(target_os = "macos")] {
+					use std::os::fd::AsRawFd;
+
+					let sockets: Vec<sys::OwnedSocket> =
+						sys::launch_activate_socket(name)
+						.map_err(|error| OpenSocketError::LaunchdActivate { error })?;
+
+					// `inherit` makes its own duplicate of the socket, so all of the sockets from launchd are closed when `sockets` is dropped.
+					Resolved::Existing(inherit(cx, sockets[0].as_raw_fd())?)
+				}
+				else {
+					let _ = name;
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
+		},
+
+		SocketAddr::WsaProtocolInfo { info } => {
+			cfg_if::cfg_if! {
+				if #[cfg(windows)] {
+					sys::startup_socket_api();
+
+					check_inherit(cx.user_options)?;
+
+					let socket: Socket =
+						sys::socket_from_protocol_info(info)
+						.map_err(|error| OpenSocketError::WsaProtocolInfo { error })?;
+
+					Resolved::Existing(check_inherited(cx, socket)?)
+				}
+				else {
+					let _ = info;
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
+		},
+
+		SocketAddr::Pair { name } => {
+			check_no_bind_options(cx.user_options)?;
+			check_no_anytime_options(cx.user_options)?;
+			check_inapplicable(cx.user_options.inherited_socket_expected_addr.as_ref(), "inherited_socket_expected_addr")?;
+			Resolved::Existing(crate::pair::open_pair(name, cx.app_options, cx.user_options)?)
+		},
+
+		// Already unwrapped by `merge_options`.
+		SocketAddr::WithOptions { .. } => unreachable!(),
+	})
+}
+
+/// Resolves an IP address, port, and zone to the address to bind a new socket to.
+fn resolve_ip(
+	cx: &OpenContext,
+	addr: IpAddr,
+	port: Option<u16>,
+	zone: Option<&str>,
+	port_range_end: Option<u16>,
+) -> Result<Resolved, OpenSocketError> {
+	// A port range only applies if a first port was given explicitly.
+	let port_range_end: Option<u16> = port.and(port_range_end);
+
+	let port: u16 = resolve_port(port, cx.app_options.default_port, cx.app_options.r#type)?;
+
+	if cx.check_policy {
+		check_allowed_ports(port, port_range_end, cx.app_options)?;
+	}
+
+	let scope_id: u32 = resolve_scope_id(addr, zone)?;
+
+	Ok(match port_range_end {
+		Some(port_range_end) => Resolved::NewPortRange { addr, scope_id, ports: port..=port_range_end },
+		None => Resolved::New(ip_sockaddr(addr, port, scope_id)),
+	})
+}
+
+fn ip_sockaddr(addr: IpAddr, port: u16, scope_id: u32) -> socket2::SockAddr {
+	let addr: std::net::SocketAddr = match addr {
+		IpAddr::V6(addr) => SocketAddrV6::new(addr, port, 0, scope_id).into(),
+		addr => std::net::SocketAddr::new(addr, port),
+	};
+
+	addr.into()
+}
+
+/// Claims an inherited socket.
+fn inherit(cx: &OpenContext, raw_socket: sys::RawSocket) -> Result<Socket, OpenSocketError> {
+	sys::startup_socket_api();
+
+	check_inherit(cx.user_options)?;
+
+	// Safety: Inherited socket file descriptors/handles are supplied by the user or by an operating system API. Either way, we assume they're valid.
+	let socket: sys::BorrowedSocket<'_> = unsafe {
+		sys::BorrowedSocket::borrow_raw(raw_socket)
 	};
 
+	let socket: sys::OwnedSocket =
+		socket.try_clone_to_owned()
+		.map_err(|error| OpenSocketError::DupInherited { error })?;
+
+	// Record the claim before checking the socket, because checking it also applies the user options to it, and that must not happen to a socket that someone else has already claimed.
+	let newly_claimed: bool =
+		CLAIMED_INHERITED.lock()
+		.unwrap_or_else(|error| error.into_inner())
+		.insert(raw_socket);
+
+	if !newly_claimed && cx.app_options.exclusive_inherit {
+		return Err(OpenSocketError::InheritedAlreadyClaimed { socket: raw_socket });
+	}
+
+	check_inherited(cx, Socket::from(socket))
+	.map_err(|error| {
+		// Take back the claim if the checks fail, so that a failed attempt doesn't prevent a later one.
+		if newly_claimed {
+			CLAIMED_INHERITED.lock()
+			.unwrap_or_else(|error| error.into_inner())
+			.remove(&raw_socket);
+		}
+
+		error
+	})
+}
+
+/// The inherited sockets that have been opened by [`inherit`], for [`SocketAppOptions::exclusive_inherit`].
+static CLAIMED_INHERITED: Mutex<BTreeSet<sys::RawSocket>> = Mutex::new(BTreeSet::new());
+
+/// Checks that a socket that was inherited, or otherwise handed to this process, has one of the expected types, is listening if and only if expected, and is bound to the expected address, if any.
+fn check_inherited(cx: &OpenContext, socket: Socket) -> Result<Socket, OpenSocketError> {
+	let OpenContext { app_options, user_options, .. } = *cx;
+
+	let actual_type: socket2::Type =
+		socket.r#type()
+		.map_err(|error| OpenSocketError::CheckInheritedSocket { error })?;
+
+	// Find out whether the socket is expected to be listening, which depends on which of the acceptable types it is.
+	let expected_listen: bool =
+		if actual_type == app_options.r#type {
+			app_options.listen
+		}
+		else if let Some(other) = app_options.other_inherited_types.iter().find(|other| other.r#type == actual_type) {
+			other.listen
+		}
+		else {
+			return Err(OpenSocketError::InheritWrongType {
+				expected: app_options.r#type,
+				actual: actual_type,
+			});
+		};
+
+	// Check whether the socket is in a listening state, if the platform supports that. Ignore errors from the socket API; the only likely error is that the operating system is an old version that doesn't support this check.
+	if is_listenable_socket_type(actual_type) {
+	if let Some(Ok(actual_listen)) = sys::is_listener(&socket) {
+	if expected_listen != actual_listen {
+		return Err(match expected_listen {
+			true => OpenSocketError::InheritedIsNotListening,
+			false => OpenSocketError::InheritedIsListening,
+		});
+	}}}
+
+	if let Some(expected) = &user_options.inherited_socket_expected_addr {
+		let actual: socket2::SockAddr =
+			socket.local_addr()
+			.map_err(|error| OpenSocketError::CheckInheritedSocket { error })?;
+
+		let actual: Option<SocketAddr> = SocketAddr::try_from(&actual).ok();
+
+		if !actual.as_ref().is_some_and(|actual| is_bound_to(actual, expected)) {
+			return Err(OpenSocketError::InheritedWrongAddr {
+				expected: Box::new(expected.clone()),
+				actual: actual.map(Box::new),
+			});
+		}
+	}
+
+	set_inherited_options(&socket, actual_type, user_options)?;
+
 	Ok(socket)
 }
+
+/// Checks whether `actual`, the local address of an inherited socket, matches `expected`, as described in the documentation for [`SocketUserOptions::inherited_socket_expected_addr`].
+fn is_bound_to(actual: &SocketAddr, expected: &SocketAddr) -> bool {
+	// Treat IPv4-mapped IPv6 addresses as the IPv4 addresses they are.
+	let unmap = |addr: IpAddr| -> IpAddr {
+		match addr {
+			IpAddr::V6(addr) => addr.to_ipv4_mapped().map_or(IpAddr::V6(addr), IpAddr::V4),
+			addr => addr,
+		}
+	};
+
+	let port_matches = |actual: Option<u16>, expected: Option<u16>, expected_end: Option<u16>| -> bool {
+		match expected {
+			Some(expected) => actual.is_some_and(|actual| (expected..=expected_end.unwrap_or(expected)).contains(&actual)),
+			None => true,
+		}
+	};
+
+	match (actual, expected.without_options()) {
+		(
+			SocketAddr::Ip { addr: actual_addr, port: actual_port, .. },
+			SocketAddr::Ip { addr: expected_addr, port: expected_port, port_range_end, .. },
+		) => unmap(*actual_addr) == unmap(*expected_addr) && port_matches(*actual_port, *expected_port, *port_range_end),
+
+		(
+			SocketAddr::Ip { addr: actual_addr, port: actual_port, .. },
+			SocketAddr::IpWildcard { port: expected_port, port_range_end },
+		) => actual_addr.is_unspecified() && port_matches(*actual_port, *expected_port, *port_range_end),
+
+		(SocketAddr::Unix { path: actual }, SocketAddr::Unix { path: expected }) => actual == expected,
+		(SocketAddr::UnixAbstract { name: actual }, SocketAddr::UnixAbstract { name: expected }) => actual == expected,
+		(SocketAddr::Vsock { cid: actual_cid, port: actual_port }, SocketAddr::Vsock { cid: expected_cid, port: expected_port }) => actual_cid == expected_cid && actual_port == expected_port,
+
+		_ => false,
+	}
+}
+
+/// Opens a new socket and binds it to `sockaddr`.
+fn open_new(cx: &OpenContext, sockaddr: socket2::SockAddr) -> Result<OpenInner, OpenSocketError> {
+	let mut new_socket: NewSocket = prepare_new(cx, sockaddr)?;
+
+	before_bind(cx, &mut new_socket)?;
+
+	bind_new(cx, new_socket)
+}
+
+/// Asynchronous version of `open_new`, which also calls the asynchronous hook.
+#[cfg(feature = "tokio")]
+async fn open_new_async(cx: &OpenContext<'_>, sockaddr: socket2::SockAddr) -> Result<OpenInner, OpenSocketError> {
+	let mut new_socket: NewSocket = prepare_new(cx, sockaddr)?;
+
+	before_bind(cx, &mut new_socket)?;
+
+	if let Some(before_bind_async) = &cx.app_options.before_bind_async {
+		let hook_context = AsyncHookContext {
+			address: cx.address.clone(),
+			sockaddr: new_socket.sockaddr.clone(),
+			user_options: cx.user_options.clone(),
+		};
+
+		new_socket.socket =
+			before_bind_async(new_socket.socket, hook_context).await
+			.map_err(OpenSocketError::BeforeBind)?;
+	}
+
+	bind_new(cx, new_socket)
+}
+
+/// Sets the preset's socket options, if any, and calls the `before_bind` hook, if any.
+fn before_bind(cx: &OpenContext, new_socket: &mut NewSocket) -> Result<(), OpenSocketError> {
+	let hook_context = cx.hook_context(&new_socket.sockaddr);
+
+	if let Some(preset) = cx.app_options.preset {
+		preset.before_bind(&mut new_socket.socket, &hook_context)
+		.map_err(OpenSocketError::BeforeBind)?;
+	}
+
+	if let Some(before_bind) = &cx.app_options.before_bind {
+		before_bind(&mut new_socket.socket, &hook_context)
+		.map_err(OpenSocketError::BeforeBind)?;
+	}
+
+	Ok(())
+}
+
+/// Checks the options, creates a new socket, and sets it up to be bound to `sockaddr`.
+fn prepare_new(cx: &OpenContext, sockaddr: socket2::SockAddr) -> Result<NewSocket, OpenSocketError> {
+	let OpenContext { app_options, user_options, .. } = *cx;
+
+	// Is this a path-based Unix-domain socket? (We can't use `socket2::SockAddr::as_pathname` here, because it isn't available on Windows.)
+	let unix_socket_path: Option<&Path> = match cx.address {
+		SocketAddr::Unix { path } => Some(path),
+		_ => None,
+	};
+
+	// Check the options, and if we need to `listen` on this socket, what the backlog should be.
+	let listen_backlog: Option<_> = check_new_socket(&sockaddr, unix_socket_path, app_options, user_options)?;
+
+	let is_raw: bool = is_raw_socket_type(app_options.r#type);
+
+	// Create the new socket.
+	let socket: socket2::Socket =
+		Socket::new(sockaddr.domain(), app_options.r#type, app_options.protocol)
+		.map_err(|error| {
+			if is_raw && error.kind() == io::ErrorKind::PermissionDenied {
+				OpenSocketError::RawPermissionDenied { error }
+			}
+			else {
+				OpenSocketError::CreateSocket { error }
+			}
+		})?;
+
+	let mut unlinked_stale_socket: bool = false;
+
+	if let Some(socket_path) = unix_socket_path {
+		// Create any needed parent folders.
+		if let Some(socket_parent_path) = socket_path.parent() {
+			fs::create_dir_all(socket_parent_path)
+			.map_err(|error| OpenSocketError::MkdirParents { error })?;
+		}
+
+		// Make sure that no other process is still using the previous socket, if desired.
+		#[cfg(unix)]
+		if user_options.unix_socket_lock_file {
+			crate::unix_lock::lock(socket_path)?;
+		}
+
+		// Clean up the previous socket, if desired and applicable. If it's going to be replaced atomically instead, it's left in place for now, but it has to actually be a socket.
+		if is_atomic_replace(user_options) {
+			check_replaceable(socket_path)?;
+		}
+		else if !user_options.unix_socket_no_unlink {
+			unlinked_stale_socket = cleanup_unix_path_socket(socket_path)?;
+		}
+	}
+
+	// Set socket options.
+
+	// `SO_REUSEADDR` is only set for TCP listening sockets on non-Windows platforms, same as the Rust standard library. See explanation: https://github.com/rust-lang/rust/blob/1b225414f325593f974c6b41e671a0a0dc5d7d5e/library/std/src/sys_common/net.rs#L395
+	#[cfg(not(windows))]
+	if listen_backlog.is_some() && is_socket_probably_tcp(&socket, &sockaddr, app_options) {
+		socket.set_reuse_address(true)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_REUSEADDR",
+			error,
+		})?;
+	}
+
+	#[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+	if user_options.ip_socket_reuse_port {
+		socket.set_reuse_port(true)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_REUSEPORT",
+			error,
+		})?;
+	}
+
+	#[cfg(any(target_os = "solaris", target_os = "illumos"))] {
+		check_unsupported_bool(user_options.ip_socket_reuse_port, "ip_socket_reuse_port")?;
+
+		if user_options.ip_socket_exclusive_bind {
+			sys::set_exclusive_bind(&socket, true)
+			.map_err(|error| OpenSocketError::SetSockOpt {
+				option: "SO_EXCLBIND",
+				error,
+			})?;
+		}
+	}
+
+	if user_options.ip_socket_v6_only {
+		socket.set_only_v6(true)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "IPV6_V6ONLY",
+			error,
+		})?;
+	}
+
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	if user_options.udp_socket_pktinfo {
+		sys::set_recv_pktinfo(&socket, sockaddr.is_ipv6())
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: if sockaddr.is_ipv6() { "IPV6_RECVPKTINFO" } else { "IP_PKTINFO" },
+			error,
+		})?;
+	}
+
+	// UDP offloads are optional features, so if the kernel doesn't have them, carry on without them. `open_with_info` reports whether they took effect.
+	#[cfg(target_os = "linux")]
+	if let Some(size) = user_options.udp_segment_size {
+		sys::set_udp_segment(&socket, size)
+		.or_else(ignore_unsupported_sockopt)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "UDP_SEGMENT",
+			error,
+		})?;
+	}
+
+	#[cfg(target_os = "linux")]
+	if user_options.udp_gro {
+		sys::set_udp_gro(&socket, true)
+		.or_else(ignore_unsupported_sockopt)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "UDP_GRO",
+			error,
+		})?;
+	}
+
+	// This has to be set before binding, or else binding to a non-local address fails.
+	#[cfg(target_os = "linux")]
+	if user_options.ip_socket_transparent {
+		sys::set_transparent(&socket, sockaddr.is_ipv6())
+		.map_err(|error| {
+			if error.kind() == io::ErrorKind::PermissionDenied {
+				OpenSocketError::TransparentPermissionDenied { error }
+			}
+			else {
+				OpenSocketError::SetSockOpt {
+					option: if sockaddr.is_ipv6() { "IPV6_TRANSPARENT" } else { "IP_TRANSPARENT" },
+					error,
+				}
+			}
+		})?;
+	}
+
+	set_anytime_options(&socket, user_options)?;
+
+	// This has to be set before the socket connects or starts listening.
+	#[cfg(windows)]
+	if user_options.tcp_loopback_fast_path {
+		sys::set_loopback_fast_path(&socket)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SIO_LOOPBACK_FAST_PATH",
+			error,
+		})?;
+	}
+
+	// This has to be set before the socket starts listening, so that accepted connections inherit it.
+	#[cfg(all(unix, not(target_os = "redox")))]
+	if let Some(mss) = user_options.tcp_max_segment {
+		socket.set_mss(mss)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "TCP_MAXSEG",
+			error,
+		})?;
+	}
+
+	Ok(NewSocket {
+		socket,
+		sockaddr,
+		unlinked_stale_socket,
+		listen_backlog,
+	})
+}
+
+/// Sets the socket options that don't have to be set before the socket is bound. These are the ones that can also be applied to inherited sockets, except for the buffer sizes, which are set separately by [`set_buffer_sizes`].
+fn set_anytime_options(socket: &Socket, user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	#[cfg(not(any(
+		target_os = "fuchsia",
+		target_os = "haiku",
+		target_os = "illumos",
+		target_os = "redox",
+		target_os = "solaris",
+	)))]
+	if let Some(tos) = user_options.ip_socket_tos {
+		socket.set_tos(tos.0.into())
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "IP_TOS",
+			error,
+		})?;
+	}
+
+	#[cfg(any(
+		target_os = "aix",
+		target_os = "android",
+		target_os = "dragonfly",
+		target_os = "freebsd",
+		target_os = "fuchsia",
+		target_os = "illumos",
+		target_os = "ios",
+		target_os = "linux",
+		target_os = "macos",
+		target_os = "netbsd",
+		target_os = "openbsd",
+		target_os = "solaris",
+		target_os = "tvos",
+		target_os = "watchos",
+	))]
+	if let Some(tclass) = user_options.ipv6_socket_tclass {
+		sys::set_tclass_v6(socket, tclass.0)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "IPV6_TCLASS",
+			error,
+		})?;
+	}
+
+	if let Some(keepalive) = user_options.tcp_keepalive_params() {
+		socket.set_tcp_keepalive(&keepalive)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_KEEPALIVE",
+			error,
+		})?;
+	}
+
+	if user_options.tcp_nodelay {
+		socket.set_nodelay(true)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "TCP_NODELAY",
+			error,
+		})?;
+	}
+
+	if let Some(timeout) = user_options.socket_recv_timeout {
+		socket.set_read_timeout(timeout_from_millis(timeout))
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_RCVTIMEO",
+			error,
+		})?;
+	}
+
+	if let Some(timeout) = user_options.socket_send_timeout {
+		socket.set_write_timeout(timeout_from_millis(timeout))
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_SNDTIMEO",
+			error,
+		})?;
+	}
+
+	Ok(())
+}
+
+/// Sets the socket's send and receive buffer sizes, if configured.
+fn set_buffer_sizes(socket: &Socket, user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	if let Some(size) = user_options.socket_recv_buffer_size {
+		socket.set_recv_buffer_size(size)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_RCVBUF",
+			error,
+		})?;
+	}
+
+	if let Some(size) = user_options.socket_send_buffer_size {
+		socket.set_send_buffer_size(size)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_SNDBUF",
+			error,
+		})?;
+	}
+
+	Ok(())
+}
+
+/// Checks that the options that can be applied to an already-bound socket are applicable to this inherited socket, and applies them.
+fn set_inherited_options(socket: &Socket, r#type: socket2::Type, user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	let local_addr: socket2::SockAddr =
+		socket.local_addr()
+		.map_err(|error| OpenSocketError::CheckInheritedSocket { error })?;
+
+	#[cfg(not(any(
+		target_os = "fuchsia",
+		target_os = "haiku",
+		target_os = "illumos",
+		target_os = "redox",
+		target_os = "solaris",
+	)))]
+	if user_options.ip_socket_tos.is_some() && !local_addr.is_ipv4() {
+		return inapplicable("ip_socket_tos");
+	}
+
+	#[cfg(any(
+		target_os = "aix",
+		target_os = "android",
+		target_os = "dragonfly",
+		target_os = "freebsd",
+		target_os = "fuchsia",
+		target_os = "illumos",
+		target_os = "ios",
+		target_os = "linux",
+		target_os = "macos",
+		target_os = "netbsd",
+		target_os = "openbsd",
+		target_os = "solaris",
+		target_os = "tvos",
+		target_os = "watchos",
+	))]
+	if user_options.ipv6_socket_tclass.is_some() && !local_addr.is_ipv6() {
+		return inapplicable("ipv6_socket_tclass");
+	}
+
+	// An inherited stream-type Internet socket is assumed to be TCP, unless the socket can be asked what protocol it's using and says otherwise.
+	let is_tcp: bool =
+		r#type == socket2::Type::STREAM &&
+		(local_addr.is_ipv4() || local_addr.is_ipv6()) &&
+		{
+			cfg_if::cfg_if! {
+				if #[cfg(any(
+					target_os = "android",
+					target_os = "freebsd",
+					target_os = "fuchsia",
+					target_os = "linux",
+				))] {
+					!matches!(socket.protocol(), Ok(Some(protocol)) if protocol != socket2::Protocol::TCP)
+				}
+				else {
+					true
+				}
+			}
+		};
+
+	if !is_tcp {
+		check_inapplicable(user_options.tcp_keepalive_params(), "tcp_keepalive")?;
+		check_inapplicable_bool(user_options.tcp_nodelay, "tcp_nodelay")?;
+	}
+
+	set_anytime_options(socket, user_options)?;
+	set_buffer_sizes(socket, user_options)
+}
+
+/// Binds a socket created by `prepare_new`, and makes it listen if applicable.
+fn bind_new(cx: &OpenContext, new_socket: NewSocket) -> Result<OpenInner, OpenSocketError> {
+	let OpenContext { app_options, user_options, .. } = *cx;
+	let NewSocket { mut socket, sockaddr, unlinked_stale_socket, listen_backlog } = new_socket;
+
+	let unix_socket_path: Option<&Path> = match cx.address {
+		SocketAddr::Unix { path } => Some(path),
+		_ => None,
+	};
+
+	// Set the buffer sizes now, rather than with the other socket options, so that they override any that the application's `before_bind` hook set.
+	set_buffer_sizes(&socket, user_options)?;
+
+	// If the socket is to atomically replace an existing one, bind it to a temporary path instead of its designated path.
+	#[cfg(unix)]
+	let replace: Option<UnixReplace> = match unix_socket_path {
+		Some(path) if user_options.unix_socket_atomic_replace => Some(UnixReplace::new(path)?),
+		_ => None,
+	};
+
+	#[cfg(unix)]
+	let (bind_sockaddr, bind_path): (&socket2::SockAddr, Option<&Path>) = match &replace {
+		Some(replace) => (&replace.temp_sockaddr, Some(&replace.temp_path)),
+		None => (&sockaddr, unix_socket_path),
+	};
+
+	// On Unix-like platforms, binding a Unix-domain socket with permissions has to be done carefully, so that the socket isn't accessible with the wrong permissions in the meantime.
+	#[cfg(unix)]
+	crate::unix_security::bind(user_options, &socket, bind_sockaddr, bind_path)
+	.map_err(|error| OpenSocketError::Bind { error })?;
+
+	#[cfg(not(unix))]
+	socket.bind(&sockaddr)
+	.map_err(|error| OpenSocketError::Bind { error })?;
+
+	// Attach the reuse-port program, if any. This has to be done after binding, because a socket that already has a program can't join an existing group.
+	#[cfg(target_os = "linux")]
+	if user_options.ip_socket_reuse_port && !app_options.reuse_port_cbpf.is_empty() {
+		sys::attach_reuse_port_cbpf(&socket, &app_options.reuse_port_cbpf)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "SO_ATTACH_REUSEPORT_CBPF",
+			error,
+		})?;
+	}
+
+	// Bind an SCTP socket to its additional addresses, if configured. They use the same port number as the primary address, which might have been chosen by the operating system if it was zero.
+	#[cfg(target_os = "linux")]
+	if !user_options.sctp_socket_additional_addresses.is_empty() {
+		let port: u16 =
+			socket.local_addr()
+			.ok()
+			.and_then(|local_addr| local_addr.as_socket())
+			.map(|local_addr| local_addr.port())
+			.unwrap_or_default();
+
+		let additional_addrs: Vec<socket2::SockAddr> =
+			user_options.sctp_socket_additional_addresses.iter()
+			.map(|addr| std::net::SocketAddr::new(*addr, port).into())
+			.collect();
+
+		sys::sctp_bindx_add(&socket, &additional_addrs)
+		.map_err(|error| OpenSocketError::SctpBindAdditional { error })?;
+	}
+
+	// Configure multicast and join the multicast groups, if applicable and configured.
+	if sockaddr.is_ipv4() {
+		set_multicast_options_v4(&socket, user_options)?;
+	}
+	else if sockaddr.is_ipv6() {
+		set_multicast_options_v6(&socket, user_options)?;
+	}
+
+	// Set security attributes on the socket, if applicable and configured.
+	#[cfg(unix)]
+	crate::unix_security::apply(user_options, &socket, bind_path)?;
+
+	if let Some(after_bind) = &app_options.after_bind {
+		after_bind(&mut socket, &cx.hook_context(&sockaddr))
+		.map_err(OpenSocketError::AfterBind)?;
+	}
+
+	// Set the socket to listening, if applicable and configured.
+	let mut deferred_listen: Option<ListenParams> = None;
+
+	if let Some(listen_backlog) = listen_backlog {
+		if let Some(before_listen) = &app_options.before_listen {
+			before_listen(&mut socket, &cx.hook_context(&sockaddr))
+			.map_err(OpenSocketError::BeforeListen)?;
+		}
+
+		let listen = ListenParams::new(listen_backlog, cx.user_options);
+
+		if cx.defer_listen {
+			deferred_listen = Some(listen);
+		}
+		else {
+			listen.listen(&socket)?;
+		}
+	}
+
+	// Move the socket into place, now that it's ready. If listening was deferred, this has to wait until after that.
+	#[cfg(unix)]
+	let deferred_replace: Option<UnixReplace> = match replace {
+		Some(replace) if deferred_listen.is_none() => {
+			replace.finish()?;
+			None
+		},
+
+		replace => replace,
+	};
+
+	Ok(OpenInner {
+		socket,
+		unix_path: unix_socket_path.map(Path::to_owned),
+		unlinked_stale_socket,
+		deferred_listen,
+
+		#[cfg(unix)]
+		deferred_replace,
+	})
+}
+
+/// Last steps of opening a socket, which apply to both new and inherited sockets.
+fn finish_open(cx: &OpenContext, opened: OpenInner) -> Result<OpenInner, OpenSocketError> {
+	if cx.app_options.nonblocking {
+		opened.socket.set_nonblocking(true)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: if cfg!(windows) { "FIONBIO" } else { "O_NONBLOCK" },
+			error,
+		})?;
+	}
+
+	Ok(opened)
+}
+
+/// A socket that was opened by [`open_with_info`], along with some information about it.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct OpenedSocket {
+	/// The socket itself.
+	pub socket: Socket,
+
+	/// The local address that the socket is bound to.
+	///
+	/// This can differ from the requested address. In particular, if the port number was zero, this has the port number that the operating system chose, and if there was a port range, this has the port number from the range that was available.
+	pub local_addr: socket2::SockAddr,
+
+	/// Whether the socket was inherited, rather than newly created. See [`SocketAddr::is_inherited`].
+	pub is_inherited: bool,
+
+	/// The path of the Unix-domain socket that was created, if any.
+	///
+	/// This is `None` for inherited Unix-domain sockets, since they weren't created by `open_with_info`.
+	pub unix_path: Option<PathBuf>,
+
+	/// Whether a stale Unix-domain socket was found at [`unix_path`][Self::unix_path] and deleted before the new socket was created. See [`SocketUserOptions::unix_socket_no_unlink`].
+	///
+	/// This is always false if [`SocketUserOptions::unix_socket_atomic_replace`] was used, since any stale socket is replaced rather than deleted.
+	pub unlinked_stale_socket: bool,
+
+	/// The size of the socket's receive buffer (`SO_RCVBUF`), in bytes, or `None` if it couldn't be determined.
+	///
+	/// This can differ from [`SocketUserOptions::socket_recv_buffer_size`], because the operating system may adjust the requested size.
+	pub recv_buffer_size: Option<usize>,
+
+	/// The size of the socket's send buffer (`SO_SNDBUF`), in bytes, or `None` if it couldn't be determined.
+	///
+	/// This can differ from [`SocketUserOptions::socket_send_buffer_size`], because the operating system may adjust the requested size.
+	pub send_buffer_size: Option<usize>,
+
+	/// The segment size for UDP generic segmentation offload (`UDP_SEGMENT`), or `None` if it isn't enabled or couldn't be determined. See [`SocketUserOptions::udp_segment_size`].
+	///
+	/// # Availability
+	///
+	/// Linux only.
+	#[cfg(target_os = "linux")]
+	pub udp_segment_size: Option<u16>,
+
+	/// Whether UDP generic receive offload (`UDP_GRO`) is enabled. This is false if it couldn't be determined, such as for sockets other than UDP sockets. See [`SocketUserOptions::udp_gro`].
+	///
+	/// # Availability
+	///
+	/// Linux only.
+	#[cfg(target_os = "linux")]
+	pub udp_gro: bool,
+}
+
+impl From<OpenedSocket> for Socket {
+	fn from(opened: OpenedSocket) -> Self {
+		opened.socket
+	}
+}
+
+/// Opens several sockets at once, such as when an application listens on more than one address. Either all of them are opened, or none are.
+///
+/// Each address is opened with [`open`][open()], in order, using the same options. If any of them can't be opened, the sockets that were already opened are closed, and any path-based Unix-domain sockets among them are deleted, as with [`SocketAddr::cleanup`]. Inherited sockets are left alone, since they weren't created by this function.
+///
+/// On success, the returned sockets are in the same order as `addresses`.
+///
+///
+/// # Errors
+///
+/// Returns an [`OpenMultipleError`] for the first address that couldn't be opened. It indicates which address that was, and why it couldn't be opened.
+pub fn open_multiple(
+	addresses: &[SocketAddr],
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<Vec<Socket>, OpenMultipleError> {
+	let mut sockets: Vec<Socket> = Vec::with_capacity(addresses.len());
+	let mut unix_paths: Vec<PathBuf> = Vec::new();
+
+	for (index, address) in addresses.iter().enumerate() {
+		match open_inner(address, app_options, user_options, false, true) {
+			Ok(opened) => {
+				sockets.push(opened.socket);
+				unix_paths.extend(opened.unix_path);
+			},
+
+			Err(error) => {
+				// Close the sockets that were already opened before deleting them, so that nothing is left listening on a socket that no longer has a path. Errors in cleaning up are ignored, since there's already an error to report.
+				drop(sockets);
+
+				for unix_path in &unix_paths {
+					let _ = cleanup_unix_path_socket(unix_path);
+				}
+
+				return Err(OpenMultipleError {
+					index,
+					address: address.clone(),
+					error,
+				});
+			},
+		}
+	}
+
+	Ok(sockets)
+}
+
+/// Like [`open`][open()], but a [`SocketAddr::IpWildcard`] (such as `*:8080`) is opened as two sockets: one bound to the IPv6 wildcard address `[::]`, with [`IPV6_V6ONLY`][SocketUserOptions::ip_socket_v6_only] set, and one bound to the IPv4 wildcard address `0.0.0.0`.
+///
+/// Whether an IPv6 socket also accepts IPv4 connections differs from one platform to another, and on some platforms, it can't be changed. Opening separate sockets for IPv4 and IPv6 behaves the same way everywhere. The application then needs to accept connections or packets on both sockets.
+///
+/// Both sockets are bound to the same port. If the port is zero, the IPv6 socket is opened first, and the IPv4 socket is bound to whichever port the IPv6 socket ended up with. If the port is a range, each port in the range is tried in turn until both sockets can be bound to it.
+///
+/// If the system doesn't support IPv6 at all, such as when it's disabled, then only the IPv4 socket is opened. Any other error in opening the IPv6 socket is returned.
+///
+/// The address is checked against [`SocketAppOptions::allowed_kinds`], [`SocketAppOptions::allowed_ports`], and [`SocketAppOptions::validate_addr`] once, as given. The IPv4 and IPv6 addresses derived from it are not checked again.
+///
+/// Any other kind of address is opened with `open`, and the returned `Vec` contains just that one socket.
+pub fn open_dual_stack(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<Vec<Socket>, OpenSocketError> {
+	let (inner_address, user_options) = merge_options(address, user_options);
+
+	let SocketAddr::IpWildcard { port, port_range_end } = *inner_address else {
+		return Ok(vec![open(address, app_options, &user_options)?]);
+	};
+
+	// Check the address that the user actually gave. The IPv4 and IPv6 addresses that are opened below are derived from it, so they aren't checked again.
+	check_ip_address_policy(inner_address, app_options, &user_options)?;
+
+	let open_v4 = |port: Option<u16>, port_range_end: Option<u16>| -> Result<Socket, OpenSocketError> {
+		let mut user_options: SocketUserOptions = user_options.clone().into_owned();
+		user_options.ip_socket_v6_only = false;
+
+		let address = SocketAddr::Ip { addr: Ipv4Addr::UNSPECIFIED.into(), port, zone: None, port_range_end };
+		open_inner(&address, app_options, &user_options, false, false)
+		.map(|opened| opened.socket)
+	};
+
+	let open_v6 = |port: Option<u16>| -> Result<Socket, OpenSocketError> {
+		let mut user_options: SocketUserOptions = user_options.clone().into_owned();
+		user_options.ip_socket_v6_only = true;
+
+		let address = SocketAddr::Ip { addr: Ipv6Addr::UNSPECIFIED.into(), port, zone: None, port_range_end: None };
+		open_inner(&address, app_options, &user_options, false, false)
+		.map(|opened| opened.socket)
+	};
+
+	// Opens both sockets on one port, or on whichever port the IPv6 socket gets if it's zero or absent. Returns `None` if the system doesn't support IPv6 at all.
+	let open_both = |port: Option<u16>| -> Result<Option<Vec<Socket>>, OpenSocketError> {
+		let v6_socket: Socket = match open_v6(port) {
+			Ok(socket) => socket,
+			Err(OpenSocketError::CreateSocket { error }) if sys::is_address_family_unsupported(&error) => return Ok(None),
+			Err(error) => return Err(error),
+		};
+
+		// Bind the IPv4 socket to the same port that the IPv6 socket got.
+		let v4_port: Option<u16> = match v6_socket.local_addr().ok().and_then(|addr| addr.as_socket()) {
+			Some(v6_addr) => Some(v6_addr.port()),
+			None => port,
+		};
+
+		let v4_socket: Socket = open_v4(v4_port, None)?;
+
+		Ok(Some(vec![v6_socket, v4_socket]))
+	};
+
+	let (port, port_range_end) = match (port, port_range_end) {
+		(Some(port), Some(port_range_end)) => (port, port_range_end),
+		_ => return match open_both(port)? {
+			Some(sockets) => Ok(sockets),
+			None => Ok(vec![open_v4(port, port_range_end)?]),
+		},
+	};
+
+	// Try each port in the range until both sockets can be bound to it. As in `open`, only binding errors are worth retrying with a different port.
+	let mut errors: Vec<(u16, io::Error)> = Vec::new();
+
+	for port in port..=port_range_end {
+		match open_both(Some(port)) {
+			Ok(Some(sockets)) => return Ok(sockets),
+			Ok(None) => return Ok(vec![open_v4(Some(port), Some(port_range_end))?]),
+			Err(OpenSocketError::Bind { error }) => errors.push((port, error)),
+			Err(error) => return Err(error),
+		}
+	}
+
+	Err(OpenSocketError::PortRangeExhausted { errors })
+}
+
+/// Opens `count` sockets, all bound to the same IP address and port, with [`SO_REUSEPORT`][SocketUserOptions::ip_socket_reuse_port] set on each of them, so that the operating system distributes incoming connections or datagrams among them.
+///
+/// This is for servers that accept connections on several threads or asynchronous runtimes, or in several processes, at a high rate. With one socket per worker, the workers don't contend for a single socket's accept queue.
+///
+/// `SO_REUSEPORT` is set regardless of the `ip_socket_reuse_port` setting in `user_options`. The sockets are opened like [`open`][open()] does, one after another. If the port is zero or a range, the first socket is bound to whichever port it gets, and the rest are bound to that same port. If any of them can't be opened, the ones that were already opened are closed.
+///
+/// The address is checked against [`SocketAppOptions::allowed_kinds`], [`SocketAppOptions::allowed_ports`], and [`SocketAppOptions::validate_addr`] once, as given. The addresses that the rest of the sockets are bound to are not checked again.
+///
+/// On Linux, the distribution of connections among the sockets can be customized with `SocketAppOptions::reuse_port_cbpf`.
+///
+///
+/// # Errors
+///
+/// Fails with [`OpenSocketError::AddressKindNotAllowed`] if `address` is not an IP address (that is, not [`SocketAddr::Ip`] or [`SocketAddr::IpWildcard`]), or with any error that `open` can return.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+#[cfg(unix)]
+pub fn open_sharded(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+	count: usize,
+) -> Result<Vec<Socket>, OpenSocketError> {
+	let (address, user_options) = merge_options(address, user_options);
+
+	if !matches!(address, SocketAddr::Ip { .. } | SocketAddr::IpWildcard { .. }) {
+		return Err(OpenSocketError::AddressKindNotAllowed { kind: address.kind() });
+	}
+
+	// Check the address that the user actually gave. The rest of the sockets are bound to whichever port the first one got, which is not necessarily a port that the user gave, so they aren't checked again.
+	check_ip_address_policy(address, app_options, &user_options)?;
+
+	let same_port = |port: u16| -> SocketAddr {
+		match address {
+			SocketAddr::Ip { addr, zone, .. } => SocketAddr::Ip { addr: *addr, port: Some(port), zone: zone.clone(), port_range_end: None },
+			_ => SocketAddr::IpWildcard { port: Some(port), port_range_end: None },
+		}
+	};
+
+	let user_options = SocketUserOptions {
+		ip_socket_reuse_port: true,
+		..user_options.into_owned()
+	};
+
+	let mut sockets: Vec<Socket> = Vec::with_capacity(count);
+
+	for _ in 0..count {
+		let address: Cow<SocketAddr> = match sockets.first().and_then(|first| first.local_addr().ok()?.as_socket()) {
+			Some(first_addr) => Cow::Owned(same_port(first_addr.port())),
+			None => Cow::Borrowed(address),
+		};
+
+		let socket: Socket = open_inner(&address, app_options, &user_options, false, false)?.socket;
+
+		sockets.push(socket);
+	}
+
+	Ok(sockets)
+}
+
+pub(crate) fn validate(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<(), OpenSocketError> {
+	let (address, user_options) = merge_options(address, user_options);
+	let address: Cow<SocketAddr> = resolve_relative_unix_path(address, app_options)?;
+	let address: &SocketAddr = &address;
+	let user_options: &SocketUserOptions = &user_options;
+
+	check_address_policy(address, app_options, user_options)?;
+
+	let validate_ip = |addr: IpAddr, port: Option<u16>, zone: Option<&str>, port_range_end: Option<u16>| -> Result<(), OpenSocketError> {
+		// See the comment in `resolve_ip`.
+		let port_range_end: Option<u16> = port.and(port_range_end);
+
+		let port: u16 = resolve_port(port, app_options.default_port, app_options.r#type)?;
+		check_allowed_ports(port, port_range_end, app_options)?;
+		let scope_id: u32 = resolve_scope_id(addr, zone)?;
+
+		let addr: std::net::SocketAddr = match addr {
+			IpAddr::V6(addr) => SocketAddrV6::new(addr, port, 0, scope_id).into(),
+			addr => std::net::SocketAddr::new(addr, port),
+		};
+
+		check_new_socket(&addr.into(), None, app_options, user_options)?;
+		Ok(())
+	};
+
+	match address {
+		SocketAddr::Ip { addr, port, zone, port_range_end } => validate_ip(*addr, *port, zone.as_deref(), *port_range_end)?,
+
+		SocketAddr::IpWildcard { port, port_range_end } => {
+			let addr: IpAddr = match app_options.wildcard_ipv6 {
+				true => Ipv6Addr::UNSPECIFIED.into(),
+				false => Ipv4Addr::UNSPECIFIED.into(),
+			};
+
+			validate_ip(addr, *port, None, *port_range_end)?
+		},
+
+		SocketAddr::Unix { path } => {
+			let address =
+				socket2::SockAddr::unix(path)
+				.map_err(|error| OpenSocketError::InvalidUnixPath { error })?;
+
+			check_new_socket(&address, Some(path), app_options, user_options)?;
+		},
+
+		SocketAddr::UnixAbstract { name } => {
+			cfg_if::cfg_if! {
+				if #[cfg(any(target_os = "android", target_os = "linux", windows))] {
+					check_new_socket(&unix_abstract_sockaddr(name)?, None, app_options, user_options)?;
+				}
+				else {
+					let _ = name;
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
+		},
+
+		SocketAddr::Vsock { cid, port } => {
+			cfg_if::cfg_if! {
+				if #[cfg(any(target_os = "android", target_os = "linux"))] {
+					check_new_socket(&socket2::SockAddr::vsock(*cid, *port), None, app_options, user_options)?;
+				}
+				else {
+					let _ = (cid, port);
+					return Err(OpenSocketError::UnsupportedSocketAddr);
+				}
+			}
+		},
+
+		// Whether an inherited socket actually exists, and is of the right type, can only be checked by claiming it.
+		SocketAddr::Inherit { .. } |
+		SocketAddr::InheritEnv { .. } |
+		SocketAddr::InheritStdin => check_inherit(user_options)?,
+
+		#[cfg(not(windows))]
+		SocketAddr::SystemdFirst |
+		SocketAddr::SystemdNumeric { .. } |
+		SocketAddr::SystemdNamed { .. } => check_inherit(user_options)?,
+
+		#[cfg(target_os = "macos")]
+		SocketAddr::Launchd { .. } => check_inherit(user_options)?,
+
+		#[cfg(not(target_os = "macos"))]
+		SocketAddr::Launchd { .. } => return Err(OpenSocketError::UnsupportedSocketAddr),
+
+		#[cfg(windows)]
+		SocketAddr::WsaProtocolInfo { .. } => check_inherit(user_options)?,
+
+		#[cfg(not(windows))]
+		SocketAddr::WsaProtocolInfo { .. } => return Err(OpenSocketError::UnsupportedSocketAddr),
+
+		SocketAddr::Pair { name: _ } => {
+			check_no_bind_options(user_options)?;
+			check_no_anytime_options(user_options)?;
+			check_inapplicable(user_options.inherited_socket_expected_addr.as_ref(), "inherited_socket_expected_addr")?;
+
+			if !(app_options.listen && is_listenable_socket_type(app_options.r#type)) {
+				check_inapplicable(user_options.listen_socket_backlog, "listen_socket_backlog")?;
+			}
+		},
+
+		// Already unwrapped by `merge_options`.
+		SocketAddr::WithOptions { .. } => unreachable!(),
+	}
+
+	Ok(())
+}
+
+/// Checks that the address is one of the [`SocketAppOptions::allowed_kinds`], then calls [`SocketAppOptions::validate_addr`], if there is one.
+fn check_address_policy(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<(), OpenSocketError> {
+	let kind: AddressKinds = address.kind();
+
+	if !app_options.allowed_kinds.contains(kind) {
+		return Err(OpenSocketError::AddressKindNotAllowed { kind });
+	}
+
+	match &app_options.validate_addr {
+		Some(validate_addr) => validate_addr(address, user_options).map_err(|reason| OpenSocketError::AddressRejected { reason }),
+		None => Ok(()),
+	}
+}
+
+/// Checks an IP address against the application's address policy, including [`SocketAppOptions::allowed_ports`]. `open_dual_stack` and `open_sharded` check the address the user gave this way, then open the addresses they derive from it without checking those again.
+fn check_ip_address_policy(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<(), OpenSocketError> {
+	check_address_policy(address, app_options, user_options)?;
+
+	let (SocketAddr::Ip { port, port_range_end, .. } | SocketAddr::IpWildcard { port, port_range_end }) = *address else {
+		return Ok(());
+	};
+
+	// See the comment in `resolve_ip`.
+	let port_range_end: Option<u16> = port.and(port_range_end);
+
+	let port: u16 = resolve_port(port, app_options.default_port, app_options.r#type)?;
+	check_allowed_ports(port, port_range_end, app_options)
+}
+
+/// Checks that the options are applicable to a new socket that is to be bound to the given `address`. If the socket is to be put into the listening state, returns the backlog to use.
+fn check_new_socket(
+	address: &socket2::SockAddr,
+	unix_socket_path: Option<&Path>,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<Option<c_int>, OpenSocketError> {
+	check_inapplicable(user_options.inherited_socket_expected_addr.as_ref(), "inherited_socket_expected_addr")?;
+
+	// Prepare any Unix security attributes, if relevant.
+	#[cfg(unix)]
+	crate::unix_security::prepare(user_options, unix_socket_path)?;
+
+	#[cfg(not(unix))]
+	let _ = unix_socket_path;
+
+	// Check if we need to `listen` on this socket, and if so, what the backlog should be.
+	let listen_backlog: Option<c_int> = {
+		if app_options.listen && is_listenable_socket_type(app_options.r#type) {
+			Some(
+				user_options.listen_socket_backlog
+				.unwrap_or(SocketUserOptions::DEFAULT_LISTEN_SOCKET_BACKLOG)
+			)
+		}
+		else {
+			check_inapplicable(user_options.listen_socket_backlog, "listen_socket_backlog")?;
+
+			#[cfg(target_os = "freebsd")]
+			check_inapplicable(user_options.freebsd_accept_filter.as_ref(), "freebsd_accept_filter")?;
+
+			None
+		}
+	};
+
+	// Peer credentials can only be checked on connections accepted from a Unix-domain listening socket.
+	#[cfg(unix)]
+	if !(address.is_unix() && listen_backlog.is_some()) {
+		check_inapplicable_bool(!user_options.unix_socket_allowed_uids.is_empty(), "unix_socket_allowed_uids")?;
+		check_inapplicable_bool(!user_options.unix_socket_allowed_gids.is_empty(), "unix_socket_allowed_gids")?;
+	}
+
+	// Check if we need to bind an SCTP socket to additional addresses.
+	#[cfg(target_os = "linux")]
+	if
+		!user_options.sctp_socket_additional_addresses.is_empty() &&
+		!(
+			app_options.protocol == Some(socket2::Protocol::SCTP) &&
+			(address.is_ipv4() || address.is_ipv6())
+		)
+	{
+		return inapplicable("sctp_socket_additional_addresses");
+	}
+
+	#[cfg(not(any(
+		target_os = "fuchsia",
+		target_os = "haiku",
+		target_os = "illumos",
+		target_os = "redox",
+		target_os = "solaris",
+	)))]
+	if user_options.ip_socket_tos.is_some() && !address.is_ipv4() {
+		return inapplicable("ip_socket_tos");
+	}
+
+	#[cfg(any(
+		target_os = "aix",
+		target_os = "android",
+		target_os = "dragonfly",
+		target_os = "freebsd",
+		target_os = "fuchsia",
+		target_os = "illumos",
+		target_os = "ios",
+		target_os = "linux",
+		target_os = "macos",
+		target_os = "netbsd",
+		target_os = "openbsd",
+		target_os = "solaris",
+		target_os = "tvos",
+		target_os = "watchos",
+	))]
+	if user_options.ipv6_socket_tclass.is_some() && !address.is_ipv6() {
+		return inapplicable("ipv6_socket_tclass");
+	}
+
+	#[cfg(target_os = "linux")]
+	if user_options.ip_socket_transparent && !(address.is_ipv4() || address.is_ipv6()) {
+		return inapplicable("ip_socket_transparent");
+	}
+
+	// TCP-specific options can only be used on TCP sockets.
+	if !is_new_socket_tcp(address, app_options) {
+		check_inapplicable(user_options.tcp_keepalive_params(), "tcp_keepalive")?;
+		check_inapplicable_bool(user_options.tcp_nodelay, "tcp_nodelay")?;
+
+		#[cfg(all(unix, not(target_os = "redox")))]
+		check_inapplicable(user_options.tcp_max_segment, "tcp_max_segment")?;
+
+		#[cfg(windows)]
+		check_inapplicable_bool(user_options.tcp_loopback_fast_path, "tcp_loopback_fast_path")?;
+	}
+
+	// Multicast options can only be used on UDP sockets, and the groups must be of the same IP version as the socket.
+	if is_new_socket_udp(address, app_options) {
+		for group in &user_options.udp_multicast_group {
+			let is_valid: bool = match group {
+				IpAddr::V4(group) => address.is_ipv4() && group.is_multicast(),
+				IpAddr::V6(group) => address.is_ipv6() && group.is_multicast(),
+			};
+
+			if !is_valid {
+				return Err(OpenSocketError::InvalidMulticastGroup { group: *group });
+			}
+		}
+
+		if address.is_ipv4() {
+			if let Some(interface) = &user_options.udp_multicast_interface {
+				multicast_interface_v4(interface)?;
+			}
+		}
+	}
+	else {
+		check_inapplicable_bool(!user_options.udp_multicast_group.is_empty(), "udp_multicast_group")?;
+		check_inapplicable(user_options.udp_multicast_interface.as_ref(), "udp_multicast_interface")?;
+		check_inapplicable(user_options.udp_multicast_loop, "udp_multicast_loop")?;
+		check_inapplicable(user_options.udp_multicast_ttl, "udp_multicast_ttl")?;
+
+		#[cfg(any(target_os = "android", target_os = "linux"))]
+		check_inapplicable_bool(user_options.udp_socket_pktinfo, "udp_socket_pktinfo")?;
+
+		#[cfg(target_os = "linux")] {
+			check_inapplicable(user_options.udp_segment_size, "udp_segment_size")?;
+			check_inapplicable_bool(user_options.udp_gro, "udp_gro")?;
+		}
+	}
+
+	// Raw sockets don't have an implied transport protocol, so one must be given explicitly.
+	if is_raw_socket_type(app_options.r#type) && app_options.protocol.is_none() {
+		return Err(OpenSocketError::RawProtocolRequired);
+	}
+
+	Ok(listen_backlog)
+}
+
+/// Checks that the options are applicable to an inherited socket.
+fn check_inherit(user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	check_no_bind_options(user_options)?;
+	check_inapplicable(user_options.listen_socket_backlog, "listen_socket_backlog")?;
+
+	if let Some(expected) = &user_options.inherited_socket_expected_addr {
+		if !matches!(
+			expected.without_options(),
+			| SocketAddr::Ip { .. }
+			| SocketAddr::IpWildcard { .. }
+			| SocketAddr::Unix { .. }
+			| SocketAddr::UnixAbstract { .. }
+			| SocketAddr::Vsock { .. }
+		) {
+			return Err(OpenSocketError::InvalidExpectedAddr { expected: expected.clone() });
+		}
+	}
+
+	Ok(())
+}
+
+/// Picks the port number to use: the one in the address, or else the application's default.
+pub(crate) fn resolve_port(port: Option<u16>, default_port: Option<u16>, r#type: socket2::Type) -> Result<u16, OpenSocketError> {
+	match port.or(default_port) {
+		Some(port) => Ok(port),
+
+		// Raw sockets don't have port numbers, so there's no need to require one.
+		None if is_raw_socket_type(r#type) => Ok(0),
+
+		None => Err(OpenSocketError::PortRequired),
+	}
+}
+
+/// Checks that the port number, and the rest of the port range if there is one, are within [`SocketAppOptions::allowed_ports`].
+fn check_allowed_ports(port: u16, port_range_end: Option<u16>, app_options: &SocketAppOptions) -> Result<(), OpenSocketError> {
+	let Some(allowed) = &app_options.allowed_ports else {
+		return Ok(());
+	};
+
+	if is_raw_socket_type(app_options.r#type) {
+		return Ok(());
+	}
+
+	let port_range_end: u16 = port_range_end.unwrap_or(port);
+
+	// An empty port range (where the end is less than the start) has no ports to check.
+	match (port..=port_range_end).find(|port| !allowed.contains(port)) {
+		Some(port) => Err(OpenSocketError::PortNotAllowed { port, allowed: allowed.clone() }),
+		None => Ok(()),
+	}
+}
+
+/// Resolves an IPv6 zone, which is either a number or an interface name, to a scope ID.
+pub(crate) fn resolve_scope_id(addr: IpAddr, zone: Option<&str>) -> Result<u32, OpenSocketError> {
+	Ok(match (addr, zone) {
+		(IpAddr::V6(_), Some(zone)) => match zone.parse() {
+			Ok(scope_id) => scope_id,
+
+			Err(_) =>
+				sys::interface_index(zone)
+				.map_err(|error| OpenSocketError::UnknownInterface { name: zone.to_owned(), error })?,
+		},
+
+		_ => 0,
+	})
+}
+
+/// Ignores the error from setting a socket option, if the error is that the operating system doesn't recognize the socket option.
+#[cfg(target_os = "linux")]
+fn ignore_unsupported_sockopt(error: io::Error) -> io::Result<()> {
+	match error.raw_os_error() {
+		Some(libc::ENOPROTOOPT) => Ok(()),
+		_ => Err(error),
+	}
+}
+
+/// Converts [`SocketUserOptions::socket_recv_timeout`] or [`SocketUserOptions::socket_send_timeout`] into the form that `socket2` expects. Zero means no timeout.
+fn timeout_from_millis(millis: u64) -> Option<Duration> {
+	match millis {
+		0 => None,
+		millis => Some(Duration::from_millis(millis)),
+	}
+}
+
+/// Parses [`SocketUserOptions::udp_multicast_interface`] for an IPv4 socket.
+fn multicast_interface_v4(interface: &str) -> Result<Ipv4Addr, OpenSocketError> {
+	interface.parse()
+	.map_err(|_| OpenSocketError::InvalidMulticastInterface { interface: interface.to_owned() })
+}
+
+/// Applies the multicast user options to a new IPv4 socket. The multicast options have already been checked by `check_new_socket`, so this assumes that the socket is UDP if any of them are used.
+fn set_multicast_options_v4(socket: &Socket, user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	let interface: Ipv4Addr = match &user_options.udp_multicast_interface {
+		Some(interface) => {
+			let interface: Ipv4Addr = multicast_interface_v4(interface)?;
+
+			socket.set_multicast_if_v4(&interface)
+			.map_err(|error| OpenSocketError::SetSockOpt {
+				option: "IP_MULTICAST_IF",
+				error,
+			})?;
+
+			interface
+		},
+
+		None => Ipv4Addr::UNSPECIFIED,
+	};
+
+	if let Some(multicast_loop) = user_options.udp_multicast_loop {
+		socket.set_multicast_loop_v4(multicast_loop)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "IP_MULTICAST_LOOP",
+			error,
+		})?;
+	}
+
+	if let Some(ttl) = user_options.udp_multicast_ttl {
+		socket.set_multicast_ttl_v4(ttl)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "IP_MULTICAST_TTL",
+			error,
+		})?;
+	}
+
+	for group in &user_options.udp_multicast_group {
+		if let IpAddr::V4(group_v4) = group {
+			socket.join_multicast_v4(group_v4, &interface)
+			.map_err(|error| OpenSocketError::JoinMulticastGroup { group: *group, error })?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Applies the multicast user options to a new IPv6 socket. The multicast options have already been checked by `check_new_socket`, so this assumes that the socket is UDP if any of them are used.
+fn set_multicast_options_v6(socket: &Socket, user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	let interface: u32 = match &user_options.udp_multicast_interface {
+		Some(interface) => {
+			let interface: u32 = resolve_scope_id(IpAddr::V6(Ipv6Addr::UNSPECIFIED), Some(interface))?;
+
+			socket.set_multicast_if_v6(interface)
+			.map_err(|error| OpenSocketError::SetSockOpt {
+				option: "IPV6_MULTICAST_IF",
+				error,
+			})?;
+
+			interface
+		},
+
+		None => 0,
+	};
+
+	if let Some(multicast_loop) = user_options.udp_multicast_loop {
+		socket.set_multicast_loop_v6(multicast_loop)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "IPV6_MULTICAST_LOOP",
+			error,
+		})?;
+	}
+
+	if let Some(hops) = user_options.udp_multicast_ttl {
+		socket.set_multicast_hops_v6(hops)
+		.map_err(|error| OpenSocketError::SetSockOpt {
+			option: "IPV6_MULTICAST_HOPS",
+			error,
+		})?;
+	}
+
+	for group in &user_options.udp_multicast_group {
+		if let IpAddr::V6(group_v6) = group {
+			socket.join_multicast_v6(group_v6, interface)
+			.map_err(|error| OpenSocketError::JoinMulticastGroup { group: *group, error })?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Whether [`SocketUserOptions::unix_socket_atomic_replace`] is set. This is always false on platforms that don't have that option.
+fn is_atomic_replace(user_options: &SocketUserOptions) -> bool {
+	#[cfg(unix)] {
+		user_options.unix_socket_atomic_replace
+	}
+
+	#[cfg(not(unix))] {
+		let _ = user_options;
+		false
+	}
+}
+
+/// Checks that the file at `path`, if there is one, is a Unix-domain socket, so that it can be replaced with a new one. See [`SocketUserOptions::unix_socket_atomic_replace`].
+fn check_replaceable(path: &Path) -> Result<(), OpenSocketError> {
+	match is_unix_socket(path) {
+		Ok(true) => Ok(()),
+		Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+
+		// Something other than a socket is in the way. This is the same error that `bind` would have raised.
+		Ok(false) => Err(OpenSocketError::Bind {
+			error: io::ErrorKind::AddrInUse.into(),
+		}),
+
+		Err(error) => Err(CleanupSocketError::Stat { error }.into()),
+	}
+}
+
+/// Makes the socket address for an abstract Unix-domain socket.
+#[cfg(any(target_os = "android", target_os = "linux", windows))]
+pub(crate) fn unix_abstract_sockaddr(name: &str) -> Result<socket2::SockAddr, OpenSocketError> {
+	// An abstract socket address is like a path-based one, except that the “path” starts with a null byte.
+	let mut path = String::with_capacity(name.len() + 1);
+	path.push('\0');
+	path.push_str(name);
+
+	socket2::SockAddr::unix(path)
+	.map_err(|error| OpenSocketError::InvalidUnixPath { error })
+}
+
+/// Checks that none of the user options that only apply when binding a new socket to an address are used. This is for sockets that are not bound by `open`, such as inherited sockets.
+fn check_no_bind_options(user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	#[cfg(unix)] {
+		check_inapplicable(user_options.unix_socket_permissions.as_ref(), "unix_socket_permissions")?;
+		check_inapplicable(user_options.unix_socket_owner.as_ref(), "unix_socket_owner")?;
+		check_inapplicable(user_options.unix_socket_group.as_ref(), "unix_socket_group")?;
+		check_inapplicable_bool(user_options.unix_socket_atomic_replace, "unix_socket_atomic_replace")?;
+		check_inapplicable_bool(user_options.unix_socket_lock_file, "unix_socket_lock_file")?;
+	}
+
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	check_inapplicable(user_options.unix_socket_selinux_context.as_ref(), "unix_socket_selinux_context")?;
+
+	#[cfg(unix)]
+	check_inapplicable_bool(user_options.ip_socket_reuse_port, "ip_socket_reuse_port")?;
+
+	#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+	check_inapplicable_bool(user_options.ip_socket_exclusive_bind, "ip_socket_exclusive_bind")?;
+
+	check_inapplicable_bool(user_options.ip_socket_v6_only, "ip_socket_v6_only")?;
+
+	#[cfg(target_os = "linux")]
+	check_inapplicable_bool(user_options.ip_socket_transparent, "ip_socket_transparent")?;
+
+	#[cfg(target_os = "linux")]
+	check_inapplicable_bool(!user_options.sctp_socket_additional_addresses.is_empty(), "sctp_socket_additional_addresses")?;
+
+	#[cfg(all(unix, not(target_os = "redox")))]
+	check_inapplicable(user_options.tcp_max_segment, "tcp_max_segment")?;
+
+	#[cfg(windows)]
+	check_inapplicable_bool(user_options.tcp_loopback_fast_path, "tcp_loopback_fast_path")?;
+
+	check_inapplicable_bool(!user_options.udp_multicast_group.is_empty(), "udp_multicast_group")?;
+	check_inapplicable(user_options.udp_multicast_interface.as_ref(), "udp_multicast_interface")?;
+	check_inapplicable(user_options.udp_multicast_loop, "udp_multicast_loop")?;
+	check_inapplicable(user_options.udp_multicast_ttl, "udp_multicast_ttl")?;
+
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	check_inapplicable_bool(user_options.udp_socket_pktinfo, "udp_socket_pktinfo")?;
+
+	#[cfg(target_os = "linux")] {
+		check_inapplicable(user_options.udp_segment_size, "udp_segment_size")?;
+		check_inapplicable_bool(user_options.udp_gro, "udp_gro")?;
+	}
+
+	#[cfg(target_os = "freebsd")]
+	check_inapplicable(user_options.freebsd_accept_filter.as_ref(), "freebsd_accept_filter")?;
+
+	Ok(())
+}
+
+/// Checks that none of the user options that can be applied to an already-bound socket are used. This is for sockets that `open` doesn't apply them to, such as socket pairs.
+fn check_no_anytime_options(user_options: &SocketUserOptions) -> Result<(), OpenSocketError> {
+	#[cfg(not(any(
+		target_os = "fuchsia",
+		target_os = "haiku",
+		target_os = "illumos",
+		target_os = "redox",
+		target_os = "solaris",
+	)))]
+	check_inapplicable(user_options.ip_socket_tos, "ip_socket_tos")?;
+
+	#[cfg(any(
+		target_os = "aix",
+		target_os = "android",
+		target_os = "dragonfly",
+		target_os = "freebsd",
+		target_os = "fuchsia",
+		target_os = "illumos",
+		target_os = "ios",
+		target_os = "linux",
+		target_os = "macos",
+		target_os = "netbsd",
+		target_os = "openbsd",
+		target_os = "solaris",
+		target_os = "tvos",
+		target_os = "watchos",
+	))]
+	check_inapplicable(user_options.ipv6_socket_tclass, "ipv6_socket_tclass")?;
+
+	check_inapplicable(user_options.tcp_keepalive_params(), "tcp_keepalive")?;
+	check_inapplicable_bool(user_options.tcp_nodelay, "tcp_nodelay")?;
+
+	check_inapplicable(user_options.socket_recv_buffer_size, "socket_recv_buffer_size")?;
+	check_inapplicable(user_options.socket_send_buffer_size, "socket_send_buffer_size")?;
+	check_inapplicable(user_options.socket_recv_timeout, "socket_recv_timeout")?;
+	check_inapplicable(user_options.socket_send_timeout, "socket_send_timeout")
+}
+
+#[test]
+fn test_open_multiple() {
+	use crate::util::TEST_SCRATCH;
+	use std::path::PathBuf;
+
+	let socket_path: PathBuf = TEST_SCRATCH.join("open_multiple.socket");
+	let occupied = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+
+	let addresses: Vec<SocketAddr> = vec![
+		SocketAddr::unix(&socket_path),
+		SocketAddr::loopback(occupied.local_addr().unwrap().port()),
+	];
+
+	let error = open_multiple(
+		&addresses,
+		&SocketAppOptions::new(socket2::Type::STREAM),
+		&SocketUserOptions::default(),
+	).unwrap_err();
+
+	assert_eq!(error.index, 1);
+	assert_eq!(error.address, addresses[1]);
+	assert!(matches!(error.error, OpenSocketError::Bind { .. }));
+
+	// The Unix-domain socket that was opened before the failure should have been cleaned up.
+	assert!(!socket_path.exists());
+}
+
+#[test]
+fn test_open_dual_stack() {
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	app_options.default_port = Some(0);
+
+	let sockets: Vec<Socket> = open_dual_stack(&"*".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+
+	let addrs: Vec<std::net::SocketAddr> =
+		sockets.iter()
+		.map(|socket| socket.local_addr().unwrap().as_socket().unwrap())
+		.collect();
+
+	match &addrs[..] {
+		[v6_addr, v4_addr] => {
+			assert!(v6_addr.is_ipv6());
+			assert!(sockets[0].only_v6().unwrap());
+			assert!(v4_addr.is_ipv4());
+			assert_eq!(v6_addr.port(), v4_addr.port());
+		},
+
+		// IPv6 is not available on this system.
+		[v4_addr] => assert!(v4_addr.is_ipv4()),
+
+		_ => panic!("unexpected sockets: {addrs:?}"),
+	}
+
+	let sockets: Vec<Socket> = open_dual_stack(&"127.0.0.1".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+	assert_eq!(sockets.len(), 1);
+
+	// If the first port in a range is only taken on IPv4, both sockets move on to the next port.
+	let taken = std::net::TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+	let taken_port: u16 = taken.local_addr().unwrap().port();
+
+	if taken_port < u16::MAX {
+		let address = SocketAddr::IpWildcard { port: Some(taken_port), port_range_end: Some(taken_port + 1) };
+		let sockets: Vec<Socket> = open_dual_stack(&address, &app_options, &SocketUserOptions::default()).unwrap();
+
+		for socket in &sockets {
+			assert_eq!(socket.local_addr().unwrap().as_socket().unwrap().port(), taken_port + 1);
+		}
+	}
+
+	// The address policy sees the address as given, once, and not the addresses derived from it.
+	{
+		use std::sync::{Arc, Mutex};
+
+		let seen: Arc<Mutex<Vec<SocketAddr>>> = Arc::default();
+
+		app_options.validate_addr = Some(Arc::new({
+			let seen = seen.clone();
+
+			move |address: &SocketAddr, _: &SocketUserOptions| {
+				seen.lock().unwrap().push(address.clone());
+
+				match address {
+					SocketAddr::IpWildcard { .. } => Ok(()),
+					_ => Err("only wildcard addresses are allowed".into()),
+				}
+			}
+		}));
+
+		open_dual_stack(&"*?backlog=10".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+		assert_eq!(*seen.lock().unwrap(), ["*".parse::<SocketAddr>().unwrap()]);
+
+		app_options.validate_addr = None;
+	}
+
+	// So does `allowed_ports`, which here allows port zero but not whichever port the kernel chooses.
+	app_options.allowed_ports = Some(0..=0);
+	open_dual_stack(&"*".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+}
+
+#[test]
+fn test_open_with_info() {
+	use crate::util::TEST_SCRATCH;
+
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = SocketUserOptions::default();
+
+	let opened = open_with_info(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	assert_ne!(opened.local_addr.as_socket().unwrap().port(), 0);
+	assert!(!opened.is_inherited);
+	assert_eq!(opened.unix_path, None);
+
+	let socket_path: PathBuf = TEST_SCRATCH.join("open_with_info.socket");
+	let address = SocketAddr::unix(&socket_path);
+
+	let opened = open_with_info(&address, &app_options, &user_options).unwrap();
+	assert_eq!(opened.unix_path.as_deref(), Some(&*socket_path));
+	assert!(!opened.unlinked_stale_socket);
+	drop(opened);
+
+	// The socket file is left behind after the socket is closed, so opening it again deletes it first.
+	let opened = open_with_info(&address, &app_options, &user_options).unwrap();
+	assert!(opened.unlinked_stale_socket);
+}
+
+#[test]
+fn test_open_bound() {
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	let bound = open_bound(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+	let address: socket2::SockAddr = bound.socket().local_addr().unwrap();
+
+	// Not listening yet, so connecting should fail.
+	let client = Socket::new(address.domain(), socket2::Type::STREAM, None).unwrap();
+	assert!(client.connect(&address).is_err());
+
+	let listener: Socket = bound.finish_listen().unwrap();
+
+	let client = Socket::new(address.domain(), socket2::Type::STREAM, None).unwrap();
+	client.connect(&address).unwrap();
+	listener.accept().unwrap();
+}
+
+#[test]
+fn test_nonblocking() {
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	app_options.nonblocking = true;
+
+	let socket: Socket = open(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+
+	// There are no connections waiting, so `accept` would block if the socket were in blocking mode.
+	assert_eq!(socket.accept().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+}
+
+#[test]
+fn test_hooks() {
+	use std::sync::{Arc, Mutex};
+
+	let calls: Arc<Mutex<Vec<&'static str>>> = Arc::default();
+
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	app_options.after_bind = Some(Arc::new({
+		let calls = calls.clone();
+
+		move |socket: &mut Socket, context: &HookContext| -> io::Result<()> {
+			assert_eq!(context.address.to_string(), "127.0.0.1:0");
+			assert_eq!(context.sockaddr.as_socket().unwrap().port(), 0);
+			assert_ne!(socket.local_addr()?.as_socket().unwrap().port(), 0);
+			calls.lock().unwrap().push("after_bind");
+			Ok(())
+		}
+	}));
+
+	app_options.before_listen = Some(Arc::new({
+		let calls = calls.clone();
+
+		move |_: &mut Socket, _: &HookContext| -> io::Result<()> {
+			calls.lock().unwrap().push("before_listen");
+			Ok(())
+		}
+	}));
+
+	open(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+	assert_eq!(*calls.lock().unwrap(), ["after_bind", "before_listen"]);
+
+	// `before_listen` is not called for sockets that don't listen.
+	calls.lock().unwrap().clear();
+	app_options.listen = false;
+
+	open(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+	assert_eq!(*calls.lock().unwrap(), ["after_bind"]);
+}
+
+#[test]
+#[cfg(feature = "tokio")]
+fn test_open_async() {
+	use std::sync::Arc;
+
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	app_options.before_bind_async = Some(Arc::new(|socket: Socket, context: AsyncHookContext| {
+		Box::pin(async move {
+			assert_eq!(context.address.to_string(), "127.0.0.1:0");
+			tokio::task::yield_now().await;
+			socket.set_ttl(42)?;
+			Ok(socket)
+		})
+	}));
+
+	// The blocking `open` can't call the asynchronous hook.
+	assert!(matches!(
+		open(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::AsyncHookInBlockingOpen),
+	));
+
+	let runtime =
+		tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.unwrap();
+
+	let socket: Socket = runtime.block_on(async {
+		// The future returned by `open_async` must be `Send`, so that it can be spawned.
+		tokio::spawn(async move {
+			open_async(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).await
+		})
+		.await
+		.unwrap()
+	}).unwrap();
+
+	assert_eq!(socket.ttl().unwrap(), 42);
+	assert!(socket.local_addr().unwrap().as_socket().is_some());
+}
+
+#[test]
+fn test_validate_addr() {
+	use std::sync::Arc;
+
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	app_options.validate_addr = Some(Arc::new(|address: &SocketAddr, _: &SocketUserOptions| {
+		match address {
+			SocketAddr::IpWildcard { .. } => Err("binding to a wildcard address is not allowed".into()),
+			_ => Ok(()),
+		}
+	}));
+
+	let error: OpenSocketError = open(&"*:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap_err();
+	assert!(matches!(error, OpenSocketError::AddressRejected { .. }));
+	assert_eq!(error.to_string(), "binding to a wildcard address is not allowed");
+
+	// The address inside a `SocketAddr::WithOptions` is checked.
+	assert!(matches!(
+		"*:0?backlog=10".parse::<SocketAddr>().unwrap().validate(&app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::AddressRejected { .. }),
+	));
+
+	open(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+}
+
+#[test]
+fn test_allowed_kinds() {
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	app_options.allowed_kinds = AddressKinds::all() - AddressKinds::INHERITED;
+
+	let error: OpenSocketError = open(&"fd:3".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap_err();
+	assert!(matches!(error, OpenSocketError::AddressKindNotAllowed { kind: AddressKinds::INHERIT }));
+	assert_eq!(error.to_string(), "this application doesn't accept inherited socket addresses");
+
+	assert!(matches!(
+		"stdin?backlog=10".parse::<SocketAddr>().unwrap().validate(&app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::AddressKindNotAllowed { kind: AddressKinds::STDIN }),
+	));
+
+	open(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+}
+
+#[test]
+fn test_allowed_ports() {
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	app_options.allowed_ports = Some(1024..=65535);
+
+	let error: OpenSocketError = open(&"127.0.0.1:80".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap_err();
+	assert!(matches!(error, OpenSocketError::PortNotAllowed { port: 80, .. }));
+	assert_eq!(error.to_string(), "port 80 is not allowed here; the allowed ports are 1024–65535");
+
+	// Port zero is not allowed unless the range includes it.
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::PortNotAllowed { port: 0, .. }),
+	));
+
+	// Every port in a port range must be allowed.
+	app_options.allowed_ports = Some(8000..=8999);
+
+	assert!(matches!(
+		"*:8990-9010".parse::<SocketAddr>().unwrap().validate(&app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::PortNotAllowed { port: 9000, .. }),
+	));
+
+	app_options.default_port = Some(8080);
+	assert!(matches!("127.0.0.1".parse::<SocketAddr>().unwrap().validate(&app_options, &SocketUserOptions::default()), Ok(())));
+	assert!(matches!("*:8000-8999".parse::<SocketAddr>().unwrap().validate(&app_options, &SocketUserOptions::default()), Ok(())));
+}
+
+#[test]
+fn test_relative_unix_paths() {
+	use crate::util::TEST_SCRATCH;
+
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = SocketUserOptions::default();
+	let address = SocketAddr::unix("relative_unix_paths.socket");
+
+	app_options.relative_unix_paths = RelativeUnixPaths::Reject;
+
+	let error: OpenSocketError = open(&address, &app_options, &user_options).unwrap_err();
+	assert!(matches!(error, OpenSocketError::RelativeUnixPath { .. }));
+	assert_eq!(error.to_string(), "the Unix-domain socket path `relative_unix_paths.socket` is relative, but it must be absolute");
+	assert!(matches!(address.validate(&app_options, &user_options), Err(OpenSocketError::RelativeUnixPath { .. })));
+
+	app_options.relative_unix_paths = RelativeUnixPaths::ResolveAgainst(TEST_SCRATCH.to_owned());
+
+	let opened = open_with_info(&address, &app_options, &user_options).unwrap();
+	assert_eq!(opened.unix_path, Some(TEST_SCRATCH.join("relative_unix_paths.socket")));
+	assert!(!Path::new("relative_unix_paths.socket").exists());
+}
+
+#[test]
+fn test_open_or_default() {
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	app_options.default_port = Some(0);
+
+	let socket: Socket = open_or_default(None, &app_options, &SocketUserOptions::default()).unwrap();
+	assert_eq!(socket.local_addr().unwrap().as_socket().unwrap().ip(), Ipv4Addr::LOCALHOST);
+
+	app_options.default_address = SocketAddr::unix(crate::util::TEST_SCRATCH.join("open_or_default.socket"));
+
+	let socket: Socket = open_or_default(Some(&"127.0.0.1:0".parse().unwrap()), &app_options, &SocketUserOptions::default()).unwrap();
+	assert!(socket.local_addr().unwrap().is_ipv4());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_other_inherited_types() {
+	use crate::InheritedType;
+	use std::os::fd::AsRawFd;
+
+	let original = Socket::new(socket2::Domain::IPV4, socket2::Type::DGRAM, None).unwrap();
+	let address: SocketAddr = format!("fd:{}", original.as_raw_fd()).parse().unwrap();
+
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	assert!(matches!(
+		open(&address, &app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::InheritWrongType { expected: socket2::Type::STREAM, actual: socket2::Type::DGRAM }),
+	));
+
+	app_options.other_inherited_types.push(InheritedType::new(socket2::Type::DGRAM, false));
+
+	let socket: Socket = open(&address, &app_options, &SocketUserOptions::default()).unwrap();
+	assert_eq!(socket.r#type().unwrap(), socket2::Type::DGRAM);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_inherited_socket_expected_addr() {
+	use std::os::fd::AsRawFd;
+
+	let original = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+	let port: u16 = original.local_addr().unwrap().port();
+	let app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+
+	let open_expecting = |expected: &str| {
+		let address: SocketAddr = format!("fd:{}?expect={expected}", original.as_raw_fd()).parse().unwrap();
+		open(&address, &app_options, &SocketUserOptions::default())
+	};
+
+	open_expecting(&format!("127.0.0.1:{port}")).unwrap();
+	open_expecting("127.0.0.1").unwrap();
+	open_expecting(&format!("127.0.0.1:{}-{}", port.saturating_sub(1), port)).unwrap();
+
+	let error: OpenSocketError = open_expecting(&format!("127.0.0.2:{port}")).unwrap_err();
+	assert!(matches!(&error, OpenSocketError::InheritedWrongAddr { actual: Some(_), .. }));
+	assert_eq!(error.to_string(), format!("the inherited socket was expected to be bound to `127.0.0.2:{port}`, but it is bound to `127.0.0.1:{port}`"));
+
+	assert!(matches!(open_expecting(&format!("*:{port}")), Err(OpenSocketError::InheritedWrongAddr { .. })));
+	assert!(matches!(open_expecting("stdin"), Err(OpenSocketError::InvalidExpectedAddr { .. })));
+
+	let user_options = SocketUserOptions {
+		inherited_socket_expected_addr: Some("127.0.0.1:80".parse().unwrap()),
+		..SocketUserOptions::default()
+	};
+
+	assert!(matches!(
+		open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "inherited_socket_expected_addr" }),
+	));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_inherited_socket_options() {
+	use std::os::fd::AsRawFd;
+
+	let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+	let address: SocketAddr = format!("fd:{}", listener.as_raw_fd()).parse().unwrap();
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	let user_options = SocketUserOptions {
+		tcp_nodelay: true,
+		tcp_keepalive: true,
+		socket_recv_timeout: Some(1500),
+		..SocketUserOptions::default()
+	};
+
+	let socket: Socket = open(&address, &app_options, &user_options).unwrap();
+	assert!(socket.nodelay().unwrap());
+	assert!(socket.keepalive().unwrap());
+	assert_eq!(socket.read_timeout().unwrap(), Some(Duration::from_millis(1500)));
+
+	// Options that have to be set before binding are still rejected.
+	let user_options = SocketUserOptions {
+		ip_socket_v6_only: true,
+		..SocketUserOptions::default()
+	};
+
+	assert!(matches!(
+		open(&address, &app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "ip_socket_v6_only" }),
+	));
+
+	// TCP options aren't applicable to an inherited UDP socket.
+	let udp_socket = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+	let address: SocketAddr = format!("fd:{}", udp_socket.as_raw_fd()).parse().unwrap();
+
+	let user_options = SocketUserOptions {
+		tcp_nodelay: true,
+		..SocketUserOptions::default()
+	};
+
+	assert!(matches!(
+		open(&address, &SocketAppOptions::new(socket2::Type::DGRAM), &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "tcp_nodelay" }),
+	));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_open_connection() {
+	use std::os::fd::AsRawFd;
+
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	// A connected socket is accepted.
+	let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+	let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+	let (server, _) = listener.accept().unwrap();
+
+	let address: SocketAddr = format!("fd:{}", server.as_raw_fd()).parse().unwrap();
+	let socket: Socket = open_connection(&address, &app_options, &SocketUserOptions::default()).unwrap();
+	assert_eq!(socket.peer_addr().unwrap().as_socket(), Some(client.local_addr().unwrap()));
+
+	// A listening socket is not.
+	let address: SocketAddr = format!("fd:{}", listener.as_raw_fd()).parse().unwrap();
+
+	assert!(matches!(
+		open_connection(&address, &app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::InheritedIsListening),
+	));
+
+	// Neither is a datagram socket that isn't connected to a peer.
+	let udp_socket = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+	let address: SocketAddr = format!("fd:{}", udp_socket.as_raw_fd()).parse().unwrap();
+	let error: OpenSocketError = open_connection(&address, &SocketAppOptions::new(socket2::Type::DGRAM), &SocketUserOptions::default()).unwrap_err();
+	assert!(matches!(error, OpenSocketError::InheritedIsNotConnected { .. }));
+	assert_eq!(io::Error::from(error).kind(), io::ErrorKind::NotConnected);
+
+	// Socket pairs are connected, even if the application options ask for a listening socket.
+	let mut listen_app_options = app_options.clone();
+	listen_app_options.listen = true;
+	open_connection(&"pair".parse().unwrap(), &listen_app_options, &SocketUserOptions::default()).unwrap();
+
+	// New sockets are not allowed.
+	assert!(matches!(
+		open_connection(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()),
+		Err(OpenSocketError::AddressKindNotAllowed { kind: AddressKinds::IP }),
+	));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_open_sharded() {
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	let sockets: Vec<Socket> = open_sharded(&"127.0.0.1:0?backlog=10".parse().unwrap(), &app_options, &SocketUserOptions::default(), 3).unwrap();
+	assert_eq!(sockets.len(), 3);
+
+	let first_addr = sockets[0].local_addr().unwrap().as_socket().unwrap();
+	assert_ne!(first_addr.port(), 0);
+
+	for socket in &sockets {
+		assert_eq!(socket.local_addr().unwrap().as_socket(), Some(first_addr));
+		assert!(socket.reuse_port().unwrap());
+	}
+
+	assert!(matches!(
+		open_sharded(&"pair".parse().unwrap(), &app_options, &SocketUserOptions::default(), 2),
+		Err(OpenSocketError::AddressKindNotAllowed { kind: AddressKinds::PAIR }),
+	));
+
+	// The address policy applies to the address as given, so a policy that allows port zero, but not whichever port the kernel chooses, doesn't stop the rest of the sockets from being opened.
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	app_options.allowed_ports = Some(0..=0);
+
+	app_options.validate_addr = Some(std::sync::Arc::new(|address: &SocketAddr, _: &SocketUserOptions| {
+		match address {
+			SocketAddr::Ip { port: Some(0), .. } => Ok(()),
+			_ => Err("only port zero is allowed".into()),
+		}
+	}));
+
+	let sockets: Vec<Socket> = open_sharded(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default(), 3).unwrap();
+	assert_eq!(sockets.len(), 3);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_exclusive_inherit() {
+	use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+	let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+
+	// Other tests open inherited sockets too, and file descriptor numbers get reused, so use one that no other test does.
+	let fd: OwnedFd = unsafe {
+		// Safety: `F_DUPFD_CLOEXEC` doesn't take a pointer, and returns a new file descriptor, which is then owned by the `OwnedFd`.
+		OwnedFd::from_raw_fd(libc::fcntl(listener.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 1000))
+	};
+
+	let address: SocketAddr = format!("fd:{}", fd.as_raw_fd()).parse().unwrap();
+	let mut app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	// Without the option, the same socket can be opened more than once.
+	open(&address, &app_options, &SocketUserOptions::default()).unwrap();
+	open(&address, &app_options, &SocketUserOptions::default()).unwrap();
+
+	app_options.exclusive_inherit = true;
+
+	// A claimed socket isn't changed by a failed attempt to claim it again.
+	let user_options = SocketUserOptions {
+		tcp_nodelay: true,
+		..SocketUserOptions::default()
+	};
+
+	let error: OpenSocketError = open(&address, &app_options, &user_options).unwrap_err();
+	assert!(matches!(error, OpenSocketError::InheritedAlreadyClaimed { socket } if socket == fd.as_raw_fd()));
+	assert!(!socket2::SockRef::from(&listener).nodelay().unwrap());
+
+	// A failed attempt to claim a socket doesn't prevent a later one.
+	let fd: OwnedFd = unsafe {
+		// Safety: Same as above.
+		OwnedFd::from_raw_fd(libc::fcntl(listener.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 1001))
+	};
+
+	let address: SocketAddr = format!("fd:{}", fd.as_raw_fd()).parse().unwrap();
+
+	let mut wrong_app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+	wrong_app_options.exclusive_inherit = true;
+
+	let error: OpenSocketError = open(&address, &wrong_app_options, &SocketUserOptions::default()).unwrap_err();
+	assert!(matches!(error, OpenSocketError::InheritWrongType { .. }));
+
+	open(&address, &app_options, &SocketUserOptions::default()).unwrap();
+}
+
+#[test]
+fn test_tcp_keepalive() {
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = SocketUserOptions {
+		tcp_keepalive_idle: Some(60),
+		..SocketUserOptions::default()
+	};
+
+	let socket: Socket = open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	assert!(socket.keepalive().unwrap());
+
+	#[cfg(target_os = "linux")]
+	assert_eq!(socket.keepalive_time().unwrap(), std::time::Duration::from_secs(60));
+
+	assert!(matches!(
+		open(&"127.0.0.1:0".parse().unwrap(), &SocketAppOptions::new(socket2::Type::DGRAM), &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "tcp_keepalive" }),
+	));
+
+	assert!(matches!(
+		SocketAddr::unix(crate::util::TEST_SCRATCH.join("tcp_keepalive.socket")).validate(&app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "tcp_keepalive" }),
+	));
+}
+
+#[test]
+fn test_buffer_sizes() {
+	let app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+	let user_options = SocketUserOptions {
+		socket_recv_buffer_size: Some(65536),
+		socket_send_buffer_size: Some(32768),
+		..SocketUserOptions::default()
+	};
+
+	let opened = open_with_info(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+
+	// The operating system may adjust the sizes, but they should at least be in the right neighborhood.
+	assert!(opened.recv_buffer_size.unwrap() >= 32768);
+	assert!(opened.send_buffer_size.unwrap() >= 16384);
+	assert_eq!(opened.recv_buffer_size, opened.socket.recv_buffer_size().ok());
+}
+
+#[test]
+fn test_ip_socket_tos() {
+	let app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+	let user_options = SocketUserOptions {
+		ip_socket_tos: Some("AF41".parse().unwrap()),
+		..SocketUserOptions::default()
+	};
+
+	let socket: Socket = open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	assert_eq!(socket.tos().unwrap(), 0x88);
+
+	assert!(matches!(
+		"[::1]:0".parse::<SocketAddr>().unwrap().validate(&app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "ip_socket_tos" }),
+	));
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+fn test_ipv6_socket_tclass() {
+	let app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+	let user_options = SocketUserOptions {
+		ipv6_socket_tclass: Some("EF".parse().unwrap()),
+		..SocketUserOptions::default()
+	};
+
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "ipv6_socket_tclass" }),
+	));
+
+	// IPv6 might not be available on the test machine.
+	match open(&"[::1]:0".parse().unwrap(), &app_options, &user_options) {
+		Ok(_) | Err(OpenSocketError::CreateSocket { .. } | OpenSocketError::Bind { .. }) => {},
+		Err(error) => panic!("{error}"),
+	}
+}
+
+#[test]
+#[cfg(target_os = "freebsd")]
+fn test_freebsd_accept_filter() {
+	let app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+	let user_options = SocketUserOptions {
+		freebsd_accept_filter: Some("dataready".to_owned()),
+		..SocketUserOptions::default()
+	};
+
+	// Datagram sockets don't listen, so they can't have an accept filter.
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "freebsd_accept_filter" }),
+	));
+
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	// The `accf_data` kernel module might not be loaded on the test machine.
+	match open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options) {
+		Ok(_) | Err(OpenSocketError::SetSockOpt { option: "SO_ACCEPTFILTER", .. }) => {},
+		Err(error) => panic!("{error}"),
+	}
+
+	let user_options = SocketUserOptions {
+		freebsd_accept_filter: Some("this_name_is_far_too_long".to_owned()),
+		..SocketUserOptions::default()
+	};
+
+	assert!(matches!(
+		open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options),
+		Err(OpenSocketError::SetSockOpt { option: "SO_ACCEPTFILTER", .. }),
+	));
+}
+
+#[test]
+fn test_udp_multicast() {
+	let app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+	let user_options = SocketUserOptions {
+		udp_multicast_group: vec!["239.255.0.1".parse().unwrap()],
+		udp_multicast_interface: Some("127.0.0.1".to_owned()),
+		udp_multicast_loop: Some(false),
+		udp_multicast_ttl: Some(4),
+		..SocketUserOptions::default()
+	};
+
+	// The test machine might not have a multicast-capable loopback interface.
+	match open(&"0.0.0.0:0".parse().unwrap(), &app_options, &user_options) {
+		Ok(socket) => {
+			assert!(!socket.multicast_loop_v4().unwrap());
+			assert_eq!(socket.multicast_ttl_v4().unwrap(), 4);
+			assert_eq!(socket.multicast_if_v4().unwrap(), Ipv4Addr::LOCALHOST);
+		},
+
+		Err(OpenSocketError::JoinMulticastGroup { .. } | OpenSocketError::SetSockOpt { option: "IP_MULTICAST_IF", .. }) => {},
+		Err(error) => panic!("{error}"),
+	}
+
+	for (addr, user_options, expected) in [
+		("[::]:0", user_options.clone(), "InvalidMulticastGroup"),
+		("0.0.0.0:0", SocketUserOptions { udp_multicast_group: vec!["127.0.0.1".parse().unwrap()], ..SocketUserOptions::default() }, "InvalidMulticastGroup"),
+		("0.0.0.0:0", SocketUserOptions { udp_multicast_interface: Some("lo".to_owned()), ..SocketUserOptions::default() }, "InvalidMulticastInterface"),
+	] {
+		let result = addr.parse::<SocketAddr>().unwrap().validate(&app_options, &user_options);
+
+		match (result, expected) {
+			(Err(OpenSocketError::InvalidMulticastGroup { .. }), "InvalidMulticastGroup") => {},
+			(Err(OpenSocketError::InvalidMulticastInterface { .. }), "InvalidMulticastInterface") => {},
+			(result, _) => panic!("{addr}: expected {expected}, got {result:?}"),
+		}
+	}
+
+	// Multicast options can't be used on TCP sockets.
+	assert!(matches!(
+		"0.0.0.0:0".parse::<SocketAddr>().unwrap().validate(&SocketAppOptions::new(socket2::Type::STREAM), &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "udp_multicast_group" }),
+	));
+}
+
+#[test]
+#[cfg(all(unix, not(target_os = "redox")))]
+fn test_tcp_max_segment() {
+	let user_options = SocketUserOptions {
+		tcp_max_segment: Some(1200),
+		..SocketUserOptions::default()
+	};
+
+	let socket: Socket = open(&"127.0.0.1:0".parse().unwrap(), &SocketAppOptions::new(socket2::Type::STREAM), &user_options).unwrap();
+
+	// The operating system might round the value, so only check that it took effect at all.
+	assert!(socket.mss().unwrap() <= 1200);
+
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&SocketAppOptions::new(socket2::Type::DGRAM), &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "tcp_max_segment" }),
+	));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_ip_socket_transparent() {
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = SocketUserOptions {
+		ip_socket_transparent: true,
+		..SocketUserOptions::default()
+	};
+
+	// The test might or might not be running with `CAP_NET_ADMIN`.
+	match open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options) {
+		Ok(_) | Err(OpenSocketError::TransparentPermissionDenied { .. }) => {},
+		Err(error) => panic!("{error}"),
+	}
+
+	assert!(matches!(
+		SocketAddr::unix(crate::util::TEST_SCRATCH.join("transparent.socket")).validate(&app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "ip_socket_transparent" }),
+	));
+}
+
+#[test]
+fn test_timeouts() {
+	let user_options = SocketUserOptions {
+		socket_recv_timeout: Some(1500),
+		socket_send_timeout: Some(0),
+		..SocketUserOptions::default()
+	};
+
+	let socket: Socket = open(&"127.0.0.1:0".parse().unwrap(), &SocketAppOptions::new(socket2::Type::DGRAM), &user_options).unwrap();
+	assert_eq!(socket.read_timeout().unwrap(), Some(Duration::from_millis(1500)));
+	assert_eq!(socket.write_timeout().unwrap(), None);
+}
+
+#[test]
+#[cfg(windows)]
+fn test_tcp_loopback_fast_path() {
+	let user_options = SocketUserOptions {
+		tcp_loopback_fast_path: true,
+		..SocketUserOptions::default()
+	};
+
+	// Newer versions of Windows might reject the control code, since it's deprecated.
+	match open(&"127.0.0.1:0".parse().unwrap(), &SocketAppOptions::new(socket2::Type::STREAM), &user_options) {
+		Ok(_) | Err(OpenSocketError::SetSockOpt { option: "SIO_LOOPBACK_FAST_PATH", .. }) => {},
+		Err(error) => panic!("{error}"),
+	}
+
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&SocketAppOptions::new(socket2::Type::DGRAM), &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "tcp_loopback_fast_path" }),
+	));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_udp_offload() {
+	let app_options = SocketAppOptions::new(socket2::Type::DGRAM);
+	let user_options = SocketUserOptions {
+		udp_segment_size: Some(1200),
+		udp_gro: true,
+		..SocketUserOptions::default()
+	};
+
+	// The test machine's kernel might be too old to support these, in which case they're ignored. Find out whether it does.
+	let probe = Socket::new(socket2::Domain::IPV4, socket2::Type::DGRAM, None).unwrap();
+	let segment_supported: bool = sys::set_udp_segment(&probe, 1200).is_ok();
+	let gro_supported: bool = sys::set_udp_gro(&probe, true).is_ok();
+
+	let opened: OpenedSocket = open_with_info(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+
+	assert_eq!(opened.udp_segment_size, segment_supported.then_some(1200));
+	assert_eq!(opened.udp_gro, gro_supported);
+	assert_eq!(sys::udp_segment(&opened.socket).ok(), segment_supported.then_some(1200));
+	assert_eq!(sys::udp_gro(&opened.socket).unwrap_or(false), gro_supported);
+
+	let opened: OpenedSocket = open_with_info(&"127.0.0.1:0".parse().unwrap(), &app_options, &SocketUserOptions::default()).unwrap();
+	assert_eq!(opened.udp_segment_size, None);
+	assert!(!opened.udp_gro);
+
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&SocketAppOptions::new(socket2::Type::STREAM), &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "udp_segment_size" }),
+	));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_reuse_port_cbpf() {
+	// A program that sends everything to the first socket in the group.
+	let app_options = SocketAppOptions::builder(socket2::Type::DGRAM)
+		.reuse_port_cbpf([libc::sock_filter { code: (libc::BPF_RET | libc::BPF_K) as u16, jt: 0, jf: 0, k: 0 }])
+		.build();
+
+	let user_options = SocketUserOptions {
+		ip_socket_reuse_port: true,
+		..SocketUserOptions::default()
+	};
+
+	let first: Socket = open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	let port: u16 = first.local_addr().unwrap().as_socket().unwrap().port();
+	let second: Socket = open(&SocketAddr::loopback(port), &app_options, &user_options).unwrap();
+
+	let client = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+
+	for _ in 0..4 {
+		client.send_to(b"x", (Ipv4Addr::LOCALHOST, port)).unwrap();
+	}
+
+	first.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	second.set_nonblocking(true).unwrap();
+
+	for _ in 0..4 {
+		first.recv(&mut [std::mem::MaybeUninit::uninit(); 1]).unwrap();
+	}
+
+	assert_eq!(second.recv(&mut [std::mem::MaybeUninit::uninit(); 1]).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn test_unix_socket_selinux_context() {
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = SocketUserOptions {
+		unix_socket_selinux_context: Some("system_u:object_r:tmp_t:s0".to_owned()),
+		..SocketUserOptions::default()
+	};
+
+	// SELinux might not be enabled on the test machine.
+	match open(&SocketAddr::unix(crate::util::TEST_SCRATCH.join("selinux.socket")), &app_options, &user_options) {
+		Ok(_) | Err(OpenSocketError::SetSecurityContext { .. }) => {},
+		Err(error) => panic!("{error}"),
+	}
+
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "unix_socket_selinux_context" }),
+	));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_unix_socket_permissions() {
+	use nix::sys::stat::Mode;
+	use std::os::unix::fs::PermissionsExt;
+
+	let path: PathBuf = crate::util::TEST_SCRATCH.join("permissions.socket");
+
+	let user_options = SocketUserOptions {
+		unix_socket_permissions: Some(Mode::S_IRUSR | Mode::S_IWUSR),
+		..SocketUserOptions::default()
+	};
+
+	let _socket: Socket = open(&SocketAddr::unix(path.clone()), &SocketAppOptions::new(socket2::Type::STREAM), &user_options).unwrap();
+
+	let mode: u32 = std::fs::metadata(&path).unwrap().permissions().mode();
+	assert_eq!(mode & 0o7777, 0o600);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_unix_socket_atomic_replace() {
+	use std::os::unix::net::UnixStream;
+
+	let dir: PathBuf = crate::util::TEST_SCRATCH.join("atomic_replace");
+	let _ = fs::remove_dir_all(&dir);
+	let path: PathBuf = dir.join("replace.socket");
+	let address = SocketAddr::unix(path.clone());
+
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = SocketUserOptions {
+		unix_socket_atomic_replace: true,
+		..SocketUserOptions::default()
+	};
+
+	let old: Socket = open(&address, &app_options, &user_options).unwrap();
+	let _old_client = UnixStream::connect(&path).unwrap();
+
+	// Until the new socket is listening, the old one stays in place.
+	let bound: BoundSocket = open_bound(&address, &app_options, &user_options).unwrap();
+	let _client = UnixStream::connect(&path).unwrap();
+	old.accept().unwrap();
+
+	let new: Socket = bound.finish_listen().unwrap();
+	let _client = UnixStream::connect(&path).unwrap();
+	new.accept().unwrap();
+
+	let opened = open_with_info(&address, &app_options, &user_options).unwrap();
+	assert!(!opened.unlinked_stale_socket);
+
+	// That socket replaced `new`, and has now been closed, so connecting fails.
+	drop(opened);
+	assert!(UnixStream::connect(&path).is_err());
+	drop(new);
+
+	// A socket that is dropped before it finishes listening leaves nothing behind, and doesn't replace anything.
+	let _new: Socket = open(&address, &app_options, &user_options).unwrap();
+	drop(open_bound(&address, &app_options, &user_options).unwrap());
+	let _client = UnixStream::connect(&path).unwrap();
+	assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+	// Files other than sockets are not replaced.
+	let file_path: PathBuf = dir.join("file.socket");
+	fs::write(&file_path, "not a socket").unwrap();
+
+	let error = open(&SocketAddr::unix(file_path.clone()), &app_options, &user_options).unwrap_err();
+	assert!(matches!(&error, OpenSocketError::Bind { error } if error.kind() == io::ErrorKind::AddrInUse), "{error:?}");
+	assert_eq!(fs::read_to_string(&file_path).unwrap(), "not a socket");
+
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&app_options, &user_options),
+		Err(OpenSocketError::InapplicableUserOption { name: "unix_socket_atomic_replace" }),
+	));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_unix_socket_lock_file() {
+	use std::os::unix::io::AsRawFd;
+
+	let path: PathBuf = crate::util::TEST_SCRATCH.join("lock_file.socket");
+	let lock_path: PathBuf = crate::unix_lock::lock_path(&path);
+	let address = SocketAddr::unix(path.clone());
+
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = SocketUserOptions {
+		unix_socket_lock_file: true,
+		..SocketUserOptions::default()
+	};
+
+	// Pretend that some other process has the socket open and the lock file locked.
+	let _ = fs::remove_file(&path);
+	let other: Socket = open(&address, &app_options, &SocketUserOptions::default()).unwrap();
+	let other_lock = fs::File::create(&lock_path).unwrap();
+	assert_eq!(unsafe { libc::flock(other_lock.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) }, 0);
+
+	let error = open(&address, &app_options, &user_options).unwrap_err();
+	assert!(matches!(&error, OpenSocketError::UnixSocketInUse { lock_path: p } if *p == lock_path), "{error:?}");
+	assert!(path.exists());
+
+	// Once the other process is gone, the stale socket can be deleted. This process then holds the lock, and can open the socket again.
+	drop(other_lock);
+	drop(other);
+
+	assert!(open_with_info(&address, &app_options, &user_options).unwrap().unlinked_stale_socket);
+	assert!(open_with_info(&address, &app_options, &user_options).unwrap().unlinked_stale_socket);
+
+	// The lock is still held, even though those sockets have been closed.
+	let other_lock = fs::File::open(&lock_path).unwrap();
+	assert_ne!(unsafe { libc::flock(other_lock.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) }, 0);
+}
@@ -0,0 +1,128 @@
+use crate::{
+	errors::OpenSocketError,
+	SocketAppOptions,
+	SocketUserOptions,
+	sys,
+	util::{check_inapplicable, is_listenable_socket_type},
+};
+use once_cell::sync::Lazy;
+use socket2::Socket;
+use std::{
+	collections::HashMap,
+	io,
+	net::{Ipv4Addr, SocketAddrV4},
+	sync::Mutex,
+};
+
+/// The other ends of socket pairs opened with [`SocketAddr::Pair`][crate::SocketAddr::Pair], by name, waiting to be claimed.
+static PAIR_PEERS: Lazy<Mutex<HashMap<String, Socket>>> = Lazy::new(Default::default);
+
+pub(crate) fn open_pair(
+	name: &str,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<Socket, OpenSocketError> {
+	sys::startup_socket_api();
+
+	let (socket, peer) = {
+		if app_options.listen && is_listenable_socket_type(app_options.r#type) {
+			open_loopback_listener_pair(app_options, user_options)?
+		}
+		else {
+			check_inapplicable(user_options.listen_socket_backlog, "listen_socket_backlog")?;
+
+			open_connected_pair(app_options)
+			.map_err(|error| OpenSocketError::CreatePair { error })?
+		}
+	};
+
+	PAIR_PEERS.lock()
+	.unwrap_or_else(|error| error.into_inner())
+	.insert(name.to_owned(), peer);
+
+	Ok(socket)
+}
+
+pub(crate) fn take_pair_peer(name: &str) -> Option<Socket> {
+	PAIR_PEERS.lock()
+	.unwrap_or_else(|error| error.into_inner())
+	.remove(name)
+}
+
+/// Opens a TCP listening socket on an ephemeral loopback port, and a second socket that is connected to it. The connection is waiting to be accepted from the listening socket.
+fn open_loopback_listener_pair(
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+) -> Result<(Socket, Socket), OpenSocketError> {
+	if app_options.r#type != socket2::Type::STREAM {
+		return Err(OpenSocketError::CreatePair {
+			error: io::Error::new(io::ErrorKind::Unsupported, "listening socket pairs are only supported for stream-type sockets"),
+		});
+	}
+
+	let address: socket2::SockAddr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0).into();
+
+	let listener =
+		Socket::new(address.domain(), socket2::Type::STREAM, None)
+		.map_err(|error| OpenSocketError::CreateSocket { error })?;
+
+	listener.bind(&address)
+	.map_err(|error| OpenSocketError::Bind { error })?;
+
+	listener.listen(
+		user_options.listen_socket_backlog
+		.unwrap_or(SocketUserOptions::DEFAULT_LISTEN_SOCKET_BACKLOG)
+	)
+	.map_err(|error| OpenSocketError::Listen { error })?;
+
+	let peer = (|| {
+		let address = listener.local_addr()?;
+		let peer = Socket::new(address.domain(), socket2::Type::STREAM, None)?;
+		peer.connect(&address)?;
+		Ok(peer)
+	})().map_err(|error| OpenSocketError::CreatePair { error })?;
+
+	Ok((listener, peer))
+}
+
+/// Opens two sockets that are connected to each other.
+fn open_connected_pair(app_options: &SocketAppOptions) -> io::Result<(Socket, Socket)> {
+	cfg_if::cfg_if! {
+		if #[cfg(unix)] {
+			Socket::pair(socket2::Domain::UNIX, app_options.r#type, None)
+		}
+		else {
+			let address: socket2::SockAddr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0).into();
+
+			if app_options.r#type == socket2::Type::STREAM {
+				// Emulate `socketpair` with a TCP connection over the loopback interface.
+				let listener = Socket::new(address.domain(), socket2::Type::STREAM, None)?;
+				listener.bind(&address)?;
+				listener.listen(1)?;
+
+				let socket = Socket::new(address.domain(), socket2::Type::STREAM, None)?;
+				socket.connect(&listener.local_addr()?)?;
+
+				let (peer, _) = listener.accept()?;
+
+				Ok((socket, peer))
+			}
+			else if app_options.r#type == socket2::Type::DGRAM {
+				// Emulate `socketpair` with two UDP sockets that are connected to each other.
+				let socket = Socket::new(address.domain(), socket2::Type::DGRAM, None)?;
+				socket.bind(&address)?;
+
+				let peer = Socket::new(address.domain(), socket2::Type::DGRAM, None)?;
+				peer.bind(&address)?;
+
+				socket.connect(&peer.local_addr()?)?;
+				peer.connect(&socket.local_addr()?)?;
+
+				Ok((socket, peer))
+			}
+			else {
+				Err(io::Error::new(io::ErrorKind::Unsupported, "socket pairs are only supported for stream-type and datagram-type sockets on this platform"))
+			}
+		}
+	}
+}
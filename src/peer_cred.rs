@@ -0,0 +1,201 @@
+use crate::{
+	errors::PeerNotAllowedError,
+	SocketUserOptions,
+	sys,
+};
+use nix::unistd::{Gid, Uid};
+use socket2::{SockAddr, Socket};
+use std::{
+	fmt::{self, Display, Formatter},
+	io,
+};
+
+/// The credentials of the process on the other end of a Unix-domain socket connection, as reported by the operating system.
+///
+/// These are the credentials that the process had when it connected (or, for a socket pair, when the pair was created), not necessarily the ones it has now.
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct PeerCredentials {
+	/// The effective user ID of the process.
+	pub uid: Uid,
+
+	/// The effective group ID of the process.
+	pub gid: Gid,
+
+	/// The process ID, or `None` if the operating system doesn't report it (which is the case on platforms other than Android and Linux).
+	///
+	/// This is only suitable for logging. The process may have exited since connecting, and its process ID may then have been reused by some other process.
+	pub pid: Option<libc::pid_t>,
+}
+
+impl Display for PeerCredentials {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "uid={} gid={}", self.uid, self.gid)?;
+
+		if let Some(pid) = self.pid {
+			write!(f, " pid={pid}")?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Gets the credentials of the process on the other end of a connected Unix-domain socket.
+///
+/// To use this function with a socket type other than [`socket2::Socket`], such as [`std::os::unix::net::UnixStream`], wrap it in a [`socket2::SockRef`] first.
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::Unsupported`] if `socket` is not a Unix-domain socket, such as if it's a TCP connection, or on platforms where there's no known way to get the peer's credentials. Otherwise, any I/O error raised by the operating system, such as if `socket` is not connected.
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn peer_credentials(socket: &Socket) -> io::Result<PeerCredentials> {
+	// Some operating systems report nonsense credentials for other kinds of sockets, instead of an error.
+	if !socket.local_addr()?.is_unix() {
+		return Err(io::Error::new(io::ErrorKind::Unsupported, "peer credentials are only available for Unix-domain sockets"));
+	}
+
+	let (uid, gid, pid) = sys::peer_credentials(socket)?;
+
+	Ok(PeerCredentials {
+		uid: Uid::from_raw(uid),
+		gid: Gid::from_raw(gid),
+		pid,
+	})
+}
+
+/// Which users and groups are allowed to connect to a Unix-domain listening socket. This is usually made from the [`SocketUserOptions::unix_socket_allowed_uids`] and [`SocketUserOptions::unix_socket_allowed_gids`] options, using [`from_user_options`][Self::from_user_options].
+///
+/// A connection is allowed if the connecting process's user ID is one of the [`allowed_uids`][Self::allowed_uids], or its group ID is one of the [`allowed_gids`][Self::allowed_gids]. If both lists are empty, all connections are allowed.
+///
+/// To check connections against it, accept them with [`accept_allowed`] (or `AnyTokioListener::accept_allowed`, if the `tokio` feature is enabled).
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct UnixPeerAcl {
+	/// User IDs that are allowed to connect.
+	pub allowed_uids: Vec<Uid>,
+
+	/// Group IDs that are allowed to connect.
+	pub allowed_gids: Vec<Gid>,
+}
+
+impl UnixPeerAcl {
+	/// Creates a new `UnixPeerAcl` that allows all connections.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a new `UnixPeerAcl` from [`SocketUserOptions::unix_socket_allowed_uids`] and [`SocketUserOptions::unix_socket_allowed_gids`].
+	pub fn from_user_options(user_options: &SocketUserOptions) -> Self {
+		Self {
+			allowed_uids: user_options.unix_socket_allowed_uids.clone(),
+			allowed_gids: user_options.unix_socket_allowed_gids.clone(),
+		}
+	}
+
+	/// Returns true if this allows all connections, which is the case if both lists are empty.
+	pub fn is_empty(&self) -> bool {
+		self.allowed_uids.is_empty() && self.allowed_gids.is_empty()
+	}
+
+	/// Checks whether a process with the given credentials is allowed to connect.
+	pub fn allows(&self, credentials: &PeerCredentials) -> bool {
+		self.is_empty()
+		|| self.allowed_uids.contains(&credentials.uid)
+		|| self.allowed_gids.contains(&credentials.gid)
+	}
+
+	/// Checks whether a newly accepted connection is allowed.
+	///
+	/// Returns the peer's credentials, if the connection is a Unix-domain connection and the credentials could be determined. Connections other than Unix-domain connections are always allowed, with no credentials.
+	pub(crate) fn check_accepted(&self, socket: &Socket, peer_addr: &SockAddr) -> io::Result<Option<PeerCredentials>> {
+		if !peer_addr.is_unix() {
+			return Ok(None);
+		}
+
+		match peer_credentials(socket) {
+			Ok(credentials) if self.allows(&credentials) => Ok(Some(credentials)),
+			Ok(credentials) => Err(PeerNotAllowedError { credentials }.into()),
+
+			// If nobody is to be rejected, then there's no need for the credentials.
+			Err(_) if self.is_empty() => Ok(None),
+			Err(error) => Err(error),
+		}
+	}
+}
+
+/// Accepts a connection on `listener`, and checks the connecting process's credentials against `acl`.
+///
+/// On success, this returns the accepted connection, the peer's address, and the peer's credentials. The credentials are `None` if the connection is not a Unix-domain connection, or if `acl` allows all connections and the credentials couldn't be determined.
+///
+/// `acl` only applies to Unix-domain connections. Other connections, such as TCP connections, are always allowed.
+///
+/// # Errors
+///
+/// Any I/O error raised by the operating system, either in accepting the connection or in getting the peer's credentials.
+///
+/// If the peer is not allowed to connect, the connection is closed, and this fails with an [`io::Error`] of kind [`io::ErrorKind::PermissionDenied`] that wraps a [`PeerNotAllowedError`]. The peer's credentials can be found in that error, such as for logging the rejected connection. This is a problem with that one connection only, so the application should keep accepting connections afterward.
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn accept_allowed(listener: &Socket, acl: &UnixPeerAcl) -> io::Result<(Socket, SockAddr, Option<PeerCredentials>)> {
+	let (socket, peer_addr) = listener.accept()?;
+	let credentials: Option<PeerCredentials> = acl.check_accepted(&socket, &peer_addr)?;
+	Ok((socket, peer_addr, credentials))
+}
+
+#[test]
+fn test_accept_allowed() {
+	use crate::{open, SocketAddr, SocketAppOptions};
+	use std::os::unix::net::UnixStream;
+
+	let path = crate::util::TEST_SCRATCH.join("accept_allowed.socket");
+	let app_options = SocketAppOptions::new(socket2::Type::STREAM);
+
+	let user_options = SocketUserOptions {
+		unix_socket_allowed_uids: vec![Uid::effective()],
+		..SocketUserOptions::default()
+	};
+
+	let listener: Socket = open(&SocketAddr::unix(path.clone()), &app_options, &user_options).unwrap();
+
+	let _client = UnixStream::connect(&path).unwrap();
+	let (_, _, credentials) = accept_allowed(&listener, &UnixPeerAcl::from_user_options(&user_options)).unwrap();
+	let credentials: PeerCredentials = credentials.unwrap();
+	assert_eq!(credentials.uid, Uid::effective());
+	assert_eq!(credentials.gid, Gid::effective());
+
+	// Allow only some other user and group.
+	let acl = UnixPeerAcl {
+		allowed_uids: vec![Uid::from_raw(Uid::effective().as_raw().wrapping_add(1))],
+		allowed_gids: vec![Gid::from_raw(Gid::effective().as_raw().wrapping_add(1))],
+	};
+
+	let _client = UnixStream::connect(&path).unwrap();
+	let error: io::Error = accept_allowed(&listener, &acl).unwrap_err();
+	assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+	let error: &PeerNotAllowedError = error.get_ref().and_then(|error| error.downcast_ref()).unwrap();
+	assert_eq!(error.credentials, credentials);
+
+	// TCP connections don't have peer credentials.
+	let tcp_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let tcp_client: Socket = std::net::TcpStream::connect(tcp_listener.local_addr().unwrap()).unwrap().into();
+	assert_eq!(peer_credentials(&tcp_client).unwrap_err().kind(), io::ErrorKind::Unsupported);
+
+	assert!(matches!(
+		"127.0.0.1:0".parse::<SocketAddr>().unwrap().validate(&app_options, &user_options),
+		Err(crate::errors::OpenSocketError::InapplicableUserOption { name: "unix_socket_allowed_uids" }),
+	));
+}
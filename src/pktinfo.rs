@@ -0,0 +1,105 @@
+use crate::sys;
+use socket2::{SockAddr, Socket};
+use std::{
+	io,
+	net::IpAddr,
+};
+
+#[cfg(doc)]
+use crate::SocketUserOptions;
+
+/// Where a datagram was received, or where to send one from. This is the packet info ancillary data (`IP_PKTINFO` or `IPV6_PKTINFO`) that goes with a datagram.
+///
+/// A UDP server whose socket is bound to a wildcard address (such as `0.0.0.0` or `[::]`), on a host with more than one address, has to reply to each client from the address that the client sent its request to; otherwise, the client won't recognize the reply. To do that, enable [`SocketUserOptions::udp_socket_pktinfo`], receive requests with [`recv_with_pktinfo`], and send the replies with [`send_with_pktinfo`], passing along the `PacketInfo` that came with the request.
+///
+/// # Availability
+///
+/// Android and Linux only.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct PacketInfo {
+	/// The local address that the datagram was sent to (when receiving), or is to be sent from (when sending).
+	///
+	/// When sending, this can be the unspecified address (`0.0.0.0` or `::`), in which case the operating system chooses the source address as usual.
+	pub local_addr: IpAddr,
+
+	/// The index of the network interface that the datagram was received on (when receiving), or is to be sent on (when sending).
+	///
+	/// When sending, this can be zero, in which case the operating system chooses the interface as usual.
+	pub interface: u32,
+}
+
+impl PacketInfo {
+	/// Creates a new `PacketInfo`.
+	pub fn new(local_addr: IpAddr, interface: u32) -> Self {
+		Self { local_addr, interface }
+	}
+}
+
+/// Receives a datagram on `socket`, along with its [`PacketInfo`].
+///
+/// The datagram is written into `buf`. If the datagram is bigger than `buf`, the rest of it is discarded.
+///
+/// On success, this returns the size of the datagram (up to the size of `buf`), the address of the sender, and the packet info. The packet info is `None` if the operating system didn't provide it, which is the case if [`SocketUserOptions::udp_socket_pktinfo`] wasn't used when opening the socket.
+///
+/// If the socket is in non-blocking mode, this fails with [`io::ErrorKind::WouldBlock`] when there is no datagram waiting to be received. To use this with a non-blocking socket such as a Tokio `UdpSocket`, wrap it in a [`socket2::SockRef`] first, and call this from inside `UdpSocket::try_io` or the equivalent.
+///
+/// # Errors
+///
+/// Any I/O error raised by the operating system.
+///
+/// # Availability
+///
+/// Android and Linux only.
+pub fn recv_with_pktinfo(socket: &Socket, buf: &mut [u8]) -> io::Result<(usize, SockAddr, Option<PacketInfo>)> {
+	sys::recv_pktinfo(socket, buf)
+}
+
+/// Sends a datagram from `socket` to `peer_addr`, from the local address and/or on the network interface given by `info`.
+///
+/// `info` is usually the [`PacketInfo`] that came with the datagram that this one is a reply to, as returned by [`recv_with_pktinfo`].
+///
+/// On success, this returns the number of bytes sent.
+///
+/// # Errors
+///
+/// Any I/O error raised by the operating system. This also fails with [`io::ErrorKind::InvalidInput`] if `socket` is an IPv4 socket and [`PacketInfo::local_addr`] is an IPv6 address (other than an IPv4-mapped one).
+///
+/// # Availability
+///
+/// Android and Linux only.
+pub fn send_with_pktinfo(socket: &Socket, buf: &[u8], peer_addr: &SockAddr, info: &PacketInfo) -> io::Result<usize> {
+	let is_ipv6: bool = socket.local_addr()?.is_ipv6();
+	sys::send_pktinfo(socket, buf, peer_addr, info, is_ipv6)
+}
+
+#[test]
+fn test_pktinfo() {
+	use crate::{open, SocketAppOptions, SocketUserOptions};
+	use std::net::{Ipv4Addr, UdpSocket};
+
+	let user_options = SocketUserOptions {
+		udp_socket_pktinfo: true,
+		..SocketUserOptions::default()
+	};
+
+	let server: Socket = open(&"0.0.0.0:0".parse().unwrap(), &SocketAppOptions::new(socket2::Type::DGRAM), &user_options).unwrap();
+	let server_port: u16 = server.local_addr().unwrap().as_socket().unwrap().port();
+
+	let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+	client.send_to(b"ping", (Ipv4Addr::LOCALHOST, server_port)).unwrap();
+
+	let mut buf = [0u8; 16];
+	let (received, peer_addr, info) = recv_with_pktinfo(&server, &mut buf).unwrap();
+	let info: PacketInfo = info.expect("no packet info was received");
+
+	assert_eq!(&buf[..received], b"ping");
+	assert_eq!(peer_addr.as_socket(), Some(client.local_addr().unwrap()));
+	assert_eq!(info.local_addr, IpAddr::from(Ipv4Addr::LOCALHOST));
+
+	send_with_pktinfo(&server, b"pong", &peer_addr, &info).unwrap();
+
+	let (received, from) = client.recv_from(&mut buf).unwrap();
+	assert_eq!(&buf[..received], b"pong");
+	assert_eq!(from, (Ipv4Addr::LOCALHOST, server_port).into());
+}
@@ -0,0 +1,86 @@
+use crate::{
+	open,
+	SocketAddr,
+	SocketAppOptions,
+	SocketUserOptions,
+};
+use socket2::Socket;
+use std::io;
+
+/// What [`prefork`] returns in the parent process and in each worker process.
+#[derive(Debug)]
+pub enum Forked {
+	/// This is the parent process.
+	Parent {
+		/// The listening socket. The parent process usually doesn't accept connections itself, and can close it, unless it will be forking more workers later.
+		socket: Socket,
+
+		/// The process IDs of the workers, in order of their indices.
+		workers: Vec<u32>,
+	},
+
+	/// This is a worker process.
+	Worker {
+		/// The listening socket, shared with the parent and all of the other workers.
+		socket: Socket,
+
+		/// Which worker this is, from 0 to one less than the number of workers.
+		index: usize,
+	},
+}
+
+/// Opens a socket with [`open`][open()], then forks the given number of worker processes, which all accept connections on that same socket.
+///
+/// This is the pre-fork server model, where the operating system distributes incoming connections among the workers. Each worker is a copy of this process, and returns from this function with [`Forked::Worker`]; the parent returns with [`Forked::Parent`]. A worker should serve connections until it's told to stop, then exit, without returning to whatever called this function.
+///
+/// If forking one of the workers fails, then the workers that were already forked are killed (with `SIGKILL`) and waited for, and the error is returned in the parent.
+///
+///
+/// # Errors
+///
+/// Any error from `open`, converted to an [`io::Error`], or any I/O error raised by the operating system in forking the workers.
+///
+///
+/// # Safety
+///
+/// This process must not have more than one thread when this function is called. In a multi-threaded process, `fork` only copies the calling thread, and anything that the other threads were doing at that moment, such as holding a lock, is left in an inconsistent state in the workers. Notably, this means that this function must be called before starting an asynchronous runtime, like a Tokio runtime with worker threads.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub unsafe fn prefork(
+	address: &SocketAddr,
+	app_options: &SocketAppOptions,
+	user_options: &SocketUserOptions,
+	workers: usize,
+) -> io::Result<Forked> {
+	let socket: Socket = open(address, app_options, user_options)?;
+	let mut worker_pids: Vec<u32> = Vec::with_capacity(workers);
+
+	for index in 0..workers {
+		// Safety: The caller promises that this process is single-threaded, so the child process can safely continue running normally.
+		match unsafe { libc::fork() } {
+			0 => return Ok(Forked::Worker { socket, index }),
+
+			-1 => {
+				let error = io::Error::last_os_error();
+
+				for pid in worker_pids {
+					unsafe {
+						// Safety: Neither `kill` nor `waitpid` takes a pointer, except that `waitpid` may take a null pointer for the exit status.
+						libc::kill(pid as libc::pid_t, libc::SIGKILL);
+						libc::waitpid(pid as libc::pid_t, std::ptr::null_mut(), 0);
+					}
+				}
+
+				return Err(error);
+			},
+
+			pid => worker_pids.push(pid as u32),
+		}
+	}
+
+	Ok(Forked::Parent { socket, workers: worker_pids })
+}
+
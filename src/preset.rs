@@ -0,0 +1,108 @@
+use crate::HookContext;
+use socket2::Socket;
+use std::io;
+
+#[cfg(doc)]
+use crate::SocketAppOptions;
+
+/// A ready-made set of [`SocketAppOptions`] for a common kind of application. Use with [`SocketAppOptions::preset`].
+///
+/// Presets exist for applications whose sockets need several socket options set in order to work well, where every application of that kind would otherwise have to discover and set them individually.
+///
+///
+/// # Availability
+///
+/// All platforms. The socket options that each preset sets differ by platform; see the documentation for each preset for details.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SocketPreset {
+	/// A UDP socket for a QUIC server (or other high-throughput UDP server).
+	///
+	/// This preset uses [`socket2::Type::DGRAM`] and [`socket2::Protocol::UDP`], and sets the following socket options on new sockets, just before the application's [`before_bind`][SocketAppOptions::before_bind] hook (if any) is called:
+	///
+	/// * Large send and receive buffers (`SO_SNDBUF` and `SO_RCVBUF`), [`QUIC_SERVER_BUFFER_SIZE`][Self::QUIC_SERVER_BUFFER_SIZE] bytes each. Most operating systems limit how large these buffers can be, and silently reduce the requested size to that limit. On Linux, the limits are the `net.core.rmem_max` and `net.core.wmem_max` sysctls, which are quite small by default.
+	/// * Reception of the ECN bits of incoming packets (`IP_RECVTOS` or `IPV6_RECVTCLASS`), on Android, FreeBSD, iOS, Linux, and macOS.
+	/// * Reception of the destination address of incoming packets (`IP_PKTINFO` or `IPV6_RECVPKTINFO`), so that replies can be sent from the address the client sent to, on Android and Linux. FreeBSD, iOS, and macOS use `IP_RECVDSTADDR` instead of `IP_PKTINFO` for IPv4.
+	/// * Generic receive offload (`UDP_GRO`), on Linux. This is silently skipped if the kernel is too old to support it.
+	///
+	/// Generic segmentation offload (`UDP_SEGMENT`) is requested per message, not per socket, so this preset has nothing to set for it.
+	///
+	/// It is still up to the application to read the ancillary data that these options enable, such as with `recvmsg`.
+	///
+	/// No [default port][SocketAppOptions::default_port] is set. QUIC servers usually use port 443, but this is left up to the application.
+	QuicServer,
+}
+
+impl SocketPreset {
+	/// Size of the send and receive buffers requested by [`SocketPreset::QuicServer`].
+	pub const QUIC_SERVER_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+	/// Sets this preset's socket options on a new socket. This is called just before [`SocketAppOptions::before_bind`].
+	pub(crate) fn before_bind(self, socket: &mut Socket, context: &HookContext) -> io::Result<()> {
+		match self {
+			Self::QuicServer => quic_server_before_bind(socket, context),
+		}
+	}
+}
+
+fn quic_server_before_bind(socket: &mut Socket, context: &HookContext) -> io::Result<()> {
+	socket.set_recv_buffer_size(SocketPreset::QUIC_SERVER_BUFFER_SIZE)?;
+	socket.set_send_buffer_size(SocketPreset::QUIC_SERVER_BUFFER_SIZE)?;
+
+	let is_ipv6: bool = context.sockaddr.is_ipv6();
+
+	#[cfg(any(
+		target_os = "android",
+		target_os = "freebsd",
+		target_os = "ios",
+		target_os = "linux",
+		target_os = "macos",
+	))] {
+		if is_ipv6 {
+			socket.set_recv_tclass_v6(true)?;
+		}
+		else {
+			socket.set_recv_tos(true)?;
+		}
+	}
+
+	#[cfg(any(
+		target_os = "android",
+		target_os = "linux",
+	))]
+	crate::sys::set_recv_pktinfo(socket, is_ipv6)?;
+
+	#[cfg(any(
+		target_os = "freebsd",
+		target_os = "ios",
+		target_os = "macos",
+	))] {
+		let (pktinfo_level, pktinfo_option) = match is_ipv6 {
+			true => (libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO),
+			false => (libc::IPPROTO_IP, libc::IP_RECVDSTADDR),
+		};
+
+		unsafe {
+			// Safety: The selected socket option level and socket option are valid, and the socket option expects a pointer to a `c_int`.
+			crate::sys::set_int_sockopt(socket, pktinfo_level, pktinfo_option, 1)?;
+		}
+	}
+
+	// Linux versions older than 5.0 don't have `UDP_GRO`. That's fine; just skip it.
+	#[cfg(target_os = "linux")]
+	match crate::sys::set_udp_gro(socket, true) {
+		Err(error) if error.raw_os_error() == Some(libc::ENOPROTOOPT) => {}
+		result => result?,
+	}
+
+	#[cfg(not(any(
+		target_os = "android",
+		target_os = "freebsd",
+		target_os = "ios",
+		target_os = "linux",
+		target_os = "macos",
+	)))]
+	let _ = is_ipv6;
+
+	Ok(())
+}
@@ -0,0 +1,292 @@
+use std::{
+	collections::BTreeMap,
+	ffi::{CString, OsStr, OsString},
+	io,
+	os::{
+		fd::{AsFd, AsRawFd, RawFd},
+		unix::{
+			ffi::{OsStrExt, OsStringExt},
+			fs::PermissionsExt,
+			process::CommandExt,
+		},
+	},
+	path::{Path, PathBuf},
+	process::{Child, Command},
+};
+
+#[cfg(doc)]
+use crate::SocketAddr;
+
+/// The first file descriptor number used for passed sockets, same as `SD_LISTEN_FDS_START`.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// The prefix of the `LISTEN_PID` environment variable, which is filled in with the child's process ID after `fork`.
+const LISTEN_PID_PREFIX: &[u8] = b"LISTEN_PID=";
+
+/// Spawns a child process, passing it the given sockets using the systemd socket activation protocol.
+///
+/// This is the same protocol that this library uses to receive sockets from systemd, so the child process can open them with a [`SocketAddr::SystemdNamed`] (like `systemd-name:http`) or [`SocketAddr::SystemdNumeric`] address, or with [`SocketAddr::all_systemd`]. Other programs that support systemd socket activation can use them as well.
+///
+/// Each socket is paired with its name, which the child process sees in the `LISTEN_FDNAMES` environment variable. Names must be 1 to 255 printable ASCII characters, and must not contain `:`.
+///
+/// In the child process, the sockets are renumbered to file descriptors 3, 4, 5, and so on, in order, and made inheritable. The sockets are not made inheritable in this process, so other child processes spawned at the same time don't inherit them. The environment variables `LISTEN_FDS`, `LISTEN_FDNAMES`, and `LISTEN_PID` are set accordingly; `LISTEN_PID` is set to the child's own process ID, as the protocol requires.
+///
+/// Any other file descriptors that already have those numbers in the child process, other than the sockets being passed, are replaced.
+///
+///
+/// # Caveats
+///
+/// In order to set `LISTEN_PID`, which isn't known until the child process has been created, this function executes the program itself, instead of letting [`Command`] do it. As a result:
+///
+/// * The child's environment is this process's environment with the changes made by [`Command::env`] and [`Command::env_remove`] applied. [`Command::env_clear`] has no effect.
+/// * [`CommandExt::arg0`] has no effect.
+/// * Any [`CommandExt::pre_exec`] closures that are added to `command` after calling this function are not run.
+///
+/// The program is looked up in `PATH` (as it would be by `Command`) before the child process is created.
+///
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] if a name is invalid, with [`io::ErrorKind::NotFound`] if the program can't be found, or with any I/O error raised by the operating system in spawning the child process.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn spawn_with_sockets<S: AsFd>(command: &mut Command, sockets: &[(S, &str)]) -> io::Result<Child> {
+	for (_, name) in sockets {
+		validate_name(name)?;
+	}
+
+	// Work out the child's environment.
+	let mut env: BTreeMap<OsString, OsString> = std::env::vars_os().collect();
+
+	for (key, value) in command.get_envs() {
+		match value {
+			Some(value) => env.insert(key.to_owned(), value.to_owned()),
+			None => env.remove(key),
+		};
+	}
+
+	let names: Vec<&str> = sockets.iter().map(|(_, name)| *name).collect();
+	env.remove(OsStr::new("LISTEN_PID"));
+	env.insert("LISTEN_FDS".into(), sockets.len().to_string().into());
+	env.insert("LISTEN_FDNAMES".into(), names.join(":").into());
+
+	let program: PathBuf = find_program(command.get_program(), env.get(OsStr::new("PATH")).map(OsString::as_os_str))?;
+
+	let mut setup = ChildSetup::new(
+		sockets.iter().map(|(socket, _)| socket.as_fd().as_raw_fd()).collect(),
+		&program,
+		std::iter::once(command.get_program()).chain(command.get_args()),
+		env,
+	)?;
+
+	unsafe {
+		// Safety: `ChildSetup::run` only makes async-signal-safe system calls, and doesn't allocate memory.
+		command.pre_exec(move || setup.run());
+	}
+
+	command.spawn()
+}
+
+/// Checks whether a socket name is valid, according to the same rules as systemd. Fails with [`io::ErrorKind::InvalidInput`] if it isn't.
+pub(crate) fn validate_name(name: &str) -> io::Result<()> {
+	let is_valid: bool =
+		(1..=255).contains(&name.len())
+		&& name.bytes().all(|byte| (b' '..=b'~').contains(&byte) && byte != b':');
+
+	if !is_valid {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid socket name `{name}`: must be 1 to 255 printable ASCII characters, not including `:`")));
+	}
+
+	Ok(())
+}
+
+/// Finds the program to execute, the way `execvp` would.
+fn find_program(program: &OsStr, path_var: Option<&OsStr>) -> io::Result<PathBuf> {
+	if program.as_bytes().contains(&b'/') {
+		return Ok(program.into());
+	}
+
+	let path_var: &OsStr = path_var.unwrap_or(OsStr::new("/usr/local/bin:/usr/bin:/bin"));
+
+	std::env::split_paths(path_var)
+	.map(|dir| dir.join(program))
+	.find(|candidate| {
+		candidate.metadata()
+		.map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+		.unwrap_or(false)
+	})
+	.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("program `{}` not found", Path::new(program).display())))
+}
+
+/// Everything that the child process needs to do between `fork` and `exec`, prepared in advance so that nothing needs to be allocated in the child.
+struct ChildSetup {
+	/// The sockets' file descriptors in this process.
+	source_fds: Vec<RawFd>,
+
+	/// Space for temporary copies of the sockets' file descriptors.
+	temp_fds: Vec<RawFd>,
+
+	program: CString,
+
+	/// The arguments, and pointers to them for `execve`.
+	_argv: Vec<CString>,
+	argv_ptrs: Vec<*const libc::c_char>,
+
+	/// The environment variables, and pointers to them for `execve`. The last one before the null pointer is `listen_pid`.
+	_envp: Vec<CString>,
+	envp_ptrs: Vec<*const libc::c_char>,
+
+	/// Buffer for the `LISTEN_PID` environment variable. It starts with `LISTEN_PID_PREFIX`, and has enough room after that for any process ID and a null terminator.
+	listen_pid: Vec<u8>,
+}
+
+// Safety: the raw pointers point into buffers owned by the `ChildSetup` itself, which don't move when it does. They're only used in the child process, after `fork`.
+unsafe impl Send for ChildSetup {}
+unsafe impl Sync for ChildSetup {}
+
+impl ChildSetup {
+	fn new<'a>(
+		source_fds: Vec<RawFd>,
+		program: &Path,
+		args: impl Iterator<Item = &'a OsStr>,
+		env: BTreeMap<OsString, OsString>,
+	) -> io::Result<Self> {
+		let to_cstring = |bytes: Vec<u8>| CString::new(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error));
+
+		let program: CString = to_cstring(program.as_os_str().as_bytes().to_owned())?;
+
+		let argv: Vec<CString> =
+			args
+			.map(|arg| to_cstring(arg.as_bytes().to_owned()))
+			.collect::<io::Result<_>>()?;
+
+		let envp: Vec<CString> =
+			env.into_iter()
+			.map(|(mut key, value)| {
+				key.push("=");
+				key.push(value);
+				to_cstring(key.into_vec())
+			})
+			.collect::<io::Result<_>>()?;
+
+		let mut listen_pid: Vec<u8> = LISTEN_PID_PREFIX.to_owned();
+		listen_pid.resize(LISTEN_PID_PREFIX.len() + 21, 0);
+
+		let argv_ptrs: Vec<*const libc::c_char> =
+			argv.iter()
+			.map(|arg| arg.as_ptr())
+			.chain(std::iter::once(std::ptr::null()))
+			.collect();
+
+		let envp_ptrs: Vec<*const libc::c_char> =
+			envp.iter()
+			.map(|var| var.as_ptr())
+			.chain([listen_pid.as_ptr() as *const libc::c_char, std::ptr::null()])
+			.collect();
+
+		Ok(Self {
+			temp_fds: vec![-1; source_fds.len()],
+			source_fds,
+			program,
+			_argv: argv,
+			argv_ptrs,
+			_envp: envp,
+			envp_ptrs,
+			listen_pid,
+		})
+	}
+
+	/// Runs in the child process, after `fork`. Only returns if something went wrong.
+	fn run(&mut self) -> io::Result<()> {
+		let target_fds_end: RawFd = LISTEN_FDS_START + self.source_fds.len() as RawFd;
+
+		// First, copy all of the sockets to file descriptors above the target range, so that moving one of them into place doesn't overwrite another one that hasn't been moved yet. The copies are closed on `exec`.
+		for (source_fd, temp_fd) in self.source_fds.iter().zip(&mut self.temp_fds) {
+			*temp_fd = unsafe {
+				// Safety: `F_DUPFD_CLOEXEC` doesn't take a pointer.
+				libc::fcntl(*source_fd, libc::F_DUPFD_CLOEXEC, target_fds_end)
+			};
+
+			if *temp_fd == -1 {
+				return Err(io::Error::last_os_error());
+			}
+		}
+
+		// Then, move them into place. The file descriptors created by `dup2` don't have `CLOEXEC` set, so they're inherited.
+		for (target_fd, temp_fd) in (LISTEN_FDS_START..).zip(&self.temp_fds) {
+			let result = unsafe {
+				// Safety: `dup2` doesn't take a pointer.
+				libc::dup2(*temp_fd, target_fd)
+			};
+
+			if result == -1 {
+				return Err(io::Error::last_os_error());
+			}
+		}
+
+		// Fill in `LISTEN_PID`. This has to be done without allocating memory, so format the number by hand.
+		let mut pid = unsafe {
+			// Safety: `getpid` has no preconditions.
+			libc::getpid()
+		} as u64;
+
+		let mut digits = [0u8; 20];
+		let mut digits_start: usize = digits.len();
+
+		loop {
+			digits_start -= 1;
+			digits[digits_start] = b'0' + (pid % 10) as u8;
+			pid /= 10;
+
+			if pid == 0 {
+				break;
+			}
+		}
+
+		let digits: &[u8] = &digits[digits_start..];
+		let value_start: usize = LISTEN_PID_PREFIX.len();
+		self.listen_pid[value_start..(value_start + digits.len())].copy_from_slice(digits);
+		self.listen_pid[value_start + digits.len()] = 0;
+
+		unsafe {
+			// Safety: `program` is a valid null-terminated string. `argv_ptrs` and `envp_ptrs` are null-terminated arrays of pointers to valid null-terminated strings, which are owned by `self`.
+			libc::execve(self.program.as_ptr(), self.argv_ptrs.as_ptr(), self.envp_ptrs.as_ptr());
+		}
+
+		// `execve` only returns if it failed.
+		Err(io::Error::last_os_error())
+	}
+}
+
+#[test]
+fn test_spawn_with_sockets() {
+	use std::{
+		io::Read,
+		process::Stdio,
+	};
+
+	let first = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let second = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+
+	// Have the child process report what it received.
+	let mut command = Command::new("sh");
+	command
+	.args(["-c", r#"echo "$LISTEN_FDS $LISTEN_FDNAMES $([ "$LISTEN_PID" = "$$" ] && echo pid-ok)"; [ -e /dev/fd/3 ] && [ -e /dev/fd/4 ] && echo fds-ok"#])
+	.stdout(Stdio::piped());
+
+	let mut child: Child = spawn_with_sockets(&mut command, &[(first.as_fd(), "first"), (second.as_fd(), "second")]).unwrap();
+
+	let mut output = String::new();
+	child.stdout.take().unwrap().read_to_string(&mut output).unwrap();
+	assert!(child.wait().unwrap().success());
+
+	assert_eq!(output, "2 first:second pid-ok\nfds-ok\n");
+
+	assert_eq!(
+		spawn_with_sockets(&mut Command::new("true"), &[(first.as_fd(), "bad:name")]).unwrap_err().kind(),
+		io::ErrorKind::InvalidInput,
+	);
+}
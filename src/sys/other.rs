@@ -13,9 +13,11 @@ use std::{
 	},
 	path::Path,
 	process,
+	time::Duration,
 };
 
 pub use std::os::fd::{
+	AsFd as AsSocket,
 	BorrowedFd as BorrowedSocket,
 	OwnedFd as OwnedSocket,
 	RawFd as RawSocket,
@@ -50,19 +52,800 @@ pub static SD_LISTEN_FDS_END: Lazy<Option<RawSocket>> = Lazy::new(|| {
 	Some(listen_fds_end)
 });
 
+/// Looks up an inherited systemd socket by name, in the `LISTEN_FDNAMES` environment variable. If more than one socket has the name, the first one is returned.
+pub fn sd_listen_fd_by_name(name: &str) -> Option<RawSocket> {
+	sd_listen_fds_by_name(name).into_iter().next()
+}
+
+/// Looks up all inherited systemd sockets with the given name, in the `LISTEN_FDNAMES` environment variable, in order.
+pub fn sd_listen_fds_by_name(name: &str) -> Vec<RawSocket> {
+	let Some(listen_fds_end) = *SD_LISTEN_FDS_END else {
+		return Vec::new();
+	};
+
+	let Ok(listen_fd_names) = env::var("LISTEN_FDNAMES") else {
+		return Vec::new();
+	};
+
+	listen_fd_names
+	.split(':')
+	.zip(SD_LISTEN_FDS_START..listen_fds_end)
+	.filter(|(listen_fd_name, _)| *listen_fd_name == name)
+	.map(|(_, socket)| socket)
+	.collect()
+}
+
+pub fn borrow_socket<S: AsSocket + ?Sized>(socket: &S) -> BorrowedSocket<'_> {
+	socket.as_fd()
+}
+
 pub fn make_socket_inheritable(
-	socket: &Socket,
+	socket: BorrowedSocket<'_>,
 	inheritable: bool,
 ) -> io::Result<RawSocket> {
-	socket.set_cloexec(!inheritable)?;
+	// `set_cloexec` works on any file descriptor, not just sockets.
+	socket2::SockRef::from(&socket).set_cloexec(!inheritable)?;
 	Ok(socket.as_raw_fd())
 }
 
+/// Clears the `CLOEXEC` flag of a file descriptor, so that it's inherited across `exec`. This is for use in a `pre_exec` closure, so it must be async-signal-safe.
+#[cfg(unix)]
+pub fn clear_cloexec(fd: RawSocket) -> io::Result<()> {
+	let flags = unsafe {
+		// Safety: `F_GETFD` doesn't take a pointer, and is harmless even if `fd` is not a valid file descriptor.
+		libc::fcntl(fd, libc::F_GETFD)
+	};
+
+	if flags == -1 {
+		return Err(io::Error::last_os_error());
+	}
+
+	let result = unsafe {
+		// Safety: same as above.
+		libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC)
+	};
+
+	if result == -1 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(())
+	}
+}
+
+/// Sets a socket option whose value is a `c_int`. This is for socket options that `socket2` doesn't have a method for.
+///
+/// # Safety
+///
+/// `level` and `option` must be a valid socket option level and socket option in that level, respectively, and the socket option must expect a pointer to a `c_int`.
+#[cfg(unix)]
+pub unsafe fn set_int_sockopt(
+	socket: &Socket,
+	level: libc::c_int,
+	option: libc::c_int,
+	value: libc::c_int,
+) -> io::Result<()> {
+	let result = unsafe {
+		// Safety: `socket.as_raw_fd()` is a valid socket file descriptor. The caller guarantees that `level` and `option` are valid, and that `option` expects a pointer to a `c_int`, which `value` is. Its length is passed as the last parameter.
+		libc::setsockopt(
+			socket.as_raw_fd(),
+			level,
+			option,
+			&value as *const libc::c_int as *const _,
+			std::mem::size_of_val(&value) as libc::socklen_t,
+		)
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(())
+	}
+}
+
+/// Gets a socket option whose value is a `c_int`. This is the counterpart to [`set_int_sockopt`].
+///
+/// # Safety
+///
+/// `level` and `option` must be a valid socket option level and socket option in that level, respectively, and the socket option must produce a `c_int`.
+#[cfg(target_os = "linux")]
+pub unsafe fn get_int_sockopt(
+	socket: &Socket,
+	level: libc::c_int,
+	option: libc::c_int,
+) -> io::Result<libc::c_int> {
+	let mut value: libc::c_int = 0;
+	let mut len = std::mem::size_of_val(&value) as libc::socklen_t;
+
+	let result = unsafe {
+		// Safety: `socket.as_raw_fd()` is a valid socket file descriptor. The caller guarantees that `level` and `option` are valid, and that `option` produces a `c_int`, which `value` is. Its length is passed as the last parameter.
+		libc::getsockopt(
+			socket.as_raw_fd(),
+			level,
+			option,
+			&mut value as *mut libc::c_int as *mut _,
+			&mut len,
+		)
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(value)
+	}
+}
+
+/// Sets the UDP generic segmentation offload segment size (`UDP_SEGMENT`).
+#[cfg(target_os = "linux")]
+pub fn set_udp_segment(socket: &Socket, size: u16) -> io::Result<()> {
+	unsafe {
+		// Safety: `SOL_UDP` and `UDP_SEGMENT` are a valid socket option level and socket option in that level, respectively, and `UDP_SEGMENT` expects a pointer to a `c_int`.
+		set_int_sockopt(socket, libc::SOL_UDP, libc::UDP_SEGMENT, size.into())
+	}
+}
+
+/// Gets the UDP generic segmentation offload segment size (`UDP_SEGMENT`).
+#[cfg(target_os = "linux")]
+pub fn udp_segment(socket: &Socket) -> io::Result<u16> {
+	unsafe {
+		// Safety: `SOL_UDP` and `UDP_SEGMENT` are a valid socket option level and socket option in that level, respectively, and `UDP_SEGMENT` produces a `c_int`.
+		get_int_sockopt(socket, libc::SOL_UDP, libc::UDP_SEGMENT)
+	}
+	.map(|size| size as u16)
+}
+
+/// Enables or disables UDP generic receive offload (`UDP_GRO`).
+#[cfg(target_os = "linux")]
+pub fn set_udp_gro(socket: &Socket, enabled: bool) -> io::Result<()> {
+	unsafe {
+		// Safety: `SOL_UDP` and `UDP_GRO` are a valid socket option level and socket option in that level, respectively, and `UDP_GRO` expects a pointer to a `c_int`.
+		set_int_sockopt(socket, libc::SOL_UDP, libc::UDP_GRO, enabled.into())
+	}
+}
+
+/// Checks whether UDP generic receive offload (`UDP_GRO`) is enabled.
+#[cfg(target_os = "linux")]
+pub fn udp_gro(socket: &Socket) -> io::Result<bool> {
+	unsafe {
+		// Safety: `SOL_UDP` and `UDP_GRO` are a valid socket option level and socket option in that level, respectively, and `UDP_GRO` produces a `c_int`.
+		get_int_sockopt(socket, libc::SOL_UDP, libc::UDP_GRO)
+	}
+	.map(|enabled| enabled != 0)
+}
+
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+pub fn set_exclusive_bind(socket: &Socket, exclusive: bool) -> io::Result<()> {
+	unsafe {
+		// Safety: `SOL_SOCKET` and `SO_EXCLBIND` are a valid socket option level and socket option in that level, respectively, and `SO_EXCLBIND` expects a pointer to a `c_int`.
+		set_int_sockopt(socket, libc::SOL_SOCKET, libc::SO_EXCLBIND, exclusive.into())
+	}
+}
+
+#[cfg(any(
+	target_os = "aix",
+	target_os = "android",
+	target_os = "dragonfly",
+	target_os = "freebsd",
+	target_os = "fuchsia",
+	target_os = "illumos",
+	target_os = "ios",
+	target_os = "linux",
+	target_os = "macos",
+	target_os = "netbsd",
+	target_os = "openbsd",
+	target_os = "solaris",
+	target_os = "tvos",
+	target_os = "watchos",
+))]
+pub fn set_tclass_v6(socket: &Socket, tclass: u8) -> io::Result<()> {
+	unsafe {
+		// Safety: `IPPROTO_IPV6` and `IPV6_TCLASS` are a valid socket option level and socket option in that level, respectively, and `IPV6_TCLASS` expects a pointer to a `c_int`.
+		set_int_sockopt(socket, libc::IPPROTO_IPV6, libc::IPV6_TCLASS, tclass.into())
+	}
+}
+
+/// Sets `IP_TRANSPARENT` or `IPV6_TRANSPARENT` on a socket, depending on whether it's IPv4 or IPv6.
+#[cfg(target_os = "linux")]
+pub fn set_transparent(socket: &Socket, is_ipv6: bool) -> io::Result<()> {
+	let (level, option) = match is_ipv6 {
+		false => (libc::IPPROTO_IP, libc::IP_TRANSPARENT),
+		true => (libc::IPPROTO_IPV6, libc::IPV6_TRANSPARENT),
+	};
+
+	unsafe {
+		// Safety: `IPPROTO_IP` and `IP_TRANSPARENT`, and `IPPROTO_IPV6` and `IPV6_TRANSPARENT`, are valid socket option levels and socket options in those levels, respectively, and both options expect a pointer to a `c_int`.
+		set_int_sockopt(socket, level, option, 1)
+	}
+}
+
+/// Attaches a classic BPF program to a socket's `SO_REUSEPORT` group.
+#[cfg(target_os = "linux")]
+pub fn attach_reuse_port_cbpf(socket: &Socket, program: &[libc::sock_filter]) -> io::Result<()> {
+	let len: u16 = program.len().try_into().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "BPF program is too long"))?;
+
+	let fprog = libc::sock_fprog {
+		len,
+		filter: program.as_ptr() as *mut _,
+	};
+
+	let result = unsafe {
+		// Safety: `socket.as_raw_fd()` is a valid socket file descriptor. `SOL_SOCKET` and `SO_ATTACH_REUSEPORT_CBPF` are a valid socket option level and socket option in that level, respectively. `SO_ATTACH_REUSEPORT_CBPF` expects a pointer to a `sock_fprog`, which `fprog` is, and its length is passed as the last parameter. `fprog` points to `program`, which is `len` instructions long; the kernel only reads it.
+		libc::setsockopt(
+			socket.as_raw_fd(),
+			libc::SOL_SOCKET,
+			libc::SO_ATTACH_REUSEPORT_CBPF,
+			&fprog as *const libc::sock_fprog as *const _,
+			std::mem::size_of_val(&fprog) as libc::socklen_t,
+		)
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(())
+	}
+}
+
+/// Attaches an accept filter, such as `httpready`, to a listening socket. The socket must already be listening.
+#[cfg(target_os = "freebsd")]
+pub fn set_accept_filter(socket: &Socket, name: &str) -> io::Result<()> {
+	let mut arg: libc::accept_filter_arg = unsafe {
+		// Safety: `accept_filter_arg` consists only of `c_char` arrays, for which all zeroes is a valid value.
+		std::mem::zeroed()
+	};
+
+	// The name must fit in `af_name` with room left over for the terminating NUL.
+	if name.len() >= arg.af_name.len() || name.contains('\0') {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "accept filter name is too long or contains a NUL character"));
+	}
+
+	for (dst, src) in arg.af_name.iter_mut().zip(name.bytes()) {
+		*dst = src as libc::c_char;
+	}
+
+	let result = unsafe {
+		// Safety: `socket.as_raw_fd()` is a valid socket file descriptor. `SOL_SOCKET` and `SO_ACCEPTFILTER` are a valid socket option level and socket option in that level, respectively. `SO_ACCEPTFILTER` expects a pointer to an `accept_filter_arg`, which `arg` is, and its length is passed as the last parameter.
+		libc::setsockopt(
+			socket.as_raw_fd(),
+			libc::SOL_SOCKET,
+			libc::SO_ACCEPTFILTER,
+			&arg as *const libc::accept_filter_arg as *const _,
+			std::mem::size_of_val(&arg) as libc::socklen_t,
+		)
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(())
+	}
+}
+
+/// Binds an SCTP socket to additional local addresses, like `sctp_bindx(…, SCTP_BINDX_ADD_ADDR)`. The socket must already be bound to its primary address.
+#[cfg(target_os = "linux")]
+pub fn sctp_bindx_add(socket: &Socket, addrs: &[socket2::SockAddr]) -> io::Result<()> {
+	// These aren't in the `libc` crate, so here they are, from `linux/sctp.h`.
+	const SOL_SCTP: libc::c_int = 132;
+	const SCTP_SOCKOPT_BINDX_ADD: libc::c_int = 100;
+
+	// The socket option expects the addresses to be packed together, one after another, with no padding.
+	let mut packed_addrs: Vec<u8> = Vec::new();
+
+	for addr in addrs {
+		let addr_bytes: &[u8] = unsafe {
+			// Safety: `addr.as_ptr()` points to a valid socket address structure that is `addr.len()` bytes long.
+			std::slice::from_raw_parts(addr.as_ptr() as *const u8, addr.len() as usize)
+		};
+
+		packed_addrs.extend_from_slice(addr_bytes);
+	}
+
+	let result = unsafe {
+		// Safety:
+		//
+		// * `socket.as_raw_fd()` is a valid socket file descriptor.
+		// * `SOL_SCTP` and `SCTP_SOCKOPT_BINDX_ADD` are a valid socket option level and socket option in that level, respectively.
+		// * `packed_addrs` is a packed array of socket address structures, which is what `SCTP_SOCKOPT_BINDX_ADD` expects a pointer to, and its length is passed as the last parameter.
+		libc::setsockopt(
+			socket.as_raw_fd(),
+			SOL_SCTP,
+			SCTP_SOCKOPT_BINDX_ADD,
+			packed_addrs.as_ptr() as *const _,
+			packed_addrs.len() as libc::socklen_t,
+		)
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(())
+	}
+}
+
+/// Enables reception of `IP_PKTINFO` or `IPV6_PKTINFO` ancillary data on a datagram socket, depending on whether it's IPv4 or IPv6.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub fn set_recv_pktinfo(socket: &Socket, is_ipv6: bool) -> io::Result<()> {
+	let (level, option) = match is_ipv6 {
+		false => (libc::IPPROTO_IP, libc::IP_PKTINFO),
+		true => (libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO),
+	};
+
+	unsafe {
+		// Safety: `IPPROTO_IP` and `IP_PKTINFO`, and `IPPROTO_IPV6` and `IPV6_RECVPKTINFO`, are valid socket option levels and socket options in those levels, respectively, and both options expect a pointer to a `c_int`.
+		set_int_sockopt(socket, level, option, 1)
+	}
+}
+
+/// Buffer for ancillary data. It's aligned for `cmsghdr`, and big enough for a packet info message plus a few others that the application might have enabled.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[repr(C, align(8))]
+struct CmsgBuffer([u8; 256]);
+
+/// Receives a datagram, along with its packet info, if any.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub fn recv_pktinfo(socket: &Socket, buf: &mut [u8]) -> io::Result<(usize, socket2::SockAddr, Option<crate::PacketInfo>)> {
+	use std::net::{Ipv4Addr, Ipv6Addr};
+
+	let mut iov = libc::iovec {
+		iov_base: buf.as_mut_ptr() as *mut _,
+		iov_len: buf.len(),
+	};
+
+	let mut cmsg_buf = CmsgBuffer([0; 256]);
+
+	let ((received, msg), peer_addr) = unsafe {
+		// Safety: `try_init` passes a pointer to a zeroed `sockaddr_storage` and its length, which `recvmsg` fills in.
+		socket2::SockAddr::try_init(|storage, storage_len| {
+			// Safety: all-zeroes is a valid `msghdr`: null pointers and zero lengths.
+			let mut msg: libc::msghdr = std::mem::zeroed();
+			msg.msg_name = storage as *mut _;
+			msg.msg_namelen = *storage_len;
+			msg.msg_iov = &mut iov;
+			msg.msg_iovlen = 1;
+			msg.msg_control = cmsg_buf.0.as_mut_ptr() as *mut _;
+			msg.msg_controllen = cmsg_buf.0.len() as _;
+
+			// Safety: `socket.as_raw_fd()` is a valid socket file descriptor, and every pointer in `msg` points to a buffer of the length given alongside it.
+			let received = libc::recvmsg(socket.as_raw_fd(), &mut msg, 0);
+
+			if received < 0 {
+				return Err(io::Error::last_os_error());
+			}
+
+			*storage_len = msg.msg_namelen;
+			Ok((received as usize, msg))
+		})?
+	};
+
+	let mut info: Option<crate::PacketInfo> = None;
+
+	unsafe {
+		// Safety: `msg` was filled in by `recvmsg`, so its control buffer contains a valid sequence of `cmsghdr`s, which these macros walk through. Each message's data is read with `read_unaligned`, after checking its level and type, which determine the data's type.
+		let mut cmsg: *mut libc::cmsghdr = libc::CMSG_FIRSTHDR(&msg);
+
+		while !cmsg.is_null() {
+			let data = libc::CMSG_DATA(cmsg);
+
+			match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+				(libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+					let pktinfo = std::ptr::read_unaligned(data as *const libc::in_pktinfo);
+
+					info = Some(crate::PacketInfo::new(
+						Ipv4Addr::from(u32::from_be(pktinfo.ipi_addr.s_addr)).into(),
+						pktinfo.ipi_ifindex as u32,
+					));
+				},
+
+				(libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+					let pktinfo = std::ptr::read_unaligned(data as *const libc::in6_pktinfo);
+
+					info = Some(crate::PacketInfo::new(
+						Ipv6Addr::from(pktinfo.ipi6_addr.s6_addr).into(),
+						pktinfo.ipi6_ifindex,
+					));
+				},
+
+				_ => {},
+			}
+
+			cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+		}
+	}
+
+	Ok((received, peer_addr, info))
+}
+
+/// Sends a datagram, with packet info that selects the source address and/or interface.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub fn send_pktinfo(socket: &Socket, buf: &[u8], peer_addr: &socket2::SockAddr, info: &crate::PacketInfo, is_ipv6: bool) -> io::Result<usize> {
+	use std::net::IpAddr;
+
+	let mut iov = libc::iovec {
+		iov_base: buf.as_ptr() as *mut _,
+		iov_len: buf.len(),
+	};
+
+	let mut cmsg_buf = CmsgBuffer([0; 256]);
+
+	let (level, r#type, data_len): (libc::c_int, libc::c_int, usize) = match is_ipv6 {
+		false => (libc::IPPROTO_IP, libc::IP_PKTINFO, std::mem::size_of::<libc::in_pktinfo>()),
+		true => (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO, std::mem::size_of::<libc::in6_pktinfo>()),
+	};
+
+	let sent = unsafe {
+		// Safety: all-zeroes is a valid `msghdr`: null pointers and zero lengths.
+		let mut msg: libc::msghdr = std::mem::zeroed();
+		msg.msg_name = peer_addr.as_ptr() as *mut _;
+		msg.msg_namelen = peer_addr.len();
+		msg.msg_iov = &mut iov;
+		msg.msg_iovlen = 1;
+		msg.msg_control = cmsg_buf.0.as_mut_ptr() as *mut _;
+		msg.msg_controllen = libc::CMSG_SPACE(data_len as _) as _;
+
+		// Safety: `cmsg_buf` is suitably aligned and is big enough for one control message with `data_len` bytes of data, so `CMSG_FIRSTHDR` returns a pointer to the start of it, and the data written below fits.
+		let cmsg: *mut libc::cmsghdr = libc::CMSG_FIRSTHDR(&msg);
+		(*cmsg).cmsg_level = level;
+		(*cmsg).cmsg_type = r#type;
+		(*cmsg).cmsg_len = libc::CMSG_LEN(data_len as _) as _;
+
+		let data = libc::CMSG_DATA(cmsg);
+
+		if is_ipv6 {
+			let addr: [u8; 16] = match info.local_addr {
+				IpAddr::V4(addr) => addr.to_ipv6_mapped().octets(),
+				IpAddr::V6(addr) => addr.octets(),
+			};
+
+			std::ptr::write_unaligned(data as *mut libc::in6_pktinfo, libc::in6_pktinfo {
+				ipi6_addr: libc::in6_addr { s6_addr: addr },
+				ipi6_ifindex: info.interface,
+			});
+		}
+		else {
+			let addr: u32 = match info.local_addr {
+				IpAddr::V4(addr) => addr.into(),
+				IpAddr::V6(addr) => match addr.to_ipv4_mapped() {
+					Some(addr) => addr.into(),
+					None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "can't send from an IPv6 address on an IPv4 socket")),
+				},
+			};
+
+			std::ptr::write_unaligned(data as *mut libc::in_pktinfo, libc::in_pktinfo {
+				ipi_ifindex: info.interface as _,
+				ipi_spec_dst: libc::in_addr { s_addr: addr.to_be() },
+				ipi_addr: libc::in_addr { s_addr: 0 },
+			});
+		}
+
+		// Safety: `socket.as_raw_fd()` is a valid socket file descriptor, and every pointer in `msg` points to a buffer of the length given alongside it.
+		libc::sendmsg(socket.as_raw_fd(), &msg, 0)
+	};
+
+	if sent < 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(sent as usize)
+	}
+}
+
+/// Sends a datagram or message, to `peer_addr` if given, passing the given file descriptors along with it as `SCM_RIGHTS` ancillary data.
+pub fn send_with_fds(socket: &Socket, buf: &[u8], peer_addr: Option<&socket2::SockAddr>, fds: &[BorrowedSocket<'_>]) -> io::Result<usize> {
+	let mut iov = libc::iovec {
+		iov_base: buf.as_ptr() as *mut _,
+		iov_len: buf.len(),
+	};
+
+	let data_len: usize = std::mem::size_of_val(fds);
+
+	// Safety: `CMSG_SPACE` only does arithmetic.
+	let cmsg_space: usize = unsafe { libc::CMSG_SPACE(data_len as _) } as usize;
+
+	// A buffer of `u64`s, so that it's aligned for `cmsghdr`.
+	let mut cmsg_buf: Vec<u64> = vec![0; (cmsg_space + 7) / 8];
+
+	let sent = unsafe {
+		// Safety: all-zeroes is a valid `msghdr`: null pointers and zero lengths.
+		let mut msg: libc::msghdr = std::mem::zeroed();
+		msg.msg_iov = &mut iov;
+		msg.msg_iovlen = 1;
+
+		if let Some(peer_addr) = peer_addr {
+			msg.msg_name = peer_addr.as_ptr() as *mut _;
+			msg.msg_namelen = peer_addr.len();
+		}
+
+		if !fds.is_empty() {
+			msg.msg_control = cmsg_buf.as_mut_ptr() as *mut _;
+			msg.msg_controllen = cmsg_space as _;
+
+			// Safety: `cmsg_buf` is suitably aligned and is big enough for one control message with `data_len` bytes of data, so `CMSG_FIRSTHDR` returns a pointer to the start of it, and the data written below fits.
+			let cmsg: *mut libc::cmsghdr = libc::CMSG_FIRSTHDR(&msg);
+			(*cmsg).cmsg_level = libc::SOL_SOCKET;
+			(*cmsg).cmsg_type = libc::SCM_RIGHTS;
+			(*cmsg).cmsg_len = libc::CMSG_LEN(data_len as _) as _;
+
+			let data = libc::CMSG_DATA(cmsg) as *mut RawSocket;
+
+			for (index, fd) in fds.iter().enumerate() {
+				std::ptr::write_unaligned(data.add(index), fd.as_raw_fd());
+			}
+		}
+
+		// Safety: `socket.as_raw_fd()` is a valid socket file descriptor, and every pointer in `msg` points to a buffer of the length given alongside it.
+		libc::sendmsg(socket.as_raw_fd(), &msg, 0)
+	};
+
+	if sent < 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(sent as usize)
+	}
+}
+
+/// Receives a datagram or message, along with up to `max_fds` file descriptors passed with it as `SCM_RIGHTS` ancillary data. The received file descriptors have the `CLOEXEC` flag set.
+pub fn recv_with_fds(socket: &Socket, buf: &mut [u8], max_fds: usize) -> io::Result<(usize, Vec<OwnedSocket>)> {
+	use std::os::fd::FromRawFd;
+
+	let mut iov = libc::iovec {
+		iov_base: buf.as_mut_ptr() as *mut _,
+		iov_len: buf.len(),
+	};
+
+	// Safety: `CMSG_SPACE` only does arithmetic.
+	let cmsg_space: usize = unsafe { libc::CMSG_SPACE((max_fds * std::mem::size_of::<RawSocket>()) as _) } as usize;
+
+	// A buffer of `u64`s, so that it's aligned for `cmsghdr`.
+	let mut cmsg_buf: Vec<u64> = vec![0; (cmsg_space + 7) / 8];
+
+	cfg_if! {
+		if #[cfg(any(target_os = "android", target_os = "dragonfly", target_os = "freebsd", target_os = "linux", target_os = "netbsd", target_os = "openbsd"))] {
+			let flags: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+		}
+		else {
+			let flags: libc::c_int = 0;
+		}
+	}
+
+	let (received, msg) = unsafe {
+		// Safety: all-zeroes is a valid `msghdr`: null pointers and zero lengths.
+		let mut msg: libc::msghdr = std::mem::zeroed();
+		msg.msg_iov = &mut iov;
+		msg.msg_iovlen = 1;
+
+		if max_fds != 0 {
+			msg.msg_control = cmsg_buf.as_mut_ptr() as *mut _;
+			msg.msg_controllen = cmsg_space as _;
+		}
+
+		// Safety: `socket.as_raw_fd()` is a valid socket file descriptor, and every pointer in `msg` points to a buffer of the length given alongside it.
+		let received = libc::recvmsg(socket.as_raw_fd(), &mut msg, flags);
+
+		if received < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		(received as usize, msg)
+	};
+
+	let mut fds: Vec<OwnedSocket> = Vec::new();
+
+	unsafe {
+		// Safety: `msg` was filled in by `recvmsg`, so its control buffer contains a valid sequence of `cmsghdr`s, which these macros walk through. `SCM_RIGHTS` messages contain an array of file descriptors, which are now owned by this process.
+		let mut cmsg: *mut libc::cmsghdr = libc::CMSG_FIRSTHDR(&msg);
+
+		while !cmsg.is_null() {
+			if ((*cmsg).cmsg_level, (*cmsg).cmsg_type) == (libc::SOL_SOCKET, libc::SCM_RIGHTS) {
+				let data = libc::CMSG_DATA(cmsg) as *const RawSocket;
+				let data_len: usize = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+
+				for index in 0..data_len / std::mem::size_of::<RawSocket>() {
+					fds.push(OwnedSocket::from_raw_fd(std::ptr::read_unaligned(data.add(index))));
+				}
+			}
+
+			cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+		}
+	}
+
+	#[cfg(not(any(target_os = "android", target_os = "dragonfly", target_os = "freebsd", target_os = "linux", target_os = "netbsd", target_os = "openbsd")))]
+	for fd in &fds {
+		make_socket_inheritable(fd.as_fd(), false)?;
+	}
+
+	if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "more file descriptors were received than expected, and the extras were discarded"));
+	}
+
+	Ok((received, fds))
+}
+
+/// Retrieves the sockets that launchd created for the given socket name in the job's property list, like `launch_activate_socket`. The returned sockets are owned by the caller.
+#[cfg(target_os = "macos")]
+pub fn launch_activate_socket(name: &str) -> io::Result<Vec<OwnedSocket>> {
+	use std::{
+		ffi::CString,
+		os::fd::FromRawFd,
+	};
+
+	// This isn't in the `libc` crate, so here it is, from `launch.h`.
+	extern "C" {
+		fn launch_activate_socket(
+			name: *const libc::c_char,
+			fds: *mut *mut libc::c_int,
+			cnt: *mut libc::size_t,
+		) -> libc::c_int;
+	}
+
+	let name = CString::new(name).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+	let mut fds: *mut libc::c_int = std::ptr::null_mut();
+	let mut cnt: libc::size_t = 0;
+
+	let result = unsafe {
+		// Safety: `name` is a valid null-terminated string, and `fds` and `cnt` are valid pointers to where `launch_activate_socket` stores its results.
+		launch_activate_socket(name.as_ptr(), &mut fds, &mut cnt)
+	};
+
+	if result != 0 {
+		return Err(io::Error::from_raw_os_error(result));
+	}
+
+	if fds.is_null() || cnt == 0 {
+		return Err(io::Error::from_raw_os_error(libc::ENOENT));
+	}
+
+	let sockets: Vec<OwnedSocket> = unsafe {
+		// Safety: On success, `fds` points to a `malloc`-allocated array of `cnt` file descriptors, which the caller is responsible for closing and freeing.
+		let sockets =
+			std::slice::from_raw_parts(fds, cnt)
+			.iter()
+			.map(|fd| OwnedSocket::from_raw_fd(*fd))
+			.collect();
+
+		libc::free(fds as *mut libc::c_void);
+
+		sockets
+	};
+
+	Ok(sockets)
+}
+
+/// Gets the user ID, group ID, and (if available) process ID of the process on the other end of a connected Unix-domain socket.
+pub fn peer_credentials(socket: &Socket) -> io::Result<(libc::uid_t, libc::gid_t, Option<libc::pid_t>)> {
+	cfg_if! {
+		if #[cfg(any(target_os = "android", target_os = "linux"))] {
+			let mut ucred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+			let mut ucred_len = std::mem::size_of_val(&ucred) as libc::socklen_t;
+
+			let result = unsafe {
+				// Safety: `socket.as_raw_fd()` is a valid socket file descriptor. `SO_PEERCRED` expects a pointer to a `ucred`, which `ucred` is, and `ucred_len` is its length.
+				libc::getsockopt(
+					socket.as_raw_fd(),
+					libc::SOL_SOCKET,
+					libc::SO_PEERCRED,
+					&mut ucred as *mut libc::ucred as *mut _,
+					&mut ucred_len,
+				)
+			};
+
+			if result != 0 {
+				Err(io::Error::last_os_error())
+			}
+			else {
+				Ok((ucred.uid, ucred.gid, Some(ucred.pid)))
+			}
+		}
+		else if #[cfg(any(
+			target_os = "dragonfly",
+			target_os = "freebsd",
+			target_os = "ios",
+			target_os = "macos",
+			target_os = "netbsd",
+			target_os = "openbsd",
+		))] {
+			let mut uid: libc::uid_t = 0;
+			let mut gid: libc::gid_t = 0;
+
+			let result = unsafe {
+				// Safety: `socket.as_raw_fd()` is a valid socket file descriptor, and `uid` and `gid` are valid places to write the results to.
+				libc::getpeereid(socket.as_raw_fd(), &mut uid, &mut gid)
+			};
+
+			if result != 0 {
+				Err(io::Error::last_os_error())
+			}
+			else {
+				Ok((uid, gid, None))
+			}
+		}
+		else {
+			let _ = socket;
+			Err(io::ErrorKind::Unsupported.into())
+		}
+	}
+}
+
+/// Looks up the index of the network interface with the given name, like `if_nametoindex`.
+pub fn interface_index(name: &str) -> io::Result<u32> {
+	let name = std::ffi::CString::new(name).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+	let index = unsafe {
+		// Safety: `name` is a valid null-terminated string.
+		libc::if_nametoindex(name.as_ptr())
+	};
+
+	if index == 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(index)
+	}
+}
+
 pub fn is_unix_socket(path: &Path) -> io::Result<bool> {
 	fs::symlink_metadata(path)
 	.map(|metadata| metadata.file_type().is_socket())
 }
 
+/// Checks whether an error from `accept` means that the process or the system has run out of some resource, rather than that something went wrong with one connection.
+#[cfg(feature = "tokio")]
+pub fn is_accept_resource_error(error: &io::Error) -> bool {
+	matches!(error.raw_os_error(), Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM))
+}
+
+/// Checks whether an error from connecting a non-blocking socket means that the connection is still being established.
+pub fn is_connect_in_progress(error: &io::Error) -> bool {
+	error.raw_os_error() == Some(libc::EINPROGRESS)
+}
+
+/// Checks whether an error from creating a socket means that the address family isn't supported at all, such as IPv6 on a system with IPv6 disabled.
+pub fn is_address_family_unsupported(error: &io::Error) -> bool {
+	error.raw_os_error() == Some(libc::EAFNOSUPPORT)
+}
+
+/// Waits until at least one of `sockets` is writable or has an error, such as because a non-blocking connection attempt finished, or until `timeout` elapses. Returns, for each socket, whether it is ready.
+pub fn poll_writable(sockets: &[&Socket], timeout: Option<Duration>) -> io::Result<Vec<bool>> {
+	let mut fds: Vec<libc::pollfd> =
+		sockets.iter()
+		.map(|socket| libc::pollfd {
+			fd: socket.as_raw_fd(),
+			events: libc::POLLOUT,
+			revents: 0,
+		})
+		.collect();
+
+	let result = unsafe {
+		// Safety: `fds` is a valid array of `pollfd`, and its length is passed as the second parameter.
+		libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, poll_timeout_millis(timeout))
+	};
+
+	if result < 0 {
+		let error = io::Error::last_os_error();
+
+		// Being interrupted by a signal is the same as nothing being ready yet. The caller will just wait again.
+		if error.kind() != io::ErrorKind::Interrupted {
+			return Err(error);
+		}
+	}
+
+	Ok(fds.iter().map(|fd| result > 0 && fd.revents != 0).collect())
+}
+
+/// Converts a timeout into milliseconds for `poll`, rounding up, so that the caller doesn't wake up just before the timeout elapses and have to wait again. `None` means no timeout.
+fn poll_timeout_millis(timeout: Option<Duration>) -> libc::c_int {
+	match timeout {
+		Some(timeout) => ((timeout.as_nanos() + 999_999) / 1_000_000).try_into().unwrap_or(libc::c_int::MAX),
+		None => -1,
+	}
+}
+
 pub fn startup_socket_api() {}
 
 pub fn get_stdin_as_socket() -> Result<RawSocket, Infallible> {
@@ -86,6 +869,13 @@ pub(crate) fn get_socket_state(socket: &Socket) -> io::Result<SocketState> {
 		}
 	}
 
+	let is_listening = is_listener(socket).transpose()?;
+
+	Ok(SocketState { r#type, protocol, is_listening })
+}
+
+/// Checks whether the socket is in a listening state. Returns `None` if this platform has no way to check.
+pub(crate) fn is_listener(socket: &Socket) -> Option<io::Result<bool>> {
 	cfg_if! {
 		if #[cfg(any(
 			target_os = "aix",
@@ -94,12 +884,44 @@ pub(crate) fn get_socket_state(socket: &Socket) -> io::Result<SocketState> {
 			target_os = "fuchsia",
 			target_os = "linux",
 		))] {
-			let is_listening = Some(socket.is_listener()?);
+			Some(socket.is_listener())
+		}
+		else if #[cfg(any(
+			target_os = "dragonfly",
+			target_os = "ios",
+			target_os = "macos",
+			target_os = "netbsd",
+			target_os = "openbsd",
+		))] {
+			// `socket2` doesn't expose `SO_ACCEPTCONN` on these platforms, but they do support it, so ask directly.
+			let mut is_listening: libc::c_int = 0;
+			let mut is_listening_len = std::mem::size_of_val(&is_listening) as libc::socklen_t;
+
+			let result = unsafe {
+				// Safety:
+				//
+				// * `socket.as_raw_fd()` is a valid socket file descriptor.
+				// * `SOL_SOCKET` and `SO_ACCEPTCONN` are a valid socket option level and socket option in that level, respectively.
+				// * `is_listening` is a valid `c_int`, which is the data type that `SO_ACCEPTCONN` expects a pointer to, and `is_listening_len` is its length.
+				libc::getsockopt(
+					socket.as_raw_fd(),
+					libc::SOL_SOCKET,
+					libc::SO_ACCEPTCONN,
+					&mut is_listening as *mut libc::c_int as *mut _,
+					&mut is_listening_len,
+				)
+			};
+
+			if result != 0 {
+				Some(Err(io::Error::last_os_error()))
+			}
+			else {
+				Some(Ok(is_listening != 0))
+			}
 		}
 		else {
-			let is_listening = None;
+			let _ = socket;
+			None
 		}
 	}
-
-	Ok(SocketState { r#type, protocol, is_listening })
 }
@@ -1,30 +1,48 @@
 use crate::convert::SocketState;
 use socket2::Socket;
 use std::{
-	ffi::c_int,
+	collections::BTreeMap,
+	ffi::{c_int, c_void, OsStr, OsString},
 	fs,
 	mem,
 	net::{Ipv4Addr, UdpSocket},
 	io,
 	os::windows::{
+		ffi::OsStrExt,
 		fs::OpenOptionsExt,
-		io::{AsRawHandle, AsRawSocket},
+		io::{AsRawHandle, AsRawSocket, FromRawHandle, FromRawSocket, OwnedHandle, RawHandle},
 	},
 	path::Path,
+	process::{Child, Command},
+	ptr,
 	sync::Once,
+	time::Duration,
 };
 use windows_sys::Win32::{
 	Foundation::{
+		CloseHandle,
+		DUPLICATE_SAME_ACCESS,
+		DuplicateHandle,
+		HANDLE,
 		HANDLE_FLAG_INHERIT,
 		INVALID_HANDLE_VALUE,
 		SetHandleInformation,
 	},
+	NetworkManagement::IpHelper::if_nametoindex,
 	Networking::WinSock::{
+		FROM_PROTOCOL_INFO,
 		getsockopt,
+		INVALID_SOCKET,
+		SIO_LOOPBACK_FAST_PATH,
 		SO_ACCEPTCONN,
 		SO_PROTOCOL_INFOW,
 		SOL_SOCKET,
+		WSA_FLAG_NO_HANDLE_INHERIT,
+		WSA_FLAG_OVERLAPPED,
+		WSADuplicateSocketW,
+		WSAIoctl,
 		WSAPROTOCOL_INFOW,
+		WSASocketW,
 	},
 	Storage::FileSystem::{
 		FILE_ATTRIBUTE_REPARSE_POINT,
@@ -34,18 +52,37 @@ use windows_sys::Win32::{
 		FileAttributeTagInfo,
 		GetFileInformationByHandleEx,
 	},
-	System::Console::{GetStdHandle, STD_INPUT_HANDLE},
+	System::Console::{GetStdHandle, STD_ERROR_HANDLE, STD_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
 	System::SystemServices::IO_REPARSE_TAG_AF_UNIX,
+	System::Threading::{
+		CREATE_UNICODE_ENVIRONMENT,
+		CreateProcessW,
+		DeleteProcThreadAttributeList,
+		EXTENDED_STARTUPINFO_PRESENT,
+		GetCurrentProcess,
+		InitializeProcThreadAttributeList,
+		LPPROC_THREAD_ATTRIBUTE_LIST,
+		PROC_THREAD_ATTRIBUTE_HANDLE_LIST,
+		PROCESS_INFORMATION,
+		STARTF_USESTDHANDLES,
+		STARTUPINFOEXW,
+		UpdateProcThreadAttribute,
+	},
 };
 
 pub use std::os::windows::io::{
+	AsSocket,
 	BorrowedSocket,
 	OwnedSocket,
 	RawSocket,
 };
 
+pub fn borrow_socket<S: AsSocket + ?Sized>(socket: &S) -> BorrowedSocket<'_> {
+	socket.as_socket()
+}
+
 pub fn make_socket_inheritable(
-	socket: &Socket,
+	socket: BorrowedSocket<'_>,
 	inheritable: bool,
 ) -> io::Result<RawSocket> {
 	let handle = socket.as_raw_socket();
@@ -70,6 +107,374 @@ pub fn make_socket_inheritable(
 	}
 }
 
+/// Duplicates a socket for the process with the given ID, using `WSADuplicateSocketW`. Returns the raw bytes of the resulting `WSAPROTOCOL_INFOW` structure.
+pub fn duplicate_socket(socket: BorrowedSocket<'_>, process_id: u32) -> io::Result<Vec<u8>> {
+	let mut protocol_info: WSAPROTOCOL_INFOW = unsafe {
+		// Safety: all zeroes is a valid instance of the `WSAPROTOCOL_INFOW` type.
+		mem::zeroed()
+	};
+
+	let result = unsafe {
+		// Safety: `socket.as_raw_socket()` is a valid socket handle. `protocol_info` is a valid place to write a `WSAPROTOCOL_INFOW`.
+		WSADuplicateSocketW(
+			socket.as_raw_socket() as _,
+			process_id,
+			&mut protocol_info,
+		)
+	};
+
+	if result != 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	let bytes: &[u8] = unsafe {
+		// Safety: `protocol_info` is a fully initialized plain-data structure, so all of its bytes may be read.
+		std::slice::from_raw_parts(
+			&protocol_info as *const WSAPROTOCOL_INFOW as *const u8,
+			mem::size_of::<WSAPROTOCOL_INFOW>(),
+		)
+	};
+
+	Ok(bytes.to_vec())
+}
+
+/// Creates a socket from the raw bytes of a `WSAPROTOCOL_INFOW` structure, as produced by [`duplicate_socket`], using `WSASocketW`. The new socket's handle is not inheritable.
+pub fn socket_from_protocol_info(info: &[u8]) -> io::Result<Socket> {
+	if info.len() != mem::size_of::<WSAPROTOCOL_INFOW>() {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!("expected {} bytes of `WSAPROTOCOL_INFOW`, but got {}", mem::size_of::<WSAPROTOCOL_INFOW>(), info.len()),
+		));
+	}
+
+	let protocol_info: WSAPROTOCOL_INFOW = unsafe {
+		// Safety: `info` is exactly the size of a `WSAPROTOCOL_INFOW`, and any bit pattern is a valid instance of that type. It might not be suitably aligned, hence `read_unaligned`.
+		ptr::read_unaligned(info.as_ptr() as *const WSAPROTOCOL_INFOW)
+	};
+
+	let handle = unsafe {
+		// Safety: `FROM_PROTOCOL_INFO` tells `WSASocketW` to take the address family, type, and protocol from `protocol_info`, which is a valid `WSAPROTOCOL_INFOW`. 0 is a valid socket group. The flags are valid socket creation flags.
+		WSASocketW(
+			FROM_PROTOCOL_INFO,
+			FROM_PROTOCOL_INFO,
+			FROM_PROTOCOL_INFO,
+			&protocol_info,
+			0,
+			WSA_FLAG_OVERLAPPED | WSA_FLAG_NO_HANDLE_INHERIT,
+		)
+	};
+
+	if handle == INVALID_SOCKET {
+		return Err(io::Error::last_os_error());
+	}
+
+	Ok(unsafe {
+		// Safety: `handle` is a new, valid socket handle, which nothing else owns.
+		Socket::from_raw_socket(handle as RawSocket)
+	})
+}
+
+/// Spawns a child process with `CreateProcessW`, using `PROC_THREAD_ATTRIBUTE_HANDLE_LIST` so that it inherits only the given handles and its standard input, output, and error. The given handles must already be inheritable.
+///
+/// The child's standard handles are inheritable duplicates of this process's standard handles, which are closed again after the child has been created.
+pub fn spawn_with_handle_list(command: &Command, handles: &[RawHandle]) -> io::Result<Child> {
+	let mut command_line: Vec<u16> = Vec::new();
+	append_arg(&mut command_line, command.get_program(), true)?;
+
+	for arg in command.get_args() {
+		command_line.push(u16::from(b' '));
+		append_arg(&mut command_line, arg, false)?;
+	}
+
+	command_line.push(0);
+
+	let env: Option<Vec<u16>> = make_env_block(command)?;
+
+	let current_dir: Option<Vec<u16>> =
+		command.get_current_dir()
+		.map(|dir| to_wide_nul(dir.as_os_str()))
+		.transpose()?;
+
+	let std_handles: [Option<OwnedHandle>; 3] = [
+		inheritable_std_handle(STD_INPUT_HANDLE)?,
+		inheritable_std_handle(STD_OUTPUT_HANDLE)?,
+		inheritable_std_handle(STD_ERROR_HANDLE)?,
+	];
+
+	let mut handle_list: Vec<HANDLE> =
+		handles.iter()
+		.map(|handle| *handle as HANDLE)
+		.chain(std_handles.iter().flatten().map(|handle| handle.as_raw_handle() as HANDLE))
+		.collect();
+
+	handle_list.sort_unstable();
+	handle_list.dedup();
+
+	let mut attribute_list = ProcThreadAttributeList::new()?;
+
+	if !handle_list.is_empty() {
+		let result = unsafe {
+			// Safety: `attribute_list` was initialized with room for one attribute. `PROC_THREAD_ATTRIBUTE_HANDLE_LIST` expects an array of handles, which `handle_list` is, and its size in bytes is passed as the next parameter. `handle_list` outlives the call to `CreateProcessW` below, as the attribute list requires.
+			UpdateProcThreadAttribute(
+				attribute_list.as_ptr(),
+				0,
+				PROC_THREAD_ATTRIBUTE_HANDLE_LIST as usize,
+				handle_list.as_ptr() as *const c_void,
+				mem::size_of_val(handle_list.as_slice()),
+				ptr::null_mut(),
+				ptr::null(),
+			)
+		};
+
+		if result == 0 {
+			return Err(io::Error::last_os_error());
+		}
+	}
+
+	let mut startup_info: STARTUPINFOEXW = unsafe {
+		// Safety: all zeroes is a valid instance of the `STARTUPINFOEXW` type.
+		mem::zeroed()
+	};
+
+	let raw_std_handle = |handle: &Option<OwnedHandle>| -> HANDLE {
+		handle.as_ref().map_or(0, |handle| handle.as_raw_handle() as HANDLE)
+	};
+
+	startup_info.StartupInfo.cb = mem::size_of::<STARTUPINFOEXW>() as u32;
+	startup_info.StartupInfo.dwFlags = STARTF_USESTDHANDLES;
+	startup_info.StartupInfo.hStdInput = raw_std_handle(&std_handles[0]);
+	startup_info.StartupInfo.hStdOutput = raw_std_handle(&std_handles[1]);
+	startup_info.StartupInfo.hStdError = raw_std_handle(&std_handles[2]);
+	startup_info.lpAttributeList = attribute_list.as_ptr();
+
+	let mut process_info: PROCESS_INFORMATION = unsafe {
+		// Safety: all zeroes is a valid instance of the `PROCESS_INFORMATION` type.
+		mem::zeroed()
+	};
+
+	let result = unsafe {
+		// Safety:
+		//
+		// * A null application name tells `CreateProcessW` to take the program from the command line.
+		// * `command_line` is a mutable, null-terminated wide string, as `CreateProcessW` requires.
+		// * Null process and thread attributes are valid.
+		// * `env` is a valid Unicode environment block, as indicated by `CREATE_UNICODE_ENVIRONMENT`, or null to inherit this process's environment.
+		// * `current_dir` is a null-terminated wide string, or null to inherit this process's current directory.
+		// * `startup_info` is a valid `STARTUPINFOEXW`, as indicated by `EXTENDED_STARTUPINFO_PRESENT`.
+		// * `process_info` is a valid place to write a `PROCESS_INFORMATION`.
+		CreateProcessW(
+			ptr::null(),
+			command_line.as_mut_ptr(),
+			ptr::null(),
+			ptr::null(),
+			(!handle_list.is_empty()).into(),
+			EXTENDED_STARTUPINFO_PRESENT | CREATE_UNICODE_ENVIRONMENT,
+			env.as_ref().map_or(ptr::null(), |env| env.as_ptr() as *const c_void),
+			current_dir.as_ref().map_or(ptr::null(), |dir| dir.as_ptr()),
+			&startup_info.StartupInfo,
+			&mut process_info,
+		)
+	};
+
+	if result == 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	unsafe {
+		// Safety: `process_info.hThread` is a valid handle that nothing else owns, and it isn't needed.
+		CloseHandle(process_info.hThread);
+	}
+
+	Ok(unsafe {
+		// Safety: `process_info.hProcess` is a valid process handle that nothing else owns.
+		Child::from_raw_handle(process_info.hProcess as RawHandle)
+	})
+}
+
+/// A `PROC_THREAD_ATTRIBUTE_LIST` with room for one attribute, which is deleted when dropped.
+struct ProcThreadAttributeList {
+	/// The memory for the list. It's made of `usize`s to make sure it's suitably aligned.
+	buffer: Vec<usize>,
+}
+
+impl ProcThreadAttributeList {
+	fn new() -> io::Result<Self> {
+		let mut size: usize = 0;
+
+		unsafe {
+			// Safety: with a null list, this only writes the needed size to `size`. It's expected to fail with `ERROR_INSUFFICIENT_BUFFER`.
+			InitializeProcThreadAttributeList(ptr::null_mut(), 1, 0, &mut size);
+		}
+
+		let mut list = Self {
+			buffer: vec![0; (size + mem::size_of::<usize>() - 1) / mem::size_of::<usize>()],
+		};
+
+		let result = unsafe {
+			// Safety: `list.buffer` is at least `size` bytes long.
+			InitializeProcThreadAttributeList(list.buffer.as_mut_ptr() as LPPROC_THREAD_ATTRIBUTE_LIST, 1, 0, &mut size)
+		};
+
+		if result == 0 {
+			// Don't try to delete the list if it wasn't initialized.
+			list.buffer = Vec::new();
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(list)
+	}
+
+	fn as_ptr(&mut self) -> LPPROC_THREAD_ATTRIBUTE_LIST {
+		self.buffer.as_mut_ptr() as LPPROC_THREAD_ATTRIBUTE_LIST
+	}
+}
+
+impl Drop for ProcThreadAttributeList {
+	fn drop(&mut self) {
+		if !self.buffer.is_empty() {
+			unsafe {
+				// Safety: the list was initialized by `new`.
+				DeleteProcThreadAttributeList(self.as_ptr());
+			}
+		}
+	}
+}
+
+/// Makes an inheritable duplicate of one of this process's standard handles. Returns `None` if this process doesn't have that standard handle.
+fn inheritable_std_handle(which: STD_HANDLE) -> io::Result<Option<OwnedHandle>> {
+	let handle: HANDLE = unsafe {
+		// Safety: `which` is a valid standard device identifier.
+		GetStdHandle(which)
+	};
+
+	if handle == 0 || handle == INVALID_HANDLE_VALUE {
+		return Ok(None);
+	}
+
+	let mut duplicate: HANDLE = 0;
+
+	let result = unsafe {
+		// Safety: `GetCurrentProcess` returns a pseudo-handle for this process, which is valid for both the source and target process. `handle` is a valid handle. `duplicate` is a valid place to write the new handle.
+		DuplicateHandle(
+			GetCurrentProcess(),
+			handle,
+			GetCurrentProcess(),
+			&mut duplicate,
+			0,
+			1,
+			DUPLICATE_SAME_ACCESS,
+		)
+	};
+
+	if result == 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	Ok(Some(unsafe {
+		// Safety: `duplicate` is a new, valid handle, which nothing else owns.
+		OwnedHandle::from_raw_handle(duplicate as RawHandle)
+	}))
+}
+
+/// Converts `s` to a null-terminated wide string. Fails if `s` contains a null character.
+fn to_wide_nul(s: &OsStr) -> io::Result<Vec<u16>> {
+	let mut wide: Vec<u16> = s.encode_wide().collect();
+
+	if wide.contains(&0) {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "strings passed to a child process must not contain null characters"));
+	}
+
+	wide.push(0);
+	Ok(wide)
+}
+
+/// Appends `arg` to `command_line`, quoted the way that the Microsoft C runtime expects to parse it, as [`Command`] does.
+fn append_arg(command_line: &mut Vec<u16>, arg: &OsStr, force_quotes: bool) -> io::Result<()> {
+	const BACKSLASH: u16 = b'\\' as u16;
+	const QUOTE: u16 = b'"' as u16;
+
+	let arg: Vec<u16> = to_wide_nul(arg)?;
+	let arg: &[u16] = &arg[..arg.len() - 1];
+
+	let quote: bool = force_quotes || arg.is_empty() || arg.iter().any(|&c| c == u16::from(b' ') || c == u16::from(b'\t'));
+
+	if quote {
+		command_line.push(QUOTE);
+	}
+
+	let mut backslashes: usize = 0;
+
+	for &c in arg {
+		if c == BACKSLASH {
+			backslashes += 1;
+		}
+		else {
+			if c == QUOTE {
+				// Backslashes before a quote, and the quote itself, have to be escaped.
+				command_line.extend(std::iter::repeat(BACKSLASH).take(backslashes + 1));
+			}
+
+			backslashes = 0;
+		}
+
+		command_line.push(c);
+	}
+
+	if quote {
+		// Backslashes before the closing quote have to be escaped too.
+		command_line.extend(std::iter::repeat(BACKSLASH).take(backslashes));
+		command_line.push(QUOTE);
+	}
+
+	Ok(())
+}
+
+/// Makes the environment block for a child process: this process's environment, with the changes made by `Command::env` and `Command::env_remove` applied. Returns `None` if there are no changes, in which case the child simply inherits this process's environment.
+fn make_env_block(command: &Command) -> io::Result<Option<Vec<u16>>> {
+	if command.get_envs().len() == 0 {
+		return Ok(None);
+	}
+
+	// Environment variable names are not case sensitive on Windows, so key the variables by their upper-case names. That also puts them in the order that `CreateProcessW` expects.
+	let key = |name: &OsStr| -> Vec<u16> {
+		name.encode_wide()
+		.map(|c| match u8::try_from(c) {
+			Ok(c) => u16::from(c.to_ascii_uppercase()),
+			Err(_) => c,
+		})
+		.collect()
+	};
+
+	let mut env: BTreeMap<Vec<u16>, (OsString, OsString)> =
+		std::env::vars_os()
+		.map(|(name, value)| (key(&name), (name, value)))
+		.collect();
+
+	for (name, value) in command.get_envs() {
+		match value {
+			Some(value) => env.insert(key(name), (name.to_owned(), value.to_owned())),
+			None => env.remove(&key(name)),
+		};
+	}
+
+	let mut block: Vec<u16> = Vec::new();
+
+	for (name, value) in env.values() {
+		let name: Vec<u16> = to_wide_nul(name)?;
+		block.extend(&name[..name.len() - 1]);
+		block.push(u16::from(b'='));
+		block.extend(to_wide_nul(value)?);
+	}
+
+	if block.is_empty() {
+		block.push(0);
+	}
+
+	block.push(0);
+
+	Ok(Some(block))
+}
+
 pub fn is_unix_socket(path: &Path) -> io::Result<bool> {
 	// On Windows, Unix-domain sockets appear in the file system as a kind of reparse point. The Rust standard library has code to figure out what kind of reparse point the file is, but it doesn't actually expose that information, so we're going to have to do it ourselves.
 
@@ -117,6 +522,101 @@ pub fn is_unix_socket(path: &Path) -> io::Result<bool> {
 	Ok(is_unix_socket)
 }
 
+pub fn interface_index(name: &str) -> io::Result<u32> {
+	let name = std::ffi::CString::new(name).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+	let index = unsafe {
+		// Safety: `name` is a valid null-terminated string.
+		if_nametoindex(name.as_ptr() as _)
+	};
+
+	// `if_nametoindex` doesn't set an error code on Windows.
+	if index == 0 {
+		Err(io::Error::new(io::ErrorKind::NotFound, "no network interface with that name"))
+	}
+	else {
+		Ok(index)
+	}
+}
+
+/// Enables the TCP loopback fast path on a socket, using the `SIO_LOOPBACK_FAST_PATH` control code.
+pub fn set_loopback_fast_path(socket: &Socket) -> io::Result<()> {
+	let enabled: u32 = 1;
+	let mut bytes_returned: u32 = 0;
+
+	let result = unsafe {
+		// Safety: `socket.as_raw_socket()` is a valid socket handle. `SIO_LOOPBACK_FAST_PATH` expects an input buffer containing a 32-bit integer, which `enabled` is, and its size is passed as the next parameter. It has no output, so the output buffer is null with size zero. `bytes_returned` is a valid place to write the number of output bytes. The operation is not overlapped, so the last two parameters are null.
+		WSAIoctl(
+			socket.as_raw_socket() as _,
+			SIO_LOOPBACK_FAST_PATH,
+			&enabled as *const u32 as *const _,
+			mem::size_of_val(&enabled) as u32,
+			ptr::null_mut(),
+			0,
+			&mut bytes_returned,
+			ptr::null_mut(),
+			None,
+		)
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(())
+	}
+}
+
+/// Checks whether an error from `accept` means that the process or the system has run out of some resource, rather than that something went wrong with one connection.
+#[cfg(feature = "tokio")]
+pub fn is_accept_resource_error(error: &io::Error) -> bool {
+	use windows_sys::Win32::Networking::WinSock::{WSAEMFILE, WSAENOBUFS};
+
+	matches!(error.raw_os_error(), Some(code) if code == WSAEMFILE || code == WSAENOBUFS)
+}
+
+/// Checks whether an error from connecting a non-blocking socket means that the connection is still being established.
+pub fn is_connect_in_progress(error: &io::Error) -> bool {
+	error.raw_os_error() == Some(windows_sys::Win32::Networking::WinSock::WSAEWOULDBLOCK)
+}
+
+/// Checks whether an error from creating a socket means that the address family isn't supported at all, such as IPv6 on a system with IPv6 disabled.
+pub fn is_address_family_unsupported(error: &io::Error) -> bool {
+	error.raw_os_error() == Some(windows_sys::Win32::Networking::WinSock::WSAEAFNOSUPPORT)
+}
+
+/// Waits until at least one of `sockets` is writable or has an error, such as because a non-blocking connection attempt finished, or until `timeout` elapses. Returns, for each socket, whether it is ready.
+///
+/// Versions of Windows older than Windows 10 version 2004 have a bug where `WSAPoll` doesn't report a failed connection attempt. On those versions, a failed attempt is only noticed when it times out.
+pub fn poll_writable(sockets: &[&Socket], timeout: Option<Duration>) -> io::Result<Vec<bool>> {
+	use windows_sys::Win32::Networking::WinSock::{POLLWRNORM, WSAPoll, WSAPOLLFD};
+
+	let mut fds: Vec<WSAPOLLFD> =
+		sockets.iter()
+		.map(|socket| WSAPOLLFD {
+			fd: socket.as_raw_socket() as _,
+			events: POLLWRNORM,
+			revents: 0,
+		})
+		.collect();
+
+	let timeout: i32 = match timeout {
+		Some(timeout) => ((timeout.as_nanos() + 999_999) / 1_000_000).try_into().unwrap_or(i32::MAX),
+		None => -1,
+	};
+
+	let result = unsafe {
+		// Safety: `fds` is a valid array of `WSAPOLLFD`, and its length is passed as the second parameter.
+		WSAPoll(fds.as_mut_ptr(), fds.len() as u32, timeout)
+	};
+
+	if result < 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	Ok(fds.iter().map(|fd| fd.revents != 0).collect())
+}
+
 pub fn startup_socket_api() {
 	static ONCE: Once = Once::new();
 
@@ -167,7 +667,13 @@ pub(crate) fn get_socket_state(socket: &Socket) -> io::Result<SocketState> {
 
 	let r#type = socket2::Type::from(protocol_info.iSocketType);
 	let protocol = Some(socket2::Protocol::from(protocol_info.iProtocol));
+	let is_listening = is_listener(socket).transpose()?;
+
+	Ok(SocketState { r#type, protocol, is_listening })
+}
 
+/// Checks whether the socket is in a listening state. Always returns `Some` on this platform.
+pub(crate) fn is_listener(socket: &Socket) -> Option<io::Result<bool>> {
 	let mut is_listening_dword: u32 = 0;
 	let mut is_listening_dword_len: c_int = mem::size_of_val(&is_listening_dword).try_into().unwrap();
 
@@ -187,10 +693,8 @@ pub(crate) fn get_socket_state(socket: &Socket) -> io::Result<SocketState> {
 	};
 
 	if getsockopt_result != 0 {
-		return Err(io::Error::last_os_error());
+		return Some(Err(io::Error::last_os_error()));
 	}
 
-	let is_listening = Some(is_listening_dword != 0);
-
-	Ok(SocketState { r#type, protocol, is_listening })
+	Some(Ok(is_listening_dword != 0))
 }
@@ -0,0 +1,216 @@
+//! Service status notifications for systemd.
+//!
+//! A systemd service with `Type=notify` (or `Type=notify-reload`) tells systemd when it has finished starting up by sending it a message over a Unix-domain datagram socket, whose address systemd passes in the `NOTIFY_SOCKET` environment variable. This module implements the sending side of that protocol. See [the systemd documentation](https://www.freedesktop.org/software/systemd/man/sd_notify.html) for details.
+//!
+//! A server would typically open all of its sockets with [`open_multiple`][crate::open_multiple()], finish any other initialization, and then call [`notify_ready`]. Until then, connections to sockets inherited from systemd wait to be accepted, and services that depend on this one wait to be started.
+//!
+//! # File descriptor store
+//!
+//! A service can also hand its listening sockets to systemd for safekeeping with [`store_fds`], so that they survive the service being restarted, and no incoming connections are refused while it is. This requires `FileDescriptorStoreMax=` to be set in the service unit. On the next start, systemd passes the stored sockets back using the socket activation protocol, along with any from socket units, under the names they were stored with. They can then be opened with a <code>systemd-name:<var>name</var></code> address, like any other socket inherited from systemd, or all at once with [`SocketAddr::all_systemd_named`].
+//!
+//! When systemd didn't start the process (or started it with a service type other than `Type=notify`), `NOTIFY_SOCKET` is not set, and the functions in this module do nothing. It is therefore safe to call them unconditionally.
+//!
+//! # Availability
+//!
+//! Unix-like platforms only. Abstract `NOTIFY_SOCKET` addresses (ones starting with `@`) are only supported on Android and Linux.
+
+use crate::sys;
+use socket2::{Domain, SockAddr, Socket, Type};
+use std::{
+	ffi::OsStr,
+	io,
+	os::{
+		fd::{AsFd, BorrowedFd},
+		unix::ffi::OsStrExt,
+	},
+};
+
+#[cfg(doc)]
+use crate::SocketAddr;
+
+/// Sends a notification to the service manager, if there is one.
+///
+/// `state` is one or more newline-separated variable assignments, such as `READY=1` or `STATUS=Processing requests`. The other functions in this module send the most common ones.
+///
+/// Returns `Ok(true)` if the notification was sent, or `Ok(false)` if the `NOTIFY_SOCKET` environment variable isn't set, which means there is no service manager to notify.
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] if `NOTIFY_SOCKET` is not a valid socket address, with [`io::ErrorKind::Unsupported`] if it is an abstract socket address on a platform that doesn't support them, or with any I/O error raised by the operating system in sending the notification.
+pub fn notify(state: &str) -> io::Result<bool> {
+	match std::env::var_os("NOTIFY_SOCKET") {
+		Some(notify_socket) => notify_to(&notify_socket, state, &[]).map(|()| true),
+		None => Ok(false),
+	}
+}
+
+/// Tells the service manager that the service has finished starting up, or finished reloading its configuration, by sending `READY=1`.
+///
+/// See [`notify`] for the return value and errors.
+pub fn notify_ready() -> io::Result<bool> {
+	notify("READY=1")
+}
+
+/// Tells the service manager that the service is reloading its configuration, by sending `RELOADING=1`. Call [`notify_ready`] when done.
+///
+/// The current time on the monotonic clock is sent along with it (as `MONOTONIC_USEC`), which `Type=notify-reload` services are required to do.
+///
+/// See [`notify`] for the return value and errors.
+pub fn notify_reloading() -> io::Result<bool> {
+	let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+
+	// Safety: `now` is a valid `timespec` for the duration of the call.
+	if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) } != 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	let now_usec: u64 = (now.tv_sec as u64) * 1_000_000 + (now.tv_nsec as u64) / 1_000;
+
+	notify(&format!("RELOADING=1\nMONOTONIC_USEC={now_usec}"))
+}
+
+/// Tells the service manager that the service is shutting down, by sending `STOPPING=1`.
+///
+/// See [`notify`] for the return value and errors.
+pub fn notify_stopping() -> io::Result<bool> {
+	notify("STOPPING=1")
+}
+
+/// Sets the status text shown by `systemctl status`, by sending <code>STATUS=<var>status</var></code>.
+///
+/// See [`notify`] for the return value and errors. This also fails with [`io::ErrorKind::InvalidInput`] if `status` contains a line break.
+pub fn notify_status(status: &str) -> io::Result<bool> {
+	if status.contains('\n') {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "the status text must not contain a line break"));
+	}
+
+	notify(&format!("STATUS={status}"))
+}
+
+/// Sends the given sockets to the service manager's file descriptor store, if there is a service manager.
+///
+/// Each socket is paired with the name to store it under, which becomes its name in `LISTEN_FDNAMES` when systemd passes it back. Names must be 1 to 255 printable ASCII characters, and must not contain `:`. More than one socket can be stored under the same name.
+///
+/// The sockets are duplicated, not moved; this process can keep using them.
+///
+/// See [`notify`] for the return value and errors. This also fails with [`io::ErrorKind::InvalidInput`] if a name is invalid, in which case none of the sockets are sent.
+pub fn store_fds<S: AsFd>(sockets: &[(S, &str)]) -> io::Result<bool> {
+	for (_, name) in sockets {
+		crate::spawn::validate_name(name)?;
+	}
+
+	let Some(notify_socket) = std::env::var_os("NOTIFY_SOCKET") else {
+		return Ok(false);
+	};
+
+	// Each name applies to every file descriptor in the same message, so send one message per socket.
+	for (socket, name) in sockets {
+		notify_to(&notify_socket, &format!("FDSTORE=1\nFDNAME={name}"), &[socket.as_fd()])?;
+	}
+
+	Ok(true)
+}
+
+/// Removes all sockets stored under the given name from the service manager's file descriptor store, if there is a service manager.
+///
+/// See [`notify`] for the return value and errors. This also fails with [`io::ErrorKind::InvalidInput`] if the name is invalid.
+pub fn remove_stored_fds(name: &str) -> io::Result<bool> {
+	crate::spawn::validate_name(name)?;
+
+	notify(&format!("FDSTOREREMOVE=1\nFDNAME={name}"))
+}
+
+/// Sends `state`, along with the file descriptors `fds`, to the socket at `notify_socket`, which is in the format of the `NOTIFY_SOCKET` environment variable.
+fn notify_to(notify_socket: &OsStr, state: &str, fds: &[BorrowedFd<'_>]) -> io::Result<()> {
+	let addr: SockAddr = notify_socket_addr(notify_socket)?;
+
+	let socket = Socket::new(Domain::UNIX, Type::DGRAM, None)?;
+	let sent: usize = sys::send_with_fds(&socket, state.as_bytes(), Some(&addr), fds)?;
+
+	if sent != state.len() {
+		return Err(io::Error::new(io::ErrorKind::WriteZero, "the notification was only partially sent"));
+	}
+
+	Ok(())
+}
+
+/// Parses the `NOTIFY_SOCKET` environment variable, which is either an absolute path or, if it starts with `@`, the name of an abstract socket.
+fn notify_socket_addr(notify_socket: &OsStr) -> io::Result<SockAddr> {
+	match notify_socket.as_bytes() {
+		[b'/', ..] => SockAddr::unix(notify_socket),
+
+		#[cfg(any(target_os = "android", target_os = "linux"))]
+		[b'@', name @ ..] => {
+			let name: &str =
+				std::str::from_utf8(name)
+				.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "`NOTIFY_SOCKET` is not valid UTF-8"))?;
+
+			crate::open::unix_abstract_sockaddr(name)
+			.map_err(io::Error::from)
+		},
+
+		#[cfg(not(any(target_os = "android", target_os = "linux")))]
+		[b'@', ..] => Err(io::Error::new(io::ErrorKind::Unsupported, "abstract `NOTIFY_SOCKET` addresses are not supported on this platform")),
+
+		_ => Err(io::Error::new(io::ErrorKind::InvalidInput, "`NOTIFY_SOCKET` is not an absolute path or abstract socket name")),
+	}
+}
+
+#[test]
+fn test_notify() {
+	use std::os::unix::net::UnixDatagram;
+
+	let path = std::env::temp_dir().join(format!("socket_config-test-notify-{}.socket", std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	let receiver = UnixDatagram::bind(&path).unwrap();
+
+	let mut buf = [0u8; 64];
+
+	notify_to(path.as_os_str(), "READY=1\nSTATUS=Ready", &[]).unwrap();
+	let received: usize = receiver.recv(&mut buf).unwrap();
+	assert_eq!(&buf[..received], b"READY=1\nSTATUS=Ready");
+
+	std::fs::remove_file(&path).unwrap();
+
+	#[cfg(target_os = "linux")] {
+		use std::os::linux::net::SocketAddrExt;
+
+		let name = format!("socket_config-test-notify-{}", std::process::id());
+		let receiver = UnixDatagram::bind_addr(&std::os::unix::net::SocketAddr::from_abstract_name(&name).unwrap()).unwrap();
+
+		notify_to(OsStr::new(&format!("@{name}")), "STOPPING=1", &[]).unwrap();
+		let received: usize = receiver.recv(&mut buf).unwrap();
+		assert_eq!(&buf[..received], b"STOPPING=1");
+	}
+
+	assert_eq!(notify_to(OsStr::new("relative.socket"), "READY=1", &[]).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+	assert_eq!(notify_status("a\nb").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_store_fds() {
+	use std::os::unix::net::UnixDatagram;
+
+	let path = std::env::temp_dir().join(format!("socket_config-test-store-fds-{}.socket", std::process::id()));
+	let _ = std::fs::remove_file(&path);
+	let receiver = Socket::from(std::os::fd::OwnedFd::from(UnixDatagram::bind(&path).unwrap()));
+
+	let listener = std::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).unwrap();
+	notify_to(path.as_os_str(), "FDSTORE=1\nFDNAME=http", &[listener.as_fd()]).unwrap();
+
+	let mut buf = [std::mem::MaybeUninit::new(0u8); 64];
+	let mut buf_slice = [socket2::MaybeUninitSlice::new(&mut buf)];
+	let mut cmsg_buf = [std::mem::MaybeUninit::new(0u8); 64];
+	let mut msg = socket2::MsgHdrMut::new().with_buffers(&mut buf_slice).with_control(&mut cmsg_buf);
+	let received: usize = receiver.recvmsg(&mut msg, 0).unwrap();
+	assert!(msg.control_len() > 0, "no file descriptor was received");
+
+	// Safety: the first `received` bytes were initialized by `recvmsg`.
+	let received: &[u8] = unsafe { std::slice::from_raw_parts(buf.as_ptr() as *const u8, received) };
+	assert_eq!(received, b"FDSTORE=1\nFDNAME=http");
+
+	std::fs::remove_file(&path).unwrap();
+
+	assert_eq!(store_fds(&[(listener.as_fd(), "a:b")]).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+	assert_eq!(remove_stored_fds("").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,60 @@
+use crate::errors::OpenSocketError;
+use std::{
+	fs::{File, OpenOptions},
+	io,
+	os::unix::io::AsRawFd,
+	path::{Path, PathBuf},
+	sync::{Mutex, PoisonError},
+};
+
+#[cfg(doc)]
+use crate::SocketUserOptions;
+
+/// Lock files that this process has locked, along with the paths of the sockets that they're for. They stay open, and therefore locked, until the process exits.
+static HELD_LOCKS: Mutex<Vec<(PathBuf, File)>> = Mutex::new(Vec::new());
+
+/// Returns the path of the lock file for the socket at `socket_path`, which is the socket path with `.lock` appended.
+pub fn lock_path(socket_path: &Path) -> PathBuf {
+	let mut path = socket_path.as_os_str().to_owned();
+	path.push(".lock");
+	path.into()
+}
+
+/// Locks the lock file for the socket at `socket_path`, creating it if needed. See [`SocketUserOptions::unix_socket_lock_file`].
+///
+/// If this process already holds the lock, this does nothing.
+pub fn lock(socket_path: &Path) -> Result<(), OpenSocketError> {
+	let mut held_locks = HELD_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+
+	if held_locks.iter().any(|(path, _)| path == socket_path) {
+		return Ok(());
+	}
+
+	let lock_path: PathBuf = lock_path(socket_path);
+
+	let file: File =
+		OpenOptions::new()
+		.read(true)
+		.write(true)
+		.create(true)
+		.truncate(false)
+		.open(&lock_path)
+		.map_err(|error| OpenSocketError::LockFile { error })?;
+
+	let result = unsafe {
+		// Safety: `file` is an open file.
+		libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB)
+	};
+
+	if result != 0 {
+		let error = io::Error::last_os_error();
+
+		return Err(match error.kind() {
+			io::ErrorKind::WouldBlock => OpenSocketError::UnixSocketInUse { lock_path },
+			_ => OpenSocketError::LockFile { error },
+		});
+	}
+
+	held_locks.push((socket_path.to_owned(), file));
+	Ok(())
+}
@@ -1,78 +1,82 @@
 use crate::{
 	errors::OpenSocketError,
 	SocketUserOptions,
-	util::check_inapplicable,
+	util::{check_inapplicable, check_inapplicable_bool},
 };
-use nix::unistd::chown;
-use socket2::Socket;
+use libc::mode_t;
+use nix::{
+	sys::stat::{Mode, umask},
+	unistd::chown,
+};
+use socket2::{SockAddr, Socket};
 use std::{
 	fs,
+	io,
 	os::unix::fs::PermissionsExt,
 	path::Path,
+	sync::Mutex,
 };
 
-#[cfg(any(feature = "clap", feature = "serde"))]
-mod parse_common {
-	use libc::{gid_t, mode_t, uid_t};
-	use nix::{
-		sys::stat::Mode,
-		unistd::{Gid, Uid},
-	};
-
-	#[cfg(not(target_os = "redox"))]
-	use nix::unistd::{Group, User};
+#[derive(Debug, thiserror::Error)]
+#[error("unrecognized character in `unix_socket_permissions` (only the letters `u`, `g`, and `o`, or an octal mode number, are recognized)")]
+pub struct UnixSocketPermissionsParseError;
 
-	#[derive(Debug, thiserror::Error)]
-	#[error("unrecognized character in `unix_socket_permissions` (only the letters `u`, `g`, and `o`, or an octal mode number, are recognized)")]
-	pub struct UnixSocketPermissionsParseError;
-
-	pub fn parse_mode(mode_str: &str) -> Result<Mode, UnixSocketPermissionsParseError> {
-		if let Ok(i) = mode_t::from_str_radix(mode_str, 8) {
-			Mode::from_bits(i)
-			.ok_or(UnixSocketPermissionsParseError)
+pub fn parse_mode(mode_str: &str) -> Result<Mode, UnixSocketPermissionsParseError> {
+	if let Ok(i) = mode_t::from_str_radix(mode_str, 8) {
+		Mode::from_bits(i)
+		.ok_or(UnixSocketPermissionsParseError)
+	}
+	else {
+		let mut mode = Mode::empty();
+
+		for byte in mode_str.bytes() {
+			mode |= match byte {
+				b'-' => Mode::empty(),
+				b'u' => Mode::S_IRUSR | Mode::S_IWUSR,
+				b'g' => Mode::S_IRGRP | Mode::S_IWGRP,
+				b'o' => Mode::S_IROTH | Mode::S_IWOTH,
+				_ => return Err(UnixSocketPermissionsParseError),
+			};
 		}
-		else {
-			let mut mode = Mode::empty();
-
-			for byte in mode_str.bytes() {
-				mode |= match byte {
-					b'-' => Mode::empty(),
-					b'u' => Mode::S_IRUSR | Mode::S_IWUSR,
-					b'g' => Mode::S_IRGRP | Mode::S_IWGRP,
-					b'o' => Mode::S_IROTH | Mode::S_IWOTH,
-					_ => return Err(UnixSocketPermissionsParseError),
-				};
-			}
 
-			Ok(mode)
-		}
+		Ok(mode)
 	}
+}
 
-	#[test]
-	fn test_parse_mode() {
-		let _ = parse_mode("77777").unwrap_err();
-
-		for (string, bits) in [
-			("", 0),
-			("-", 0),
-			("0", 0),
-			("420", 0o420),
-			("u", 0o600),
-			("g", 0o060),
-			("ug", 0o660),
-			("o", 0o006),
-			("uo", 0o606),
-			("go", 0o066),
-			("ugo", 0o666),
-			("-u-g-o-", 0o666),
-		] {
-			assert_eq!(
-				parse_mode(string).unwrap().bits(),
-				bits,
-			);
-		}
+#[test]
+fn test_parse_mode() {
+	let _ = parse_mode("77777").unwrap_err();
+
+	for (string, bits) in [
+		("", 0),
+		("-", 0),
+		("0", 0),
+		("420", 0o420),
+		("u", 0o600),
+		("g", 0o060),
+		("ug", 0o660),
+		("o", 0o006),
+		("uo", 0o606),
+		("go", 0o066),
+		("ugo", 0o666),
+		("-u-g-o-", 0o666),
+	] {
+		assert_eq!(
+			parse_mode(string).unwrap().bits(),
+			bits,
+		);
 	}
+}
 
+#[cfg(any(feature = "clap", feature = "serde"))]
+mod parse_common {
+	use libc::{gid_t, uid_t};
+	use nix::unistd::{Gid, Uid};
+
+	#[cfg(not(target_os = "redox"))]
+	use nix::unistd::{Group, User};
+
+	#[derive(Clone, Copy)]
 	#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize), serde(rename = "UnixPrincipal", untagged))]
 	pub(super) enum SerdeUnixPrincipal<'a, I> {
 		Id(I),
@@ -536,15 +540,44 @@ pub fn prepare(
 	options: &SocketUserOptions,
 	socket_path: Option<&Path>,
 ) -> Result<(), OpenSocketError> {
-	if let None = socket_path {
+	if socket_path.is_none() {
 		check_inapplicable(options.unix_socket_permissions, "unix_socket_permissions")?;
 		check_inapplicable(options.unix_socket_owner, "unix_socket_owner")?;
 		check_inapplicable(options.unix_socket_group, "unix_socket_group")?;
+		check_inapplicable_bool(options.unix_socket_atomic_replace, "unix_socket_atomic_replace")?;
+		check_inapplicable_bool(options.unix_socket_lock_file, "unix_socket_lock_file")?;
+
+		#[cfg(any(target_os = "android", target_os = "linux"))]
+		check_inapplicable(options.unix_socket_selinux_context.as_ref(), "unix_socket_selinux_context")?;
 	}
 
 	Ok(())
 }
 
+/// Serializes umask changes made by [`bind`], so that two threads binding sockets at the same time don't restore each other's umask.
+static UMASK_LOCK: Mutex<()> = Mutex::new(());
+
+/// Binds a socket to its address. If the socket is a path-based Unix-domain socket with [`SocketUserOptions::unix_socket_permissions`], then it's bound with a umask that denies all permissions, so that the socket file isn't accessible until [`apply`] sets its permissions.
+pub fn bind(
+	options: &SocketUserOptions,
+	socket: &Socket,
+	sockaddr: &SockAddr,
+	socket_path: Option<&Path>,
+) -> io::Result<()> {
+	if socket_path.is_none() || options.unix_socket_permissions.is_none() {
+		return socket.bind(sockaddr);
+	}
+
+	// A poisoned lock is fine. The lock doesn't protect any data; it only keeps umask changes from overlapping.
+	let _lock = UMASK_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+
+	let old_umask: Mode = umask(Mode::S_IRWXU | Mode::S_IRWXG | Mode::S_IRWXO);
+	let result = socket.bind(sockaddr);
+	umask(old_umask);
+
+	result
+}
+
 pub fn apply(
 	options: &SocketUserOptions,
 	_socket: &Socket,
@@ -564,7 +597,46 @@ pub fn apply(
 			fs::set_permissions(socket_path, permissions)
 			.map_err(|error| OpenSocketError::SetPermissions { error })?;
 		}
+
+		#[cfg(any(target_os = "android", target_os = "linux"))]
+		if let Some(context) = &options.unix_socket_selinux_context {
+			set_selinux_context(socket_path, context)
+			.map_err(|error| OpenSocketError::SetSecurityContext { error })?;
+		}
 	}
 
 	Ok(())
 }
+
+/// Sets the SELinux security context of a file, without following symbolic links, like `lsetfilecon`.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn set_selinux_context(path: &Path, context: &str) -> io::Result<()> {
+	use std::{
+		ffi::CString,
+		os::unix::ffi::OsStrExt,
+	};
+
+	let path = CString::new(path.as_os_str().as_bytes()).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+	// The context is stored with a terminating null character, as `libselinux` does.
+	let context = CString::new(context).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+	let context: &[u8] = context.as_bytes_with_nul();
+
+	let result = unsafe {
+		// Safety: `path` and the attribute name are valid null-terminated strings. `context` points to a buffer of `context.len()` bytes.
+		libc::lsetxattr(
+			path.as_ptr(),
+			b"security.selinux\0".as_ptr() as *const libc::c_char,
+			context.as_ptr() as *const libc::c_void,
+			context.len(),
+			0,
+		)
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	}
+	else {
+		Ok(())
+	}
+}
@@ -0,0 +1,162 @@
+use crate::{
+	base64,
+	inherit_socket_in_child,
+	SocketAddr,
+};
+use once_cell::sync::Lazy;
+use std::{
+	ffi::OsString,
+	io,
+	os::fd::{AsFd, AsRawFd, RawFd},
+	process::{Child, Command},
+};
+
+/// The environment variable that [`spawn_upgrade`] uses to tell the new process which sockets it inherited, and for which addresses.
+///
+/// Its value is the process ID of the old process, followed by `;`, followed by a `,`-separated list of <code><var>fd</var>:<var>address</var></code> entries, where each <code><var>address</var></code> is a [`SocketAddr`] in base64.
+const UPGRADE_ENV_VAR: &str = "SOCKET_CONFIG_UPGRADE";
+
+/// The sockets inherited from the old process by [`spawn_upgrade`], each paired with the address that the old process opened it from. This is empty if this process wasn't spawned that way.
+static UPGRADED_SOCKETS: Lazy<Vec<(SocketAddr, RawFd)>> = Lazy::new(|| {
+	std::env::var(UPGRADE_ENV_VAR).ok()
+	.and_then(|value| parse_upgrade_env_var(&value, std::os::unix::process::parent_id()))
+	.unwrap_or_default()
+});
+
+/// Starts a new version of this program, handing it the sockets that this one has open, so that it can take over from this one without ever refusing a connection.
+///
+/// This is the first half of the upgrade procedure used by servers like nginx and HAProxy. The new process opens its sockets with [`upgraded_addr`], which finds the sockets it inherited from this process. Once it's ready, it tells this process to exit (such as by sending it a signal), and this process stops accepting connections, finishes serving the ones it has, and exits. In the meantime, both processes have the same listening sockets, so connections are accepted by one or the other.
+///
+/// Each socket is paired with the address that it was opened from. The new process usually reads the same configuration as this one, and so it asks for the same addresses.
+///
+/// `command` is usually made with [`upgrade_command`], which runs the same program with the same arguments as this process. The sockets are inherited with [`inherit_socket_in_child`], and listed in an environment variable for the new process.
+///
+///
+/// # Errors
+///
+/// Any I/O error raised by the operating system in spawning the child process.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn spawn_upgrade<S: AsFd>(command: &mut Command, sockets: &[(&SocketAddr, S)]) -> io::Result<Child> {
+	let mut value: String = format!("{};", std::process::id());
+
+	for (index, (address, socket)) in sockets.iter().enumerate() {
+		inherit_socket_in_child(command, socket.as_fd());
+		let fd: RawFd = socket.as_fd().as_raw_fd();
+
+		if index != 0 {
+			value.push(',');
+		}
+
+		value.push_str(&format!("{fd}:{}", base64::encode(address.to_string().as_bytes())));
+	}
+
+	command.env(UPGRADE_ENV_VAR, value);
+	command.spawn()
+}
+
+/// Makes a [`Command`] that runs this program again, with the same command-line arguments, for [`spawn_upgrade`].
+///
+/// The program is the one named by this process's `argv[0]`, not [`std::env::current_exe`]. That way, if the program file has been replaced with a new version, as is usual when upgrading, then the new version is run. If `argv[0]` is a relative path, it's relative to the current directory, so that must not have changed since this process started.
+///
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::NotFound`] if this process has no `argv[0]`.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn upgrade_command() -> io::Result<Command> {
+	let mut args = std::env::args_os();
+
+	let program: OsString =
+		args.next()
+		.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "this process has no `argv[0]`"))?;
+
+	let mut command = Command::new(program);
+	command.args(args);
+	Ok(command)
+}
+
+/// If this process was started by [`spawn_upgrade`], and the old process had a socket open for `address`, returns a [`SocketAddr::Inherit`] for this process's copy of that socket. Otherwise, returns `address` itself.
+///
+/// The result can be passed to [`open`][crate::open()] as usual. Sockets that the old process didn't have, such as when the configuration changed in the meantime, are opened anew.
+///
+/// Addresses are compared exactly, including any options in a [`SocketAddr::WithOptions`]. The returned `SocketAddr::Inherit` doesn't carry those options over, since most of them only apply when binding a new socket, and the inherited socket is already bound.
+///
+/// Only the process that the old process spawned sees the inherited sockets. The list is ignored if this process's parent is not the old process, such as in a process that inherited the environment variable from the new process.
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only.
+pub fn upgraded_addr(address: &SocketAddr) -> SocketAddr {
+	UPGRADED_SOCKETS.iter()
+	.find(|(old_address, _)| old_address == address)
+	.map_or_else(|| address.clone(), |(_, fd)| SocketAddr::new_inherit(*fd))
+}
+
+/// Parses the value of [`UPGRADE_ENV_VAR`]. Returns `None` if it's invalid, or if `parent_pid` isn't the old process's ID.
+fn parse_upgrade_env_var(value: &str, parent_pid: u32) -> Option<Vec<(SocketAddr, RawFd)>> {
+	let (old_pid, entries) = value.split_once(';')?;
+
+	if old_pid.parse::<u32>().ok()? != parent_pid {
+		return None;
+	}
+
+	entries.split(',')
+	.filter(|entry| !entry.is_empty())
+	.map(|entry| {
+		let (fd, address) = entry.split_once(':')?;
+		let fd: RawFd = fd.parse().ok()?;
+		let address: Vec<u8> = base64::decode(address)?;
+		let address: SocketAddr = String::from_utf8(address).ok()?.parse().ok()?;
+		Some((address, fd))
+	})
+	.collect()
+}
+
+#[test]
+fn test_spawn_upgrade() {
+	use std::{
+		io::Read,
+		process::Stdio,
+	};
+
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let address: SocketAddr = "127.0.0.1:8080?backlog=10".parse().unwrap();
+
+	let mut command = Command::new("sh");
+	command
+	.args(["-c", r#"echo "$SOCKET_CONFIG_UPGRADE"; [ -e "/dev/fd/$1" ] && echo fd-ok"#, "sh", &listener.as_raw_fd().to_string()])
+	.stdout(Stdio::piped());
+
+	let mut child: Child = spawn_upgrade(&mut command, &[(&address, listener.as_fd())]).unwrap();
+
+	let mut output = String::new();
+	child.stdout.take().unwrap().read_to_string(&mut output).unwrap();
+	assert!(child.wait().unwrap().success());
+
+	let (value, rest) = output.split_once('\n').unwrap();
+	assert_eq!(rest, "fd-ok\n");
+
+	// The child process sees this process as its parent, so it accepts the list.
+	assert_eq!(
+		parse_upgrade_env_var(value, std::process::id()),
+		Some(vec![(address, listener.as_raw_fd())]),
+	);
+
+	// Any other process doesn't.
+	assert_eq!(parse_upgrade_env_var(value, std::process::id().wrapping_add(1)), None);
+
+	assert_eq!(parse_upgrade_env_var("1;", 1), Some(Vec::new()));
+	assert_eq!(parse_upgrade_env_var("1;3:!", 1), None);
+
+	// This process wasn't started by `spawn_upgrade`, so addresses are passed through.
+	assert_eq!(upgraded_addr(&"127.0.0.1:8080".parse().unwrap()), "127.0.0.1:8080".parse().unwrap());
+}
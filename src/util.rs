@@ -1,5 +1,6 @@
 use crate::{
 	errors::OpenSocketError,
+	SocketAppOptions,
 	sys,
 };
 use socket2::Socket;
@@ -8,9 +9,6 @@ use std::{
 	path::Path,
 };
 
-#[cfg(not(windows))]
-use crate::SocketAppOptions;
-
 #[cfg(test)]
 use {
 	assert_matches::assert_matches,
@@ -46,10 +44,54 @@ pub(crate) fn check_inapplicable_bool(option: bool, name: &'static str) -> Resul
 	}
 }
 
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+pub(crate) fn check_unsupported_bool(option: bool, name: &'static str) -> Result<(), OpenSocketError> {
+	if option {
+		Err(OpenSocketError::UnsupportedUserOption { name })
+	}
+	else {
+		Ok(())
+	}
+}
+
+/// Checks whether the given socket type is [`socket2::Type::RAW`]. Always false on platforms where `socket2` doesn't support raw sockets.
+pub(crate) fn is_raw_socket_type(r#type: socket2::Type) -> bool {
+	cfg_if::cfg_if! {
+		if #[cfg(not(any(target_os = "redox", target_os = "espidf")))] {
+			r#type == socket2::Type::RAW
+		}
+		else {
+			let _ = r#type;
+			false
+		}
+	}
+}
+
+/// Checks whether sockets of the given type are connection-oriented, and therefore need to `listen` in order to accept connections.
+pub(crate) fn is_listenable_socket_type(r#type: socket2::Type) -> bool {
+	if r#type == socket2::Type::STREAM {
+		return true;
+	}
+
+	#[cfg(not(target_os = "espidf"))]
+	if r#type == socket2::Type::SEQPACKET {
+		return true;
+	}
+
+	#[cfg(target_os = "linux")]
+	if r#type == socket2::Type::DCCP {
+		return true;
+	}
+
+	false
+}
+
 /// Mark a socket as inheritable (or not), so that a child process will (or will not) inherit it.
 ///
 /// If the `inheritable` parameter is true, the socket is made inheritable; otherwise, it is made non-inheritable.
 ///
+/// `socket` can be anything that has a file descriptor (`AsFd`) or, on Windows, a socket handle (`AsSocket`). That includes [`socket2::Socket`], the standard library's and Tokio's socket types (such as `std::net::TcpListener` and `tokio::net::TcpListener`), and `OwnedFd`, so they don't need to be converted into a `socket2::Socket` first. Pass a reference, such as `&socket`, to keep using the socket afterward.
+///
 /// If this function is successful, the return value is the file descriptor or handle to pass to the child process.
 ///
 /// For the child process to use the inherited socket, the child process must be informed of the socket's file descriptor or handle number, which is returned by this function. If the child process also uses this library, then you can use [`SocketAddr::new_inherit`] to create a suitable [`SocketAddr`], and pass that to the child process. See the `SocketAddr::new_inherit` documentation for an example.
@@ -59,10 +101,14 @@ pub(crate) fn check_inapplicable_bool(option: bool, name: &'static str) -> Resul
 ///
 /// When a socket is marked as inheritable, it is inherited by *any and all* child processes spawned afterward, until the socket is closed or marked non-inheritable. In a multithreaded program that spawns child processes from more than one thread at the same time, this can result in a socket intended for one child process being also inherited by another child process.
 ///
-/// It is possible to avoid this problem on Unix-like platforms, by making the socket inheritable after `fork` but before `exec`. (See [`std::os::unix::process::CommandExt::pre_exec`](https://doc.rust-lang.org/stable/std/os/unix/process/trait.CommandExt.html#tymethod.pre_exec) for how to do so with [`std::process::Command`].) A convenient API for doing that may be added to a future version of this library.
+/// It is possible to avoid this problem on Unix-like platforms, by making the socket inheritable after `fork` but before `exec`, so that only that one child process inherits it. [`inherit_socket_in_child`] does that for a [`std::process::Command`].
 ///
 /// On Windows, however, it appears to be impossible to solve this problem. There is a way to control which sockets (or other handles) are inherited by a child process (the `PROC_THREAD_ATTRIBUTE_HANDLE_LIST` attribute for the Windows API function [`UpdateProcThreadAttribute`](https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-updateprocthreadattribute)), but all such handles must be marked as inheritable first, and unfortunately, child processes inherit all inheritable handles by default. In other words, `PROC_THREAD_ATTRIBUTE_HANDLE_LIST` can only filter out inheritable handles when creating a child process; it cannot make a handle inheritable only by that specific child process.
 ///
+/// What can be done on Windows is to make sure that a particular child process inherits *only* the sockets intended for it, which the Windows-only function `spawn_with_handle_list` does.
+///
+/// Instead of inheritance, the Windows-only function `duplicate_socket_for_process` can be used to hand a socket off to one specific process. That also works when LSPs are installed (see below).
+///
 ///
 /// # Availability
 ///
@@ -79,11 +125,245 @@ pub(crate) fn check_inapplicable_bool(option: bool, name: &'static str) -> Resul
 ///
 /// On Unix-like platforms, file descriptors (including but not limited to sockets) can be inherited, but only if the `CLOEXEC` flag is not set. Rust socket libraries always create sockets with the `CLOEXEC` flag set. This function sets or clears it using the `fcntl` system call.
 pub fn make_socket_inheritable(
-	socket: &Socket,
+	socket: impl sys::AsSocket,
 	inheritable: bool,
 ) -> io::Result<sys::RawSocket> {
-	// TODO: Consider adding something that uses `CommandExt::pre_exec`, as described above, to make a socket inheritable after `fork` but before `exec`.
-	sys::make_socket_inheritable(socket, inheritable)
+	sys::make_socket_inheritable(sys::borrow_socket(&socket), inheritable)
+}
+
+/// Marks a socket as inheritable, like [`make_socket_inheritable`], until the returned guard is dropped, at which point the socket is marked non-inheritable again.
+///
+/// The same warning applies as for `make_socket_inheritable`: while the guard exists, the socket is inherited by *any* child process that is spawned, including by other threads. To keep that window as small as possible, spawn the child process right after calling this function, and then drop the guard. On Unix-like platforms, [`inherit_socket_in_child`] avoids the problem entirely.
+///
+/// `socket` can be any kind of socket that `make_socket_inheritable` accepts. Use [`InheritableSocketGuard::raw_socket`] to get the file descriptor or handle to pass to the child process.
+///
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use socket_config::{make_socket_inheritable_scoped, SocketAddr};
+/// # use std::process::Command;
+/// #
+/// # fn create_a_socket_somehow() -> std::io::Result<socket2::Socket> { unimplemented!() }
+/// #
+/// # fn run() -> std::io::Result<()> {
+/// let socket = create_a_socket_somehow()?;
+///
+/// let child = {
+/// 	let guard = make_socket_inheritable_scoped(&socket)?;
+///
+/// 	Command::new("some_program")
+/// 	.arg(SocketAddr::new_inherit(guard.raw_socket()).to_string())
+/// 	.spawn()?
+///
+/// 	// The socket is no longer inheritable once `guard` is dropped here.
+/// };
+/// #
+/// # drop(child);
+/// # Ok(())
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// All platforms. The same caveat about Layered Service Providers on Windows applies as for `make_socket_inheritable`.
+pub fn make_socket_inheritable_scoped<S: sys::AsSocket + ?Sized>(socket: &S) -> io::Result<InheritableSocketGuard<'_>> {
+	let socket: sys::BorrowedSocket = sys::borrow_socket(socket);
+	let raw_socket: sys::RawSocket = sys::make_socket_inheritable(socket, true)?;
+
+	Ok(InheritableSocketGuard {
+		socket,
+		raw_socket,
+	})
+}
+
+/// Keeps a socket inheritable until dropped. Returned by [`make_socket_inheritable_scoped`].
+///
+/// If marking the socket non-inheritable again fails when this is dropped, the error is ignored.
+#[derive(Debug)]
+#[must_use = "the socket is marked non-inheritable again as soon as the guard is dropped"]
+pub struct InheritableSocketGuard<'a> {
+	socket: sys::BorrowedSocket<'a>,
+	raw_socket: sys::RawSocket,
+}
+
+impl InheritableSocketGuard<'_> {
+	/// Returns the file descriptor or handle to pass to the child process, as [`make_socket_inheritable`] would.
+	pub fn raw_socket(&self) -> sys::RawSocket {
+		self.raw_socket
+	}
+}
+
+impl Drop for InheritableSocketGuard<'_> {
+	fn drop(&mut self) {
+		let _ = sys::make_socket_inheritable(self.socket, false);
+	}
+}
+
+/// Arranges for the child process that will be spawned by `command` to inherit `socket`, without making the socket inheritable by any other child process.
+///
+/// This is the thread-safe alternative to [`make_socket_inheritable`] on Unix-like platforms. Instead of clearing the socket's `CLOEXEC` flag in this process, it clears the flag in the child process only, after `fork` but before `exec`, using [`CommandExt::pre_exec`][std::os::unix::process::CommandExt::pre_exec]. Child processes spawned by other threads in the meantime don't inherit the socket.
+///
+/// The return value is a [`SocketAddr`] for the inherited socket, which can be passed to the child process (such as on its command line), as with [`SocketAddr::new_inherit`].
+///
+/// `socket` can be anything that has a file descriptor, as with [`make_socket_inheritable`]. The socket must stay open until the child process has been spawned. If `command` is used to spawn more than one child process, each of them inherits the socket.
+///
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use socket_config::inherit_socket_in_child;
+/// # use std::process::Command;
+/// #
+/// # fn create_a_socket_somehow() -> std::io::Result<socket2::Socket> { unimplemented!() }
+/// #
+/// # fn run() -> std::io::Result<()> {
+/// let socket = create_a_socket_somehow()?;
+///
+/// let mut command = Command::new("some_program");
+/// let addr = inherit_socket_in_child(&mut command, &socket);
+///
+/// command
+/// .arg(addr.to_string())
+/// .spawn()?;
+/// #
+/// # Ok(())
+/// # }
+/// ```
+///
+///
+/// # Availability
+///
+/// Unix-like platforms only. There is no equivalent on Windows; see the warning on [`make_socket_inheritable`] for why.
+#[cfg(unix)]
+pub fn inherit_socket_in_child(command: &mut std::process::Command, socket: impl std::os::fd::AsFd) -> crate::SocketAddr {
+	use std::os::{
+		fd::AsRawFd,
+		unix::process::CommandExt,
+	};
+
+	let fd: sys::RawSocket = socket.as_fd().as_raw_fd();
+
+	unsafe {
+		// Safety: `clear_cloexec` only calls `fcntl`, which is async-signal-safe, and doesn't allocate memory.
+		command.pre_exec(move || sys::clear_cloexec(fd));
+	}
+
+	crate::SocketAddr::new_inherit(fd)
+}
+
+/// Spawns a child process that inherits the given sockets, and no other inheritable handles besides its standard input, output, and error.
+///
+/// This is the Windows counterpart of [`make_socket_inheritable`] followed by [`Command::spawn`][std::process::Command::spawn]. The child process is created with the `PROC_THREAD_ATTRIBUTE_HANDLE_LIST` attribute listing only `sockets`, so it doesn't inherit any other handles that happen to be inheritable, such as sockets meant for some other child process.
+///
+/// Socket handles keep their values in the child process, so the child can be told about them in advance, such as with a [`SocketAddr::new_inherit`] of each socket's raw handle on its command line.
+///
+/// `sockets` can be anything that has a socket handle, as with [`make_socket_inheritable`]. Each socket is made inheritable only while the child process is being created, and non-inheritable again afterward, as with [`make_socket_inheritable_scoped`].
+///
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #[cfg(windows)] {
+/// # use socket_config::{spawn_with_handle_list, SocketAddr};
+/// # use std::{os::windows::io::AsRawSocket, process::Command};
+/// #
+/// # fn create_a_socket_somehow() -> std::io::Result<socket2::Socket> { unimplemented!() }
+/// #
+/// # fn run() -> std::io::Result<()> {
+/// let socket = create_a_socket_somehow()?;
+///
+/// let mut command = Command::new("some_program");
+/// command.arg(SocketAddr::new_inherit(socket.as_raw_socket()).to_string());
+///
+/// let child = spawn_with_handle_list(&mut command, &[&socket])?;
+/// #
+/// # Ok(())
+/// # }
+/// # }
+/// ```
+///
+///
+/// # Caveats
+///
+/// Because [`Command`][std::process::Command] doesn't support `PROC_THREAD_ATTRIBUTE_HANDLE_LIST`, this function creates the child process itself, with `CreateProcessW`, instead of letting `Command` do it. As a result:
+///
+/// * The child's standard input, output, and error are always inherited from this process. [`Command::stdin`][std::process::Command::stdin], [`Command::stdout`][std::process::Command::stdout], and [`Command::stderr`][std::process::Command::stderr] have no effect.
+/// * The child's environment is this process's environment with the changes made by [`Command::env`][std::process::Command::env] and [`Command::env_remove`][std::process::Command::env_remove] applied. [`Command::env_clear`][std::process::Command::env_clear] has no effect.
+/// * Creation flags and raw arguments set with `CommandExt` have no effect.
+///
+/// While the child process is being created, the sockets are inheritable, so a child process spawned by another thread at the same moment may still inherit them. This only affects processes spawned in some other way; child processes spawned with this function never inherit anything they weren't given.
+///
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] if the program, an argument, or an environment variable contains a null character, or with any I/O error raised by the operating system in spawning the child process.
+///
+///
+/// # Availability
+///
+/// Windows only. On Unix-like platforms, use `inherit_socket_in_child` or `spawn_with_sockets` instead.
+#[cfg(windows)]
+pub fn spawn_with_handle_list<S: sys::AsSocket>(command: &mut std::process::Command, sockets: &[S]) -> io::Result<std::process::Child> {
+	let guards: Vec<InheritableSocketGuard<'_>> =
+		sockets.iter()
+		.map(make_socket_inheritable_scoped)
+		.collect::<io::Result<_>>()?;
+
+	let handles: Vec<std::os::windows::io::RawHandle> =
+		guards.iter()
+		.map(|guard| guard.raw_socket() as std::os::windows::io::RawHandle)
+		.collect();
+
+	sys::spawn_with_handle_list(command, &handles)
+}
+
+/// Duplicates `socket` for the process with the given ID, so that it can be handed off to that process without being inherited, using `WSADuplicateSocketW`.
+///
+/// The return value is a [`SocketAddr::WsaProtocolInfo`], which is to be passed to the other process (such as on its command line, or through a pipe). [Opening][crate::open()] it in that process yields its own handle for the socket. This works with any process, not just a child process, and isn't affected by layered service providers the way that inherited socket handles are.
+///
+/// `socket` can be anything that has a socket handle, as with [`make_socket_inheritable`]. This process may close its own handle afterward; the socket stays open until the other process opens the returned address and then closes its handle, or exits. The returned address can only be opened once, and only by the process that it was made for.
+///
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #[cfg(windows)] {
+/// # use socket_config::duplicate_socket_for_process;
+/// # use std::process::{Command, Stdio};
+/// # use std::io::Write;
+/// #
+/// # fn create_a_socket_somehow() -> std::io::Result<socket2::Socket> { unimplemented!() }
+/// #
+/// # fn run() -> std::io::Result<()> {
+/// let socket = create_a_socket_somehow()?;
+///
+/// let mut child = Command::new("some_program")
+/// .stdin(Stdio::piped())
+/// .spawn()?;
+///
+/// let addr = duplicate_socket_for_process(&socket, child.id())?;
+/// writeln!(child.stdin.as_mut().unwrap(), "{addr}")?;
+/// #
+/// # Ok(())
+/// # }
+/// # }
+/// ```
+///
+///
+/// # Errors
+///
+/// Any I/O error raised by `WSADuplicateSocketW`. In particular, this fails if there is no process with the given ID.
+///
+///
+/// # Availability
+///
+/// Windows only.
+#[cfg(windows)]
+pub fn duplicate_socket_for_process(socket: impl sys::AsSocket, process_id: u32) -> io::Result<crate::SocketAddr> {
+	let info: Vec<u8> = sys::duplicate_socket(sys::borrow_socket(&socket), process_id)?;
+	Ok(crate::SocketAddr::new_wsa_protocol_info(info))
 }
 
 /// Checks whether the file at the given `path` is a Unix-domain socket.
@@ -108,6 +388,94 @@ pub fn is_unix_socket(path: &Path) -> io::Result<bool> {
 	sys::is_unix_socket(path)
 }
 
+/// Returns the original destination address of a TCP connection that was redirected to this host by the firewall, such as with the `REDIRECT` target of `iptables` on Linux. Transparent proxies use this to find out where the client was actually trying to connect to.
+///
+/// `socket` is the accepted connection. To use this function with a socket type other than [`socket2::Socket`], such as [`std::net::TcpStream`], wrap it in a [`socket2::SockRef`] first.
+#[cfg_attr(feature = "tokio", doc = " [`AnyTokioStream`][crate::convert::AnyTokioStream] has an equivalent method, [`AnyTokioStream::original_destination`][crate::convert::AnyTokioStream::original_destination].")]
+///
+/// This uses the socket option `SO_ORIGINAL_DST` for IPv4 connections, and `IP6T_SO_ORIGINAL_DST` for IPv6 connections.
+///
+///
+/// # Errors
+///
+/// Any I/O error raised by the operating system. In particular, this fails if the connection is not a TCP connection. On Linux, it also fails if connection tracking (the `nf_conntrack` kernel module) is not active.
+///
+/// If the connection wasn't redirected, this either fails or returns the connection's local address, depending on the operating system and firewall configuration.
+///
+///
+/// # Availability
+///
+/// Android, Linux, and Windows only.
+#[cfg(any(target_os = "android", target_os = "linux", windows))]
+pub fn original_destination(socket: &Socket) -> io::Result<std::net::SocketAddr> {
+	let addr: socket2::SockAddr = match socket.local_addr()?.is_ipv6() {
+		false => socket.original_dst()?,
+		true => socket.original_dst_ipv6()?,
+	};
+
+	addr.as_socket()
+	.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "original destination address is not an IP address"))
+}
+
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux", windows))]
+fn test_original_destination() {
+	let listener = std::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).unwrap();
+	let _client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+	let (accepted, _) = listener.accept().unwrap();
+
+	// The connection wasn't redirected, so there's either no original destination, or it's the same as the local address.
+	if let Ok(addr) = original_destination(&socket2::SockRef::from(&accepted)) {
+		assert_eq!(addr, accepted.local_addr().unwrap());
+	}
+}
+
+#[test]
+#[cfg(unix)]
+fn test_make_socket_inheritable_scoped() {
+	use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+	use std::os::fd::AsRawFd;
+
+	// Any socket type will do, not just `socket2::Socket`.
+	let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+
+	let is_cloexec = || FdFlag::from_bits_truncate(fcntl(socket.as_raw_fd(), FcntlArg::F_GETFD).unwrap()).contains(FdFlag::FD_CLOEXEC);
+
+	assert!(is_cloexec());
+
+	let guard = make_socket_inheritable_scoped(&socket).unwrap();
+	assert_eq!(guard.raw_socket(), socket.as_raw_fd());
+	assert!(!is_cloexec());
+
+	drop(guard);
+	assert!(is_cloexec());
+
+	make_socket_inheritable(&socket, true).unwrap();
+	assert!(!is_cloexec());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_is_socket_probably_tcp() {
+	let local_addr: socket2::SockAddr = std::net::SocketAddr::from((std::net::Ipv4Addr::LOCALHOST, 0)).into();
+
+	let tcp = Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+	assert!(is_socket_probably_tcp(&tcp, &local_addr, &SocketAppOptions::new(socket2::Type::STREAM)));
+
+	// A UDP-Lite socket is not TCP, even if the application expected a stream-type socket.
+	let udp_lite = Socket::new(socket2::Domain::IPV4, socket2::Type::DGRAM, Some(socket2::Protocol::UDPLITE)).unwrap();
+	assert!(!is_socket_probably_tcp(&udp_lite, &local_addr, &SocketAppOptions::new(socket2::Type::STREAM)));
+
+	let mut app_options = SocketAppOptions::new(socket2::Type::DCCP);
+	app_options.protocol = Some(socket2::Protocol::DCCP);
+
+	// The kernel might not have DCCP.
+	if let Ok(dccp) = Socket::new(socket2::Domain::IPV4, socket2::Type::DCCP, None) {
+		assert!(!is_socket_probably_tcp(&dccp, &local_addr, &app_options));
+		assert!(!is_socket_probably_tcp(&dccp, &local_addr, &SocketAppOptions::new(socket2::Type::STREAM)));
+	}
+}
+
 #[test]
 fn test_is_unix_socket() {
 	let socket_path: PathBuf = TEST_SCRATCH.join("test1.socket");
@@ -162,6 +530,32 @@ pub(crate) static TEST_SCRATCH: Lazy<PathBuf> = Lazy::new(|| {
 	path
 });
 
+/// Checks whether a new socket, with the given options, that is to be bound to the given address, is going to be a TCP socket. Unlike [`is_socket_probably_tcp`], this doesn't need the socket to exist yet.
+pub(crate) fn is_new_socket_tcp(
+	address: &socket2::SockAddr,
+	app_options: &SocketAppOptions,
+) -> bool {
+	let is_tcp_protocol: bool = match app_options.protocol {
+		Some(protocol) => protocol == socket2::Protocol::TCP,
+		None => app_options.r#type == socket2::Type::STREAM,
+	};
+
+	is_tcp_protocol && (address.is_ipv4() || address.is_ipv6())
+}
+
+/// Checks whether a new socket, with the given options, that is to be bound to the given address, is going to be a UDP socket.
+pub(crate) fn is_new_socket_udp(
+	address: &socket2::SockAddr,
+	app_options: &SocketAppOptions,
+) -> bool {
+	let is_udp_protocol: bool = match app_options.protocol {
+		Some(protocol) => protocol == socket2::Protocol::UDP,
+		None => app_options.r#type == socket2::Type::DGRAM,
+	};
+
+	is_udp_protocol && (address.is_ipv4() || address.is_ipv6())
+}
+
 #[cfg(not(windows))]
 pub(crate) fn is_socket_probably_tcp(
 	socket: &Socket,
@@ -184,12 +578,10 @@ pub(crate) fn is_socket_probably_tcp(
 				return protocol == socket2::Protocol::TCP;
 			}
 		}
-		// On all others, we're going to have to infer the protocol…
-		else {
-			// …which means we aren't actually going to use the socket itself, so just suppress the unused-variable warning.
-			let _ = socket;
-		}
 	}
 
-	app_options.r#type == socket2::Type::STREAM && (local_addr.is_ipv4() || local_addr.is_ipv6())
+	// Otherwise, we're going to have to infer the protocol from the socket's type. Use the type that the socket actually has, if it can be found out, rather than the type that the application asked for. A DCCP socket has its own type, and a UDP-Lite socket is datagram-type, so neither of them is mistaken for TCP this way.
+	let r#type: socket2::Type = socket.r#type().unwrap_or(app_options.r#type);
+
+	r#type == socket2::Type::STREAM && (local_addr.is_ipv4() || local_addr.is_ipv6())
 }
@@ -58,6 +58,128 @@ fn inherit() {
 	drop(child_process);
 }
 
+#[test]
+#[cfg(unix)]
+fn inherit_in_child() {
+	let socket_addr: socket2::SockAddr =
+		std::net::SocketAddrV4::new(
+			Ipv4Addr::LOCALHOST,
+			0,
+		)
+		.into();
+
+	let socket = Socket::new(socket_addr.domain(), socket2::Type::STREAM, None).unwrap();
+	socket.bind(&socket_addr).unwrap();
+	socket.listen(128).unwrap();
+
+	let socket_addr: socket2::SockAddr = socket.local_addr().unwrap();
+
+	let mut command =
+		CargoBuild::new()
+		.example("echo_incr")
+		.features("clap tokio")
+		.run()
+		.unwrap()
+		.command();
+
+	let inherited_socket_addr: socket_config::SocketAddr = socket_config::inherit_socket_in_child(&mut command, &socket);
+
+	let child_process = KillOnDrop(
+		command
+		.arg(inherited_socket_addr.to_string())
+		.spawn()
+		.unwrap()
+	);
+
+	// The socket is only inheritable in the child process, not this one.
+	let flags = nix::fcntl::fcntl(std::os::fd::AsRawFd::as_raw_fd(&socket), nix::fcntl::FcntlArg::F_GETFD).unwrap();
+	assert!(nix::fcntl::FdFlag::from_bits_truncate(flags).contains(nix::fcntl::FdFlag::FD_CLOEXEC));
+
+	drop(socket);
+
+	let socket = Socket::new(socket_addr.domain(), socket2::Type::STREAM, None).unwrap();
+	socket.connect(&socket_addr).unwrap();
+	echo_incr_client(socket);
+
+	drop(child_process);
+}
+
+#[test]
+#[cfg(windows)]
+fn inherit_with_handle_list() {
+	let socket_addr: socket2::SockAddr =
+		std::net::SocketAddrV4::new(
+			Ipv4Addr::LOCALHOST,
+			0,
+		)
+		.into();
+
+	let socket = Socket::new(socket_addr.domain(), socket2::Type::STREAM, None).unwrap();
+	socket.bind(&socket_addr).unwrap();
+	socket.listen(128).unwrap();
+
+	let socket_addr: socket2::SockAddr = socket.local_addr().unwrap();
+
+	let mut command =
+		CargoBuild::new()
+		.example("echo_incr")
+		.features("clap tokio")
+		.run()
+		.unwrap()
+		.command();
+
+	command.arg(socket_config::SocketAddr::new_inherit(std::os::windows::io::AsRawSocket::as_raw_socket(&socket)).to_string());
+
+	let child_process = KillOnDrop(socket_config::spawn_with_handle_list(&mut command, &[&socket]).unwrap());
+
+	drop(socket);
+
+	let socket = Socket::new(socket_addr.domain(), socket2::Type::STREAM, None).unwrap();
+	socket.connect(&socket_addr).unwrap();
+	echo_incr_client(socket);
+
+	drop(child_process);
+}
+
+#[test]
+fn inherit_env() {
+	let socket_addr: socket2::SockAddr =
+		std::net::SocketAddrV4::new(
+			Ipv4Addr::LOCALHOST,
+			0,
+		)
+		.into();
+
+	let socket = Socket::new(socket_addr.domain(), socket2::Type::STREAM, None).unwrap();
+	socket.bind(&socket_addr).unwrap();
+	socket.listen(128).unwrap();
+
+	let socket_addr: socket2::SockAddr = socket.local_addr().unwrap();
+
+	let raw_socket = socket_config::make_socket_inheritable(&socket, true).unwrap();
+
+	let child_process = KillOnDrop(
+		CargoBuild::new()
+		.example("echo_incr")
+		.features("clap tokio")
+		.run()
+		.unwrap()
+		.command()
+		.env("ECHO_INCR_SOCKET", raw_socket.to_string())
+		.arg("env:ECHO_INCR_SOCKET")
+		.spawn()
+		.unwrap()
+	);
+
+	drop(socket);
+
+	let socket = Socket::new(socket_addr.domain(), socket2::Type::STREAM, None).unwrap();
+	socket.connect(&socket_addr).unwrap();
+	echo_incr_client(socket);
+
+	drop(child_process);
+}
+
 #[test]
 fn unix() {
 	// Do this twice, in order to verify that deleting and replacing the Unix socket works.
@@ -90,6 +212,27 @@ fn unix() {
 	}
 }
 
+#[test]
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn unix_abstract() {
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let address: socket_config::SocketAddr = format!("@socket_config-test-{}", std::process::id()).parse().unwrap();
+
+	let (server_addr, server_thread) = echo_incr_server(
+		&address,
+		&app_options,
+		&user_options
+	);
+
+	let socket = Socket::new(server_addr.domain(), app_options.r#type, app_options.protocol).unwrap();
+	socket.connect(&server_addr).unwrap();
+	echo_incr_client(socket);
+
+	server_thread.join().unwrap();
+}
+
 #[test]
 fn udp() {
 	let mut app_options = socket_config::SocketAppOptions::new(socket2::Type::DGRAM);
@@ -111,6 +254,957 @@ fn udp() {
 	server_thread.join().unwrap();
 }
 
+#[test]
+fn port_range() {
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	// Occupy a port, then ask for a range starting at that port. The occupied port should be skipped.
+	let occupied = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+	let first_port: u16 = occupied.local_addr().unwrap().port().min(u16::MAX - 100);
+	let last_port: u16 = first_port + 100;
+
+	let (server_addr, server_thread) = echo_incr_server(
+		&format!("127.0.0.1:{first_port}-{last_port}").parse().unwrap(),
+		&app_options,
+		&user_options
+	);
+
+	let bound_port: u16 = server_addr.as_socket().unwrap().port();
+	assert!((first_port..=last_port).contains(&bound_port));
+	assert_ne!(bound_port, occupied.local_addr().unwrap().port());
+
+	let socket = Socket::new(server_addr.domain(), app_options.r#type, app_options.protocol).unwrap();
+	socket.connect(&server_addr).unwrap();
+	echo_incr_client(socket);
+
+	server_thread.join().unwrap();
+}
+
+#[test]
+fn udp_quic_preset() {
+	let mut app_options = socket_config::SocketAppOptions::preset(socket_config::SocketPreset::QuicServer);
+	app_options.default_port = Some(0);
+
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let (server_addr, server_thread) = echo_incr_server(
+		&"127.0.0.1".parse().unwrap(),
+		&app_options,
+		&user_options
+	);
+
+	let socket = Socket::new(server_addr.domain(), app_options.r#type, app_options.protocol).unwrap();
+	socket.connect(&server_addr).unwrap();
+	echo_incr_client(socket);
+
+	server_thread.join().unwrap();
+
+	// The preset's socket options are set, even if the application has its own `before_bind` hook.
+	app_options.before_bind = Some(std::sync::Arc::new(|socket, _| socket.set_broadcast(true)));
+
+	let socket = socket_config::open(&"127.0.0.1".parse().unwrap(), &app_options, &user_options).unwrap();
+	assert!(socket.broadcast().unwrap());
+
+	let plain = Socket::new(socket2::Domain::IPV4, socket2::Type::DGRAM, None).unwrap();
+	assert!(socket.recv_buffer_size().unwrap() >= plain.recv_buffer_size().unwrap());
+	assert!(socket.send_buffer_size().unwrap() >= plain.send_buffer_size().unwrap());
+
+	#[cfg(target_os = "linux")] {
+		use std::os::fd::AsRawFd;
+
+		let get_int_sockopt = |level: libc::c_int, option: libc::c_int| -> std::io::Result<libc::c_int> {
+			let mut value: libc::c_int = 0;
+			let mut len = std::mem::size_of_val(&value) as libc::socklen_t;
+
+			// Safety: `value` is a `c_int`, which is what the socket options being queried produce, and `len` is its size.
+			match unsafe { libc::getsockopt(socket.as_raw_fd(), level, option, &mut value as *mut libc::c_int as *mut _, &mut len) } {
+				0 => Ok(value),
+				_ => Err(std::io::Error::last_os_error()),
+			}
+		};
+
+		assert!(socket.recv_tos().unwrap());
+		assert_eq!(get_int_sockopt(libc::IPPROTO_IP, libc::IP_PKTINFO).unwrap(), 1);
+
+		match get_int_sockopt(libc::SOL_UDP, libc::UDP_GRO) {
+			// Linux versions older than 5.0 don't have `UDP_GRO`.
+			Err(error) if error.raw_os_error() == Some(libc::ENOPROTOOPT) => {}
+			result => assert_eq!(result.unwrap(), 1),
+		}
+	}
+}
+
+#[test]
+fn pair() {
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+	let address: socket_config::SocketAddr = "pair:test".parse().unwrap();
+
+	let (_, server_thread) = echo_incr_server(
+		&address,
+		&app_options,
+		&user_options
+	);
+
+	let socket = address.take_pair_peer().unwrap();
+	assert!(address.take_pair_peer().is_none());
+	echo_incr_client(socket);
+
+	server_thread.join().unwrap();
+}
+
+#[test]
+fn connect() {
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let (server_addr, server_thread) = echo_incr_server(
+		&"127.0.0.1:0".parse().unwrap(),
+		&app_options,
+		&user_options
+	);
+
+	let mut connect_app_options = socket_config::ConnectAppOptions::new(socket2::Type::STREAM);
+	connect_app_options.default_port = Some(server_addr.as_socket().unwrap().port());
+
+	let mut connect_user_options = socket_config::ConnectUserOptions::default();
+	connect_user_options.connect_timeout_ms = Some(10_000);
+
+	let socket = socket_config::open_connect(
+		&"127.0.0.1".parse().unwrap(),
+		&connect_app_options,
+		&connect_user_options,
+	).unwrap();
+
+	echo_incr_client(socket);
+
+	server_thread.join().unwrap();
+}
+
+#[test]
+fn connect_happy_eyeballs() {
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let (server_addr, server_thread) = echo_incr_server(
+		&"127.0.0.1:0".parse().unwrap(),
+		&app_options,
+		&user_options
+	);
+
+	// The first candidate is a port that nothing is listening on, so connecting to it fails, and the second candidate should be tried right away.
+	let refused_addr: std::net::SocketAddr = {
+		let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+		listener.local_addr().unwrap()
+	};
+
+	let socket = socket_config::open_connect_happy_eyeballs(
+		[refused_addr, server_addr.as_socket().unwrap()],
+		&socket_config::ConnectAppOptions::new(socket2::Type::STREAM),
+		&socket_config::ConnectUserOptions::default(),
+	).unwrap();
+
+	// The returned socket is in blocking mode, which `echo_incr_client` expects.
+	echo_incr_client(socket);
+
+	// If every attempt fails, all of the errors are reported.
+	let error = socket_config::open_connect_happy_eyeballs(
+		[refused_addr],
+		&socket_config::ConnectAppOptions::new(socket2::Type::STREAM),
+		&socket_config::ConnectUserOptions::default(),
+	).unwrap_err();
+
+	assert!(matches!(
+		&error,
+		socket_config::errors::OpenSocketError::ConnectAttemptsFailed { errors, .. }
+		if errors.len() == 1 && errors[0].0 == refused_addr && errors[0].1.kind() == std::io::ErrorKind::ConnectionRefused
+	), "{error:?}");
+
+	server_thread.join().unwrap();
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn connect_happy_eyeballs_tokio() {
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let (server_addr, server_thread) = echo_incr_server(
+		&"127.0.0.1:0".parse().unwrap(),
+		&app_options,
+		&user_options
+	);
+
+	let refused_addr: std::net::SocketAddr = {
+		let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+		listener.local_addr().unwrap()
+	};
+
+	let connect = socket_config::open_connect_happy_eyeballs_tokio(
+		[refused_addr, server_addr.as_socket().unwrap()],
+		&socket_config::ConnectAppOptions::new(socket2::Type::STREAM),
+		&socket_config::ConnectUserOptions::default(),
+	);
+
+	let stream: tokio::net::TcpStream =
+		tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(connect)
+		.unwrap();
+
+	let socket = Socket::from(stream.into_std().unwrap());
+	socket.set_nonblocking(false).unwrap();
+	echo_incr_client(socket);
+
+	server_thread.join().unwrap();
+
+	// Only stream-type sockets are supported.
+	let connect = socket_config::open_connect_happy_eyeballs_tokio(
+		[refused_addr],
+		&socket_config::ConnectAppOptions::new(socket2::Type::DGRAM),
+		&socket_config::ConnectUserOptions::default(),
+	);
+
+	let error =
+		tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(connect)
+		.unwrap_err();
+
+	assert!(matches!(
+		error,
+		socket_config::errors::OpenSocketError::UnsupportedConnectType { actual, .. }
+		if actual == socket2::Type::DGRAM
+	), "{error:?}");
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tcp_nodelay_tokio_accept() {
+	use socket_config::convert::{AnyTokioListener, AnyTokioStream};
+
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let mut user_options = socket_config::SocketUserOptions::default();
+	user_options.tcp_nodelay = true;
+
+	let socket: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	assert!(socket.nodelay().unwrap());
+
+	let server_addr = socket.local_addr().unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let listener = AnyTokioListener::try_from(socket).unwrap();
+		let _client = tokio::net::TcpStream::connect(server_addr.as_socket().unwrap()).await.unwrap();
+
+		let (connection, _) = listener.accept().await.unwrap();
+
+		let AnyTokioStream::Tcp(connection) = connection else {
+			panic!("expected a TCP connection");
+		};
+
+		assert!(connection.nodelay().unwrap());
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_accepted_options() {
+	use socket_config::convert::AnyTokioListener;
+
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let socket: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	let server_addr = socket.local_addr().unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let listener = AnyTokioListener::try_from(socket).unwrap();
+		listener.set_ttl(42).unwrap();
+		listener.set_accepted_nodelay(true).unwrap();
+		listener.set_accepted_keepalive(Some(&socket2::TcpKeepalive::new())).unwrap();
+
+		let _client = tokio::net::TcpStream::connect(server_addr.as_socket().unwrap()).await.unwrap();
+		let (connection, _) = listener.accept().await.unwrap();
+		let connection = socket2::SockRef::from(&connection);
+
+		assert_eq!(connection.ttl().unwrap(), 42);
+		assert!(connection.nodelay().unwrap());
+		assert!(connection.keepalive().unwrap());
+	});
+
+	#[cfg(unix)] {
+		let socket: Socket = socket_config::open(&"./target/accepted_options.socket".parse().unwrap(), &app_options, &user_options).unwrap();
+
+		tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(async move {
+			let listener = AnyTokioListener::try_from(socket).unwrap();
+			assert_eq!(listener.set_ttl(42).unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+		});
+	}
+}
+
+#[test]
+#[cfg(all(feature = "tokio", feature = "futures", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_incoming_with_addr() {
+	use futures::StreamExt;
+	use socket_config::convert::AnyTokioListener;
+
+	let socket: Socket = socket_config::open(
+		&"127.0.0.1:0".parse().unwrap(),
+		&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+		&Default::default(),
+	).unwrap();
+	let server_addr = socket.local_addr().unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let listener = AnyTokioListener::try_from(socket).unwrap();
+		let mut incoming = std::pin::pin!(listener.incoming_with_addr());
+
+		let client = tokio::net::TcpStream::connect(server_addr.as_socket().unwrap()).await.unwrap();
+		let (connection, peer_addr) = incoming.next().await.unwrap().unwrap();
+
+		assert_eq!(peer_addr.as_socket(), Some(client.local_addr().unwrap()));
+		assert_eq!(connection.peer_addr().unwrap().as_socket(), Some(client.local_addr().unwrap()));
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_close_and_drain() {
+	use socket_config::convert::AnyTokioListener;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		// Connections waiting in the backlog are accepted, not reset.
+		let listener = AnyTokioListener::try_from(socket_config::open(
+			&"127.0.0.1:0".parse().unwrap(),
+			&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+			&Default::default(),
+		).unwrap()).unwrap();
+		let server_addr = listener.local_addr().unwrap().as_socket().unwrap();
+
+		let mut clients = Vec::new();
+		for _ in 0..2 {
+			clients.push(tokio::net::TcpStream::connect(server_addr).await.unwrap());
+		}
+
+		let mut drain = listener.close_and_drain(false).unwrap();
+		for client in &mut clients {
+			let (mut connection, peer_addr) = drain.next().unwrap().unwrap();
+			assert_eq!(peer_addr.as_socket(), Some(client.local_addr().unwrap()));
+
+			let mut buf = [0u8; 1];
+			connection.write_all(b"x").await.unwrap();
+			client.read_exact(&mut buf).await.unwrap();
+			assert_eq!(&buf, b"x");
+		}
+
+		assert!(drain.next().is_none());
+		assert!(drain.is_closed());
+		assert!(tokio::net::TcpStream::connect(server_addr).await.is_err());
+
+		// The socket file of a Unix-domain socket is deleted on request.
+		#[cfg(unix)] {
+			let path = std::path::Path::new("./target/close_and_drain.socket");
+
+			let listener = AnyTokioListener::try_from(socket_config::open(
+				&"./target/close_and_drain.socket".parse().unwrap(),
+				&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+				&Default::default(),
+			).unwrap()).unwrap();
+
+			let drain = listener.close_and_drain(true).unwrap();
+			assert!(!path.exists());
+			assert_eq!(drain.count(), 0);
+		}
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_serve() {
+	use socket_config::convert::{AnyTokioListener, ServeBuilder};
+	use std::time::Duration;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	let socket: Socket = socket_config::open(
+		&"127.0.0.1:0".parse().unwrap(),
+		&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+		&Default::default(),
+	).unwrap();
+	let server_addr = socket.local_addr().unwrap().as_socket().unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let listener = AnyTokioListener::try_from(socket).unwrap();
+		let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+		let server = tokio::spawn(
+			ServeBuilder::new()
+			.drain_timeout(Duration::from_millis(100))
+			.serve(
+				listener,
+				|mut connection, _| async move {
+					let mut buf = [0u8; 1];
+
+					// Echo one byte, then wait for the client to close the connection.
+					connection.read_exact(&mut buf).await.unwrap();
+					connection.write_all(&buf).await.unwrap();
+					let _ = connection.read(&mut buf).await;
+				},
+				async { let _ = shutdown_rx.await; },
+			)
+		);
+
+		// One client that finishes in time, and one that doesn't.
+		let mut finishing_client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+		let mut stuck_client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+
+		for client in [&mut finishing_client, &mut stuck_client] {
+			let mut buf = [0u8; 1];
+			client.write_all(b"x").await.unwrap();
+			client.read_exact(&mut buf).await.unwrap();
+			assert_eq!(&buf, b"x");
+		}
+
+		shutdown_tx.send(()).unwrap();
+		drop(finishing_client);
+
+		assert_eq!(server.await.unwrap(), 1);
+
+		// The listener is closed once `serve` returns.
+		assert!(tokio::net::TcpStream::connect(server_addr).await.is_err());
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_serve_max_connections() {
+	use socket_config::convert::{AnyTokioListener, ConnectionLimitPolicy, ServeBuilder};
+	use std::{num::NonZeroUsize, time::Duration};
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	async fn echo_one(client: &mut tokio::net::TcpStream) -> std::io::Result<usize> {
+		client.write_all(b"x").await?;
+		client.read(&mut [0u8; 1]).await
+	}
+
+	for policy in [ConnectionLimitPolicy::Wait, ConnectionLimitPolicy::Close] {
+		let socket: Socket = socket_config::open(
+			&"127.0.0.1:0".parse().unwrap(),
+			&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+			&Default::default(),
+		).unwrap();
+		let server_addr = socket.local_addr().unwrap().as_socket().unwrap();
+
+		tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.unwrap()
+		.block_on(async move {
+			let listener = AnyTokioListener::try_from(socket).unwrap();
+
+			tokio::spawn(
+				ServeBuilder::new()
+				.max_connections(NonZeroUsize::new(1).unwrap())
+				.connection_limit_policy(policy)
+				.serve(
+					listener,
+					|mut connection, _| async move {
+						// Echo bytes until the client closes the connection.
+						let mut buf = [0u8; 1];
+						while connection.read(&mut buf).await.unwrap_or(0) != 0 {
+							connection.write_all(&buf).await.unwrap();
+						}
+					},
+					std::future::pending(),
+				)
+			);
+
+			let mut first_client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+			assert_eq!(echo_one(&mut first_client).await.unwrap(), 1);
+
+			let mut second_client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+
+			match policy {
+				ConnectionLimitPolicy::Wait => {
+					// The second connection isn't served until the first one finishes.
+					assert!(tokio::time::timeout(Duration::from_millis(100), echo_one(&mut second_client)).await.is_err());
+					drop(first_client);
+					assert_eq!(second_client.read(&mut [0u8; 1]).await.unwrap(), 1);
+				}
+
+				// The second connection is closed without being served.
+				_ => assert!(!matches!(echo_one(&mut second_client).await, Ok(1))),
+			}
+		});
+	}
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_serve_max_accept_rate() {
+	use socket_config::convert::{AnyTokioListener, ServeBuilder};
+	use std::{num::NonZeroU32, time::{Duration, Instant}};
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	let socket: Socket = socket_config::open(
+		&"127.0.0.1:0".parse().unwrap(),
+		&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+		&Default::default(),
+	).unwrap();
+	let server_addr = socket.local_addr().unwrap().as_socket().unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let listener = AnyTokioListener::try_from(socket).unwrap();
+
+		tokio::spawn(
+			ServeBuilder::new()
+			.max_accept_rate(NonZeroU32::new(10).unwrap(), NonZeroU32::new(2).unwrap())
+			.serve(
+				listener,
+				|mut connection, _| async move {
+					let _ = connection.write_all(b"x").await;
+				},
+				std::future::pending(),
+			)
+		);
+
+		// The first two connections are a burst, and the other two have to wait 100 milliseconds each.
+		let start = Instant::now();
+
+		for _ in 0..4 {
+			let mut client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+			assert_eq!(client.read(&mut [0u8; 1]).await.unwrap(), 1);
+		}
+
+		assert!(start.elapsed() >= Duration::from_millis(150));
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tokio-util", feature = "futures", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_framed() {
+	use futures::{SinkExt, StreamExt};
+	use socket_config::convert::AnyTokioListener;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	let socket: Socket = socket_config::open(
+		&"127.0.0.1:0".parse().unwrap(),
+		&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+		&Default::default(),
+	).unwrap();
+	let server_addr = socket.local_addr().unwrap().as_socket().unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let listener = AnyTokioListener::try_from(socket).unwrap();
+
+		// Lines.
+		let mut client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+		let (connection, _) = listener.accept().await.unwrap();
+		let mut connection = connection.framed_lines(8);
+
+		client.write_all(b"hello\nworld\n0123456789\n").await.unwrap();
+		assert_eq!(connection.next().await.unwrap().unwrap(), "hello");
+		assert_eq!(connection.next().await.unwrap().unwrap(), "world");
+		assert!(connection.next().await.unwrap().is_err());
+
+		connection.send("bye").await.unwrap();
+		let mut buf = [0u8; 4];
+		client.read_exact(&mut buf).await.unwrap();
+		assert_eq!(&buf, b"bye\n");
+
+		// Length-delimited.
+		let mut client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+		let (connection, _) = listener.accept().await.unwrap();
+		let mut connection = connection.framed_length_delimited();
+
+		client.write_all(b"\0\0\0\x05hello").await.unwrap();
+		assert_eq!(&connection.next().await.unwrap().unwrap()[..], b"hello");
+
+		connection.send(tokio_util::bytes::Bytes::from_static(b"bye")).await.unwrap();
+		let mut buf = [0u8; 7];
+		client.read_exact(&mut buf).await.unwrap();
+		assert_eq!(&buf, b"\0\0\0\x03bye");
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tonic", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tonic_connect_info() {
+	use socket_config::convert::{AnyTokioConnectInfo, AnyTokioListener};
+	use tonic::transport::server::Connected;
+
+	let socket: Socket = socket_config::open(
+		&"127.0.0.1:0".parse().unwrap(),
+		&socket_config::SocketAppOptions::new(socket2::Type::STREAM),
+		&Default::default(),
+	).unwrap();
+	let server_addr = socket.local_addr().unwrap().as_socket().unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let listener = AnyTokioListener::try_from(socket).unwrap();
+
+		let client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+		let (connection, _) = listener.accept().await.unwrap();
+
+		match connection.connect_info() {
+			AnyTokioConnectInfo::Tcp(info) => {
+				assert_eq!(info.local_addr(), Some(server_addr));
+				assert_eq!(info.remote_addr(), Some(client.local_addr().unwrap()));
+			},
+
+			#[allow(unreachable_patterns)]
+			other => panic!("expected TCP connection info, got {other:?}"),
+		}
+	});
+}
+
+#[test]
+#[cfg(all(feature = "rustls", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn rustls_open_tls() {
+	use rustls::pki_types::ServerName;
+	use socket_config::{convert::open_tls, errors::{OpenTlsError, TlsConfigError}, SocketUserOptions, TlsUserOptions};
+	use std::{path::PathBuf, sync::Arc};
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	let dir = PathBuf::from("./target/rustls_open_tls");
+	std::fs::create_dir_all(&dir).unwrap();
+
+	let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+	std::fs::write(dir.join("cert.pem"), certified.cert.pem()).unwrap();
+	std::fs::write(dir.join("key.pem"), certified.key_pair.serialize_pem()).unwrap();
+
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+
+	let user_options = SocketUserOptions::default();
+	let mut tls_options = TlsUserOptions::default();
+	tls_options.tls_cert = Some(dir.join("cert.pem"));
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		// A certificate without a key is an error.
+		assert!(matches!(
+			open_tls(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options, &tls_options),
+			Err(OpenTlsError::TlsConfig(TlsConfigError::MissingKey))
+		));
+
+		// With both, connections use TLS.
+		tls_options.tls_key = Some(dir.join("key.pem"));
+		let mut listener = open_tls(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options, &tls_options).unwrap();
+		assert!(listener.is_tls());
+		let server_addr = listener.local_addr().unwrap().as_socket().unwrap();
+
+		let mut roots = rustls::RootCertStore::empty();
+		roots.add(certified.cert.der().clone()).unwrap();
+
+		let client_config =
+			rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+			.with_safe_default_protocol_versions()
+			.unwrap()
+			.with_root_certificates(roots)
+			.with_no_client_auth();
+
+		let client = async {
+			let client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+			let mut client =
+				tokio_rustls::TlsConnector::from(Arc::new(client_config))
+				.connect(ServerName::try_from("localhost").unwrap(), client)
+				.await
+				.unwrap();
+
+			client.write_all(b"hello").await.unwrap();
+			client.flush().await.unwrap();
+
+			let mut reply = [0u8; 5];
+			client.read_exact(&mut reply).await.unwrap();
+			assert_eq!(&reply, b"HELLO");
+		};
+
+		let server = async {
+			let (mut connection, _) = listener.accept().await.unwrap();
+			assert!(connection.is_tls());
+
+			let mut request = [0u8; 5];
+			connection.read_exact(&mut request).await.unwrap();
+			request.make_ascii_uppercase();
+			connection.write_all(&request).await.unwrap();
+			connection.flush().await.unwrap();
+		};
+
+		tokio::join!(client, server);
+
+		// Without TLS options, connections are plaintext.
+		let mut listener = open_tls(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options, &TlsUserOptions::default()).unwrap();
+		assert!(!listener.is_tls());
+
+		let _client = tokio::net::TcpStream::connect(listener.local_addr().unwrap().as_socket().unwrap()).await.unwrap();
+		let (connection, _) = listener.accept().await.unwrap();
+		assert!(!connection.is_tls());
+	});
+}
+
+#[test]
+#[cfg(all(feature = "native-tls", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn native_tls_open() {
+	use socket_config::{convert::open_native_tls, errors::{OpenTlsError, TlsConfigError}, NativeTlsUserOptions, SocketUserOptions};
+	use std::path::PathBuf;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	let dir = PathBuf::from("./target/native_tls_open");
+	std::fs::create_dir_all(&dir).unwrap();
+
+	let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+	std::fs::write(dir.join("cert.pem"), certified.cert.pem()).unwrap();
+	std::fs::write(dir.join("key.pem"), certified.key_pair.serialize_pem()).unwrap();
+
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+
+	let user_options = SocketUserOptions::default();
+	let mut tls_options = NativeTlsUserOptions::default();
+	tls_options.tls_key = Some(dir.join("key.pem"));
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		// A key without a certificate is an error.
+		assert!(matches!(
+			open_native_tls(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options, &tls_options),
+			Err(OpenTlsError::TlsConfig(TlsConfigError::MissingCert { name: "tls_key", .. }))
+		));
+
+		// With both, connections use TLS.
+		tls_options.tls_cert = Some(dir.join("cert.pem"));
+		let mut listener = open_native_tls(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options, &tls_options).unwrap();
+		assert!(listener.is_tls());
+		let server_addr = listener.local_addr().unwrap().as_socket().unwrap();
+
+		let connector =
+			native_tls::TlsConnector::builder()
+			.add_root_certificate(native_tls::Certificate::from_pem(certified.cert.pem().as_bytes()).unwrap())
+			.build()
+			.unwrap();
+
+		let client = async {
+			let client = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+			let mut client =
+				tokio_native_tls::TlsConnector::from(connector)
+				.connect("localhost", client)
+				.await
+				.unwrap();
+
+			client.write_all(b"hello").await.unwrap();
+			client.flush().await.unwrap();
+
+			let mut reply = [0u8; 5];
+			client.read_exact(&mut reply).await.unwrap();
+			assert_eq!(&reply, b"HELLO");
+		};
+
+		let server = async {
+			let (mut connection, _) = listener.accept().await.unwrap();
+			assert!(connection.is_tls());
+
+			let mut request = [0u8; 5];
+			connection.read_exact(&mut request).await.unwrap();
+			request.make_ascii_uppercase();
+			connection.write_all(&request).await.unwrap();
+			connection.flush().await.unwrap();
+		};
+
+		tokio::join!(client, server);
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tokio", unix, not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_peer_credentials() {
+	use socket_config::convert::AnyTokioStream;
+
+	let (server, _client) = Socket::pair(socket2::Domain::UNIX, socket2::Type::STREAM, None).unwrap();
+	let tcp_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let tcp_client: Socket = std::net::TcpStream::connect(tcp_listener.local_addr().unwrap()).unwrap().into();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let server = AnyTokioStream::try_from(server).unwrap();
+		let credentials = server.peer_credentials().unwrap();
+		assert_eq!(credentials.uid, nix::unistd::Uid::effective());
+
+		let tcp_client = AnyTokioStream::try_from(tcp_client).unwrap();
+		assert_eq!(tcp_client.peer_credentials().unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+	});
+}
+
+#[test]
+#[cfg(all(feature = "tokio", not(any(target_os = "fuchsia", target_os = "solaris"))))]
+fn tokio_datagram() {
+	use socket_config::convert::AnyTokioDatagram;
+
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::DGRAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let server: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	let client: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+
+	#[cfg(unix)]
+	let (unix_server, unix_client) = Socket::pair(socket2::Domain::UNIX, socket2::Type::DGRAM, None).unwrap();
+
+	tokio::runtime::Builder::new_current_thread()
+	.enable_all()
+	.build()
+	.unwrap()
+	.block_on(async move {
+		let server = AnyTokioDatagram::try_from(server).unwrap();
+		let client = AnyTokioDatagram::try_from(client).unwrap();
+		assert!(matches!(server, AnyTokioDatagram::Udp(_)));
+
+		let mut buf = [0u8; TEST_MSG_LEN];
+
+		client.send_to(TEST_MSG, &server.local_addr().unwrap()).await.unwrap();
+		let (size, peer_addr) = server.recv_from(&mut buf).await.unwrap();
+		assert_eq!(&buf[..size], TEST_MSG);
+		assert_eq!(peer_addr, client.local_addr().unwrap());
+
+		#[cfg(unix)] {
+			let unix_server = AnyTokioDatagram::try_from(unix_server).unwrap();
+			let unix_client = AnyTokioDatagram::try_from(unix_client).unwrap();
+			assert!(matches!(unix_server, AnyTokioDatagram::Unix(_)));
+
+			unix_client.send(TEST_MSG).await.unwrap();
+			let size = unix_server.recv(&mut buf).await.unwrap();
+			assert_eq!(&buf[..size], TEST_MSG);
+
+			// A UDP socket can't send to a Unix-domain socket address.
+			let error = client.send_to(TEST_MSG, &unix_server.local_addr().unwrap()).await.unwrap_err();
+			assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+		}
+	});
+
+	// A stream socket is not a datagram socket.
+	let stream: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &socket_config::SocketAppOptions::new(socket2::Type::STREAM), &user_options).unwrap();
+	assert!(AnyTokioDatagram::try_from(stream).is_err());
+}
+
+#[test]
+#[cfg(feature = "async-std")]
+fn async_std_accept() {
+	use async_std::io::{ReadExt, WriteExt};
+	use socket_config::convert::{AnyAsyncStdListener, AnyAsyncStdStream};
+
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let mut user_options = socket_config::SocketUserOptions::default();
+	user_options.tcp_nodelay = true;
+
+	let socket: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	let listener = AnyAsyncStdListener::try_from(socket).unwrap();
+	let server_addr = listener.local_addr().unwrap();
+
+	async_std::task::block_on(async move {
+		let mut client = async_std::net::TcpStream::connect(server_addr.as_socket().unwrap()).await.unwrap();
+		let (mut connection, peer_addr): (AnyAsyncStdStream, _) = listener.accept().await.unwrap();
+		assert_eq!(peer_addr.as_socket(), Some(client.local_addr().unwrap()));
+
+		let AnyAsyncStdStream::Tcp(tcp_connection) = &connection else {
+			panic!("expected a TCP connection");
+		};
+
+		assert!(tcp_connection.nodelay().unwrap());
+
+		client.write_all(TEST_MSG).await.unwrap();
+		let mut buf = [0u8; TEST_MSG_LEN];
+		connection.read_exact(&mut buf).await.unwrap();
+		assert_eq!(&buf, TEST_MSG);
+	});
+
+	// A UDP socket is not a listening socket.
+	let udp: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &socket_config::SocketAppOptions::new(socket2::Type::DGRAM), &socket_config::SocketUserOptions::default()).unwrap();
+	assert!(AnyAsyncStdListener::try_from(udp).is_err());
+}
+
+#[test]
+#[cfg(feature = "smol")]
+fn smol_accept() {
+	use futures_io::{AsyncRead, AsyncWrite};
+	use socket_config::convert::{AnySmolListener, AnySmolStream};
+	use std::{future::poll_fn, pin::Pin};
+
+	let app_options = socket_config::SocketAppOptions::new(socket2::Type::STREAM);
+	let user_options = socket_config::SocketUserOptions::default();
+
+	let socket: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &app_options, &user_options).unwrap();
+	let listener = AnySmolListener::try_from(socket).unwrap();
+	let server_addr = listener.local_addr().unwrap();
+
+	async_io::block_on(async move {
+		let mut client = AnySmolStream::from(async_net::TcpStream::connect(server_addr.as_socket().unwrap()).await.unwrap());
+		let (mut connection, peer_addr): (AnySmolStream, _) = listener.accept().await.unwrap();
+		assert_eq!(peer_addr, client.local_addr().unwrap());
+
+		let size = poll_fn(|cx| Pin::new(&mut client).poll_write(cx, TEST_MSG)).await.unwrap();
+		assert_eq!(size, TEST_MSG_LEN);
+
+		let mut buf = [0u8; TEST_MSG_LEN];
+		let size = poll_fn(|cx| Pin::new(&mut connection).poll_read(cx, &mut buf)).await.unwrap();
+		assert_eq!(&buf[..size], &TEST_MSG[..size]);
+
+		// The connection can be converted back into a `Socket`, since it hasn't been cloned.
+		let connection = Socket::try_from(connection).unwrap();
+		assert_eq!(connection.peer_addr().unwrap(), client.local_addr().unwrap());
+	});
+
+	// A UDP socket is not a listening socket.
+	let udp: Socket = socket_config::open(&"127.0.0.1:0".parse().unwrap(), &socket_config::SocketAppOptions::new(socket2::Type::DGRAM), &user_options).unwrap();
+	assert!(AnySmolListener::try_from(udp).is_err());
+}
+
 /// Connects to the [`echo_incr_server`] and checks if it echoes correctly. Also works with the `echo_incr` example program running in a child process. Expects `socket` to already be connected.
 fn echo_incr_client(mut socket: Socket) {
 	let mut actual_input = [0u8; TEST_MSG_LEN];
@@ -0,0 +1,67 @@
+//! Tests `prefork`. It must be called while the process has only one thread, so this test is run without the libtest harness, which runs tests on several threads.
+
+#[cfg(unix)]
+fn main() {
+	use socket_config::{
+		Forked,
+		SocketAppOptions,
+		SocketUserOptions,
+	};
+	use socket2::Socket;
+	use std::{
+		collections::BTreeSet,
+		io::Read,
+	};
+
+	let forked: Forked = unsafe {
+		// Safety: This test runs without the libtest harness, so this process has only one thread.
+		socket_config::prefork(&"127.0.0.1:0".parse().unwrap(), &SocketAppOptions::new(socket2::Type::STREAM), &SocketUserOptions::default(), 2)
+	}.unwrap();
+
+	let (socket, workers): (Socket, Vec<u32>) = match forked {
+		Forked::Worker { socket, index } => {
+			// Accept one connection, and tell the client which worker this is.
+			let status = match socket.accept().and_then(|(connection, _)| connection.send(&[index as u8])) {
+				Ok(_) => 0,
+				Err(_) => 1,
+			};
+
+			unsafe {
+				// Safety: `_exit` has no preconditions.
+				libc::_exit(status)
+			}
+		},
+
+		Forked::Parent { socket, workers } => (socket, workers),
+	};
+
+	assert_eq!(workers.len(), 2);
+
+	let local_addr = socket.local_addr().unwrap().as_socket().unwrap();
+	let mut indices: BTreeSet<u8> = BTreeSet::new();
+
+	for _ in 0..2 {
+		let mut connection = std::net::TcpStream::connect(local_addr).unwrap();
+		let mut index = [0u8; 1];
+		connection.read_exact(&mut index).unwrap();
+		indices.insert(index[0]);
+	}
+
+	assert_eq!(indices, BTreeSet::from([0, 1]));
+
+	for pid in workers {
+		let mut status: libc::c_int = 0;
+
+		unsafe {
+			// Safety: `status` is a valid `c_int` for `waitpid` to write to.
+			assert_eq!(libc::waitpid(pid as libc::pid_t, &mut status, 0), pid as libc::pid_t);
+		}
+
+		assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+	}
+
+	println!("prefork test passed");
+}
+
+#[cfg(not(unix))]
+fn main() {}